@@ -0,0 +1,569 @@
+use super::conv::{Conv2dParams, Pool2dParams};
+use super::tensor::{broadcast_shapes, normalize_axis, try_buffer, Tensor, TensorError, TensorResult, GELU_SCALE};
+use crate::math;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+/// Operación que produjo un nodo de la cinta, con los índices de sus entradas.
+#[derive(Debug, Clone)]
+enum Op {
+    Leaf,
+    Add(usize, usize),
+    Sub(usize, usize),
+    Mul(usize, usize),
+    MatMul(usize, usize),
+    AddScalar(usize),
+    MulScalar(usize, f32),
+    Transpose(usize, usize, usize),
+    Permute(usize, Vec<usize>),
+    Reshape(usize),
+    Narrow { input: usize, axis: usize, start: usize },
+    Concat { inputs: Vec<usize>, axis: usize },
+    IndexSelect { input: usize, axis: usize, indices: Vec<usize> },
+    Relu(usize),
+    Sigmoid(usize),
+    Tanh(usize),
+    Gelu(usize),
+    Silu(usize),
+    Rsqrt(usize),
+    Softmax(usize, usize),
+    Conv2d { input: usize, weight: usize, params: Conv2dParams },
+    MaxPool2d(usize, Pool2dParams),
+    AvgPool2d(usize, Pool2dParams),
+    SumAll(usize),
+    MeanAll(usize),
+    Sum { input: usize, axis: usize, keepdim: bool },
+    Mean { input: usize, axis: usize, keepdim: bool },
+    /// Escalar cuyo gradiente respecto a la entrada se calculó al crearlo
+    /// y se guarda en `Node::saved`.
+    Precomputed(usize),
+}
+
+struct Node {
+    value: Tensor,
+    op: Op,
+    requires_grad: bool,
+    grad: Option<Tensor>,
+    saved: Option<Tensor>,
+}
+
+/// Cinta de diferenciación automática en modo inverso.
+///
+/// Cada operación sobre un `Var` calcula su resultado en el momento y lo
+/// apunta en la cinta junto con sus entradas; `Var::backward` recorre la
+/// cinta hacia atrás aplicando la regla de la cadena. Como los nodos se
+/// añaden siempre después de sus entradas, el orden de la cinta ya es
+/// topológico.
+///
+/// Una cinta sirve para una pasada: para el siguiente paso de
+/// entrenamiento se crea otra con los parámetros actualizados.
+#[derive(Default)]
+pub struct Tape {
+    nodes: RefCell<Vec<Node>>,
+}
+
+impl Tape {
+    pub fn new() -> Self {
+        Tape { nodes: RefCell::new(Vec::new()) }
+    }
+
+    /// Añade un tensor de entrada. Solo los que tienen `requires_grad`
+    /// (típicamente los parámetros) reciben gradiente en `backward`.
+    pub fn var(&self, value: Tensor, requires_grad: bool) -> Var<'_> {
+        self.push(value, Op::Leaf, requires_grad)
+    }
+
+    /// Número de nodos apuntados.
+    pub fn len(&self) -> usize {
+        self.nodes.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Borra los gradientes acumulados por llamadas anteriores a `backward`.
+    pub fn zero_grad(&self) {
+        for node in self.nodes.borrow_mut().iter_mut() {
+            node.grad = None;
+        }
+    }
+
+    fn push(&self, value: Tensor, op: Op, requires_grad: bool) -> Var<'_> {
+        let mut nodes = self.nodes.borrow_mut();
+        nodes.push(Node { value, op, requires_grad, grad: None, saved: None });
+        Var { tape: self, index: nodes.len() - 1 }
+    }
+
+    fn value(&self, index: usize) -> Tensor {
+        self.nodes.borrow()[index].value.clone()
+    }
+
+    fn requires_grad(&self, index: usize) -> bool {
+        self.nodes.borrow()[index].requires_grad
+    }
+}
+
+/// Tensor apuntado en una `Tape`. Es un índice a la cinta, así que copiarlo
+/// es gratis y todas sus copias comparten valor y gradiente.
+#[derive(Clone, Copy)]
+pub struct Var<'t> {
+    tape: &'t Tape,
+    index: usize,
+}
+
+impl<'t> Var<'t> {
+    pub fn value(&self) -> Tensor {
+        self.tape.value(self.index)
+    }
+
+    /// Cinta en la que está apuntado, para añadir más entradas.
+    pub fn tape(&self) -> &'t Tape {
+        self.tape
+    }
+
+    pub fn shape(&self) -> Vec<usize> {
+        self.tape.nodes.borrow()[self.index].value.shape().to_vec()
+    }
+
+    /// Si el valor depende de alguna entrada con `requires_grad`.
+    pub fn requires_grad(&self) -> bool {
+        self.tape.requires_grad(self.index)
+    }
+
+    /// Gradiente acumulado tras `backward`. Solo se conserva en las
+    /// entradas de la cinta: el de los nodos intermedios se libera en
+    /// cuanto se propaga, para no duplicar la memoria de la pasada.
+    pub fn grad(&self) -> Option<Tensor> {
+        self.tape.nodes.borrow()[self.index].grad.clone()
+    }
+
+    fn unary(&self, value: Tensor, op: Op) -> Var<'t> {
+        self.tape.push(value, op, self.requires_grad())
+    }
+
+    fn binary(&self, other: &Var<'t>, value: TensorResult<Tensor>, op: Op) -> TensorResult<Var<'t>> {
+        if !core::ptr::eq(self.tape, other.tape) {
+            return Err(TensorError::InvalidArgument("Los operandos pertenecen a cintas distintas"));
+        }
+        let requires_grad = self.requires_grad() || other.requires_grad();
+        Ok(self.tape.push(value?, op, requires_grad))
+    }
+
+    pub fn add(&self, other: &Var<'t>) -> TensorResult<Var<'t>> {
+        let value = self.value().add(&other.value());
+        self.binary(other, value, Op::Add(self.index, other.index))
+    }
+
+    pub fn sub(&self, other: &Var<'t>) -> TensorResult<Var<'t>> {
+        let value = self.value().sub(&other.value());
+        self.binary(other, value, Op::Sub(self.index, other.index))
+    }
+
+    pub fn mul(&self, other: &Var<'t>) -> TensorResult<Var<'t>> {
+        let value = self.value().mul(&other.value());
+        self.binary(other, value, Op::Mul(self.index, other.index))
+    }
+
+    pub fn matmul(&self, other: &Var<'t>) -> TensorResult<Var<'t>> {
+        let value = self.value().matmul(&other.value());
+        self.binary(other, value, Op::MatMul(self.index, other.index))
+    }
+
+    pub fn add_scalar(&self, value: f32) -> TensorResult<Var<'t>> {
+        Ok(self.unary(self.value().add_scalar(value)?, Op::AddScalar(self.index)))
+    }
+
+    pub fn mul_scalar(&self, value: f32) -> TensorResult<Var<'t>> {
+        Ok(self.unary(self.value().mul_scalar(value)?, Op::MulScalar(self.index, value)))
+    }
+
+    pub fn transpose(&self, dim0: isize, dim1: isize) -> TensorResult<Var<'t>> {
+        let value = self.value();
+        let dim0 = normalize_axis(dim0, value.ndim())?;
+        let dim1 = normalize_axis(dim1, value.ndim())?;
+        let value = value.transpose(dim0 as isize, dim1 as isize)?;
+        Ok(self.unary(value, Op::Transpose(self.index, dim0, dim1)))
+    }
+
+    pub fn permute(&self, dims: &[usize]) -> TensorResult<Var<'t>> {
+        let value = self.value().permute(dims)?;
+        Ok(self.unary(value, Op::Permute(self.index, dims.to_vec())))
+    }
+
+    pub fn reshape(&self, shape: &[usize]) -> TensorResult<Var<'t>> {
+        Ok(self.unary(self.value().reshape(shape)?, Op::Reshape(self.index)))
+    }
+
+    pub fn narrow(&self, axis: isize, start: usize, length: usize) -> TensorResult<Var<'t>> {
+        let value = self.value();
+        let axis = normalize_axis(axis, value.ndim())?;
+        let value = value.narrow(axis as isize, start, length)?;
+        Ok(self.unary(value, Op::Narrow { input: self.index, axis, start }))
+    }
+
+    /// Como `Tensor::concat`; todas las partes deben estar en la misma cinta.
+    pub fn concat(parts: &[Var<'t>], axis: isize) -> TensorResult<Var<'t>> {
+        let Some(first) = parts.first() else {
+            return Err(TensorError::EmptyReduction);
+        };
+        if parts.iter().any(|part| !core::ptr::eq(first.tape, part.tape)) {
+            return Err(TensorError::InvalidArgument("Los operandos pertenecen a cintas distintas"));
+        }
+        let values: Vec<Tensor> = parts.iter().map(Var::value).collect();
+        let values: Vec<&Tensor> = values.iter().collect();
+        let axis = normalize_axis(axis, values[0].ndim())?;
+        let value = Tensor::concat(&values, axis as isize)?;
+        let inputs = parts.iter().map(|part| part.index).collect();
+        let requires_grad = parts.iter().any(Var::requires_grad);
+        Ok(first.tape.push(value, Op::Concat { inputs, axis }, requires_grad))
+    }
+
+    /// Como `Tensor::index_select`; el gradiente de las posiciones repetidas
+    /// se suma.
+    pub fn index_select(&self, axis: isize, indices: &[usize]) -> TensorResult<Var<'t>> {
+        let value = self.value();
+        let axis = normalize_axis(axis, value.ndim())?;
+        let value = value.index_select(axis as isize, indices)?;
+        Ok(self.unary(value, Op::IndexSelect { input: self.index, axis, indices: indices.to_vec() }))
+    }
+
+    pub fn relu(&self) -> TensorResult<Var<'t>> {
+        Ok(self.unary(self.value().relu()?, Op::Relu(self.index)))
+    }
+
+    pub fn sigmoid(&self) -> TensorResult<Var<'t>> {
+        Ok(self.unary(self.value().sigmoid()?, Op::Sigmoid(self.index)))
+    }
+
+    pub fn tanh(&self) -> TensorResult<Var<'t>> {
+        Ok(self.unary(self.value().tanh()?, Op::Tanh(self.index)))
+    }
+
+    pub fn gelu(&self) -> TensorResult<Var<'t>> {
+        Ok(self.unary(self.value().gelu()?, Op::Gelu(self.index)))
+    }
+
+    pub fn silu(&self) -> TensorResult<Var<'t>> {
+        Ok(self.unary(self.value().silu()?, Op::Silu(self.index)))
+    }
+
+    /// `1 / sqrt(x)` elemento a elemento, para dividir por una desviación típica.
+    pub fn rsqrt(&self) -> TensorResult<Var<'t>> {
+        Ok(self.unary(self.value().map(|x| 1.0 / math::sqrt(x))?, Op::Rsqrt(self.index)))
+    }
+
+    pub fn softmax(&self, axis: isize) -> TensorResult<Var<'t>> {
+        let value = self.value();
+        let axis = normalize_axis(axis, value.ndim())?;
+        Ok(self.unary(value.softmax(axis as isize)?, Op::Softmax(self.index, axis)))
+    }
+
+    /// Como `Tensor::conv2d` sin sesgo, que se suma aparte con `add`.
+    pub fn conv2d(&self, weight: &Var<'t>, params: Conv2dParams) -> TensorResult<Var<'t>> {
+        let value = self.value().conv2d(&weight.value(), None, params);
+        self.binary(weight, value, Op::Conv2d { input: self.index, weight: weight.index, params })
+    }
+
+    pub fn max_pool2d(&self, params: Pool2dParams) -> TensorResult<Var<'t>> {
+        Ok(self.unary(self.value().max_pool2d(params)?, Op::MaxPool2d(self.index, params)))
+    }
+
+    pub fn avg_pool2d(&self, params: Pool2dParams) -> TensorResult<Var<'t>> {
+        Ok(self.unary(self.value().avg_pool2d(params)?, Op::AvgPool2d(self.index, params)))
+    }
+
+    /// Suma de todos los elementos, como tensor de dimensión cero.
+    pub fn sum_all(&self) -> Var<'t> {
+        self.unary(Tensor::scalar(self.value().sum_all()), Op::SumAll(self.index))
+    }
+
+    /// Media de todos los elementos, como tensor de dimensión cero.
+    pub fn mean_all(&self) -> Var<'t> {
+        self.unary(Tensor::scalar(self.value().mean_all()), Op::MeanAll(self.index))
+    }
+
+    pub fn sum(&self, axis: isize, keepdim: bool) -> TensorResult<Var<'t>> {
+        let value = self.value();
+        let axis = normalize_axis(axis, value.ndim())?;
+        let value = value.sum(axis as isize, keepdim)?;
+        Ok(self.unary(value, Op::Sum { input: self.index, axis, keepdim }))
+    }
+
+    pub fn mean(&self, axis: isize, keepdim: bool) -> TensorResult<Var<'t>> {
+        let value = self.value();
+        let axis = normalize_axis(axis, value.ndim())?;
+        let value = value.mean(axis as isize, keepdim)?;
+        Ok(self.unary(value, Op::Mean { input: self.index, axis, keepdim }))
+    }
+
+    /// Escalar `value` cuya derivada respecto a este nodo es `grad`, ya
+    /// calculada de forma analítica (p. ej. por una función de pérdida).
+    pub(crate) fn scalar_with_grad(&self, value: f32, grad: Tensor) -> TensorResult<Var<'t>> {
+        if grad.shape() != self.shape().as_slice() {
+            return Err(TensorError::ShapeMismatch { lhs: grad.shape().to_vec(), rhs: self.shape() });
+        }
+        let var = self.unary(Tensor::scalar(value), Op::Precomputed(self.index));
+        self.tape.nodes.borrow_mut()[var.index].saved = Some(grad);
+        Ok(var)
+    }
+
+    /// Propaga hacia atrás desde este nodo, que debe tener un solo elemento
+    /// (la pérdida), y acumula el gradiente en las entradas con
+    /// `requires_grad`. Varias llamadas suman sus gradientes.
+    pub fn backward(&self) -> TensorResult<()> {
+        let mut nodes = self.tape.nodes.borrow_mut();
+        let root = &mut nodes[self.index];
+        if root.value.len() != 1 {
+            return Err(TensorError::InvalidArgument("backward necesita una salida de un solo elemento"));
+        }
+        if !root.requires_grad {
+            return Ok(());
+        }
+        let seed = Tensor::ones(root.value.shape())?;
+        accumulate(root, seed)?;
+
+        for index in (0..=self.index).rev() {
+            let node = &mut nodes[index];
+            if matches!(node.op, Op::Leaf) {
+                continue;
+            }
+            let Some(grad) = node.grad.take() else { continue };
+            for (input, grad) in input_grads(&nodes, index, grad)? {
+                if nodes[input].requires_grad {
+                    accumulate(&mut nodes[input], grad)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn accumulate(node: &mut Node, grad: Tensor) -> TensorResult<()> {
+    node.grad = Some(match node.grad.take() {
+        Some(previous) => previous.add(&grad)?,
+        None => grad,
+    });
+    Ok(())
+}
+
+/// Reduce un gradiente difundido a la forma `shape` del operando original,
+/// sumando sobre los ejes que la difusión añadió o estiró.
+fn sum_to(grad: Tensor, shape: &[usize]) -> TensorResult<Tensor> {
+    let mut grad = grad;
+    while grad.ndim() > shape.len() {
+        grad = grad.sum(0, false)?;
+    }
+    for (axis, &dim) in shape.iter().enumerate() {
+        if dim == 1 && grad.shape()[axis] != 1 {
+            grad = grad.sum(axis as isize, true)?;
+        }
+    }
+    Ok(grad)
+}
+
+/// Gradiente de una reducción sobre `axis` extendido a la forma de la entrada.
+fn expand_reduced(grad: Tensor, shape: &[usize], axis: usize, keepdim: bool) -> TensorResult<Tensor> {
+    let grad = if keepdim { grad } else { grad.unsqueeze(axis as isize)? };
+    grad.broadcast_to(shape)
+}
+
+/// Gradientes de las entradas del nodo `index` a partir del de su salida.
+fn input_grads(nodes: &[Node], index: usize, grad: Tensor) -> TensorResult<Vec<(usize, Tensor)>> {
+    let value = |i: usize| &nodes[i].value;
+    let output = &nodes[index].value;
+    Ok(match nodes[index].op {
+        Op::Leaf => Vec::new(),
+        Op::Add(a, b) => vec![
+            (a, sum_to(grad.clone(), value(a).shape())?),
+            (b, sum_to(grad, value(b).shape())?),
+        ],
+        Op::Sub(a, b) => vec![
+            (a, sum_to(grad.clone(), value(a).shape())?),
+            (b, sum_to(grad.mul_scalar(-1.0)?, value(b).shape())?),
+        ],
+        Op::Mul(a, b) => vec![
+            (a, sum_to(grad.mul(value(b))?, value(a).shape())?),
+            (b, sum_to(grad.mul(value(a))?, value(b).shape())?),
+        ],
+        Op::MatMul(a, b) => matmul_grads(value(a), value(b), grad)
+            .map(|(grad_a, grad_b)| vec![(a, grad_a), (b, grad_b)])?,
+        Op::AddScalar(input) => vec![(input, grad)],
+        Op::MulScalar(input, scale) => vec![(input, grad.mul_scalar(scale)?)],
+        Op::Transpose(input, dim0, dim1) => vec![(input, grad.transpose(dim0 as isize, dim1 as isize)?)],
+        Op::Permute(input, ref dims) => {
+            let mut inverse = vec![0; dims.len()];
+            for (position, &dim) in dims.iter().enumerate() {
+                inverse[dim] = position;
+            }
+            vec![(input, grad.permute(&inverse)?)]
+        }
+        Op::Reshape(input) => vec![(input, grad.reshape(value(input).shape())?)],
+        Op::Narrow { input, axis, start } => vec![(input, pad_narrowed(grad, value(input).shape(), axis, start)?)],
+        Op::Concat { ref inputs, axis } => {
+            let mut start = 0;
+            let mut grads = Vec::with_capacity(inputs.len());
+            for &input in inputs {
+                let length = value(input).shape()[axis];
+                grads.push((input, grad.narrow(axis as isize, start, length)?));
+                start += length;
+            }
+            grads
+        }
+        Op::IndexSelect { input, axis, ref indices } => {
+            vec![(input, scatter_add(grad, value(input).shape(), axis, indices)?)]
+        }
+        Op::Relu(input) => {
+            vec![(input, grad.zip_with(value(input), |g, x| if x > 0.0 { g } else { 0.0 })?)]
+        }
+        Op::Sigmoid(input) => vec![(input, grad.zip_with(output, |g, y| g * y * (1.0 - y))?)],
+        Op::Tanh(input) => vec![(input, grad.zip_with(output, |g, y| g * (1.0 - y * y))?)],
+        Op::Gelu(input) => {
+            const CUBIC: f32 = 0.044_715;
+            let derivative = |x: f32| {
+                let t = math::tanh(GELU_SCALE * (x + CUBIC * x * x * x));
+                0.5 * (1.0 + t) + 0.5 * x * (1.0 - t * t) * GELU_SCALE * (1.0 + 3.0 * CUBIC * x * x)
+            };
+            vec![(input, grad.zip_with(value(input), |g, x| g * derivative(x))?)]
+        }
+        Op::Silu(input) => {
+            let derivative = |x: f32| {
+                let s = math::sigmoid(x);
+                s * (1.0 + x * (1.0 - s))
+            };
+            vec![(input, grad.zip_with(value(input), |g, x| g * derivative(x))?)]
+        }
+        Op::Rsqrt(input) => vec![(input, grad.zip_with(output, |g, y| -0.5 * g * y * y * y)?)],
+        Op::Softmax(input, axis) => {
+            // dx = y * (g - Σ g y)
+            let dot = grad.mul(output)?.sum(axis as isize, true)?;
+            vec![(input, output.mul(&grad.sub(&dot)?)?)]
+        }
+        Op::Conv2d { input, weight, params } => {
+            let (grad_input, grad_weight) = value(input).conv2d_backward(value(weight), &grad, params)?;
+            vec![(input, grad_input), (weight, grad_weight)]
+        }
+        Op::MaxPool2d(input, params) => vec![(input, value(input).max_pool2d_backward(&grad, params)?)],
+        Op::AvgPool2d(input, params) => vec![(input, value(input).avg_pool2d_backward(&grad, params)?)],
+        Op::SumAll(input) => {
+            let g = grad.sum_all();
+            vec![(input, Tensor::full(value(input).shape(), g)?)]
+        }
+        Op::MeanAll(input) => {
+            let g = grad.sum_all() / value(input).len() as f32;
+            vec![(input, Tensor::full(value(input).shape(), g)?)]
+        }
+        Op::Sum { input, axis, keepdim } => {
+            vec![(input, expand_reduced(grad, value(input).shape(), axis, keepdim)?)]
+        }
+        Op::Mean { input, axis, keepdim } => {
+            let shape = value(input).shape();
+            let grad = grad.mul_scalar(1.0 / shape[axis] as f32)?;
+            vec![(input, expand_reduced(grad, shape, axis, keepdim)?)]
+        }
+        Op::Precomputed(input) => {
+            let saved = nodes[index].saved.as_ref().expect("los nodos precalculados guardan su gradiente");
+            vec![(input, saved.mul_scalar(grad.sum_all())?)]
+        }
+    })
+}
+
+/// Gradiente de `narrow`: `grad` en su tramo del eje `axis` y ceros en el
+/// resto de la forma `shape` de la entrada.
+fn pad_narrowed(grad: Tensor, shape: &[usize], axis: usize, start: usize) -> TensorResult<Tensor> {
+    let end = start + grad.shape()[axis];
+    let mut parts = Vec::with_capacity(3);
+    for (from, to) in [(0, start), (end, shape[axis])] {
+        if from < to {
+            let mut zeros = shape.to_vec();
+            zeros[axis] = to - from;
+            parts.push((from, Tensor::zeros(&zeros)?));
+        }
+    }
+    parts.push((start, grad));
+    parts.sort_by_key(|&(from, _)| from);
+    let parts: Vec<&Tensor> = parts.iter().map(|(_, part)| part).collect();
+    Tensor::concat(&parts, axis as isize)
+}
+
+/// Gradiente de `index_select`: suma cada posición de `grad` en la del
+/// índice que la seleccionó, dentro de un tensor de ceros de forma `shape`.
+fn scatter_add(grad: Tensor, shape: &[usize], axis: usize, indices: &[usize]) -> TensorResult<Tensor> {
+    let inner: usize = shape[axis + 1..].iter().product();
+    let len = shape.iter().product();
+    let mut values = try_buffer(len)?;
+    values.resize(len, 0.0);
+    let mut source = grad.iter();
+    for block in 0..shape[..axis].iter().product() {
+        for &index in indices {
+            let start = (block * shape[axis] + index) * inner;
+            for (value, g) in values[start..start + inner].iter_mut().zip(source.by_ref()) {
+                *value += g;
+            }
+        }
+    }
+    Tensor::from_vec(values, shape)
+}
+
+/// Gradientes de `a @ b` con la semántica de `Tensor::matmul`: los vectores
+/// pasan a matrices de una fila o columna y los lotes difundidos se suman.
+fn matmul_grads(a: &Tensor, b: &Tensor, grad: Tensor) -> TensorResult<(Tensor, Tensor)> {
+    let a2 = if a.ndim() == 1 { a.reshape(&[1, a.len()])? } else { a.clone() };
+    let b2 = if b.ndim() == 1 { b.reshape(&[b.len(), 1])? } else { b.clone() };
+    let (a_batch, a_mat) = a2.shape().split_at(a2.ndim() - 2);
+    let (b_batch, b_mat) = b2.shape().split_at(b2.ndim() - 2);
+    let mut shape = broadcast_shapes(a_batch, b_batch)?;
+    shape.extend_from_slice(&[a_mat[0], b_mat[1]]);
+    let grad = grad.reshape(&shape)?;
+
+    let grad_a = grad.matmul(&b2.transpose(-1, -2)?)?;
+    let grad_b = a2.transpose(-1, -2)?.matmul(&grad)?;
+    Ok((
+        sum_to(grad_a, a2.shape())?.reshape(a.shape())?,
+        sum_to(grad_b, b2.shape())?.reshape(b.shape())?,
+    ))
+}
+
+/// Compara los gradientes de `f` en `inputs` con diferencias centrales de
+/// paso `eps` y devuelve el mayor error relativo, `|a - n| / max(1, |a|, |n|)`.
+///
+/// `f` recibe una `Var` por entrada y debe devolver un valor de un solo
+/// elemento; se evalúa dos veces por cada elemento de las entradas, así
+/// que conviene usarla con tensores pequeños.
+pub fn check_gradients<F>(f: F, inputs: &[Tensor], eps: f32) -> TensorResult<f32>
+where
+    F: for<'t> Fn(&[Var<'t>]) -> TensorResult<Var<'t>>,
+{
+    let tape = Tape::new();
+    let vars: Vec<Var> = inputs.iter().map(|input| tape.var(input.clone(), true)).collect();
+    f(&vars)?.backward()?;
+
+    let evaluate = |inputs: &[Tensor]| -> TensorResult<f32> {
+        let tape = Tape::new();
+        let vars: Vec<Var> = inputs.iter().map(|input| tape.var(input.clone(), false)).collect();
+        Ok(f(&vars)?.value().sum_all())
+    };
+
+    let mut worst = 0.0f32;
+    for (i, var) in vars.iter().enumerate() {
+        let analytic = match var.grad() {
+            Some(grad) => grad.to_vec(),
+            None => Tensor::zeros(inputs[i].shape())?.to_vec(),
+        };
+        let values = inputs[i].to_vec();
+        for (j, &a) in analytic.iter().enumerate() {
+            let mut perturbed = inputs.to_vec();
+            let mut shifted = |delta: f32| -> TensorResult<f32> {
+                let mut values = values.clone();
+                values[j] += delta;
+                perturbed[i] = Tensor::from_vec(values, inputs[i].shape())?;
+                evaluate(&perturbed)
+            };
+            let numeric = (shifted(eps)? - shifted(-eps)?) / (2.0 * eps);
+            let error = (a - numeric).abs() / 1.0f32.max(a.abs()).max(numeric.abs());
+            worst = worst.max(error);
+        }
+    }
+    Ok(worst)
+}
@@ -0,0 +1,636 @@
+use super::autograd::Var;
+use super::dtype::DType;
+use super::init::Initializer;
+use super::module::{parameter_names, Module};
+use super::random::with_global_rng;
+use super::tensor::{normalize_axis, try_buffer, Tensor, TensorError, TensorResult};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Parámetros de `Tensor::conv2d`; los pares son `(alto, ancho)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Conv2dParams {
+    pub stride: (usize, usize),
+    /// Ceros añadidos a cada lado de la entrada.
+    pub padding: (usize, usize),
+    /// Separación entre los elementos del núcleo (1 = núcleo compacto).
+    pub dilation: (usize, usize),
+    /// Grupos independientes en que se dividen los canales de entrada y salida.
+    pub groups: usize,
+}
+
+impl Default for Conv2dParams {
+    fn default() -> Self {
+        Conv2dParams {
+            stride: (1, 1),
+            padding: (0, 0),
+            dilation: (1, 1),
+            groups: 1,
+        }
+    }
+}
+
+/// Parámetros de `Tensor::max_pool2d` y `Tensor::avg_pool2d`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pool2dParams {
+    pub kernel: (usize, usize),
+    pub stride: (usize, usize),
+    /// Relleno a cada lado; como mucho la mitad de la ventana.
+    pub padding: (usize, usize),
+}
+
+impl Pool2dParams {
+    /// Ventanas de `kernel` sin solapamiento ni relleno.
+    pub fn new(kernel: (usize, usize)) -> Self {
+        Pool2dParams { kernel, stride: kernel, padding: (0, 0) }
+    }
+}
+
+/// Posiciones de salida de una ventana deslizante en una dimensión.
+fn output_size(input: usize, kernel: usize, stride: usize, padding: usize, dilation: usize) -> TensorResult<usize> {
+    if kernel == 0 || stride == 0 || dilation == 0 {
+        return Err(TensorError::InvalidArgument(
+            "El núcleo, el paso y la dilatación deben ser mayores que 0",
+        ));
+    }
+    let span = dilation * (kernel - 1) + 1;
+    let padded = input + 2 * padding;
+    if padded < span {
+        return Err(TensorError::InvalidArgument("La ventana es mayor que la entrada con relleno"));
+    }
+    Ok((padded - span) / stride + 1)
+}
+
+/// Índice en la entrada del elemento `k` de la ventana `o`, o `None` si cae
+/// en el relleno.
+#[inline]
+fn source_index(o: usize, k: usize, stride: usize, dilation: usize, padding: usize, len: usize) -> Option<usize> {
+    (o * stride + k * dilation).checked_sub(padding).filter(|&i| i < len)
+}
+
+/// Dimensiones `[N, C, H, W]` de una entrada de imagen.
+fn nchw(input: &Tensor) -> TensorResult<[usize; 4]> {
+    nchw_shape(input.shape())
+}
+
+fn nchw_shape(shape: &[usize]) -> TensorResult<[usize; 4]> {
+    match *shape {
+        [n, c, h, w] => Ok([n, c, h, w]),
+        _ => Err(TensorError::RankMismatch { expected: 4, found: shape.len() }),
+    }
+}
+
+/// Forma de salida de `max_pool2d` y `avg_pool2d`.
+fn pool_output_shape(input: &[usize], params: Pool2dParams) -> TensorResult<[usize; 4]> {
+    let [n, c, h, w] = nchw_shape(input)?;
+    let (kh, kw) = params.kernel;
+    // Así ninguna ventana queda entera en el relleno
+    if 2 * params.padding.0 > kh || 2 * params.padding.1 > kw {
+        return Err(TensorError::InvalidArgument("El relleno no puede superar la mitad de la ventana"));
+    }
+    let oh = output_size(h, kh, params.stride.0, params.padding.0, 1)?;
+    let ow = output_size(w, kw, params.stride.1, params.padding.1, 1)?;
+    Ok([n, c, oh, ow])
+}
+
+/// Copia contigua en `f32`, para leerla como slice.
+fn dense(tensor: &Tensor) -> TensorResult<Tensor> {
+    tensor.to_dtype(DType::F32)
+}
+
+fn values(tensor: &Tensor) -> &[f32] {
+    tensor.as_slice().expect("to_dtype(F32) devuelve un tensor contiguo")
+}
+
+/// Buffer de `len` ceros donde acumular gradientes.
+fn zeroed(len: usize) -> TensorResult<Vec<f32>> {
+    let mut buffer = try_buffer(len)?;
+    buffer.resize(len, 0.0);
+    Ok(buffer)
+}
+
+impl Tensor {
+    /// Convolución 2D de una entrada `[N, C_in, H, W]` con pesos
+    /// `[C_out, C_in / groups, KH, KW]` y sesgo opcional `[C_out]`.
+    ///
+    /// La entrada se despliega (im2col) a `[N, groups, K, OH * OW]` y se
+    /// multiplica por los pesos vistos como `[groups, C_out / groups, K]`
+    /// con el `matmul` por lotes. Las convoluciones en profundidad (un
+    /// canal de entrada por grupo) usan un bucle directo sin im2col.
+    pub fn conv2d(&self, weight: &Tensor, bias: Option<&Tensor>, params: Conv2dParams) -> TensorResult<Tensor> {
+        let [n, c_in, h, w] = nchw(self)?;
+        let [c_out, c_group, kh, kw] = match *weight.shape() {
+            [c_out, c_group, kh, kw] => [c_out, c_group, kh, kw],
+            ref shape => return Err(TensorError::RankMismatch { expected: 4, found: shape.len() }),
+        };
+        let groups = params.groups;
+        if groups == 0 || c_in % groups != 0 || c_out % groups != 0 {
+            return Err(TensorError::InvalidArgument(
+                "El número de grupos debe dividir los canales de entrada y de salida",
+            ));
+        }
+        if c_group != c_in / groups {
+            return Err(TensorError::ShapeMismatch {
+                lhs: self.shape().to_vec(),
+                rhs: weight.shape().to_vec(),
+            });
+        }
+        if let Some(bias) = bias.filter(|bias| bias.shape() != [c_out]) {
+            return Err(TensorError::ShapeMismatch {
+                lhs: weight.shape().to_vec(),
+                rhs: bias.shape().to_vec(),
+            });
+        }
+        let (sh, sw) = params.stride;
+        let (ph, pw) = params.padding;
+        let (dh, dw) = params.dilation;
+        let oh = output_size(h, kh, sh, ph, dh)?;
+        let ow = output_size(w, kw, sw, pw, dw)?;
+
+        let input = dense(self)?;
+        let x = values(&input);
+        let output = if groups == c_in && c_group == 1 {
+            let weight = dense(weight)?;
+            let kernels = values(&weight);
+            let multiplier = c_out / c_in;
+            let mut out = try_buffer(n * c_out * oh * ow)?;
+            for b in 0..n {
+                for oc in 0..c_out {
+                    let plane = &x[(b * c_in + oc / multiplier) * h * w..][..h * w];
+                    let kernel = &kernels[oc * kh * kw..(oc + 1) * kh * kw];
+                    for oy in 0..oh {
+                        for ox in 0..ow {
+                            let mut acc = 0.0;
+                            for ki in 0..kh {
+                                let Some(iy) = source_index(oy, ki, sh, dh, ph, h) else { continue };
+                                for kj in 0..kw {
+                                    if let Some(ix) = source_index(ox, kj, sw, dw, pw, w) {
+                                        acc += plane[iy * w + ix] * kernel[ki * kw + kj];
+                                    }
+                                }
+                            }
+                            out.push(acc);
+                        }
+                    }
+                }
+            }
+            Tensor::from_vec(out, &[n, c_out, oh, ow])?
+        } else {
+            // Fila (canal, ki, kj) de la matriz desplegada: la entrada
+            // desplazada que ve ese elemento del núcleo en cada posición
+            let k = c_group * kh * kw;
+            let mut col = try_buffer(n * c_in * kh * kw * oh * ow)?;
+            for plane in 0..n * c_in {
+                let plane = &x[plane * h * w..(plane + 1) * h * w];
+                for ki in 0..kh {
+                    for kj in 0..kw {
+                        for oy in 0..oh {
+                            match source_index(oy, ki, sh, dh, ph, h) {
+                                Some(iy) => {
+                                    let row = &plane[iy * w..(iy + 1) * w];
+                                    col.extend((0..ow).map(|ox| {
+                                        source_index(ox, kj, sw, dw, pw, w).map_or(0.0, |ix| row[ix])
+                                    }));
+                                }
+                                None => col.resize(col.len() + ow, 0.0),
+                            }
+                        }
+                    }
+                }
+            }
+            let col = Tensor::from_vec(col, &[n, groups, k, oh * ow])?;
+            let weight = weight.reshape(&[groups, c_out / groups, k])?;
+            weight.matmul(&col)?.reshape(&[n, c_out, oh, ow])?
+        };
+
+        match bias {
+            Some(bias) => output.add(&bias.reshape(&[c_out, 1, 1])?),
+            None => Ok(output),
+        }
+    }
+
+    /// Convolución en profundidad: cada canal de entrada se filtra por
+    /// separado con pesos `[C * multiplicador, 1, KH, KW]`.
+    ///
+    /// Ignora `params.groups`, que es siempre el número de canales.
+    pub fn depthwise_conv2d(&self, weight: &Tensor, bias: Option<&Tensor>, params: Conv2dParams) -> TensorResult<Tensor> {
+        let [_, channels, _, _] = nchw(self)?;
+        self.conv2d(weight, bias, Conv2dParams { groups: channels, ..params })
+    }
+
+    /// Máximo de cada ventana de una entrada `[N, C, H, W]`.
+    pub fn max_pool2d(&self, params: Pool2dParams) -> TensorResult<Tensor> {
+        self.pool2d(params, f32::NEG_INFINITY, f32::max, |acc, _| acc)
+    }
+
+    /// Media de cada ventana de una entrada `[N, C, H, W]`.
+    ///
+    /// Las posiciones de relleno no cuentan en la media (como
+    /// `count_include_pad = 0` en ONNX).
+    pub fn avg_pool2d(&self, params: Pool2dParams) -> TensorResult<Tensor> {
+        self.pool2d(params, 0.0, |acc, x| acc + x, |acc, count| acc / count as f32)
+    }
+
+    /// Media de cada canal de `[N, C, H, W]`, con forma `[N, C, 1, 1]`.
+    pub fn global_avg_pool2d(&self) -> TensorResult<Tensor> {
+        nchw(self)?;
+        self.mean(-1, true)?.mean(-2, true)
+    }
+
+    /// Aplana a dos dimensiones: `[d0 * ... * d(axis-1), d(axis) * ... * dn]`.
+    ///
+    /// `axis` puede valer `ndim`, como en el operador `Flatten` de ONNX.
+    pub fn flatten(&self, axis: isize) -> TensorResult<Tensor> {
+        let axis = normalize_axis(axis, self.ndim() + 1)?;
+        let outer = self.shape()[..axis].iter().product();
+        let inner = self.shape()[axis..].iter().product();
+        self.reshape(&[outer, inner])
+    }
+
+    /// Gradientes de `conv2d` (sin sesgo) respecto a la entrada y a los
+    /// pesos, dado el de la salida `grad`.
+    pub(crate) fn conv2d_backward(&self, weight: &Tensor, grad: &Tensor, params: Conv2dParams) -> TensorResult<(Tensor, Tensor)> {
+        let [n, c_in, h, w] = nchw(self)?;
+        let [c_out, c_group, kh, kw] = nchw_shape(weight.shape())?;
+        let [_, _, oh, ow] = nchw(grad)?;
+        let (sh, sw) = params.stride;
+        let (ph, pw) = params.padding;
+        let (dh, dw) = params.dilation;
+        let per_group = c_out / params.groups;
+
+        let input = dense(self)?;
+        let x = values(&input);
+        let weight = dense(weight)?;
+        let kernels = values(&weight);
+        let grad = dense(grad)?;
+        let g = values(&grad);
+        let mut grad_input = zeroed(n * c_in * h * w)?;
+        let mut grad_weight = zeroed(kernels.len())?;
+        for b in 0..n {
+            for oc in 0..c_out {
+                let first = oc / per_group * c_group;
+                let g = &g[(b * c_out + oc) * oh * ow..][..oh * ow];
+                for ic in 0..c_group {
+                    let plane = (b * c_in + first + ic) * h * w;
+                    for ki in 0..kh {
+                        for kj in 0..kw {
+                            let wi = ((oc * c_group + ic) * kh + ki) * kw + kj;
+                            for oy in 0..oh {
+                                let Some(iy) = source_index(oy, ki, sh, dh, ph, h) else { continue };
+                                for ox in 0..ow {
+                                    if let Some(ix) = source_index(ox, kj, sw, dw, pw, w) {
+                                        let go = g[oy * ow + ox];
+                                        grad_input[plane + iy * w + ix] += go * kernels[wi];
+                                        grad_weight[wi] += go * x[plane + iy * w + ix];
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok((Tensor::from_vec(grad_input, &[n, c_in, h, w])?, Tensor::from_vec(grad_weight, weight.shape())?))
+    }
+
+    /// Gradiente de `max_pool2d`: cada ventana lo pasa entero a su máximo
+    /// (el primero si hay empate).
+    pub(crate) fn max_pool2d_backward(&self, grad: &Tensor, params: Pool2dParams) -> TensorResult<Tensor> {
+        self.pool2d_backward(grad, params, |plane, window, g, out| {
+            let mut best = window[0];
+            for &i in &window[1..] {
+                if plane[i] > plane[best] {
+                    best = i;
+                }
+            }
+            out[best] += g;
+        })
+    }
+
+    /// Gradiente de `avg_pool2d`, repartido entre las posiciones de la
+    /// ventana que no son relleno.
+    pub(crate) fn avg_pool2d_backward(&self, grad: &Tensor, params: Pool2dParams) -> TensorResult<Tensor> {
+        self.pool2d_backward(grad, params, |_, window, g, out| {
+            let share = g / window.len() as f32;
+            for &i in window {
+                out[i] += share;
+            }
+        })
+    }
+
+    /// Recorre las ventanas como `pool2d`; `spread` recibe el plano de
+    /// entrada, los índices de la ventana en él, el gradiente de esa salida y
+    /// el plano de gradiente donde acumularlo.
+    fn pool2d_backward<S>(&self, grad: &Tensor, params: Pool2dParams, spread: S) -> TensorResult<Tensor>
+    where
+        S: Fn(&[f32], &[usize], f32, &mut [f32]),
+    {
+        let [n, c, oh, ow] = pool_output_shape(self.shape(), params)?;
+        if grad.shape() != [n, c, oh, ow] {
+            return Err(TensorError::ShapeMismatch { lhs: [n, c, oh, ow].to_vec(), rhs: grad.shape().to_vec() });
+        }
+        let [_, _, h, w] = nchw(self)?;
+        let (kh, kw) = params.kernel;
+        let (sh, sw) = params.stride;
+        let (ph, pw) = params.padding;
+
+        let input = dense(self)?;
+        let x = values(&input);
+        let grad = dense(grad)?;
+        let g = values(&grad);
+        let mut out = zeroed(x.len())?;
+        let mut window = try_buffer(kh * kw)?;
+        for plane in 0..n * c {
+            let input = &x[plane * h * w..(plane + 1) * h * w];
+            let out = &mut out[plane * h * w..(plane + 1) * h * w];
+            let g = &g[plane * oh * ow..(plane + 1) * oh * ow];
+            for oy in 0..oh {
+                for ox in 0..ow {
+                    window.clear();
+                    for ki in 0..kh {
+                        let Some(iy) = source_index(oy, ki, sh, 1, ph, h) else { continue };
+                        window.extend((0..kw).filter_map(|kj| source_index(ox, kj, sw, 1, pw, w)).map(|ix| iy * w + ix));
+                    }
+                    spread(input, &window, g[oy * ow + ox], out);
+                }
+            }
+        }
+        Tensor::from_vec(out, &[n, c, h, w])
+    }
+
+    fn pool2d<C, F>(&self, params: Pool2dParams, init: f32, combine: C, finish: F) -> TensorResult<Tensor>
+    where
+        C: Fn(f32, f32) -> f32,
+        F: Fn(f32, usize) -> f32,
+    {
+        let [n, c, oh, ow] = pool_output_shape(self.shape(), params)?;
+        let [_, _, h, w] = nchw(self)?;
+        let (kh, kw) = params.kernel;
+        let (sh, sw) = params.stride;
+        let (ph, pw) = params.padding;
+
+        let input = dense(self)?;
+        let x = values(&input);
+        let mut out = try_buffer(n * c * oh * ow)?;
+        for plane in 0..n * c {
+            let plane = &x[plane * h * w..(plane + 1) * h * w];
+            for oy in 0..oh {
+                for ox in 0..ow {
+                    let mut acc = init;
+                    let mut count = 0;
+                    for ki in 0..kh {
+                        let Some(iy) = source_index(oy, ki, sh, 1, ph, h) else { continue };
+                        for kj in 0..kw {
+                            if let Some(ix) = source_index(ox, kj, sw, 1, pw, w) {
+                                acc = combine(acc, plane[iy * w + ix]);
+                                count += 1;
+                            }
+                        }
+                    }
+                    out.push(finish(acc, count));
+                }
+            }
+        }
+        Tensor::from_vec(out, &[n, c, oh, ow])
+    }
+}
+
+/// Capa convolucional 2D sobre entradas `[N, C, H, W]`.
+pub struct Conv2d {
+    weight: Tensor,
+    bias: Option<Tensor>,
+    params: Conv2dParams,
+}
+
+impl Conv2d {
+    /// Crea una capa con pesos `[salida, entrada / grupos, KH, KW]` de He
+    /// uniforme (del generador global) y sesgo a cero.
+    pub fn new(in_channels: usize, out_channels: usize, kernel: (usize, usize), params: Conv2dParams) -> TensorResult<Self> {
+        let groups = params.groups.max(1);
+        let shape = [out_channels, in_channels / groups, kernel.0, kernel.1];
+        let receptive = kernel.0 * kernel.1;
+        let (fan_in, fan_out) = (in_channels / groups * receptive, out_channels / groups * receptive);
+        Ok(Conv2d {
+            weight: with_global_rng(|rng| Initializer::HeUniform.tensor(&shape, fan_in, fan_out, rng))?,
+            bias: Some(Tensor::zeros(&[out_channels])?),
+            params,
+        })
+    }
+
+    /// Capa en profundidad: `multiplier` filtros por canal de entrada.
+    pub fn depthwise(channels: usize, multiplier: usize, kernel: (usize, usize), params: Conv2dParams) -> TensorResult<Self> {
+        Conv2d::new(channels, channels * multiplier, kernel, Conv2dParams { groups: channels, ..params })
+    }
+
+    /// Crea una capa a partir de pesos `[C_out, C_in / groups, KH, KW]` ya entrenados.
+    pub fn from_parameters(weight: Tensor, bias: Option<Tensor>, params: Conv2dParams) -> TensorResult<Self> {
+        let out_channels = match *weight.shape() {
+            [out_channels, _, _, _] => out_channels,
+            ref shape => return Err(TensorError::RankMismatch { expected: 4, found: shape.len() }),
+        };
+        if let Some(bias) = bias.as_ref().filter(|bias| bias.shape() != [out_channels]) {
+            return Err(TensorError::ShapeMismatch {
+                lhs: weight.shape().to_vec(),
+                rhs: bias.shape().to_vec(),
+            });
+        }
+        if params.groups == 0 || out_channels % params.groups != 0 {
+            return Err(TensorError::InvalidArgument(
+                "El número de grupos debe dividir los canales de entrada y de salida",
+            ));
+        }
+        Ok(Conv2d { weight, bias, params })
+    }
+
+    pub fn weight(&self) -> &Tensor {
+        &self.weight
+    }
+
+    pub fn bias(&self) -> Option<&Tensor> {
+        self.bias.as_ref()
+    }
+
+    pub fn params(&self) -> Conv2dParams {
+        self.params
+    }
+
+    /// Almacena pesos y sesgo en `dtype`; el cálculo sigue haciéndose en `f32`.
+    pub fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        self.weight = self.weight.to_dtype(dtype)?;
+        if let Some(bias) = &self.bias {
+            self.bias = Some(bias.to_dtype(dtype)?);
+        }
+        Ok(())
+    }
+
+    /// Memoria ocupada por pesos y sesgo.
+    pub fn parameter_bytes(&self) -> usize {
+        self.weight.size_in_bytes() + self.bias.as_ref().map_or(0, Tensor::size_in_bytes)
+    }
+
+    pub fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        input.conv2d(&self.weight, self.bias.as_ref(), self.params)
+    }
+}
+
+impl Module for Conv2d {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        Conv2d::forward(self, input)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        core::iter::once(&self.weight).chain(self.bias.as_ref()).collect()
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        parameter_names(if self.bias.is_some() { &["weight", "bias"] } else { &["weight"] })
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        core::iter::once(&mut self.weight).chain(self.bias.as_mut()).collect()
+    }
+
+    fn name(&self) -> &str {
+        "Conv2d"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        let [n, c_in, h, w] = nchw_shape(input)?;
+        let [c_out, c_group, kh, kw] = nchw_shape(self.weight.shape())?;
+        if c_in != c_group * self.params.groups {
+            return Err(TensorError::ShapeMismatch { lhs: input.to_vec(), rhs: self.weight.shape().to_vec() });
+        }
+        let oh = output_size(h, kh, self.params.stride.0, self.params.padding.0, self.params.dilation.0)?;
+        let ow = output_size(w, kw, self.params.stride.1, self.params.padding.1, self.params.dilation.1)?;
+        Ok([n, c_out, oh, ow].to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        match (parameters, &self.bias) {
+            ([weight], None) => input.conv2d(weight, self.params),
+            ([weight, bias], Some(_)) => {
+                let bias = bias.reshape(&[bias.shape()[0], 1, 1])?;
+                input.conv2d(weight, self.params)?.add(&bias)
+            }
+            _ => Err(TensorError::LengthMismatch { expected: self.parameters().len(), found: parameters.len() }),
+        }
+    }
+
+    fn parameter_bytes(&self) -> usize {
+        Conv2d::parameter_bytes(self)
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        Conv2d::to_dtype(self, dtype)
+    }
+}
+
+/// Capa de `Tensor::max_pool2d`.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxPool2d(pub Pool2dParams);
+
+impl Module for MaxPool2d {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        input.max_pool2d(self.0)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        "MaxPool2d"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        pool_output_shape(input, self.0).map(|shape| shape.to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, _parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        input.max_pool2d(self.0)
+    }
+}
+
+/// Capa de `Tensor::avg_pool2d`.
+#[derive(Debug, Clone, Copy)]
+pub struct AvgPool2d(pub Pool2dParams);
+
+impl Module for AvgPool2d {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        input.avg_pool2d(self.0)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        "AvgPool2d"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        pool_output_shape(input, self.0).map(|shape| shape.to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, _parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        input.avg_pool2d(self.0)
+    }
+}
+
+/// `[N, C, H, W]` a `[N, C, 1, 1]`.
+#[derive(Debug, Clone, Copy)]
+pub struct GlobalAvgPool2d;
+
+impl Module for GlobalAvgPool2d {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        input.global_avg_pool2d()
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        "GlobalAvgPool2d"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        let [n, c, _, _] = nchw_shape(input)?;
+        Ok([n, c, 1, 1].to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, _parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        nchw_shape(&input.shape())?;
+        input.mean(-1, true)?.mean(-2, true)
+    }
+}
+
+/// `[N, ...]` a `[N, resto]`, para pasar de capas convolucionales a densas.
+#[derive(Debug, Clone, Copy)]
+pub struct Flatten;
+
+impl Module for Flatten {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        input.flatten(1)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        "Flatten"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        match input.split_first() {
+            Some((&n, rest)) => Ok([n, rest.iter().product()].to_vec()),
+            None => Err(TensorError::RankMismatch { expected: 1, found: 0 }),
+        }
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, _parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let shape = self.output_shape(&input.shape())?;
+        input.reshape(&shape)
+    }
+}
@@ -0,0 +1,208 @@
+use super::tensor::{try_buffer, TensorResult};
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+use half::slice::HalfFloatSliceExt;
+use half::{bf16, f16};
+
+/// Tipo de elemento con el que se almacena un tensor.
+///
+/// El cálculo se hace siempre en `f32`; `F16` y `BF16` solo reducen a la
+/// mitad la memoria que ocupan los datos (por ejemplo, los pesos de un modelo
+/// dentro del heap de 1 MiB del kernel).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DType {
+    F32,
+    F16,
+    BF16,
+}
+
+impl DType {
+    /// Tamaño en bytes de un elemento.
+    pub fn size(self) -> usize {
+        match self {
+            DType::F32 => 4,
+            DType::F16 | DType::BF16 => 2,
+        }
+    }
+}
+
+/// Elementos que se convierten en bloque a `f32` para calcular.
+pub(crate) const UPCAST_BLOCK: usize = 64;
+
+/// Elementos de un `Storage`: propios, en el heap, o prestados de una
+/// región que vive tanto como el kernel (p. ej. un modelo enlazado con
+/// `include_bytes!`). Los prestados se copian al heap la primera vez que se
+/// escriben.
+#[derive(Debug, Clone)]
+pub enum Buffer<T: 'static> {
+    Owned(Vec<T>),
+    Static(&'static [T]),
+}
+
+impl<T> Deref for Buffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Buffer::Owned(data) => data,
+            Buffer::Static(data) => data,
+        }
+    }
+}
+
+impl<T: Copy> DerefMut for Buffer<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        if let Buffer::Static(data) = *self {
+            *self = Buffer::Owned(data.to_vec());
+        }
+        match self {
+            Buffer::Owned(data) => data,
+            Buffer::Static(_) => unreachable!(),
+        }
+    }
+}
+
+impl<T> From<Vec<T>> for Buffer<T> {
+    fn from(data: Vec<T>) -> Self {
+        Buffer::Owned(data)
+    }
+}
+
+/// Búfer de elementos de un tensor en su tipo de almacenamiento.
+#[derive(Debug, Clone)]
+pub enum Storage {
+    F32(Buffer<f32>),
+    F16(Buffer<f16>),
+    BF16(Buffer<bf16>),
+}
+
+impl Storage {
+    pub fn dtype(&self) -> DType {
+        match self {
+            Storage::F32(_) => DType::F32,
+            Storage::F16(_) => DType::F16,
+            Storage::BF16(_) => DType::BF16,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Storage::F32(data) => data.len(),
+            Storage::F16(data) => data.len(),
+            Storage::BF16(data) => data.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Si los elementos están prestados en vez de en el heap.
+    pub fn is_static(&self) -> bool {
+        matches!(
+            self,
+            Storage::F32(Buffer::Static(_)) | Storage::F16(Buffer::Static(_)) | Storage::BF16(Buffer::Static(_))
+        )
+    }
+
+    /// Lee un elemento convertido a `f32`.
+    #[inline]
+    pub fn get(&self, index: usize) -> f32 {
+        match self {
+            Storage::F32(data) => data[index],
+            Storage::F16(data) => data[index].to_f32(),
+            Storage::BF16(data) => data[index].to_f32(),
+        }
+    }
+
+    /// Escribe un elemento redondeándolo al tipo de almacenamiento.
+    #[inline]
+    pub fn set(&mut self, index: usize, value: f32) {
+        match self {
+            Storage::F32(data) => data[index] = value,
+            Storage::F16(data) => data[index] = f16::from_f32(value),
+            Storage::BF16(data) => data[index] = bf16::from_f32(value),
+        }
+    }
+
+    /// Slice de `f32` si el almacenamiento ya es de ese tipo.
+    pub fn as_f32(&self) -> Option<&[f32]> {
+        match self {
+            Storage::F32(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Convierte valores `f32` al tipo indicado.
+    pub fn from_f32(values: &[f32], dtype: DType) -> TensorResult<Storage> {
+        Ok(match dtype {
+            DType::F32 => {
+                let mut data = try_buffer(values.len())?;
+                data.extend_from_slice(values);
+                Storage::F32(data.into())
+            }
+            DType::F16 => {
+                let mut data = try_buffer(values.len())?;
+                data.resize(values.len(), f16::ZERO);
+                data.convert_from_f32_slice(values);
+                Storage::F16(data.into())
+            }
+            DType::BF16 => {
+                let mut data = try_buffer(values.len())?;
+                data.resize(values.len(), bf16::ZERO);
+                data.convert_from_f32_slice(values);
+                Storage::BF16(data.into())
+            }
+        })
+    }
+
+    /// Copia los elementos de `offsets` a un almacenamiento nuevo del mismo tipo.
+    pub fn gather<I: Iterator<Item = usize>>(&self, offsets: I, len: usize) -> TensorResult<Storage> {
+        Ok(match self {
+            Storage::F32(data) => {
+                let mut out = try_buffer(len)?;
+                out.extend(offsets.map(|i| data[i]));
+                Storage::F32(out.into())
+            }
+            Storage::F16(data) => {
+                let mut out = try_buffer(len)?;
+                out.extend(offsets.map(|i| data[i]));
+                Storage::F16(out.into())
+            }
+            Storage::BF16(data) => {
+                let mut out = try_buffer(len)?;
+                out.extend(offsets.map(|i| data[i]));
+                Storage::BF16(out.into())
+            }
+        })
+    }
+
+    /// Convierte a `f32` los elementos `start + i * stride` para `i < out.len()`.
+    ///
+    /// Los tramos contiguos de `f16`/`bf16` se convierten por bloques con
+    /// las rutinas de slice del crate `half`.
+    pub fn upcast_into(&self, start: usize, stride: usize, out: &mut [f32]) {
+        match self {
+            Storage::F32(data) if stride == 1 => {
+                out.copy_from_slice(&data[start..start + out.len()]);
+            }
+            Storage::F16(data) if stride == 1 => {
+                for (i, block) in out.chunks_mut(UPCAST_BLOCK).enumerate() {
+                    let from = start + i * UPCAST_BLOCK;
+                    data[from..from + block.len()].convert_to_f32_slice(block);
+                }
+            }
+            Storage::BF16(data) if stride == 1 => {
+                for (i, block) in out.chunks_mut(UPCAST_BLOCK).enumerate() {
+                    let from = start + i * UPCAST_BLOCK;
+                    data[from..from + block.len()].convert_to_f32_slice(block);
+                }
+            }
+            _ => {
+                for (i, o) in out.iter_mut().enumerate() {
+                    *o = self.get(start + i * stride);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use super::nn::{ActivationFunction, Layer, LayerWeights};
+use super::quant::round_to_i32;
+use super::tensor::{try_buffer, Tensor, TensorError, TensorResult};
+use alloc::vec::Vec;
+
+/// Formato de coma fija para la ejecución sin unidad de coma flotante.
+///
+/// El target del kernel compila con `+soft-float`, así que cada operación
+/// `f32` es una llamada a biblioteca; en coma fija todo el `forward` se
+/// reduce a sumas, productos y desplazamientos enteros.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixedFormat {
+    /// 15 bits fraccionarios saturando al rango de `i16` (`[-1, 1)`).
+    Q15,
+    /// 16 bits enteros y 16 fraccionarios en un `i32` (`[-32768, 32768)`).
+    Q16_16,
+}
+
+impl FixedFormat {
+    pub fn frac_bits(self) -> u32 {
+        match self {
+            FixedFormat::Q15 => 15,
+            FixedFormat::Q16_16 => 16,
+        }
+    }
+
+    fn saturate(self, value: i64) -> i32 {
+        match self {
+            FixedFormat::Q15 => value.clamp(i16::MIN as i64, i16::MAX as i64) as i32,
+            FixedFormat::Q16_16 => value.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+        }
+    }
+
+    fn encode(self, value: f32) -> i32 {
+        let scaled = value * (1u32 << self.frac_bits()) as f32;
+        // `as` satura, así que basta con recortar después al rango del formato
+        self.saturate(round_to_i32(scaled) as i64)
+    }
+
+    fn decode(self, value: i32) -> f32 {
+        value as f32 / (1u32 << self.frac_bits()) as f32
+    }
+
+    /// Pasa un valor de este formato a Q16.16.
+    fn to_q16(self, value: i32) -> i64 {
+        (value as i64) << (16 - self.frac_bits())
+    }
+
+    /// Pasa un valor Q16.16 a este formato, redondeando y saturando.
+    fn narrow_q16(self, value: i64) -> i32 {
+        let shift = 16 - self.frac_bits();
+        self.saturate(round_shift(value, shift))
+    }
+}
+
+/// Modo de ejecución de `NeuralNetwork::forward`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    Float,
+    Fixed(FixedFormat),
+}
+
+/// Desplazamiento aritmético a la derecha redondeando al más cercano.
+fn round_shift(value: i64, shift: u32) -> i64 {
+    if shift == 0 {
+        value
+    } else {
+        (value + (1 << (shift - 1))) >> shift
+    }
+}
+
+/// `sigmoid(i / 16)` en Q16.16 para `i` en `0..=128` (x de 0 a 8).
+const SIGMOID_LUT: [i32; 129] = [
+    32768, 33792, 34813, 35831, 36843, 37847, 38841, 39824,
+    40793, 41748, 42687, 43608, 44511, 45393, 46254, 47094,
+    47911, 48704, 49474, 50220, 50941, 51638, 52310, 52957,
+    53581, 54179, 54754, 55306, 55834, 56339, 56822, 57284,
+    57724, 58144, 58544, 58925, 59287, 59632, 59959, 60270,
+    60565, 60844, 61109, 61360, 61598, 61823, 62036, 62238,
+    62428, 62608, 62778, 62938, 63090, 63233, 63368, 63495,
+    63615, 63728, 63835, 63935, 64030, 64119, 64203, 64283,
+    64357, 64427, 64494, 64556, 64614, 64669, 64721, 64770,
+    64816, 64859, 64900, 64938, 64974, 65008, 65039, 65069,
+    65097, 65124, 65149, 65172, 65194, 65215, 65234, 65252,
+    65269, 65285, 65300, 65315, 65328, 65341, 65352, 65364,
+    65374, 65384, 65393, 65402, 65410, 65417, 65425, 65431,
+    65438, 65444, 65449, 65454, 65459, 65464, 65468, 65472,
+    65476, 65480, 65483, 65486, 65489, 65492, 65495, 65497,
+    65500, 65502, 65504, 65506, 65508, 65509, 65511, 65513,
+    65514,
+];
+
+/// `exp(-i / 16)` en Q16.16 para `i` en `0..=256` (x de 0 a 16).
+const EXP_NEG_LUT: [i32; 257] = [
+    65536, 61565, 57835, 54331, 51039, 47947, 45042, 42313,
+    39750, 37341, 35079, 32954, 30957, 29081, 27319, 25664,
+    24109, 22649, 21276, 19987, 18776, 17639, 16570, 15566,
+    14623, 13737, 12905, 12123, 11388, 10698, 10050, 9441,
+    8869, 8332, 7827, 7353, 6907, 6489, 6096, 5726,
+    5380, 5054, 4747, 4460, 4190, 3936, 3697, 3473,
+    3263, 3065, 2879, 2705, 2541, 2387, 2243, 2107,
+    1979, 1859, 1746, 1641, 1541, 1448, 1360, 1278,
+    1200, 1128, 1059, 995, 935, 878, 825, 775,
+    728, 684, 642, 604, 567, 533, 500, 470,
+    442, 415, 390, 366, 344, 323, 303, 285,
+    268, 252, 236, 222, 209, 196, 184, 173,
+    162, 153, 143, 135, 127, 119, 112, 105,
+    99, 93, 87, 82, 77, 72, 68, 64,
+    60, 56, 53, 50, 47, 44, 41, 39,
+    36, 34, 32, 30, 28, 27, 25, 23,
+    22, 21, 19, 18, 17, 16, 15, 14,
+    13, 13, 12, 11, 10, 10, 9, 9,
+    8, 8, 7, 7, 6, 6, 6, 5,
+    5, 5, 4, 4, 4, 4, 3, 3,
+    3, 3, 3, 2, 2, 2, 2, 2,
+    2, 2, 2, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0,
+    0,
+];
+
+/// `sigmoid(x)` en Q16.16 mediante la tabla e interpolación lineal.
+///
+/// El error máximo de la interpolación con paso 1/16 es de unos 6e-5, cuatro
+/// unidades del último bit de Q16.16. A partir de `|x| = 8` se devuelve 0 o 1,
+/// con un error de hasta 3.4e-4.
+fn sigmoid_q16(x: i64) -> i64 {
+    let ax = x.unsigned_abs() as i64;
+    let y = if ax >= 8 << 16 {
+        1 << 16
+    } else {
+        let index = (ax >> 12) as usize;
+        let frac = ax & 0xfff;
+        let low = SIGMOID_LUT[index] as i64;
+        let high = SIGMOID_LUT[index + 1] as i64;
+        low + (((high - low) * frac) >> 12)
+    };
+    if x < 0 { (1 << 16) - y } else { y }
+}
+
+/// `tanh(x) = 2·sigmoid(2x) - 1` en Q16.16.
+fn tanh_q16(x: i64) -> i64 {
+    2 * sigmoid_q16(2 * x) - (1 << 16)
+}
+
+/// `sqrt(2/π)` y `0.044715` en Q16.16, para la GELU.
+const GELU_SCALE_Q16: i64 = 52_290;
+const GELU_CUBIC_Q16: i64 = 2_930;
+
+/// `exp(-t)` en Q16.16 para `t >= 0`.
+///
+/// Con paso 1/16 el error de la interpolación llega a 5e-4 cerca de `t = 0`
+/// y decrece como `exp(-t)`.
+fn exp_neg_q16(t: i64) -> i64 {
+    if t >= 16 << 16 {
+        return 0;
+    }
+    let index = (t >> 12) as usize;
+    let frac = t & 0xfff;
+    let low = EXP_NEG_LUT[index] as i64;
+    let high = EXP_NEG_LUT[index + 1] as i64;
+    low + (((high - low) * frac) >> 12)
+}
+
+/// Tensor en coma fija: `x ≈ data / 2^frac_bits`.
+#[derive(Debug, Clone)]
+pub struct FixedTensor {
+    data: Vec<i32>,
+    shape: Vec<usize>,
+    format: FixedFormat,
+}
+
+impl FixedTensor {
+    /// Convierte un tensor a coma fija, saturando los valores fuera de rango.
+    pub fn from_tensor(tensor: &Tensor, format: FixedFormat) -> TensorResult<FixedTensor> {
+        let mut data = try_buffer(tensor.len())?;
+        data.extend(tensor.iter().map(|x| format.encode(x)));
+        Ok(FixedTensor { data, shape: tensor.shape().to_vec(), format })
+    }
+
+    pub fn to_tensor(&self) -> TensorResult<Tensor> {
+        let mut values = try_buffer(self.data.len())?;
+        values.extend(self.data.iter().map(|&x| self.format.decode(x)));
+        Tensor::from_vec(values, &self.shape)
+    }
+
+    /// Reescala los valores a otro formato de coma fija.
+    pub fn to_format(&self, format: FixedFormat) -> TensorResult<FixedTensor> {
+        let mut data = try_buffer(self.data.len())?;
+        data.extend(self.data.iter().map(|&x| format.narrow_q16(self.format.to_q16(x))));
+        Ok(FixedTensor { data, shape: self.shape.clone(), format })
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn data(&self) -> &[i32] {
+        &self.data
+    }
+
+    pub fn format(&self) -> FixedFormat {
+        self.format
+    }
+}
+
+/// Copia en coma fija de una capa densa.
+pub struct FixedLayer {
+    weights: Vec<i32>,
+    bias: Vec<i32>,
+    inputs: usize,
+    outputs: usize,
+    activation: ActivationFunction,
+    format: FixedFormat,
+}
+
+impl FixedLayer {
+    /// Convierte los parámetros de `layer`; los pesos cuantizados se
+    /// decuantizan antes.
+    pub fn from_layer(layer: &Layer, format: FixedFormat) -> TensorResult<FixedLayer> {
+        let weights = match layer.weights() {
+            LayerWeights::Float(weights) => FixedTensor::from_tensor(weights, format)?,
+            LayerWeights::Quantized(weights) => FixedTensor::from_tensor(&weights.dequantize()?, format)?,
+        };
+        let bias = FixedTensor::from_tensor(layer.bias(), format)?;
+        let (inputs, outputs) = (weights.shape[0], weights.shape[1]);
+        Ok(FixedLayer {
+            weights: weights.data,
+            bias: bias.data,
+            inputs,
+            outputs,
+            activation: layer.activation(),
+            format,
+        })
+    }
+
+    /// `activación(input · pesos + sesgo)` usando solo aritmética entera.
+    pub fn forward(&self, input: &FixedTensor) -> TensorResult<FixedTensor> {
+        let rows = match input.shape.as_slice() {
+            [k] if *k == self.inputs => 1,
+            [m, k] if *k == self.inputs => *m,
+            _ => {
+                return Err(TensorError::ShapeMismatch {
+                    lhs: input.shape.clone(),
+                    rhs: alloc::vec![self.inputs, self.outputs],
+                })
+            }
+        };
+        let converted;
+        let input = if input.format == self.format {
+            input
+        } else {
+            converted = input.to_format(self.format)?;
+            &converted
+        };
+
+        let frac = self.format.frac_bits();
+        let (k, n) = (self.inputs, self.outputs);
+        let mut acc = try_buffer::<i64>(n)?;
+        let mut out = try_buffer(rows * n)?;
+        // Con índices en vez de `chunks`, que no admite tamaño 0: una capa
+        // sin entradas devuelve la activación del sesgo y una sin salidas,
+        // filas vacías
+        for row in (0..rows).map(|r| &input.data[r * k..(r + 1) * k]) {
+            // El producto de dos valores Qf tiene 2f bits fraccionarios
+            acc.clear();
+            acc.extend(self.bias.iter().map(|&b| (b as i64) << frac));
+            for (p, &x) in row.iter().enumerate() {
+                if x == 0 {
+                    continue;
+                }
+                let w_row = &self.weights[p * n..(p + 1) * n];
+                for (a, &w) in acc.iter_mut().zip(w_row) {
+                    *a += x as i64 * w as i64;
+                }
+            }
+            let start = out.len();
+            out.extend(acc.iter().map(|&a| self.format.saturate(round_shift(a, frac))));
+            self.activate(&mut out[start..]);
+        }
+
+        let shape = match input.shape.len() {
+            1 => alloc::vec![n],
+            _ => alloc::vec![rows, n],
+        };
+        Ok(FixedTensor { data: out, shape, format: self.format })
+    }
+
+    fn activate(&self, row: &mut [i32]) {
+        let format = self.format;
+        match self.activation {
+            ActivationFunction::ReLU => row.iter_mut().for_each(|x| *x = (*x).max(0)),
+            ActivationFunction::Sigmoid => row.iter_mut()
+                .for_each(|x| *x = format.narrow_q16(sigmoid_q16(format.to_q16(*x)))),
+            ActivationFunction::Tanh => row.iter_mut()
+                .for_each(|x| *x = format.narrow_q16(tanh_q16(format.to_q16(*x)))),
+            ActivationFunction::Identity => {}
+            ActivationFunction::Gelu => row.iter_mut().for_each(|x| {
+                // 0.5 x (1 + tanh(u)) = x · sigmoid(2u), con u = sqrt(2/π)(x + 0.044715 x³)
+                let v = format.to_q16(*x);
+                // Fuera de [-8, 8] la sigmoide ya está saturada; se recorta para
+                // que el cubo no desborde
+                let c = v.clamp(-8 << 16, 8 << 16);
+                let cube = (((c * c) >> 16) * c) >> 16;
+                let u = (GELU_SCALE_Q16 * (c + ((GELU_CUBIC_Q16 * cube) >> 16))) >> 16;
+                *x = format.narrow_q16((v * sigmoid_q16(2 * u)) >> 16);
+            }),
+            ActivationFunction::Silu => row.iter_mut().for_each(|x| {
+                let v = format.to_q16(*x);
+                *x = format.narrow_q16((v * sigmoid_q16(v)) >> 16);
+            }),
+            ActivationFunction::Softmax => {
+                let max = format.to_q16(row.iter().copied().max().unwrap_or(0));
+                let mut sum = 0i64;
+                for x in row.iter_mut() {
+                    let e = exp_neg_q16(max - format.to_q16(*x));
+                    *x = e as i32;
+                    sum += e;
+                }
+                if sum > 0 {
+                    for x in row.iter_mut() {
+                        *x = format.saturate(((*x as i64) << format.frac_bits()) / sum);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+/// Lector secuencial de valores little-endian sobre un slice, sin copiarlo.
+///
+/// Cada formato decide qué error devuelve cuando faltan bytes: `truncated`
+/// recibe la posición hasta la que se quería leer y la longitud del slice.
+pub(crate) struct ByteReader<'a, E> {
+    bytes: &'a [u8],
+    pos: usize,
+    truncated: fn(usize, usize) -> E,
+}
+
+impl<'a, E> ByteReader<'a, E> {
+    pub(crate) fn new(bytes: &'a [u8], truncated: fn(usize, usize) -> E) -> Self {
+        ByteReader { bytes, pos: 0, truncated }
+    }
+
+    /// Bytes leídos desde el inicio del slice.
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Continúa la lectura en la posición `pos`, para formatos con
+    /// desplazamientos absolutos; leer fuera del slice da `truncated`.
+    pub(crate) fn seek(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    /// Bytes leídos desde la posición `start`.
+    pub(crate) fn since(&self, start: usize) -> &'a [u8] {
+        &self.bytes[start..self.pos]
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], E> {
+        let end = self.pos.saturating_add(len);
+        let Some(slice) = self.bytes.get(self.pos..end) else {
+            return Err((self.truncated)(end, self.bytes.len()));
+        };
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> Result<[u8; N], E> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, E> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, E> {
+        self.array().map(u16::from_le_bytes)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, E> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, E> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, E> {
+        self.array().map(i32::from_le_bytes)
+    }
+
+    pub(crate) fn f32(&mut self) -> Result<f32, E> {
+        self.array().map(f32::from_le_bytes)
+    }
+
+    pub(crate) fn f64(&mut self) -> Result<f64, E> {
+        self.array().map(f64::from_le_bytes)
+    }
+}
+
+/// Parte común de los errores de los formatos de fichero, que envuelven
+/// `TensorError` en una variante `Tensor`.
+///
+/// Genera `message()` a partir de los brazos dados, `From<TensorError>` y
+/// un `Display` que escribe el mensaje seguido de lo que añada el método
+/// `write_details` del tipo (p. ej. `": nombre"`).
+macro_rules! format_error {
+    ($error:ident { $($pattern:pat => $message:expr,)* }) => {
+        impl $error {
+            /// Descripción breve y estática del error.
+            pub fn message(&self) -> &'static str {
+                match self {
+                    $($pattern => $message,)*
+                    $error::Tensor(error) => error.message(),
+                }
+            }
+        }
+
+        impl core::fmt::Display for $error {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                match self {
+                    $error::Tensor(error) => write!(f, "{}", error),
+                    _ => {
+                        f.write_str(self.message())?;
+                        self.write_details(f)
+                    }
+                }
+            }
+        }
+
+        impl From<TensorError> for $error {
+            fn from(error: TensorError) -> Self {
+                $error::Tensor(error)
+            }
+        }
+    };
+}
+
+pub(crate) use format_error;
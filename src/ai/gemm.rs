@@ -0,0 +1,322 @@
+use super::dtype::Storage;
+use super::tensor::{try_buffer, Tensor, TensorResult};
+use alloc::vec::Vec;
+
+/// Profundidad (`k`) de los paneles empaquetados; un panel de `a` y otro
+/// de `b` caben juntos en la L1.
+const KC: usize = 128;
+/// Filas de `a` empaquetadas por bloque (múltiplo de todos los `MR`).
+const MC: usize = 48;
+/// Columnas de `b` empaquetadas por bloque (múltiplo de todos los `NR`).
+const NC: usize = 256;
+/// Tamaño máximo del bloque de `out` de un microkernel (`MR * NR`).
+const MAX_TILE: usize = 6 * 16;
+
+/// Microkernel con el que se calcula cada bloque `MR x NR` del resultado.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GemmKernel {
+    /// Bucle escalar portable; siempre disponible.
+    Scalar,
+    /// Bloques de 4x8 en registros XMM de 128 bits.
+    Sse2,
+    /// Bloques de 6x16 en registros YMM de 256 bits con FMA.
+    Avx2Fma,
+}
+
+impl GemmKernel {
+    /// El mejor microkernel de los habilitados por `fpu::init`.
+    ///
+    /// Sin la característica `simd` el kernel compila con soft-float y
+    /// solo queda el bucle escalar.
+    pub fn detect() -> GemmKernel {
+        [GemmKernel::Avx2Fma, GemmKernel::Sse2]
+            .into_iter()
+            .find(|kernel| kernel.is_supported())
+            .unwrap_or(GemmKernel::Scalar)
+    }
+
+    /// Indica si la CPU tiene habilitadas las extensiones del microkernel.
+    pub fn is_supported(self) -> bool {
+        #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+        {
+            let features = crate::fpu::features();
+            match self {
+                GemmKernel::Scalar => true,
+                GemmKernel::Sse2 => features.sse2,
+                GemmKernel::Avx2Fma => features.avx2 && features.fma,
+            }
+        }
+        #[cfg(not(all(target_arch = "x86_64", feature = "simd")))]
+        {
+            self == GemmKernel::Scalar
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GemmKernel::Scalar => "escalar",
+            GemmKernel::Sse2 => "sse2",
+            GemmKernel::Avx2Fma => "avx2+fma",
+        }
+    }
+
+    /// Filas (`MR`) y columnas (`NR`) del bloque que calcula el microkernel.
+    fn tile(self) -> (usize, usize) {
+        match self {
+            GemmKernel::Scalar => (4, 4),
+            GemmKernel::Sse2 => (4, 8),
+            GemmKernel::Avx2Fma => (6, 16),
+        }
+    }
+
+    /// `c[i * ldc + j] += Σ_p a[p * MR + i] * b[p * NR + j]` sobre paneles empaquetados.
+    fn run(self, kc: usize, a: &[f32], b: &[f32], c: &mut [f32], ldc: usize) {
+        let (mr, nr) = self.tile();
+        assert!(a.len() >= kc * mr && b.len() >= kc * nr && c.len() >= (mr - 1) * ldc + nr);
+        match self {
+            GemmKernel::Scalar => kernel_scalar_4x4(kc, a, b, c, ldc),
+            // SAFETY: las longitudes se acaban de comprobar y `is_supported`
+            // garantiza que la CPU tiene habilitadas las instrucciones.
+            #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+            GemmKernel::Sse2 => unsafe {
+                simd::kernel_sse2_4x8(kc, a.as_ptr(), b.as_ptr(), c.as_mut_ptr(), ldc)
+            },
+            #[cfg(all(target_arch = "x86_64", feature = "simd"))]
+            GemmKernel::Avx2Fma => unsafe {
+                simd::kernel_avx2_6x16(kc, a.as_ptr(), b.as_ptr(), c.as_mut_ptr(), ldc)
+            },
+            #[cfg(not(all(target_arch = "x86_64", feature = "simd")))]
+            GemmKernel::Sse2 | GemmKernel::Avx2Fma => unreachable!(),
+        }
+    }
+}
+
+/// Acumula `out += a · b` para una matriz descrita por strides.
+///
+/// Sigue el esquema de Goto: `b` se empaqueta por bloques de `KC x NC`
+/// en tiras de `NR` columnas y `a` por bloques de `MC x KC` en tiras de
+/// `MR` filas, convirtiendo a `f32` al empaquetar (así funciona igual para
+/// `f16` y `bf16`). El microkernel recorre los paneles de forma secuencial
+/// y los bordes se calculan sobre un bloque temporal rellenado con ceros.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gemm_strided(
+    kernel: GemmKernel,
+    m: usize, n: usize, k: usize,
+    a: &Storage, a_off: usize, a_rs: usize, a_cs: usize,
+    b: &Storage, b_off: usize, b_rs: usize, b_cs: usize,
+    out: &mut [f32],
+) -> TensorResult<()> {
+    if m == 0 || n == 0 || k == 0 {
+        return Ok(());
+    }
+    let kernel = if kernel.is_supported() { kernel } else { GemmKernel::Scalar };
+    let (mr, nr) = kernel.tile();
+
+    let kc_max = KC.min(k);
+    let mc_max = MC.min(m).next_multiple_of(mr);
+    let nc_max = NC.min(n).next_multiple_of(nr);
+    let mut a_pack = zeroed(mc_max * kc_max)?;
+    let mut b_pack = zeroed(kc_max * nc_max)?;
+    let mut line = zeroed(kc_max.max(nc_max))?;
+    let mut tile = [0.0f32; MAX_TILE];
+
+    for jc in (0..n).step_by(NC) {
+        let nc = NC.min(n - jc);
+        for pc in (0..k).step_by(KC) {
+            let kc = KC.min(k - pc);
+            pack_b(b, b_off + pc * b_rs + jc * b_cs, b_rs, b_cs, kc, nc, nr, &mut b_pack, &mut line);
+            for ic in (0..m).step_by(MC) {
+                let mc = MC.min(m - ic);
+                pack_a(a, a_off + ic * a_rs + pc * a_cs, a_rs, a_cs, mc, kc, mr, &mut a_pack, &mut line);
+                for jr in (0..nc).step_by(nr) {
+                    let cols = nr.min(nc - jr);
+                    let b_panel = &b_pack[jr * kc..(jr + nr) * kc];
+                    for ir in (0..mc).step_by(mr) {
+                        let rows = mr.min(mc - ir);
+                        let a_panel = &a_pack[ir * kc..(ir + mr) * kc];
+                        let c_start = (ic + ir) * n + jc + jr;
+                        if rows == mr && cols == nr {
+                            kernel.run(kc, a_panel, b_panel, &mut out[c_start..], n);
+                        } else {
+                            let tile = &mut tile[..mr * nr];
+                            tile.fill(0.0);
+                            kernel.run(kc, a_panel, b_panel, tile, nr);
+                            for (i, src) in tile.chunks(nr).take(rows).enumerate() {
+                                let dst = &mut out[c_start + i * n..c_start + i * n + cols];
+                                for (o, &x) in dst.iter_mut().zip(src) {
+                                    *o += x;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn zeroed(len: usize) -> TensorResult<Vec<f32>> {
+    let mut buffer = try_buffer(len)?;
+    buffer.resize(len, 0.0);
+    Ok(buffer)
+}
+
+/// Empaqueta `kc x nc` elementos de `b` en tiras de `nr` columnas, cada una
+/// con sus `kc` filas consecutivas; las columnas que faltan quedan a cero.
+#[allow(clippy::too_many_arguments)]
+fn pack_b(
+    b: &Storage, start: usize, rs: usize, cs: usize,
+    kc: usize, nc: usize, nr: usize,
+    packed: &mut [f32], line: &mut [f32],
+) {
+    let line = &mut line[..nc];
+    for p in 0..kc {
+        b.upcast_into(start + p * rs, cs, line);
+        for (s, src) in line.chunks(nr).enumerate() {
+            let dst = &mut packed[(s * kc + p) * nr..(s * kc + p + 1) * nr];
+            dst[..src.len()].copy_from_slice(src);
+            dst[src.len()..].fill(0.0);
+        }
+    }
+}
+
+/// Empaqueta `mc x kc` elementos de `a` en tiras de `mr` filas, cada una
+/// con sus `kc` columnas consecutivas; las filas que faltan quedan a cero.
+#[allow(clippy::too_many_arguments)]
+fn pack_a(
+    a: &Storage, start: usize, rs: usize, cs: usize,
+    mc: usize, kc: usize, mr: usize,
+    packed: &mut [f32], line: &mut [f32],
+) {
+    let line = &mut line[..kc];
+    for i in 0..mc.next_multiple_of(mr) {
+        if i < mc {
+            a.upcast_into(start + i * rs, cs, line);
+        } else {
+            line.fill(0.0);
+        }
+        let panel = &mut packed[(i / mr) * mr * kc..(i / mr + 1) * mr * kc];
+        for (p, &x) in line.iter().enumerate() {
+            panel[p * mr + i % mr] = x;
+        }
+    }
+}
+
+fn kernel_scalar_4x4(kc: usize, a: &[f32], b: &[f32], c: &mut [f32], ldc: usize) {
+    let mut acc = [[0.0f32; 4]; 4];
+    for (a, b) in a.chunks_exact(4).zip(b.chunks_exact(4)).take(kc) {
+        for (row, &a_i) in acc.iter_mut().zip(a) {
+            for (slot, &b_j) in row.iter_mut().zip(b) {
+                *slot += a_i * b_j;
+            }
+        }
+    }
+    for (i, row) in acc.iter().enumerate() {
+        for (o, &x) in c[i * ldc..i * ldc + 4].iter_mut().zip(row) {
+            *o += x;
+        }
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", feature = "simd"))]
+mod simd {
+    use core::arch::x86_64::*;
+
+    /// Microkernel SSE2: cada fila del bloque ocupa dos registros XMM.
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn kernel_sse2_4x8(kc: usize, a: *const f32, b: *const f32, c: *mut f32, ldc: usize) {
+        let mut acc = [_mm_setzero_ps(); 8];
+        for p in 0..kc {
+            let b0 = _mm_loadu_ps(b.add(p * 8));
+            let b1 = _mm_loadu_ps(b.add(p * 8 + 4));
+            for i in 0..4 {
+                let a_i = _mm_set1_ps(*a.add(p * 4 + i));
+                acc[2 * i] = _mm_add_ps(acc[2 * i], _mm_mul_ps(a_i, b0));
+                acc[2 * i + 1] = _mm_add_ps(acc[2 * i + 1], _mm_mul_ps(a_i, b1));
+            }
+        }
+        for i in 0..4 {
+            let row = c.add(i * ldc);
+            _mm_storeu_ps(row, _mm_add_ps(_mm_loadu_ps(row), acc[2 * i]));
+            _mm_storeu_ps(row.add(4), _mm_add_ps(_mm_loadu_ps(row.add(4)), acc[2 * i + 1]));
+        }
+    }
+
+    /// Microkernel AVX2/FMA: 12 acumuladores YMM, dos cargas de `b` y un
+    /// broadcast de `a` ocupan 15 de los 16 registros.
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn kernel_avx2_6x16(kc: usize, a: *const f32, b: *const f32, c: *mut f32, ldc: usize) {
+        let mut acc = [_mm256_setzero_ps(); 12];
+        for p in 0..kc {
+            let b0 = _mm256_loadu_ps(b.add(p * 16));
+            let b1 = _mm256_loadu_ps(b.add(p * 16 + 8));
+            for i in 0..6 {
+                let a_i = _mm256_broadcast_ss(&*a.add(p * 6 + i));
+                acc[2 * i] = _mm256_fmadd_ps(a_i, b0, acc[2 * i]);
+                acc[2 * i + 1] = _mm256_fmadd_ps(a_i, b1, acc[2 * i + 1]);
+            }
+        }
+        for i in 0..6 {
+            let row = c.add(i * ldc);
+            _mm256_storeu_ps(row, _mm256_add_ps(_mm256_loadu_ps(row), acc[2 * i]));
+            _mm256_storeu_ps(row.add(8), _mm256_add_ps(_mm256_loadu_ps(row.add(8)), acc[2 * i + 1]));
+        }
+    }
+}
+
+/// Resultado de `benchmark` para un microkernel.
+#[derive(Debug, Clone, Copy)]
+pub struct GemmBenchmark {
+    pub kernel: GemmKernel,
+    /// Lado de las matrices cuadradas multiplicadas.
+    pub size: usize,
+    /// Ciclos del TSC de la mejor repetición.
+    pub cycles: u64,
+    /// Nanosegundos de la mejor repetición (0 si el TSC no está calibrado).
+    pub nanos: u64,
+}
+
+impl GemmBenchmark {
+    /// Operaciones de coma flotante por nanosegundo (= GFLOPS).
+    pub fn gflops(&self) -> f64 {
+        if self.nanos == 0 {
+            return 0.0;
+        }
+        let flops = 2.0 * (self.size as f64) * (self.size as f64) * (self.size as f64);
+        flops / self.nanos as f64
+    }
+}
+
+/// Repeticiones medidas en `benchmark`; se queda la más rápida.
+const BENCHMARK_REPEATS: usize = 3;
+
+/// Mide un producto `size x size x size` con el microkernel indicado.
+pub fn benchmark(kernel: GemmKernel, size: usize) -> TensorResult<GemmBenchmark> {
+    let values = |seed: u32| -> TensorResult<Tensor> {
+        let len = size * size;
+        let mut data = try_buffer(len)?;
+        data.extend((0..len as u32).map(|i| {
+            let x = i.wrapping_mul(2_654_435_761).wrapping_add(seed) >> 16;
+            (x % 2000) as f32 / 1000.0 - 1.0
+        }));
+        Tensor::from_vec(data, &[size, size])
+    };
+    let a = values(1)?;
+    let b = values(7)?;
+
+    // La primera ejecución calienta las cachés y reserva el heap
+    a.matmul_with(&b, kernel)?;
+    let mut best = u64::MAX;
+    for _ in 0..BENCHMARK_REPEATS {
+        let start = crate::tsc::read();
+        a.matmul_with(&b, kernel)?;
+        best = best.min(crate::tsc::read() - start);
+    }
+    Ok(GemmBenchmark {
+        kernel,
+        size,
+        cycles: best,
+        nanos: crate::tsc::cycles_to_nanos(best),
+    })
+}
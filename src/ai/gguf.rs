@@ -0,0 +1,493 @@
+use super::format::{format_error, ByteReader};
+use super::quant::{block_matmul, dequantize_blocks, BlockFormat, BlockQuantizedTensor, BLOCK_LEN};
+use super::tensor::{try_buffer, Tensor, TensorError};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use half::f16;
+
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+
+/// Alineación de los datos si los metadatos no indican `general.alignment`.
+const DEFAULT_ALIGNMENT: usize = 32;
+
+/// Máximo de dimensiones de un tensor de ggml.
+const MAX_DIMS: usize = 4;
+
+/// Profundidad máxima de arrays anidados en los metadatos.
+const MAX_ARRAY_DEPTH: usize = 8;
+
+/// Códigos de tipo de los valores de los metadatos.
+mod value_type {
+    pub const U8: u32 = 0;
+    pub const I8: u32 = 1;
+    pub const U16: u32 = 2;
+    pub const I16: u32 = 3;
+    pub const U32: u32 = 4;
+    pub const I32: u32 = 5;
+    pub const F32: u32 = 6;
+    pub const BOOL: u32 = 7;
+    pub const STRING: u32 = 8;
+    pub const ARRAY: u32 = 9;
+    pub const U64: u32 = 10;
+    pub const I64: u32 = 11;
+    pub const F64: u32 = 12;
+}
+
+/// Errores al leer un fichero GGUF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GgufError {
+    /// El fichero no empieza por `GGUF`.
+    BadMagic,
+    /// Solo se admiten las versiones 2 y 3 (contadores de 64 bits).
+    UnsupportedVersion(u32),
+    /// El fichero es más corto que lo que anuncian sus cabeceras.
+    Truncated { expected: usize, found: usize },
+    /// Las cabeceras tienen un valor imposible.
+    Invalid(&'static str),
+    /// Tensor de un tipo de ggml que no se sabe leer.
+    UnsupportedTensorType { name: String, ggml_type: u32 },
+    Tensor(TensorError),
+}
+
+format_error!(GgufError {
+    GgufError::BadMagic => "El fichero no es un GGUF",
+    GgufError::UnsupportedVersion(_) => "Versión de GGUF no soportada",
+    GgufError::Truncated { .. } => "Fichero GGUF truncado",
+    GgufError::Invalid(reason) => reason,
+    GgufError::UnsupportedTensorType { .. } => "Tipo de tensor de GGUF no soportado",
+});
+
+impl GgufError {
+    fn write_details(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GgufError::UnsupportedVersion(version) => write!(f, ": {}", version),
+            GgufError::Truncated { expected, found } => {
+                write!(f, ": se esperaban {} bytes, hay {}", expected, found)
+            }
+            GgufError::UnsupportedTensorType { name, ggml_type } => write!(f, " {}: {}", name, ggml_type),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Tipo de elemento de un tensor de ggml. Los que no se saben leer se
+/// conservan con su código en `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GgmlType {
+    F32,
+    F16,
+    Q4_0,
+    Q8_0,
+    Other(u32),
+}
+
+impl GgmlType {
+    fn from_code(code: u32) -> GgmlType {
+        match code {
+            0 => GgmlType::F32,
+            1 => GgmlType::F16,
+            2 => GgmlType::Q4_0,
+            8 => GgmlType::Q8_0,
+            other => GgmlType::Other(other),
+        }
+    }
+
+    pub fn code(self) -> u32 {
+        match self {
+            GgmlType::F32 => 0,
+            GgmlType::F16 => 1,
+            GgmlType::Q4_0 => 2,
+            GgmlType::Q8_0 => 8,
+            GgmlType::Other(code) => code,
+        }
+    }
+
+    /// Formato por bloques equivalente, si lo es.
+    pub fn block_format(self) -> Option<BlockFormat> {
+        match self {
+            GgmlType::Q4_0 => Some(BlockFormat::Q4_0),
+            GgmlType::Q8_0 => Some(BlockFormat::Q8_0),
+            _ => None,
+        }
+    }
+}
+
+/// Valor de los metadatos. Las cadenas y los arrays apuntan al fichero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GgufValue<'a> {
+    U8(u8),
+    I8(i8),
+    U16(u16),
+    I16(i16),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+    Bool(bool),
+    String(&'a str),
+    Array(GgufArray<'a>),
+    U64(u64),
+    I64(i64),
+    F64(f64),
+}
+
+impl<'a> GgufValue<'a> {
+    pub fn as_str(&self) -> Option<&'a str> {
+        match *self {
+            GgufValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Cualquier entero no negativo, sea cual sea su tipo en el fichero.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            GgufValue::U8(value) => Some(value as u64),
+            GgufValue::U16(value) => Some(value as u64),
+            GgufValue::U32(value) => Some(value as u64),
+            GgufValue::U64(value) => Some(value),
+            GgufValue::I8(value) => u64::try_from(value).ok(),
+            GgufValue::I16(value) => u64::try_from(value).ok(),
+            GgufValue::I32(value) => u64::try_from(value).ok(),
+            GgufValue::I64(value) => u64::try_from(value).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match *self {
+            GgufValue::F32(value) => Some(value),
+            GgufValue::F64(value) => Some(value as f32),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&GgufArray<'a>> {
+        match self {
+            GgufValue::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+}
+
+/// Array de los metadatos (p. ej. el vocabulario del tokenizador), que se
+/// decodifica elemento a elemento al recorrerlo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GgufArray<'a> {
+    element_type: u32,
+    len: usize,
+    data: &'a [u8],
+}
+
+impl<'a> GgufArray<'a> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = GgufValue<'a>> + 'a {
+        let mut reader = Reader::open(self.data);
+        let element_type = self.element_type;
+        // Los elementos ya se validaron al leer el fichero
+        (0..self.len).map_while(move |_| reader.value(element_type, 1).ok())
+    }
+
+    pub fn get(&self, index: usize) -> Option<GgufValue<'a>> {
+        self.iter().nth(index)
+    }
+}
+
+/// Lector de los valores little-endian de las cabeceras.
+type Reader<'a> = ByteReader<'a, GgufError>;
+
+impl<'a> Reader<'a> {
+    fn open(bytes: &'a [u8]) -> Self {
+        ByteReader::new(bytes, |expected, found| GgufError::Truncated { expected, found })
+    }
+
+    /// Un `u64` del fichero como tamaño o posición.
+    fn usize(&mut self) -> Result<usize, GgufError> {
+        usize::try_from(self.u64()?).map_err(|_| GgufError::Invalid("Tamaño de GGUF fuera de rango"))
+    }
+
+    /// Cadena: longitud `u64` y bytes UTF-8 sin terminador.
+    fn string(&mut self) -> Result<&'a str, GgufError> {
+        let len = self.usize()?;
+        core::str::from_utf8(self.take(len)?).map_err(|_| GgufError::Invalid("Cadena de GGUF que no es UTF-8"))
+    }
+
+    fn value(&mut self, value_type: u32, depth: usize) -> Result<GgufValue<'a>, GgufError> {
+        Ok(match value_type {
+            value_type::U8 => GgufValue::U8(self.u8()?),
+            value_type::I8 => GgufValue::I8(self.u8()? as i8),
+            value_type::U16 => GgufValue::U16(self.u16()?),
+            value_type::I16 => GgufValue::I16(i16::from_le_bytes(self.array()?)),
+            value_type::U32 => GgufValue::U32(self.u32()?),
+            value_type::I32 => GgufValue::I32(self.i32()?),
+            value_type::F32 => GgufValue::F32(self.f32()?),
+            value_type::BOOL => match self.u8()? {
+                0 => GgufValue::Bool(false),
+                1 => GgufValue::Bool(true),
+                _ => return Err(GgufError::Invalid("Booleano de GGUF distinto de 0 y 1")),
+            },
+            value_type::STRING => GgufValue::String(self.string()?),
+            value_type::ARRAY => {
+                if depth >= MAX_ARRAY_DEPTH {
+                    return Err(GgufError::Invalid("Demasiados arrays anidados en los metadatos"));
+                }
+                let element_type = self.u32()?;
+                let len = self.usize()?;
+                let start = self.position();
+                // Se recorre para validarlo y encontrar su final; cada
+                // elemento ocupa al menos un byte, así que una longitud
+                // falsa acaba enseguida en `Truncated`
+                for _ in 0..len {
+                    self.value(element_type, depth + 1)?;
+                }
+                GgufValue::Array(GgufArray { element_type, len, data: self.since(start) })
+            }
+            value_type::U64 => GgufValue::U64(self.u64()?),
+            value_type::I64 => GgufValue::I64(i64::from_le_bytes(self.array()?)),
+            value_type::F64 => GgufValue::F64(self.f64()?),
+            _ => return Err(GgufError::Invalid("Tipo de metadato de GGUF desconocido")),
+        })
+    }
+}
+
+/// Tensor de un fichero GGUF, sin copiar sus datos.
+#[derive(Debug, Clone)]
+pub struct GgufTensor<'a> {
+    name: &'a str,
+    shape: Vec<usize>,
+    ggml_type: GgmlType,
+    data: &'a [u8],
+}
+
+impl<'a> GgufTensor<'a> {
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Forma en orden de filas, es decir, las dimensiones del fichero al
+    /// revés (ggml guarda primero la contigua): una matriz de pesos
+    /// `ne = [entrada, salida]` es `[salida, entrada]`.
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn ggml_type(&self) -> GgmlType {
+        self.ggml_type
+    }
+
+    /// Bytes del tensor dentro del fichero; vacío si su tipo no se sabe leer
+    /// y por tanto se desconoce su tamaño.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copia el tensor a un `Tensor`: `F32` y `F16` conservan su tipo y los
+    /// cuantizados se decuantizan enteros a `f32`. Para los pesos grandes es
+    /// mejor `matmul` o `to_block_tensor`, que no los expanden.
+    pub fn to_tensor(&self) -> Result<Tensor, GgufError> {
+        let len = self.len();
+        Ok(match self.ggml_type {
+            GgmlType::F32 => {
+                let mut values = try_buffer(len)?;
+                values.extend(self.data.chunks_exact(4).map(|bytes| {
+                    f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+                }));
+                Tensor::from_vec(values, &self.shape)?
+            }
+            GgmlType::F16 => {
+                let mut values = try_buffer(len)?;
+                values.extend(self.data.chunks_exact(2).map(|bytes| f16::from_le_bytes([bytes[0], bytes[1]])));
+                Tensor::from_f16(values, &self.shape)?
+            }
+            ggml_type => {
+                let format = self.block_format(ggml_type)?;
+                Tensor::from_vec(dequantize_blocks(self.data, format)?, &self.shape)?
+            }
+        })
+    }
+
+    /// Copia los bloques de un tensor `Q8_0` o `Q4_0` sin decuantizarlos,
+    /// p. ej. para construir un `BlockLinear`.
+    pub fn to_block_tensor(&self) -> Result<BlockQuantizedTensor, GgufError> {
+        let format = self.block_format(self.ggml_type)?;
+        let mut data = try_buffer(self.data.len())?;
+        data.extend_from_slice(self.data);
+        Ok(BlockQuantizedTensor::from_blocks(data, &self.shape, format)?)
+    }
+
+    /// Como `BlockQuantizedTensor::matmul`, leyendo los bloques directamente
+    /// del fichero: `input [..., k]` por este tensor `[n, k]` traspuesto.
+    pub fn matmul(&self, input: &Tensor) -> Result<Tensor, GgufError> {
+        let format = self.block_format(self.ggml_type)?;
+        Ok(block_matmul(input, self.data, &self.shape, format)?)
+    }
+
+    fn block_format(&self, ggml_type: GgmlType) -> Result<BlockFormat, GgufError> {
+        ggml_type.block_format().ok_or_else(|| GgufError::UnsupportedTensorType {
+            name: self.name.to_string(),
+            ggml_type: ggml_type.code(),
+        })
+    }
+}
+
+/// Bytes de un tensor de `ggml_type` y forma `shape`, si se sabe calcular.
+fn tensor_data_len(shape: &[usize], ggml_type: GgmlType) -> Result<Option<usize>, GgufError> {
+    let too_large = GgufError::Invalid("Tensor de GGUF demasiado grande");
+    let len = shape.iter().try_fold(1usize, |len, &dim| len.checked_mul(dim)).ok_or(too_large.clone())?;
+    let bytes = match (ggml_type, ggml_type.block_format()) {
+        (_, Some(format)) => {
+            // Como en ggml, los bloques no cruzan filas
+            if !shape.last().is_some_and(|&inner| inner.is_multiple_of(BLOCK_LEN)) {
+                return Err(GgufError::Invalid("Las filas del tensor de GGUF no son múltiplo del bloque"));
+            }
+            (len / BLOCK_LEN).checked_mul(format.block_bytes())
+        }
+        (GgmlType::F32, None) => len.checked_mul(4),
+        (GgmlType::F16, None) => len.checked_mul(2),
+        _ => return Ok(None),
+    };
+    bytes.map(Some).ok_or(too_large)
+}
+
+/// Fichero GGUF (el formato de llama.cpp): cabecera, metadatos clave-valor,
+/// descripción de cada tensor y, alineados, los datos:
+///
+/// ```text
+/// "GGUF" | versión u32 | nº de tensores u64 | nº de metadatos u64
+///        | (clave, tipo u32, valor)... | (nombre, nº de dims u32, dims u64...,
+///          tipo de ggml u32, posición u64)... | relleno | datos
+/// ```
+///
+/// Los tensores y los valores son vistas sobre `bytes`: leer el fichero no
+/// copia los pesos.
+pub struct GgufFile<'a> {
+    version: u32,
+    alignment: usize,
+    metadata: Vec<(&'a str, GgufValue<'a>)>,
+    tensors: Vec<GgufTensor<'a>>,
+}
+
+impl<'a> GgufFile<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, GgufError> {
+        let mut reader = Reader::open(bytes);
+        if reader.take(4).ok() != Some(&GGUF_MAGIC[..]) {
+            return Err(GgufError::BadMagic);
+        }
+        let version = reader.u32()?;
+        if !(2..=3).contains(&version) {
+            return Err(GgufError::UnsupportedVersion(version));
+        }
+        let tensor_count = reader.usize()?;
+        let metadata_count = reader.usize()?;
+
+        // Sin reservar por adelantado: los contadores aún no son de fiar
+        let mut metadata = Vec::new();
+        for _ in 0..metadata_count {
+            let key = reader.string()?;
+            let value_type = reader.u32()?;
+            metadata.push((key, reader.value(value_type, 0)?));
+        }
+        let alignment = match metadata.iter().find(|(key, _)| *key == "general.alignment") {
+            Some((_, value)) => match value.as_u64().and_then(|value| usize::try_from(value).ok()) {
+                Some(alignment) if alignment.is_power_of_two() => alignment,
+                _ => return Err(GgufError::Invalid("general.alignment no es una potencia de dos")),
+            },
+            None => DEFAULT_ALIGNMENT,
+        };
+
+        let mut infos = Vec::new();
+        for _ in 0..tensor_count {
+            let name = reader.string()?;
+            let dims = reader.u32()? as usize;
+            if dims > MAX_DIMS {
+                return Err(GgufError::Invalid("Tensor de GGUF con más de 4 dimensiones"));
+            }
+            let mut shape = Vec::with_capacity(dims);
+            for _ in 0..dims {
+                shape.push(reader.usize()?);
+            }
+            shape.reverse();
+            let ggml_type = GgmlType::from_code(reader.u32()?);
+            let offset = reader.usize()?;
+            infos.push((name, shape, ggml_type, offset));
+        }
+
+        let data_start = reader.position().div_ceil(alignment).saturating_mul(alignment);
+        let mut tensors = Vec::with_capacity(infos.len());
+        for (name, shape, ggml_type, offset) in infos {
+            if !offset.is_multiple_of(alignment) {
+                return Err(GgufError::Invalid("Tensor de GGUF sin alinear"));
+            }
+            let data = match tensor_data_len(&shape, ggml_type)? {
+                Some(len) => {
+                    let start = data_start.saturating_add(offset);
+                    let end = start.saturating_add(len);
+                    match bytes.get(start..end) {
+                        Some(data) => data,
+                        None => return Err(GgufError::Truncated { expected: end, found: bytes.len() }),
+                    }
+                }
+                None => &[],
+            };
+            tensors.push(GgufTensor { name, shape, ggml_type, data });
+        }
+        Ok(GgufFile { version, alignment, metadata, tensors })
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Alineación de los datos de los tensores, en bytes.
+    pub fn alignment(&self) -> usize {
+        self.alignment
+    }
+
+    /// Pares clave-valor en el orden del fichero.
+    pub fn metadata(&self) -> &[(&'a str, GgufValue<'a>)] {
+        &self.metadata
+    }
+
+    /// Valor de los metadatos con clave `key`, p. ej. `llama.block_count`.
+    pub fn value(&self, key: &str) -> Option<&GgufValue<'a>> {
+        self.metadata.iter().find(|(name, _)| *name == key).map(|(_, value)| value)
+    }
+
+    /// `general.architecture` (`llama`, `gpt2`...), que prefija las claves
+    /// de los hiperparámetros.
+    pub fn architecture(&self) -> Option<&'a str> {
+        self.value("general.architecture").and_then(|value| value.as_str())
+    }
+
+    /// Tensores en el orden del fichero.
+    pub fn tensors(&self) -> &[GgufTensor<'a>] {
+        &self.tensors
+    }
+
+    pub fn get(&self, name: &str) -> Option<&GgufTensor<'a>> {
+        self.tensors.iter().find(|tensor| tensor.name == name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tensors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tensors.is_empty()
+    }
+}
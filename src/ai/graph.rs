@@ -0,0 +1,497 @@
+use super::autograd::Var;
+use super::dtype::DType;
+use super::module::{prefixed, set_module_buffers, Module};
+use super::quant::QuantScheme;
+use super::tensor::{broadcast_shapes, normalize_axis, Tensor, TensorError, TensorResult};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Operación de un nodo de `GraphModel`.
+pub enum GraphOp {
+    /// Entrada del modelo; recibe el tensor de `forward_many` en el orden
+    /// en que se añadieron las entradas.
+    Input,
+    /// Un módulo aplicado a su única entrada.
+    Module(Box<dyn Module>),
+    /// Suma elemento a elemento (con difusión) de todas sus entradas, p. ej.
+    /// para conexiones residuales.
+    Add,
+    /// Concatenación de sus entradas a lo largo de `axis`.
+    Concat { axis: isize },
+}
+
+impl GraphOp {
+    /// Número de entradas admitido, `(mínimo, máximo)`.
+    fn arity(&self) -> (usize, usize) {
+        match self {
+            GraphOp::Input => (0, 0),
+            GraphOp::Module(_) => (1, 1),
+            GraphOp::Add => (2, usize::MAX),
+            GraphOp::Concat { .. } => (1, usize::MAX),
+        }
+    }
+}
+
+struct Node {
+    name: String,
+    op: GraphOp,
+    // Las aristas se nombran por el nodo de origen y se resuelven en `build`,
+    // así que pueden referirse a nodos que se añaden después
+    input_names: Vec<String>,
+    inputs: Vec<usize>,
+}
+
+/// Estado de un nodo durante el recorrido en profundidad.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Unvisited,
+    InProgress,
+    Done,
+}
+
+/// Modelo como grafo dirigido acíclico de nodos con nombre: admite
+/// conexiones residuales, uniones por concatenación y varias entradas y
+/// salidas.
+///
+/// Tras añadir los nodos y elegir las salidas, `build` resuelve las aristas
+/// y calcula el orden topológico; solo se ejecutan los nodos de los que
+/// depende alguna salida.
+pub struct GraphModel {
+    name: String,
+    nodes: Vec<Node>,
+    by_name: BTreeMap<String, usize>,
+    inputs: Vec<usize>,
+    output_names: Vec<String>,
+    outputs: Vec<usize>,
+    // Vacío mientras el grafo no esté construido
+    order: Vec<usize>,
+    // Cuántas veces se lee el valor de cada nodo en `forward_many`, para
+    // liberar los intermedios en cuanto dejan de hacer falta
+    uses: Vec<usize>,
+    training: bool,
+}
+
+impl GraphModel {
+    pub fn new(name: &str) -> Self {
+        GraphModel {
+            name: String::from(name),
+            nodes: Vec::new(),
+            by_name: BTreeMap::new(),
+            inputs: Vec::new(),
+            output_names: Vec::new(),
+            outputs: Vec::new(),
+            order: Vec::new(),
+            uses: Vec::new(),
+            training: false,
+        }
+    }
+
+    /// Añade un nodo que lee las salidas de los nodos `inputs`.
+    pub fn add_node(&mut self, name: &str, mut op: GraphOp, inputs: &[&str]) -> TensorResult<()> {
+        if self.by_name.contains_key(name) {
+            return Err(TensorError::InvalidArgument("Nombre de nodo repetido en el grafo"));
+        }
+        let (min, max) = op.arity();
+        if inputs.len() < min || inputs.len() > max {
+            return Err(TensorError::InvalidArgument("Número de entradas no válido para el nodo"));
+        }
+        if let GraphOp::Module(module) = &mut op {
+            module.set_training(self.training);
+        }
+        let index = self.nodes.len();
+        if let GraphOp::Input = op {
+            self.inputs.push(index);
+        }
+        self.by_name.insert(String::from(name), index);
+        self.nodes.push(Node {
+            name: String::from(name),
+            op,
+            input_names: inputs.iter().map(|&input| String::from(input)).collect(),
+            inputs: Vec::new(),
+        });
+        self.order.clear();
+        Ok(())
+    }
+
+    pub fn add_input(&mut self, name: &str) -> TensorResult<()> {
+        self.add_node(name, GraphOp::Input, &[])
+    }
+
+    pub fn add_module(&mut self, name: &str, module: impl Module, input: &str) -> TensorResult<()> {
+        self.add_node(name, GraphOp::Module(Box::new(module)), &[input])
+    }
+
+    pub fn add_sum(&mut self, name: &str, inputs: &[&str]) -> TensorResult<()> {
+        self.add_node(name, GraphOp::Add, inputs)
+    }
+
+    pub fn add_concat(&mut self, name: &str, inputs: &[&str], axis: isize) -> TensorResult<()> {
+        self.add_node(name, GraphOp::Concat { axis }, inputs)
+    }
+
+    /// Nodos cuyos valores devuelve `forward_many`, en este orden.
+    pub fn set_outputs(&mut self, outputs: &[&str]) {
+        self.output_names = outputs.iter().map(|&output| String::from(output)).collect();
+        self.order.clear();
+    }
+
+    /// Resuelve las aristas por nombre y calcula el orden de ejecución.
+    ///
+    /// Falla si una arista apunta a un nodo inexistente, si no hay salidas
+    /// o si el grafo tiene un ciclo.
+    pub fn build(&mut self) -> TensorResult<()> {
+        self.order.clear();
+        let resolve = |name: &String| {
+            self.by_name
+                .get(name)
+                .copied()
+                .ok_or(TensorError::InvalidArgument("Arista hacia un nodo inexistente"))
+        };
+        let edges = self.nodes
+            .iter()
+            .map(|node| node.input_names.iter().map(resolve).collect::<TensorResult<Vec<_>>>())
+            .collect::<TensorResult<Vec<_>>>()?;
+        let outputs = self.output_names.iter().map(resolve).collect::<TensorResult<Vec<_>>>()?;
+        if outputs.is_empty() {
+            return Err(TensorError::InvalidArgument("El grafo no tiene salidas"));
+        }
+        for (node, inputs) in self.nodes.iter_mut().zip(edges) {
+            node.inputs = inputs;
+        }
+        self.outputs = outputs;
+
+        let order = self.topological_sort()?;
+        let mut uses = vec![0; self.nodes.len()];
+        for &index in &order {
+            for &input in &self.nodes[index].inputs {
+                uses[input] += 1;
+            }
+        }
+        for &output in &self.outputs {
+            uses[output] += 1;
+        }
+        self.uses = uses;
+        self.order = order;
+        Ok(())
+    }
+
+    pub fn is_built(&self) -> bool {
+        !self.order.is_empty()
+    }
+
+    pub fn input_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    pub fn output_count(&self) -> usize {
+        self.output_names.len()
+    }
+
+    /// Orden topológico de los nodos de los que dependen las salidas: cada
+    /// nodo aparece después de todas sus entradas.
+    fn topological_sort(&self) -> TensorResult<Vec<usize>> {
+        let mut result = Vec::new();
+        let mut marks = vec![Mark::Unvisited; self.nodes.len()];
+
+        for &output in &self.outputs {
+            self.dfs(output, &mut marks, &mut result)?;
+        }
+
+        Ok(result)
+    }
+
+    fn dfs(&self, node: usize, marks: &mut [Mark], result: &mut Vec<usize>) -> TensorResult<()> {
+        match marks[node] {
+            Mark::Done => return Ok(()),
+            // Volver a un nodo cuya visita no ha terminado es cerrar un ciclo
+            Mark::InProgress => return Err(TensorError::InvalidArgument("El grafo tiene un ciclo")),
+            Mark::Unvisited => {}
+        }
+        marks[node] = Mark::InProgress;
+
+        for &input in &self.nodes[node].inputs {
+            self.dfs(input, marks, result)?;
+        }
+
+        marks[node] = Mark::Done;
+        result.push(node);
+        Ok(())
+    }
+
+    fn check_built(&self) -> TensorResult<()> {
+        if !self.is_built() {
+            return Err(TensorError::InvalidArgument("El grafo no está construido; falta llamar a `build`"));
+        }
+        Ok(())
+    }
+
+    /// Ejecuta el grafo con un tensor por cada entrada (en el orden en que
+    /// se añadieron) y devuelve las salidas en el orden de `set_outputs`.
+    pub fn forward_many(&self, inputs: Vec<Tensor>) -> TensorResult<Vec<Tensor>> {
+        self.check_built()?;
+        if inputs.len() != self.inputs.len() {
+            return Err(TensorError::LengthMismatch { expected: self.inputs.len(), found: inputs.len() });
+        }
+        let mut values: Vec<Option<Tensor>> = vec![None; self.nodes.len()];
+        for (&index, input) in self.inputs.iter().zip(inputs) {
+            values[index] = Some(input);
+        }
+        let mut uses = self.uses.clone();
+
+        for &index in &self.order {
+            let node = &self.nodes[index];
+            let value = match &node.op {
+                GraphOp::Input => continue,
+                GraphOp::Module(module) => module.forward(take(&mut values, &mut uses, node.inputs[0]))?,
+                GraphOp::Add => {
+                    let mut sum = take(&mut values, &mut uses, node.inputs[0]);
+                    for &input in &node.inputs[1..] {
+                        sum = sum.add(&take(&mut values, &mut uses, input))?;
+                    }
+                    sum
+                }
+                GraphOp::Concat { axis } => {
+                    let parts: Vec<Tensor> = node.inputs
+                        .iter()
+                        .map(|&input| take(&mut values, &mut uses, input))
+                        .collect();
+                    let parts: Vec<&Tensor> = parts.iter().collect();
+                    Tensor::concat(&parts, *axis)?
+                }
+            };
+            values[index] = Some(value);
+        }
+
+        Ok(self.outputs.iter().map(|&output| take(&mut values, &mut uses, output)).collect())
+    }
+
+    /// Como `forward_many`, con las entradas y los parámetros (en el orden
+    /// de `parameters`) en la misma cinta.
+    pub fn forward_many_tape<'t>(&self, inputs: Vec<Var<'t>>, parameters: &[Var<'t>]) -> TensorResult<Vec<Var<'t>>> {
+        self.check_built()?;
+        if inputs.len() != self.inputs.len() {
+            return Err(TensorError::LengthMismatch { expected: self.inputs.len(), found: inputs.len() });
+        }
+        // Primer parámetro de cada nodo: los módulos los aportan en el orden
+        // en que se añadieron, no en el de ejecución
+        let mut first = vec![0; self.nodes.len() + 1];
+        for (index, node) in self.nodes.iter().enumerate() {
+            let count = match &node.op {
+                GraphOp::Module(module) => module.parameters().len(),
+                _ => 0,
+            };
+            first[index + 1] = first[index] + count;
+        }
+        if first[self.nodes.len()] != parameters.len() {
+            return Err(TensorError::LengthMismatch { expected: first[self.nodes.len()], found: parameters.len() });
+        }
+
+        let mut values: Vec<Option<Var<'t>>> = vec![None; self.nodes.len()];
+        for (&index, input) in self.inputs.iter().zip(inputs) {
+            values[index] = Some(input);
+        }
+        let value = |values: &[Option<Var<'t>>], index: usize| {
+            values[index].expect("el orden topológico calcula cada nodo antes que sus consumidores")
+        };
+
+        for &index in &self.order {
+            let node = &self.nodes[index];
+            let output = match &node.op {
+                GraphOp::Input => continue,
+                GraphOp::Module(module) => {
+                    let parameters = &parameters[first[index]..first[index + 1]];
+                    module.forward_tape(value(&values, node.inputs[0]), parameters)?
+                }
+                GraphOp::Add => {
+                    let mut sum = value(&values, node.inputs[0]);
+                    for &input in &node.inputs[1..] {
+                        sum = sum.add(&value(&values, input))?;
+                    }
+                    sum
+                }
+                GraphOp::Concat { axis } => {
+                    let parts: Vec<Var<'t>> = node.inputs.iter().map(|&input| value(&values, input)).collect();
+                    Var::concat(&parts, *axis)?
+                }
+            };
+            values[index] = Some(output);
+        }
+
+        Ok(self.outputs.iter().map(|&output| value(&values, output)).collect())
+    }
+
+    /// Formas de las salidas para entradas de formas `inputs`, sin ejecutar
+    /// el grafo.
+    pub fn output_shapes(&self, inputs: &[&[usize]]) -> TensorResult<Vec<Vec<usize>>> {
+        self.check_built()?;
+        if inputs.len() != self.inputs.len() {
+            return Err(TensorError::LengthMismatch { expected: self.inputs.len(), found: inputs.len() });
+        }
+        let mut shapes: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        for (&index, input) in self.inputs.iter().zip(inputs) {
+            shapes[index] = input.to_vec();
+        }
+
+        for &index in &self.order {
+            let node = &self.nodes[index];
+            let shape = match &node.op {
+                GraphOp::Input => continue,
+                GraphOp::Module(module) => module.output_shape(&shapes[node.inputs[0]])?,
+                GraphOp::Add => node.inputs[1..].iter().try_fold(shapes[node.inputs[0]].clone(), |acc, &input| {
+                    broadcast_shapes(&acc, &shapes[input])
+                })?,
+                GraphOp::Concat { axis } => {
+                    let first = &shapes[node.inputs[0]];
+                    let axis = normalize_axis(*axis, first.len())?;
+                    let mut shape = first.clone();
+                    for &input in &node.inputs[1..] {
+                        let other = &shapes[input];
+                        let compatible = other.len() == first.len()
+                            && other.iter().zip(first).enumerate().all(|(i, (a, b))| i == axis || a == b);
+                        if !compatible {
+                            return Err(TensorError::ShapeMismatch { lhs: first.clone(), rhs: other.clone() });
+                        }
+                        shape[axis] += other[axis];
+                    }
+                    shape
+                }
+            };
+            shapes[index] = shape;
+        }
+
+        Ok(self.outputs.iter().map(|&output| shapes[output].clone()).collect())
+    }
+
+    /// Nombres de los nodos en orden de ejecución.
+    pub fn execution_order(&self) -> Vec<&str> {
+        self.order.iter().map(|&index| self.nodes[index].name.as_str()).collect()
+    }
+
+    /// Cambia entre entrenamiento y evaluación los módulos de todos los nodos.
+    pub fn set_training(&mut self, training: bool) {
+        self.training = training;
+        for node in &mut self.nodes {
+            if let GraphOp::Module(module) = &mut node.op {
+                module.set_training(training);
+            }
+        }
+    }
+
+    fn modules(&self) -> impl Iterator<Item = &dyn Module> {
+        self.nodes.iter().filter_map(|node| match &node.op {
+            GraphOp::Module(module) => Some(module.as_ref()),
+            _ => None,
+        })
+    }
+}
+
+/// Valor del nodo `index` para uno de sus consumidores; en la última lectura
+/// se saca de `values` para liberarlo.
+fn take(values: &mut [Option<Tensor>], uses: &mut [usize], index: usize) -> Tensor {
+    uses[index] -= 1;
+    let value = if uses[index] == 0 { values[index].take() } else { values[index].clone() };
+    value.expect("el orden topológico calcula cada nodo antes que sus consumidores")
+}
+
+/// Un grafo de una entrada y una salida también es un módulo, p. ej. para
+/// usarlo dentro de un `NeuralNetwork` o como bloque de otro grafo.
+impl Module for GraphModel {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        if self.outputs.len() != 1 {
+            return Err(TensorError::LengthMismatch { expected: 1, found: self.outputs.len() });
+        }
+        let mut outputs = self.forward_many(vec![input])?;
+        Ok(outputs.remove(0))
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        self.modules().flat_map(|module| module.parameters()).collect()
+    }
+
+    /// Prefijados con el nombre del nodo (`proj.weight`).
+    fn parameter_names(&self) -> Vec<String> {
+        self.nodes
+            .iter()
+            .filter_map(|node| match &node.op {
+                GraphOp::Module(module) => Some(prefixed(&node.name, module.parameter_names())),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        self.nodes
+            .iter_mut()
+            .filter_map(|node| match &mut node.op {
+                GraphOp::Module(module) => Some(module.parameters_mut()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        let mut shapes = self.output_shapes(&[input])?;
+        if shapes.len() != 1 {
+            return Err(TensorError::LengthMismatch { expected: 1, found: shapes.len() });
+        }
+        Ok(shapes.remove(0))
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        if self.outputs.len() != 1 {
+            return Err(TensorError::LengthMismatch { expected: 1, found: self.outputs.len() });
+        }
+        let mut outputs = self.forward_many_tape(vec![input], parameters)?;
+        Ok(outputs.remove(0))
+    }
+
+    fn parameter_bytes(&self) -> usize {
+        self.modules().map(|module| module.parameter_bytes()).sum()
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        for node in &mut self.nodes {
+            if let GraphOp::Module(module) = &mut node.op {
+                module.to_dtype(dtype)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn quantize(&mut self, scheme: QuantScheme) -> TensorResult<()> {
+        for node in &mut self.nodes {
+            if let GraphOp::Module(module) = &mut node.op {
+                module.quantize(scheme)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn set_training(&mut self, training: bool) {
+        GraphModel::set_training(self, training);
+    }
+
+    fn buffers(&self) -> Vec<Tensor> {
+        self.modules().flat_map(|module| module.buffers()).collect()
+    }
+
+    fn set_buffers(&mut self, values: &[Tensor]) -> TensorResult<()> {
+        let modules = self.nodes
+            .iter_mut()
+            .filter_map(|node| match &mut node.op {
+                GraphOp::Module(module) => Some(module.as_mut()),
+                _ => None,
+            })
+            .collect();
+        set_module_buffers(modules, values)
+    }
+}
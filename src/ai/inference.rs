@@ -1,11 +1,22 @@
-use super::nn::NeuralNetwork;
+use super::nn::{NetworkState, NeuralNetwork};
+use super::onnx::OnnxError;
 use super::tensor::Tensor;
+use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use alloc::string::String;
 
+/// Sesión de inferencia con estado: conserva el estado oculto de las capas
+/// recurrentes de un modelo entre llamadas a `predict_session`.
+struct Session {
+    model_id: usize,
+    state: NetworkState,
+}
+
 pub struct InferenceEngine {
     models: Vec<NeuralNetwork>,
     current_model: Option<usize>,
+    sessions: BTreeMap<usize, Session>,
+    next_session_id: usize,
 }
 
 impl InferenceEngine {
@@ -13,6 +24,8 @@ impl InferenceEngine {
         InferenceEngine {
             models: Vec::new(),
             current_model: None,
+            sessions: BTreeMap::new(),
+            next_session_id: 0,
         }
     }
     
@@ -23,6 +36,13 @@ impl InferenceEngine {
         model_id
     }
     
+    /// Importa un modelo ONNX de una entrada y una salida (ver
+    /// `GraphModel::from_onnx`) y lo carga como modelo actual.
+    pub fn load_onnx(&mut self, bytes: &[u8]) -> Result<usize, OnnxError> {
+        let model = NeuralNetwork::from_onnx(bytes)?;
+        Ok(self.load_model(model))
+    }
+    
     pub fn set_current_model(&mut self, model_id: usize) -> Result<(), &'static str> {
         if model_id < self.models.len() {
             self.current_model = Some(model_id);
@@ -36,12 +56,46 @@ impl InferenceEngine {
         match self.current_model {
             Some(model_id) => {
                 let model = &self.models[model_id];
-                Ok(model.forward(input))
+                model.forward(input).map_err(|error| error.message())
             },
             None => Err("No hay modelo seleccionado para inferencia"),
         }
     }
     
+    /// Abre una sesión sobre el modelo actual y devuelve su identificador.
+    ///
+    /// Cada sesión (p. ej. cada sensor) lleva su propio estado, así que
+    /// varias secuencias pueden intercalarse sobre el mismo modelo.
+    pub fn open_session(&mut self) -> Result<usize, &'static str> {
+        let model_id = self.current_model.ok_or("No hay modelo seleccionado para inferencia")?;
+        let session_id = self.next_session_id;
+        self.next_session_id += 1;
+        self.sessions.insert(session_id, Session { model_id, state: NetworkState::new() });
+        Ok(session_id)
+    }
+    
+    /// Como `predict`, con el modelo de la sesión y continuando su estado.
+    pub fn predict_session(&mut self, session_id: usize, input: Tensor) -> Result<Tensor, &'static str> {
+        let session = self.sessions.get_mut(&session_id).ok_or("ID de sesión no válido")?;
+        let model = &self.models[session.model_id];
+        model.forward_stateful(input, &mut session.state).map_err(|error| {
+            // Un paso a medias dejaría el estado incoherente
+            session.state.reset();
+            error.message()
+        })
+    }
+    
+    /// Vuelve al estado inicial de la sesión, p. ej. al empezar otra secuencia.
+    pub fn reset_session(&mut self, session_id: usize) -> Result<(), &'static str> {
+        let session = self.sessions.get_mut(&session_id).ok_or("ID de sesión no válido")?;
+        session.state.reset();
+        Ok(())
+    }
+    
+    pub fn close_session(&mut self, session_id: usize) -> Result<(), &'static str> {
+        self.sessions.remove(&session_id).map(|_| ()).ok_or("ID de sesión no válido")
+    }
+    
     pub fn get_model_names(&self) -> Vec<String> {
         self.models.iter()
             .map(|model| String::from(model.name()))
@@ -0,0 +1,79 @@
+use super::nn::ActivationFunction;
+use super::random::Rng;
+use super::tensor::{checked_len, try_buffer, Tensor, TensorResult};
+use crate::math;
+
+/// Distribución con la que se inicializa un tensor de parámetros.
+///
+/// Las variantes de Xavier (Glorot) y He (Kaiming) escalan la distribución
+/// según `fan_in` y `fan_out`, las conexiones de entrada y de salida de cada
+/// neurona, para que la varianza de las activaciones no crezca ni se anule
+/// al atravesar capas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Initializer {
+    Zeros,
+    Constant(f32),
+    /// `U(low, high)`.
+    Uniform { low: f32, high: f32 },
+    /// `N(mean, std²)`.
+    Normal { mean: f32, std: f32 },
+    /// `U(-a, a)` con `a = sqrt(6 / (fan_in + fan_out))`.
+    XavierUniform,
+    /// `N(0, 2 / (fan_in + fan_out))`.
+    XavierNormal,
+    /// `U(-a, a)` con `a = sqrt(6 / fan_in)`.
+    HeUniform,
+    /// `N(0, 2 / fan_in)`.
+    HeNormal,
+}
+
+impl Initializer {
+    /// Inicialización por defecto de los pesos de una capa seguida de
+    /// `activation`: He para ReLU y sus variantes suaves, Xavier para las
+    /// saturantes y la identidad.
+    pub fn for_activation(activation: ActivationFunction) -> Self {
+        match activation {
+            ActivationFunction::ReLU | ActivationFunction::Gelu | ActivationFunction::Silu => {
+                Initializer::HeUniform
+            }
+            ActivationFunction::Sigmoid
+            | ActivationFunction::Tanh
+            | ActivationFunction::Softmax
+            | ActivationFunction::Identity => Initializer::XavierUniform,
+        }
+    }
+
+    /// Crea un tensor de forma `shape` con valores de esta distribución.
+    /// `fan_in` y `fan_out` solo se usan en Xavier y He; dependen de cómo
+    /// guarda cada capa sus pesos (en una densa `[entrada, salida]` son
+    /// `entrada` y `salida`).
+    pub fn tensor(self, shape: &[usize], fan_in: usize, fan_out: usize, rng: &mut Rng) -> TensorResult<Tensor> {
+        let xavier = || 2.0 / (fan_in + fan_out).max(1) as f32;
+        let he = || 2.0 / fan_in.max(1) as f32;
+        // Varianza de `U(-a, a)`: `a² / 3`
+        let (uniform, a, b) = match self {
+            Initializer::Zeros => return Tensor::zeros(shape),
+            Initializer::Constant(value) => return Tensor::full(shape, value),
+            Initializer::Uniform { low, high } => (true, low, high),
+            Initializer::Normal { mean, std } => (false, mean, std),
+            Initializer::XavierUniform => {
+                let limit = math::sqrt(3.0 * xavier());
+                (true, -limit, limit)
+            }
+            Initializer::XavierNormal => (false, 0.0, math::sqrt(xavier())),
+            Initializer::HeUniform => {
+                let limit = math::sqrt(3.0 * he());
+                (true, -limit, limit)
+            }
+            Initializer::HeNormal => (false, 0.0, math::sqrt(he())),
+        };
+        let len = checked_len(shape)?;
+        let mut values = try_buffer(len)?;
+        if uniform {
+            values.extend((0..len).map(|_| rng.uniform(a, b)));
+        } else {
+            values.extend((0..len).map(|_| rng.normal(a, b)));
+        }
+        Tensor::from_vec(values, shape)
+    }
+}
@@ -0,0 +1,106 @@
+use super::autograd::Var;
+use super::tensor::{Tensor, TensorError, TensorResult};
+use crate::math;
+use alloc::vec::Vec;
+
+/// Límite de las probabilidades en las entropías cruzadas, para que el
+/// logaritmo y su derivada sean finitos.
+const PROBABILITY_EPS: f32 = 1e-7;
+
+/// Función de pérdida entre una predicción y su objetivo, de la misma forma.
+///
+/// Las entropías cruzadas reciben probabilidades, no logits: la salida de
+/// una capa con activación `Sigmoid` (binaria) o `Softmax` sobre el último
+/// eje (categórica, con objetivos *one-hot* o distribuciones).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Loss {
+    /// Media de `(p - t)²`.
+    MeanSquaredError,
+    /// Media de `|p - t|`.
+    MeanAbsoluteError,
+    /// Media de `-(t ln p + (1 - t) ln(1 - p))`.
+    BinaryCrossEntropy,
+    /// `-Σ t ln p` sobre el último eje, promediada entre las filas.
+    CategoricalCrossEntropy,
+    /// Cuadrática hasta `|p - t| = delta` y lineal a partir de ahí.
+    Huber { delta: f32 },
+}
+
+impl Loss {
+    /// Valor de la pérdida y su gradiente respecto a `prediction`.
+    pub fn evaluate(self, prediction: &Tensor, target: &Tensor) -> TensorResult<(f32, Tensor)> {
+        if prediction.shape() != target.shape() {
+            return Err(TensorError::ShapeMismatch {
+                lhs: prediction.shape().to_vec(),
+                rhs: target.shape().to_vec(),
+            });
+        }
+        if prediction.is_empty() {
+            return Err(TensorError::EmptyReduction);
+        }
+        // Las pérdidas elemento a elemento se promedian sobre todos los
+        // elementos; la categórica, sobre las filas
+        let count = match self {
+            Loss::CategoricalCrossEntropy => match prediction.shape().last() {
+                Some(&classes) => prediction.len() / classes,
+                None => return Err(TensorError::RankMismatch { expected: 1, found: 0 }),
+            },
+            _ => prediction.len(),
+        } as f32;
+
+        let mut total = 0.0;
+        let grad: Vec<f32> = prediction
+            .iter()
+            .zip(target.iter())
+            .map(|(p, t)| {
+                let (value, grad) = self.pointwise(p, t);
+                total += value;
+                grad / count
+            })
+            .collect();
+        Ok((total / count, Tensor::from_vec(grad, prediction.shape())?))
+    }
+
+    /// La pérdida como nodo de la cinta de `prediction`, para propagar su
+    /// gradiente con `backward`.
+    pub fn forward<'t>(self, prediction: &Var<'t>, target: &Tensor) -> TensorResult<Var<'t>> {
+        let (value, grad) = self.evaluate(&prediction.value(), target)?;
+        prediction.scalar_with_grad(value, grad)
+    }
+
+    /// Término de un elemento y su derivada respecto a `p`.
+    fn pointwise(self, p: f32, t: f32) -> (f32, f32) {
+        let diff = p - t;
+        match self {
+            Loss::MeanSquaredError => (diff * diff, 2.0 * diff),
+            Loss::MeanAbsoluteError => (diff.abs(), sign(diff)),
+            Loss::BinaryCrossEntropy => {
+                let p = p.clamp(PROBABILITY_EPS, 1.0 - PROBABILITY_EPS);
+                let value = -(t * math::ln(p) + (1.0 - t) * math::ln(1.0 - p));
+                (value, (p - t) / (p * (1.0 - p)))
+            }
+            Loss::CategoricalCrossEntropy => {
+                let p = p.max(PROBABILITY_EPS);
+                (-t * math::ln(p), -t / p)
+            }
+            Loss::Huber { delta } => {
+                if diff.abs() <= delta {
+                    (0.5 * diff * diff, diff)
+                } else {
+                    (delta * (diff.abs() - 0.5 * delta), delta * sign(diff))
+                }
+            }
+        }
+    }
+}
+
+/// Signo con `sign(0) = 0`, el subgradiente habitual de `|x|`.
+fn sign(x: f32) -> f32 {
+    if x > 0.0 {
+        1.0
+    } else if x < 0.0 {
+        -1.0
+    } else {
+        0.0
+    }
+}
@@ -1,14 +1,59 @@
 mod nn;
 mod inference;
 mod tensor;
+mod dtype;
+mod quant;
+mod fixed;
+mod gemm;
+mod conv;
+mod norm;
+mod random;
+mod transformer;
+mod recurrent;
+mod module;
+mod graph;
+mod init;
+mod autograd;
+mod loss;
+mod optimizer;
+mod trainer;
+mod format;
+mod serialize;
+mod safetensors;
+mod onnx;
+mod npy;
+mod gguf;
 
 use crate::println;
+#[cfg(feature = "gemm-benchmark")]
+use crate::serial_println;
 use lazy_static::lazy_static;
 use spin::Mutex;
 
 pub use self::nn::*;
 pub use self::inference::*;
 pub use self::tensor::*;
+pub use self::dtype::*;
+pub use self::quant::*;
+pub use self::fixed::*;
+pub use self::gemm::*;
+pub use self::conv::*;
+pub use self::norm::*;
+pub use self::random::*;
+pub use self::transformer::*;
+pub use self::recurrent::*;
+pub use self::module::*;
+pub use self::graph::*;
+pub use self::init::*;
+pub use self::autograd::*;
+pub use self::loss::*;
+pub use self::optimizer::*;
+pub use self::trainer::*;
+pub use self::serialize::*;
+pub use self::safetensors::*;
+pub use self::onnx::*;
+pub use self::npy::*;
+pub use self::gguf::*;
 
 pub struct AISubsystem {
     initialized: bool,
@@ -36,6 +81,9 @@ impl AISubsystem {
         println!("Subsistema de IA inicializado");
         println!("  - Núcleos tensores disponibles: {}", self.tensor_cores_available);
         println!("  - Memoria máxima para IA: {} MB", self.max_memory_usage / (1024 * 1024));
+
+        #[cfg(feature = "gemm-benchmark")]
+        report_gemm_benchmark();
     }
     
     pub fn is_initialized(&self) -> bool {
@@ -51,6 +99,31 @@ pub fn init() {
     AI_SUBSYSTEM.lock().initialize();
 }
 
+/// Lado de las matrices del benchmark de arranque (3 x 64 KiB en `f32`).
+#[cfg(feature = "gemm-benchmark")]
+const GEMM_BENCHMARK_SIZE: usize = 128;
+
+/// Mide el microkernel escalar y el elegido por CPUID y envía los GFLOPS
+/// por el puerto serie. Solo con la feature `gemm-benchmark`.
+#[cfg(feature = "gemm-benchmark")]
+fn report_gemm_benchmark() {
+    let best = GemmKernel::detect();
+    let kernels: &[GemmKernel] = if best == GemmKernel::Scalar {
+        &[GemmKernel::Scalar]
+    } else {
+        &[GemmKernel::Scalar, best]
+    };
+    for &kernel in kernels {
+        match benchmark(kernel, GEMM_BENCHMARK_SIZE) {
+            Ok(result) => serial_println!(
+                "GEMM {0}x{0}x{0} [{1}]: {2:.2} GFLOPS ({3} ciclos)",
+                result.size, kernel.name(), result.gflops(), result.cycles
+            ),
+            Err(error) => serial_println!("GEMM [{}]: benchmark fallido: {}", kernel.name(), error),
+        }
+    }
+}
+
 fn detect_tensor_cores() -> usize {
     // Simulación: en un hardware real, esto detectaría hardware de aceleración
     4 // Valor ficticio para demostración
@@ -0,0 +1,154 @@
+use super::autograd::Var;
+use super::dtype::DType;
+use super::quant::QuantScheme;
+use super::recurrent::RecurrentState;
+use super::tensor::{Tensor, TensorError, TensorResult};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::any::Any;
+
+/// Componente de un `NeuralNetwork`: una capa densa, convolucional, de
+/// normalización, de atención... o cualquier tipo definido fuera de `ai`
+/// que implemente el trait.
+///
+/// Como `Module` extiende `Any`, un `&dyn Module` puede convertirse a
+/// `&dyn Any` para recuperar el tipo concreto con `downcast_ref` (así se
+/// localizan, p. ej., las capas densas para la ejecución en coma fija).
+pub trait Module: Any + Send + Sync {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor>;
+
+    /// Tensores de parámetros de la capa, siempre en el mismo orden.
+    fn parameters(&self) -> Vec<&Tensor>;
+
+    /// Nombre del tipo de capa, para descripciones del modelo y mensajes.
+    fn name(&self) -> &str;
+
+    /// Forma de la salida para una entrada de forma `input`, sin ejecutar
+    /// la capa; falla si la entrada no es válida para ella.
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>>;
+
+    /// Nombre de cada tensor de `parameters`, en el mismo orden. Las capas de
+    /// la biblioteca usan los de PyTorch cuando tienen equivalente (`weight`,
+    /// `bias`...) y las compuestas los prefijan con el de la parte
+    /// (`query.weight`); por defecto, la posición.
+    fn parameter_names(&self) -> Vec<String> {
+        (0..self.parameters().len()).map(|index| index.to_string()).collect()
+    }
+
+    /// Los mismos tensores que `parameters`, en el mismo orden, para que un
+    /// optimizador los actualice. Una capa que no lo implemente no se puede
+    /// entrenar.
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        Vec::new()
+    }
+
+    /// Como `forward`, pero apuntando las operaciones en la cinta de `input`
+    /// para calcular gradientes. `parameters` son los de `parameters` ya
+    /// añadidos a esa cinta.
+    fn forward_tape<'t>(&self, _input: Var<'t>, _parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        Err(TensorError::InvalidArgument("La capa no admite entrenamiento"))
+    }
+
+    /// Memoria ocupada por los parámetros.
+    fn parameter_bytes(&self) -> usize {
+        self.parameters().iter().map(|parameter| parameter.size_in_bytes()).sum()
+    }
+
+    /// Cambia el tipo de almacenamiento de los parámetros, si los hay.
+    fn to_dtype(&mut self, _dtype: DType) -> TensorResult<()> {
+        Ok(())
+    }
+
+    /// Cuantiza a `i8` los pesos de las capas densas que contenga la capa
+    /// (ver `NeuralNetwork::quantize`); las demás no cambian.
+    fn quantize(&mut self, _scheme: QuantScheme) -> TensorResult<()> {
+        Ok(())
+    }
+
+    /// Cambia entre entrenamiento y evaluación; solo afecta a las capas que
+    /// distinguen ambos modos.
+    fn set_training(&mut self, _training: bool) {}
+
+    /// Estado que no se entrena pero cambia al entrenar (las estadísticas
+    /// acumuladas de `BatchNorm`), siempre en el mismo orden. Por defecto,
+    /// ninguno.
+    fn buffers(&self) -> Vec<Tensor> {
+        Vec::new()
+    }
+
+    /// Sustituye el estado de `buffers` por `values`, en el mismo orden.
+    fn set_buffers(&mut self, values: &[Tensor]) -> TensorResult<()> {
+        if !values.is_empty() {
+            return Err(TensorError::LengthMismatch { expected: 0, found: values.len() });
+        }
+        Ok(())
+    }
+
+    /// Como `forward`, pero las capas con estado (las recurrentes) parten
+    /// de `state` (ceros si es `None`) y lo sustituyen por su estado final.
+    fn forward_stateful(&self, input: Tensor, _state: &mut Option<RecurrentState>) -> TensorResult<Tensor> {
+        self.forward(input)
+    }
+}
+
+/// Error de `output_shape` cuando el último eje de la entrada no es `expected`.
+pub(crate) fn check_last_dim(input: &[usize], expected: usize) -> TensorResult<()> {
+    match input.last() {
+        Some(&last) if last == expected => Ok(()),
+        Some(_) => Err(TensorError::ShapeMismatch { lhs: input.to_vec(), rhs: [expected].to_vec() }),
+        None => Err(TensorError::RankMismatch { expected: 1, found: 0 }),
+    }
+}
+
+/// Nombres de parámetros a partir de literales.
+pub(crate) fn parameter_names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+/// Antepone `prefix.` a cada nombre de `names`.
+pub(crate) fn prefixed(prefix: &str, names: Vec<String>) -> Vec<String> {
+    names.into_iter().map(|name| format!("{}.{}", prefix, name)).collect()
+}
+
+/// Reparte `values` entre los `set_buffers` de `modules`, según cuántos
+/// tensores devuelve el `buffers` de cada uno.
+pub(crate) fn set_module_buffers(modules: Vec<&mut dyn Module>, values: &[Tensor]) -> TensorResult<()> {
+    let counts: Vec<usize> = modules.iter().map(|module| module.buffers().len()).collect();
+    let expected = counts.iter().sum();
+    if values.len() != expected {
+        return Err(TensorError::LengthMismatch { expected, found: values.len() });
+    }
+    let mut rest = values;
+    for (module, count) in modules.into_iter().zip(counts) {
+        let (own, tail) = rest.split_at(count);
+        module.set_buffers(own)?;
+        rest = tail;
+    }
+    Ok(())
+}
+
+/// Los `N` parámetros ya en la cinta que recibe `forward_tape`.
+pub(crate) fn tape_parameters<'p, 't, const N: usize>(parameters: &'p [Var<'t>]) -> TensorResult<&'p [Var<'t>; N]> {
+    parameters
+        .try_into()
+        .map_err(|_| TensorError::LengthMismatch { expected: N, found: parameters.len() })
+}
+
+/// Reparte los parámetros ya en la cinta de una capa compuesta entre sus
+/// `N` partes, con `counts[i]` tensores la parte `i`.
+pub(crate) fn split_parameters<'p, 't, const N: usize>(
+    parameters: &'p [Var<'t>],
+    counts: [usize; N],
+) -> TensorResult<[&'p [Var<'t>]; N]> {
+    let expected = counts.iter().sum();
+    if parameters.len() != expected {
+        return Err(TensorError::LengthMismatch { expected, found: parameters.len() });
+    }
+    let mut rest = parameters;
+    Ok(counts.map(|count| {
+        let (part, tail) = rest.split_at(count);
+        rest = tail;
+        part
+    }))
+}
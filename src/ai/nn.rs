@@ -1,18 +1,124 @@
-use super::tensor::Tensor;
+use super::autograd::{Tape, Var};
+use super::dtype::DType;
+use super::fixed::{ExecutionMode, FixedLayer, FixedTensor};
+use super::init::Initializer;
+use super::loss::Loss;
+use super::module::{check_last_dim, parameter_names, prefixed, set_module_buffers, Module};
+use super::optimizer::Optimizer;
+use super::random::{with_global_rng, Rng};
+use super::recurrent::RecurrentState;
+use super::quant::{quantized_matmul, QuantGranularity, QuantScheme, QuantizedTensor};
+use super::tensor::{Tensor, TensorError, TensorResult};
+use alloc::boxed::Box;
 use alloc::vec::Vec;
-use alloc::string::String;
+use alloc::string::{String, ToString};
+use core::any::Any;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ActivationFunction {
     ReLU,
     Sigmoid,
     Tanh,
     Softmax,
+    /// Sin activación, p. ej. en las proyecciones de la atención.
+    Identity,
+    /// GELU con la aproximación por `tanh` (la de GPT-2 y BERT).
+    Gelu,
+    /// SiLU o *swish*: `x * sigmoid(x)`.
+    Silu,
 }
 
+impl ActivationFunction {
+    /// Aplica la activación elemento a elemento (softmax sobre el último eje).
+    pub fn apply(self, input: &Tensor) -> TensorResult<Tensor> {
+        Ok(match self {
+            ActivationFunction::ReLU => input.relu()?,
+            ActivationFunction::Sigmoid => input.sigmoid()?,
+            ActivationFunction::Tanh => input.tanh()?,
+            ActivationFunction::Softmax => input.softmax(-1)?,
+            ActivationFunction::Identity => input.clone(),
+            ActivationFunction::Gelu => input.gelu()?,
+            ActivationFunction::Silu => input.silu()?,
+        })
+    }
+
+    /// Como `apply`, apuntando la operación en la cinta de `input`.
+    pub fn apply_tape<'t>(self, input: &Var<'t>) -> TensorResult<Var<'t>> {
+        match self {
+            ActivationFunction::ReLU => input.relu(),
+            ActivationFunction::Sigmoid => input.sigmoid(),
+            ActivationFunction::Tanh => input.tanh(),
+            ActivationFunction::Softmax => input.softmax(-1),
+            ActivationFunction::Identity => Ok(*input),
+            ActivationFunction::Gelu => input.gelu(),
+            ActivationFunction::Silu => input.silu(),
+        }
+    }
+}
+
+/// Una activación suelta también es una capa, p. ej. tras una `Conv2d`.
+impl Module for ActivationFunction {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        self.apply(&input)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            ActivationFunction::ReLU => "ReLU",
+            ActivationFunction::Sigmoid => "Sigmoid",
+            ActivationFunction::Tanh => "Tanh",
+            ActivationFunction::Softmax => "Softmax",
+            ActivationFunction::Identity => "Identity",
+            ActivationFunction::Gelu => "GELU",
+            ActivationFunction::Silu => "SiLU",
+        }
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        Ok(input.to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, _parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        self.apply_tape(&input)
+    }
+}
+
+/// Estado de las capas recurrentes de un modelo entre llamadas a
+/// `NeuralNetwork::forward_stateful` (una entrada por capa).
+#[derive(Debug, Clone, Default)]
+pub struct NetworkState {
+    layers: Vec<Option<RecurrentState>>,
+}
+
+impl NetworkState {
+    pub fn new() -> Self {
+        NetworkState { layers: Vec::new() }
+    }
+
+    /// Olvida el estado: la siguiente llamada parte de ceros.
+    pub fn reset(&mut self) {
+        self.layers.clear();
+    }
+
+    /// Estado final de la capa `index`, si es recurrente y ya se ha ejecutado.
+    pub fn layer(&self, index: usize) -> Option<&RecurrentState> {
+        self.layers.get(index).and_then(Option::as_ref)
+    }
+}
+
+/// Modelo secuencial: una lista de módulos que se ejecutan en orden.
 pub struct NeuralNetwork {
-    layers: Vec<Layer>,
+    layers: Vec<Box<dyn Module>>,
     name: String,
+    mode: ExecutionMode,
+    training: bool,
+    // Copia en coma fija de `layers`, solo en `ExecutionMode::Fixed` y si
+    // todas las capas son densas
+    fixed_layers: Vec<FixedLayer>,
 }
 
 impl NeuralNetwork {
@@ -20,57 +126,489 @@ impl NeuralNetwork {
         NeuralNetwork {
             layers: Vec::new(),
             name: String::from(name),
+            mode: ExecutionMode::Float,
+            training: false,
+            fixed_layers: Vec::new(),
         }
     }
     
-    pub fn add_layer(&mut self, layer: Layer) {
+    /// Añade una capa al final del modelo.
+    ///
+    /// En `ExecutionMode::Fixed` rehace la copia en coma fija; si falla
+    /// (heap agotado) la capa queda añadida, se devuelve el error y `forward`
+    /// usa la ruta en `f32` hasta que se vuelva a llamar a
+    /// `set_execution_mode`.
+    pub fn add_layer(&mut self, layer: impl Module) -> TensorResult<()> {
+        self.add_module(Box::new(layer))
+    }
+    
+    /// Como `add_layer`, para módulos que ya están en una caja.
+    pub fn add_module(&mut self, mut layer: Box<dyn Module>) -> TensorResult<()> {
+        layer.set_training(self.training);
         self.layers.push(layer);
+        self.fixed_layers.clear();
+        self.rebuild_fixed_layers()
+    }
+    
+    pub fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        if let ExecutionMode::Fixed(format) = self.mode {
+            if self.fixed_layers.len() == self.layers.len() {
+                let input = FixedTensor::from_tensor(&input, format)?;
+                return self.forward_fixed(input)?.to_tensor();
+            }
+        }
+        
+        let mut current = input;
+        
+        for layer in &self.layers {
+            current = layer.forward(current)?;
+        }
+        
+        Ok(current)
     }
     
-    pub fn forward(&self, input: Tensor) -> Tensor {
+    pub fn layers(&self) -> &[Box<dyn Module>] {
+        &self.layers
+    }
+    
+    /// Acceso a las capas para los cargadores de pesos; quien las modifique
+    /// debe terminar con `set_parameters` para rehacer la copia en coma fija.
+    pub(crate) fn layers_mut(&mut self) -> &mut [Box<dyn Module>] {
+        &mut self.layers
+    }
+    
+    /// Forma de la salida para una entrada de forma `input`; comprueba que
+    /// las capas encajan entre sí sin ejecutar el modelo.
+    pub fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        self.layers.iter().try_fold(input.to_vec(), |shape, layer| layer.output_shape(&shape))
+    }
+    
+    /// Parámetros de todas las capas, en orden.
+    pub fn parameters(&self) -> Vec<&Tensor> {
+        self.layers.iter().flat_map(|layer| layer.parameters()).collect()
+    }
+    
+    /// Parámetros de todas las capas con su nombre, `{posición}.{nombre}`
+    /// como en un `nn.Sequential` de PyTorch (`0.weight`, `2.bias`...).
+    pub fn named_parameters(&self) -> Vec<(String, &Tensor)> {
+        self.layers
+            .iter()
+            .enumerate()
+            .flat_map(|(index, layer)| {
+                prefixed(&index.to_string(), layer.parameter_names()).into_iter().zip(layer.parameters())
+            })
+            .collect()
+    }
+    
+    /// Parámetros modificables de todas las capas, en el orden de `parameters`.
+    pub fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        self.layers.iter_mut().flat_map(|layer| layer.parameters_mut()).collect()
+    }
+    
+    /// Sustituye los parámetros por `values` (p. ej. un punto de control), en
+    /// el orden de `parameters` y con las mismas formas.
+    pub fn set_parameters(&mut self, values: &[Tensor]) -> TensorResult<()> {
+        let mut parameters = self.parameters_mut();
+        if parameters.len() != values.len() {
+            return Err(TensorError::LengthMismatch { expected: parameters.len(), found: values.len() });
+        }
+        for (parameter, value) in parameters.iter().zip(values) {
+            if parameter.shape() != value.shape() {
+                return Err(TensorError::ShapeMismatch {
+                    lhs: parameter.shape().to_vec(),
+                    rhs: value.shape().to_vec(),
+                });
+            }
+        }
+        for (parameter, value) in parameters.iter_mut().zip(values) {
+            **parameter = value.clone();
+        }
+        self.fixed_layers.clear();
+        self.rebuild_fixed_layers()
+    }
+    
+    /// Estado no entrenable de todas las capas (ver `Module::buffers`), en orden.
+    pub fn buffers(&self) -> Vec<Tensor> {
+        self.layers.iter().flat_map(|layer| layer.buffers()).collect()
+    }
+    
+    /// Sustituye el estado de `buffers` por `values`, en el mismo orden.
+    pub fn set_buffers(&mut self, values: &[Tensor]) -> TensorResult<()> {
+        set_module_buffers(self.layers.iter_mut().map(|layer| layer.as_mut()).collect(), values)
+    }
+    
+    /// Como `forward`, apuntando las operaciones en la cinta de `input`.
+    /// Devuelve también los parámetros del modelo en la cinta, en el orden
+    /// de `parameters`, para leer sus gradientes tras `backward`.
+    pub fn forward_tape<'t>(&self, input: Var<'t>) -> TensorResult<(Var<'t>, Vec<Var<'t>>)> {
+        let tape = input.tape();
+        let mut parameters = Vec::new();
         let mut current = input;
         
         for layer in &self.layers {
-            current = layer.forward(current);
+            let first = parameters.len();
+            parameters.extend(layer.parameters().into_iter().map(|p| tape.var(p.clone(), true)));
+            current = layer.forward_tape(current, &parameters[first..])?;
+        }
+        
+        Ok((current, parameters))
+    }
+    
+    /// Pérdida del modelo sobre el lote `(input, target)` y su gradiente
+    /// respecto a cada parámetro, en el orden de `parameters`.
+    pub fn gradients(&self, input: Tensor, target: &Tensor, loss: Loss) -> TensorResult<(f32, Vec<Tensor>)> {
+        let tape = Tape::new();
+        let (output, parameters) = self.forward_tape(tape.var(input, false))?;
+        let loss = loss.forward(&output, target)?;
+        loss.backward()?;
+        let grads = parameters
+            .iter()
+            .map(|p| p.grad().map_or_else(|| Tensor::zeros(&p.shape()), Ok))
+            .collect::<TensorResult<_>>()?;
+        Ok((loss.value().sum_all(), grads))
+    }
+    
+    /// Un paso de entrenamiento sobre el lote `(input, target)`: calcula los
+    /// gradientes y actualiza los parámetros con `optimizer`. Devuelve la
+    /// pérdida antes de la actualización.
+    pub fn train_step(
+        &mut self,
+        input: Tensor,
+        target: &Tensor,
+        loss: Loss,
+        optimizer: &mut dyn Optimizer,
+    ) -> TensorResult<f32> {
+        let (value, grads) = self.gradients(input, target, loss)?;
+        self.apply_gradients(&grads, optimizer)?;
+        Ok(value)
+    }
+    
+    /// Actualiza los parámetros con unos gradientes ya calculados (por
+    /// ejemplo, recortados) en el orden de `parameters`.
+    pub fn apply_gradients(&mut self, grads: &[Tensor], optimizer: &mut dyn Optimizer) -> TensorResult<()> {
+        optimizer.step(&mut self.parameters_mut(), grads)?;
+        // La copia en coma fija se queda con los pesos antiguos
+        self.fixed_layers.clear();
+        self.rebuild_fixed_layers()
+    }
+    
+    /// Como `forward`, pero las capas recurrentes continúan desde `state` y
+    /// lo actualizan, para procesar una secuencia por trozos.
+    ///
+    /// Si falla, `state` puede quedar actualizado solo en parte; conviene
+    /// llamar a `NetworkState::reset` antes de seguir.
+    pub fn forward_stateful(&self, input: Tensor, state: &mut NetworkState) -> TensorResult<Tensor> {
+        if !self.fixed_layers.is_empty() {
+            // Solo hay versión en coma fija si todas las capas son densas
+            return self.forward(input);
+        }
+        state.layers.resize(self.layers.len(), None);
+        
+        let mut current = input;
+        
+        for (layer, layer_state) in self.layers.iter().zip(&mut state.layers) {
+            current = layer.forward_stateful(current, layer_state)?;
+        }
+        
+        Ok(current)
+    }
+    
+    /// Ejecuta el modelo completo en coma fija, sin operaciones `f32`.
+    ///
+    /// Requiere haber seleccionado antes `ExecutionMode::Fixed`.
+    pub fn forward_fixed(&self, input: FixedTensor) -> TensorResult<FixedTensor> {
+        let mut current = input;
+        
+        for layer in &self.fixed_layers {
+            current = layer.forward(&current)?;
         }
         
-        current
+        Ok(current)
+    }
+    
+    /// Selecciona cómo se ejecuta `forward` para este modelo.
+    ///
+    /// En modo de coma fija se precalcula una copia entera de los pesos. Solo
+    /// las capas densas tienen versión en coma fija; si hay otras, `forward`
+    /// sigue usando `f32`.
+    pub fn set_execution_mode(&mut self, mode: ExecutionMode) -> TensorResult<()> {
+        self.mode = mode;
+        self.fixed_layers.clear();
+        self.rebuild_fixed_layers()
+    }
+    
+    pub fn execution_mode(&self) -> ExecutionMode {
+        self.mode
+    }
+    
+    /// Modo entrenamiento (`true`) o evaluación (`false`, el inicial).
+    ///
+    /// Afecta a `BatchNorm`, que en entrenamiento usa las estadísticas del
+    /// lote, y a `Dropout`, que solo anula elementos en entrenamiento.
+    pub fn set_training(&mut self, training: bool) {
+        self.training = training;
+        for layer in &mut self.layers {
+            layer.set_training(training);
+        }
+    }
+    
+    pub fn is_training(&self) -> bool {
+        self.training
+    }
+    
+    fn rebuild_fixed_layers(&mut self) -> TensorResult<()> {
+        if let ExecutionMode::Fixed(format) = self.mode {
+            let mut fixed_layers = Vec::with_capacity(self.layers.len());
+            for layer in &self.layers {
+                let layer: &dyn Any = layer.as_ref();
+                match layer.downcast_ref::<Layer>() {
+                    Some(dense) => fixed_layers.push(FixedLayer::from_layer(dense, format)?),
+                    None => return Ok(()),
+                }
+            }
+            self.fixed_layers = fixed_layers;
+        }
+        Ok(())
     }
     
     pub fn name(&self) -> &str {
         &self.name
     }
+    
+    /// Convierte los pesos de todas las capas al tipo de almacenamiento dado.
+    pub fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        for layer in &mut self.layers {
+            layer.to_dtype(dtype)?;
+        }
+        Ok(())
+    }
+    
+    /// Memoria ocupada por los parámetros del modelo.
+    pub fn parameter_bytes(&self) -> usize {
+        self.layers.iter().map(|layer| layer.parameter_bytes()).sum()
+    }
+    
+    /// Cuantiza a `i8` los pesos de todas las capas densas, también las de
+    /// las capas compuestas (la atención, `TransformerBlock`, `GraphModel`).
+    pub fn quantize(&mut self, scheme: QuantScheme) -> TensorResult<()> {
+        for layer in &mut self.layers {
+            layer.quantize(scheme)?;
+        }
+        Ok(())
+    }
+}
+
+/// Pesos de una capa densa, en coma flotante o cuantizados a `i8`.
+pub enum LayerWeights {
+    Float(Tensor),
+    Quantized(QuantizedTensor),
+}
+
+impl LayerWeights {
+    pub fn shape(&self) -> &[usize] {
+        match self {
+            LayerWeights::Float(weights) => weights.shape(),
+            LayerWeights::Quantized(weights) => weights.shape(),
+        }
+    }
+    
+    pub fn size_in_bytes(&self) -> usize {
+        match self {
+            LayerWeights::Float(weights) => weights.size_in_bytes(),
+            LayerWeights::Quantized(weights) => weights.size_in_bytes(),
+        }
+    }
 }
 
 pub struct Layer {
-    weights: Tensor,
+    weights: LayerWeights,
     bias: Tensor,
     activation: ActivationFunction,
 }
 
 impl Layer {
-    pub fn new(input_size: usize, output_size: usize, activation: ActivationFunction) -> Self {
-        // Inicialización simple para demostración
-        let weights = Tensor::zeros(&[input_size, output_size]);
-        let bias = Tensor::zeros(&[output_size]);
+    /// Crea una capa con los pesos inicializados según la activación (ver
+    /// `Initializer::for_activation`) a partir del generador global, y el
+    /// sesgo a cero.
+    pub fn new(input_size: usize, output_size: usize, activation: ActivationFunction) -> TensorResult<Self> {
+        let init = Initializer::for_activation(activation);
+        with_global_rng(|rng| Layer::with_initializer(input_size, output_size, activation, init, rng))
+    }
+    
+    /// Como `new`, con la inicialización de los pesos y el generador explícitos.
+    pub fn with_initializer(
+        input_size: usize,
+        output_size: usize,
+        activation: ActivationFunction,
+        init: Initializer,
+        rng: &mut Rng,
+    ) -> TensorResult<Self> {
+        let weights = init.tensor(&[input_size, output_size], input_size, output_size, rng)?;
+        let bias = Tensor::zeros(&[output_size])?;
         
-        Layer {
-            weights,
+        Ok(Layer {
+            weights: LayerWeights::Float(weights),
             bias,
             activation,
+        })
+    }
+    
+    /// Crea una capa a partir de pesos `[entrada, salida]` y sesgo `[salida]` ya entrenados.
+    pub fn from_parameters(weights: Tensor, bias: Tensor, activation: ActivationFunction) -> TensorResult<Self> {
+        match (weights.shape(), bias.shape()) {
+            ([_, outputs], [bias_len]) if outputs == bias_len => Ok(Layer {
+                weights: LayerWeights::Float(weights),
+                bias,
+                activation,
+            }),
+            ([_, _], _) => Err(TensorError::ShapeMismatch {
+                lhs: weights.shape().to_vec(),
+                rhs: bias.shape().to_vec(),
+            }),
+            (shape, _) => Err(TensorError::RankMismatch { expected: 2, found: shape.len() }),
+        }
+    }
+    
+    /// Como `from_parameters`, con los pesos ya cuantizados a `i8`.
+    pub fn from_quantized(weights: QuantizedTensor, bias: Tensor, activation: ActivationFunction) -> TensorResult<Self> {
+        match (weights.shape(), bias.shape()) {
+            ([_, outputs], [bias_len]) if outputs == bias_len => Ok(Layer {
+                weights: LayerWeights::Quantized(weights),
+                bias,
+                activation,
+            }),
+            ([_, _], _) => Err(TensorError::ShapeMismatch {
+                lhs: weights.shape().to_vec(),
+                rhs: bias.shape().to_vec(),
+            }),
+            (shape, _) => Err(TensorError::RankMismatch { expected: 2, found: shape.len() }),
         }
     }
     
-    pub fn forward(&self, input: Tensor) -> Tensor {
+    pub fn weights(&self) -> &LayerWeights {
+        &self.weights
+    }
+    
+    pub fn bias(&self) -> &Tensor {
+        &self.bias
+    }
+    
+    pub fn activation(&self) -> ActivationFunction {
+        self.activation
+    }
+    
+    pub fn parameter_bytes(&self) -> usize {
+        self.weights.size_in_bytes() + self.bias.size_in_bytes()
+    }
+    
+    /// Almacena pesos y sesgo en `dtype`; el cálculo sigue haciéndose en `f32`.
+    ///
+    /// Los pesos ya cuantizados se dejan como están.
+    pub fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        if let LayerWeights::Float(weights) = &self.weights {
+            self.weights = LayerWeights::Float(weights.to_dtype(dtype)?);
+        }
+        self.bias = self.bias.to_dtype(dtype)?;
+        Ok(())
+    }
+    
+    /// Cuantiza los pesos a `i8` con una escala por neurona de salida.
+    ///
+    /// Las entradas se cuantizan dinámicamente (por tensor, asimétricas) en
+    /// cada `forward` y el producto se acumula en enteros.
+    pub fn quantize(&mut self, scheme: QuantScheme) -> TensorResult<()> {
+        if let LayerWeights::Float(weights) = &self.weights {
+            let quantized = QuantizedTensor::quantize(weights, scheme, QuantGranularity::PerChannel(1))?;
+            self.weights = LayerWeights::Quantized(quantized);
+        }
+        Ok(())
+    }
+    
+    pub fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
         // z = input * weights + bias
-        let z = input.matmul(&self.weights).add(&self.bias);
+        let product = match &self.weights {
+            LayerWeights::Float(weights) => input.matmul(weights)?,
+            LayerWeights::Quantized(weights) => {
+                // `quantized_matmul` es de matrices: los ejes anteriores al
+                // último se aplanan en filas y se recuperan en la salida
+                let mut shape = input.shape().to_vec();
+                let rows = match shape.len() {
+                    0..=2 => input,
+                    n => input.reshape(&[shape[..n - 1].iter().product(), shape[n - 1]])?,
+                };
+                let rows = QuantizedTensor::quantize(&rows, QuantScheme::Asymmetric, QuantGranularity::PerTensor)?;
+                let product = quantized_matmul(&rows, weights)?;
+                if shape.len() > 2 {
+                    shape.pop();
+                    shape.push(weights.shape()[1]);
+                    product.reshape(&shape)?
+                } else {
+                    product
+                }
+            }
+        };
+        let z = product.add(&self.bias)?;
         
         // Aplicar función de activación
-        match self.activation {
-            ActivationFunction::ReLU => z.relu(),
-            ActivationFunction::Sigmoid => z.sigmoid(),
-            ActivationFunction::Tanh => z.tanh(),
-            ActivationFunction::Softmax => z.softmax(),
+        self.activation.apply(&z)
+    }
+}
+impl Module for Layer {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        Layer::forward(self, input)
+    }
+
+    /// Los pesos cuantizados no son un `Tensor`: en ese caso solo el sesgo.
+    fn parameters(&self) -> Vec<&Tensor> {
+        match &self.weights {
+            LayerWeights::Float(weights) => alloc::vec![weights, &self.bias],
+            LayerWeights::Quantized(_) => alloc::vec![&self.bias],
         }
     }
-}
\ No newline at end of file
+
+    fn parameter_names(&self) -> Vec<String> {
+        match &self.weights {
+            LayerWeights::Float(_) => parameter_names(&["weight", "bias"]),
+            LayerWeights::Quantized(_) => parameter_names(&["bias"]),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Dense"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        let shape = self.weights.shape();
+        check_last_dim(input, shape[0])?;
+        let mut output = input[..input.len() - 1].to_vec();
+        output.push(shape[1]);
+        Ok(output)
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        match &mut self.weights {
+            LayerWeights::Float(weights) => alloc::vec![weights, &mut self.bias],
+            LayerWeights::Quantized(_) => alloc::vec![&mut self.bias],
+        }
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let [weights, bias] = parameters else {
+            return Err(TensorError::InvalidArgument("Los pesos cuantizados no admiten entrenamiento"));
+        };
+        let z = input.matmul(weights)?.add(bias)?;
+        self.activation.apply_tape(&z)
+    }
+
+    fn parameter_bytes(&self) -> usize {
+        Layer::parameter_bytes(self)
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        Layer::to_dtype(self, dtype)
+    }
+
+    fn quantize(&mut self, scheme: QuantScheme) -> TensorResult<()> {
+        Layer::quantize(self, scheme)
+    }
+}
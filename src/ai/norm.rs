@@ -0,0 +1,623 @@
+use super::autograd::Var;
+use super::dtype::DType;
+use super::module::{parameter_names, tape_parameters, Module};
+use super::random::Rng;
+use super::tensor::{try_buffer, Tensor, TensorError, TensorResult};
+use crate::math;
+use alloc::string::String;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+/// `1 / sqrt(v + eps)` elemento a elemento.
+fn inv_std(variance: &Tensor, eps: f32) -> TensorResult<Tensor> {
+    variance.map(|v| 1.0 / math::sqrt(v + eps))
+}
+
+/// Comprueba que `shape` termina en las dimensiones de `params`.
+fn check_trailing(shape: &[usize], params: &Tensor) -> TensorResult<()> {
+    let dims = params.shape();
+    if shape.len() < dims.len() || shape[shape.len() - dims.len()..] != *dims {
+        return Err(TensorError::ShapeMismatch { lhs: shape.to_vec(), rhs: dims.to_vec() });
+    }
+    Ok(())
+}
+
+/// Comprueba los parámetros de `LayerNorm` y `RmsNorm`: `gamma` con al
+/// menos una dimensión y un elemento, y `eps` positivo.
+fn check_normalized(gamma: &Tensor, eps: f32) -> TensorResult<()> {
+    if gamma.ndim() == 0 {
+        return Err(TensorError::RankMismatch { expected: 1, found: 0 });
+    }
+    if gamma.is_empty() {
+        return Err(TensorError::InvalidArgument("La forma normalizada no puede estar vacía"));
+    }
+    if eps.is_nan() || eps <= 0.0 {
+        return Err(TensorError::InvalidArgument("eps debe ser positivo"));
+    }
+    Ok(())
+}
+
+/// Comprueba que `input` termina en las dimensiones de `params` y devuelve
+/// la vista `[filas, elementos normalizados]`.
+fn trailing_lanes(input: &Tensor, params: &Tensor) -> TensorResult<Tensor> {
+    check_trailing(input.shape(), params)?;
+    let inner = params.len().max(1);
+    input.reshape(&[input.len() / inner, inner])
+}
+
+/// Como `trailing_lanes`, para una entrada en la cinta.
+fn trailing_lanes_tape<'t>(input: &Var<'t>, params: &Tensor) -> TensorResult<Var<'t>> {
+    let shape = input.shape();
+    check_trailing(&shape, params)?;
+    let inner = params.len().max(1);
+    input.reshape(&[shape.iter().product::<usize>() / inner, inner])
+}
+
+/// Normalización por capa: media 0 y varianza 1 sobre las últimas
+/// dimensiones de cada muestra, seguida de `gamma * x + beta`.
+pub struct LayerNorm {
+    gamma: Tensor,
+    beta: Tensor,
+    eps: f32,
+}
+
+impl LayerNorm {
+    /// Normaliza sobre las últimas dimensiones, de forma `normalized_shape`.
+    pub fn new(normalized_shape: &[usize], eps: f32) -> TensorResult<Self> {
+        LayerNorm::from_parameters(Tensor::ones(normalized_shape)?, Tensor::zeros(normalized_shape)?, eps)
+    }
+
+    pub fn from_parameters(gamma: Tensor, beta: Tensor, eps: f32) -> TensorResult<Self> {
+        check_normalized(&gamma, eps)?;
+        if gamma.shape() != beta.shape() {
+            return Err(TensorError::ShapeMismatch {
+                lhs: gamma.shape().to_vec(),
+                rhs: beta.shape().to_vec(),
+            });
+        }
+        Ok(LayerNorm { gamma, beta, eps })
+    }
+
+    pub fn gamma(&self) -> &Tensor {
+        &self.gamma
+    }
+
+    pub fn beta(&self) -> &Tensor {
+        &self.beta
+    }
+
+    pub fn eps(&self) -> f32 {
+        self.eps
+    }
+
+    pub fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        let x = trailing_lanes(&input, &self.gamma)?;
+        let inner = x.shape()[1];
+        let centered = x.sub(&x.mean(-1, true)?)?;
+        let variance = centered.mul(&centered)?.mean(-1, true)?;
+        centered
+            .mul(&inv_std(&variance, self.eps)?)?
+            .mul(&self.gamma.reshape(&[inner])?)?
+            .add(&self.beta.reshape(&[inner])?)?
+            .reshape(input.shape())
+    }
+
+    /// Como `forward`, con `gamma` y `beta` ya en la cinta de `input`.
+    pub fn forward_tape<'t>(&self, input: Var<'t>, gamma: &Var<'t>, beta: &Var<'t>) -> TensorResult<Var<'t>> {
+        let x = trailing_lanes_tape(&input, &self.gamma)?;
+        let inner = x.shape()[1];
+        let centered = x.sub(&x.mean(-1, true)?)?;
+        let variance = centered.mul(&centered)?.mean(-1, true)?;
+        centered
+            .mul(&variance.add_scalar(self.eps)?.rsqrt()?)?
+            .mul(&gamma.reshape(&[inner])?)?
+            .add(&beta.reshape(&[inner])?)?
+            .reshape(&input.shape())
+    }
+
+    pub fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        self.gamma = self.gamma.to_dtype(dtype)?;
+        self.beta = self.beta.to_dtype(dtype)?;
+        Ok(())
+    }
+
+    pub fn parameter_bytes(&self) -> usize {
+        self.gamma.size_in_bytes() + self.beta.size_in_bytes()
+    }
+}
+
+/// Normalización por la media cuadrática (RMSNorm): `x / sqrt(mean(x²) + eps) * gamma`.
+///
+/// Como `LayerNorm` pero sin centrar ni desplazar; la usan muchos modelos
+/// de lenguaje pequeños.
+pub struct RmsNorm {
+    gamma: Tensor,
+    eps: f32,
+}
+
+impl RmsNorm {
+    pub fn new(normalized_shape: &[usize], eps: f32) -> TensorResult<Self> {
+        RmsNorm::from_parameters(Tensor::ones(normalized_shape)?, eps)
+    }
+
+    /// Como `LayerNorm::from_parameters`, sin `beta`.
+    pub fn from_parameters(gamma: Tensor, eps: f32) -> TensorResult<Self> {
+        check_normalized(&gamma, eps)?;
+        Ok(RmsNorm { gamma, eps })
+    }
+
+    pub fn gamma(&self) -> &Tensor {
+        &self.gamma
+    }
+
+    pub fn eps(&self) -> f32 {
+        self.eps
+    }
+
+    pub fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        let x = trailing_lanes(&input, &self.gamma)?;
+        let inner = x.shape()[1];
+        let mean_square = x.mul(&x)?.mean(-1, true)?;
+        x.mul(&inv_std(&mean_square, self.eps)?)?
+            .mul(&self.gamma.reshape(&[inner])?)?
+            .reshape(input.shape())
+    }
+
+    /// Como `forward`, con `gamma` ya en la cinta de `input`.
+    pub fn forward_tape<'t>(&self, input: Var<'t>, gamma: &Var<'t>) -> TensorResult<Var<'t>> {
+        let x = trailing_lanes_tape(&input, &self.gamma)?;
+        let inner = x.shape()[1];
+        let mean_square = x.mul(&x)?.mean(-1, true)?;
+        x.mul(&mean_square.add_scalar(self.eps)?.rsqrt()?)?
+            .mul(&gamma.reshape(&[inner])?)?
+            .reshape(&input.shape())
+    }
+
+    pub fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        self.gamma = self.gamma.to_dtype(dtype)?;
+        Ok(())
+    }
+
+    pub fn parameter_bytes(&self) -> usize {
+        self.gamma.size_in_bytes()
+    }
+}
+
+/// Media y varianza acumuladas de `BatchNorm`.
+struct RunningStats {
+    mean: Tensor,
+    var: Tensor,
+}
+
+/// Normalización por lotes sobre el canal (eje 1) de `[N, C]` o `[N, C, ...]`.
+///
+/// En evaluación usa la media y la varianza acumuladas; en entrenamiento
+/// normaliza con las del lote y actualiza las acumuladas con
+/// `(1 - momentum) * acumulada + momentum * lote` (varianza insesgada,
+/// como PyTorch).
+pub struct BatchNorm {
+    gamma: Tensor,
+    beta: Tensor,
+    // `forward` recibe `&self`; las estadísticas cambian en entrenamiento
+    running: Mutex<RunningStats>,
+    eps: f32,
+    momentum: f32,
+    training: bool,
+}
+
+impl BatchNorm {
+    pub fn new(channels: usize, eps: f32, momentum: f32) -> TensorResult<Self> {
+        Ok(BatchNorm {
+            gamma: Tensor::ones(&[channels])?,
+            beta: Tensor::zeros(&[channels])?,
+            running: Mutex::new(RunningStats {
+                mean: Tensor::zeros(&[channels])?,
+                var: Tensor::ones(&[channels])?,
+            }),
+            eps,
+            momentum,
+            training: false,
+        })
+    }
+
+    /// Crea la capa a partir de parámetros y estadísticas ya entrenados, todos `[C]`.
+    pub fn from_parameters(
+        gamma: Tensor,
+        beta: Tensor,
+        running_mean: Tensor,
+        running_var: Tensor,
+        eps: f32,
+    ) -> TensorResult<Self> {
+        if gamma.ndim() != 1 {
+            return Err(TensorError::RankMismatch { expected: 1, found: gamma.ndim() });
+        }
+        for other in [&beta, &running_mean, &running_var] {
+            if other.shape() != gamma.shape() {
+                return Err(TensorError::ShapeMismatch {
+                    lhs: gamma.shape().to_vec(),
+                    rhs: other.shape().to_vec(),
+                });
+            }
+        }
+        Ok(BatchNorm {
+            gamma,
+            beta,
+            running: Mutex::new(RunningStats { mean: running_mean, var: running_var }),
+            eps,
+            momentum: 0.1,
+            training: false,
+        })
+    }
+
+    pub fn gamma(&self) -> &Tensor {
+        &self.gamma
+    }
+
+    pub fn beta(&self) -> &Tensor {
+        &self.beta
+    }
+
+    pub fn running_mean(&self) -> Tensor {
+        self.running.lock().mean.clone()
+    }
+
+    pub fn running_var(&self) -> Tensor {
+        self.running.lock().var.clone()
+    }
+
+    /// Sustituye la media y la varianza acumuladas, p. ej. por las de un
+    /// modelo entrenado fuera; deben ser `[C]`, como `gamma`.
+    pub fn set_running_stats(&mut self, mean: Tensor, var: Tensor) -> TensorResult<()> {
+        for stat in [&mean, &var] {
+            if stat.shape() != self.gamma.shape() {
+                return Err(TensorError::ShapeMismatch {
+                    lhs: self.gamma.shape().to_vec(),
+                    rhs: stat.shape().to_vec(),
+                });
+            }
+        }
+        *self.running.lock() = RunningStats { mean, var };
+        Ok(())
+    }
+
+    pub fn eps(&self) -> f32 {
+        self.eps
+    }
+
+    pub fn momentum(&self) -> f32 {
+        self.momentum
+    }
+
+    /// Cambia el momento de las estadísticas acumuladas (0.1 en
+    /// `from_parameters`).
+    pub fn set_momentum(&mut self, momentum: f32) {
+        self.momentum = momentum;
+    }
+
+    pub fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+    pub fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        let channels = self.gamma.len();
+        self.check_channels(input.shape())?;
+        let shape = input.shape();
+        let n = shape[0];
+        let spatial = shape[2..].iter().product::<usize>();
+        let x = input.reshape(&[n, channels, spatial])?;
+        let per_channel = [1, channels, 1];
+
+        let (mean, var) = if self.training {
+            let count = n * spatial;
+            if count == 0 {
+                return Err(TensorError::EmptyReduction);
+            }
+            // Los grupos de cada canal tienen el mismo tamaño: la media de
+            // las medias por muestra es la media del canal
+            let mean = x.mean(2, true)?.mean(0, true)?;
+            let centered = x.sub(&mean)?;
+            let var = centered.mul(&centered)?.mean(2, true)?.mean(0, true)?;
+            self.update_running(&mean, &var, count)?;
+            (mean, var)
+        } else {
+            let running = self.running.lock();
+            (running.mean.reshape(&per_channel)?, running.var.reshape(&per_channel)?)
+        };
+
+        // y = x * scale + shift, con scale = gamma / std y shift = beta - mean * scale
+        let scale = self.gamma.reshape(&per_channel)?.mul(&inv_std(&var, self.eps)?)?;
+        let shift = self.beta.reshape(&per_channel)?.sub(&mean.mul(&scale)?)?;
+        x.mul(&scale)?.add(&shift)?.reshape(input.shape())
+    }
+
+    /// Como `forward`, con `gamma` y `beta` ya en la cinta de `input`. En
+    /// entrenamiento el gradiente pasa también por la media y la varianza
+    /// del lote; en evaluación las acumuladas son constantes.
+    pub fn forward_tape<'t>(&self, input: Var<'t>, gamma: &Var<'t>, beta: &Var<'t>) -> TensorResult<Var<'t>> {
+        let channels = self.gamma.len();
+        let shape = input.shape();
+        self.check_channels(&shape)?;
+        let n = shape[0];
+        let spatial = shape[2..].iter().product::<usize>();
+        let x = input.reshape(&[n, channels, spatial])?;
+        let per_channel = [1, channels, 1];
+
+        let normalized = if self.training {
+            let count = n * spatial;
+            if count == 0 {
+                return Err(TensorError::EmptyReduction);
+            }
+            let mean = x.mean(2, true)?.mean(0, true)?;
+            let centered = x.sub(&mean)?;
+            let var = centered.mul(&centered)?.mean(2, true)?.mean(0, true)?;
+            self.update_running(&mean.value(), &var.value(), count)?;
+            centered.mul(&var.add_scalar(self.eps)?.rsqrt()?)?
+        } else {
+            let tape = input.tape();
+            let running = self.running.lock();
+            let mean = tape.var(running.mean.reshape(&per_channel)?, false);
+            let scale = tape.var(inv_std(&running.var, self.eps)?.reshape(&per_channel)?, false);
+            x.sub(&mean)?.mul(&scale)?
+        };
+        normalized
+            .mul(&gamma.reshape(&per_channel)?)?
+            .add(&beta.reshape(&per_channel)?)?
+            .reshape(&shape)
+    }
+
+    /// Mezcla la media y la varianza (sesgada) de un lote de `count`
+    /// elementos por canal en las acumuladas.
+    fn update_running(&self, mean: &Tensor, var: &Tensor, count: usize) -> TensorResult<()> {
+        let channels = self.gamma.len();
+        let unbiased = if count > 1 { count as f32 / (count - 1) as f32 } else { 1.0 };
+        let keep = 1.0 - self.momentum;
+        let mut running = self.running.lock();
+        running.mean = running.mean
+            .mul_scalar(keep)?
+            .add(&mean.reshape(&[channels])?.mul_scalar(self.momentum)?)?;
+        running.var = running.var
+            .mul_scalar(keep)?
+            .add(&var.reshape(&[channels])?.mul_scalar(self.momentum * unbiased)?)?;
+        Ok(())
+    }
+
+    fn check_channels(&self, shape: &[usize]) -> TensorResult<()> {
+        if shape.len() < 2 || shape[1] != self.gamma.len() {
+            return Err(TensorError::ShapeMismatch {
+                lhs: shape.to_vec(),
+                rhs: self.gamma.shape().to_vec(),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        self.gamma = self.gamma.to_dtype(dtype)?;
+        self.beta = self.beta.to_dtype(dtype)?;
+        Ok(())
+    }
+
+    pub fn parameter_bytes(&self) -> usize {
+        let running = self.running.lock();
+        self.gamma.size_in_bytes()
+            + self.beta.size_in_bytes()
+            + running.mean.size_in_bytes()
+            + running.var.size_in_bytes()
+    }
+}
+
+/// Dropout: en entrenamiento anula cada elemento con probabilidad `p` y
+/// escala el resto por `1 / (1 - p)`; en evaluación es la identidad.
+pub struct Dropout {
+    probability: f32,
+    // La máscara cambia en cada `forward`, que recibe `&self`
+    rng: Mutex<Rng>,
+    training: bool,
+}
+
+impl Dropout {
+    /// `probability` debe estar en `[0, 1)`; `seed` hace la secuencia de
+    /// máscaras reproducible.
+    pub fn new(probability: f32, seed: u64) -> TensorResult<Self> {
+        if !(0.0..1.0).contains(&probability) {
+            return Err(TensorError::InvalidArgument("La probabilidad de dropout debe estar en [0, 1)"));
+        }
+        Ok(Dropout {
+            probability,
+            rng: Mutex::new(Rng::new(seed)),
+            training: false,
+        })
+    }
+
+    pub fn probability(&self) -> f32 {
+        self.probability
+    }
+
+    pub fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+    pub fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        if !self.training || self.probability == 0.0 {
+            return Ok(input);
+        }
+        input.mul(&self.mask(input.shape())?)
+    }
+
+    /// Como `forward`; la máscara entra en la cinta como constante.
+    pub fn forward_tape<'t>(&self, input: Var<'t>) -> TensorResult<Var<'t>> {
+        if !self.training || self.probability == 0.0 {
+            return Ok(input);
+        }
+        let mask = self.mask(&input.shape())?;
+        input.mul(&input.tape().var(mask, false))
+    }
+
+    /// Siguiente máscara de forma `shape`: `1 / (1 - p)` en los elementos
+    /// que se conservan y 0 en el resto.
+    fn mask(&self, shape: &[usize]) -> TensorResult<Tensor> {
+        let keep = 1.0 - self.probability;
+        let scale = 1.0 / keep;
+        let len = shape.iter().product();
+        let mut rng = self.rng.lock();
+        let mut values = try_buffer(len)?;
+        values.extend((0..len).map(|_| if rng.next_f32() < keep { scale } else { 0.0 }));
+        Tensor::from_vec(values, shape)
+    }
+}
+
+impl Module for LayerNorm {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        LayerNorm::forward(self, input)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        alloc::vec![&self.gamma, &self.beta]
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        parameter_names(&["weight", "bias"])
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        alloc::vec![&mut self.gamma, &mut self.beta]
+    }
+
+    fn name(&self) -> &str {
+        "LayerNorm"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        check_trailing(input, &self.gamma)?;
+        Ok(input.to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let [gamma, beta] = tape_parameters(parameters)?;
+        LayerNorm::forward_tape(self, input, gamma, beta)
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        LayerNorm::to_dtype(self, dtype)
+    }
+}
+
+impl Module for RmsNorm {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        RmsNorm::forward(self, input)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        alloc::vec![&self.gamma]
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        parameter_names(&["weight"])
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        alloc::vec![&mut self.gamma]
+    }
+
+    fn name(&self) -> &str {
+        "RMSNorm"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        check_trailing(input, &self.gamma)?;
+        Ok(input.to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let [gamma] = tape_parameters(parameters)?;
+        RmsNorm::forward_tape(self, input, gamma)
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        RmsNorm::to_dtype(self, dtype)
+    }
+}
+
+impl Module for BatchNorm {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        BatchNorm::forward(self, input)
+    }
+
+    /// Solo `gamma` y `beta`; las estadísticas acumuladas no se entrenan.
+    fn parameters(&self) -> Vec<&Tensor> {
+        alloc::vec![&self.gamma, &self.beta]
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        parameter_names(&["weight", "bias"])
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        alloc::vec![&mut self.gamma, &mut self.beta]
+    }
+
+    fn name(&self) -> &str {
+        "BatchNorm"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        self.check_channels(input)?;
+        Ok(input.to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let [gamma, beta] = tape_parameters(parameters)?;
+        BatchNorm::forward_tape(self, input, gamma, beta)
+    }
+
+    fn parameter_bytes(&self) -> usize {
+        BatchNorm::parameter_bytes(self)
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        BatchNorm::to_dtype(self, dtype)
+    }
+
+    fn set_training(&mut self, training: bool) {
+        BatchNorm::set_training(self, training);
+    }
+
+    /// La media y la varianza acumuladas.
+    fn buffers(&self) -> Vec<Tensor> {
+        let running = self.running.lock();
+        alloc::vec![running.mean.clone(), running.var.clone()]
+    }
+
+    fn set_buffers(&mut self, values: &[Tensor]) -> TensorResult<()> {
+        let [mean, var] = values else {
+            return Err(TensorError::LengthMismatch { expected: 2, found: values.len() });
+        };
+        self.set_running_stats(mean.clone(), var.clone())
+    }
+}
+
+impl Module for Dropout {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        Dropout::forward(self, input)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        "Dropout"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        Ok(input.to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, _parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        Dropout::forward_tape(self, input)
+    }
+
+    fn set_training(&mut self, training: bool) {
+        Dropout::set_training(self, training);
+    }
+}
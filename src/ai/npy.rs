@@ -0,0 +1,564 @@
+use super::dtype::DType;
+use super::format::{format_error, ByteReader};
+use super::serialize::crc32;
+use super::tensor::{try_buffer, Tensor, TensorError, TensorResult};
+use crate::serial_println;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use half::f16;
+
+const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+/// Múltiplo al que NumPy alinea el inicio de los datos.
+const NPY_ALIGNMENT: usize = 64;
+
+const ZIP_LOCAL_HEADER: u32 = 0x0403_4b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x0201_4b50;
+const ZIP_END_OF_DIRECTORY: u32 = 0x0605_4b50;
+
+/// Identificador del campo extra con los tamaños de 64 bits de zip64.
+const ZIP64_EXTRA: u16 = 0x0001;
+
+/// Bytes de `.npy` por línea del volcado serie (76 caracteres en base64).
+const DUMP_LINE_BYTES: usize = 57;
+
+/// Errores al leer un `.npy` o un `.npz`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NpyError {
+    /// El fichero no empieza por `\x93NUMPY`.
+    BadMagic,
+    UnsupportedVersion { major: u8, minor: u8 },
+    /// La cabecera no es el diccionario de Python que escribe NumPy.
+    InvalidHeader(&'static str),
+    /// `descr` de un tipo que no se puede convertir a `Tensor`.
+    UnsupportedDType(String),
+    /// El fichero es más corto que lo que anuncia su cabecera.
+    Truncated { expected: usize, found: usize },
+    /// El `.npz` no es un zip válido o usa algo no soportado (compresión,
+    /// cifrado...).
+    InvalidArchive(&'static str),
+    /// El CRC32 de una entrada del `.npz` no coincide con su contenido.
+    ChecksumMismatch { name: String, expected: u32, found: u32 },
+    /// El `.npz` no tiene un array con ese nombre.
+    MissingArray(String),
+    /// El texto de `base64_decode` no es base64 estándar.
+    InvalidBase64,
+    Tensor(TensorError),
+}
+
+format_error!(NpyError {
+    NpyError::BadMagic => "El fichero no es un .npy",
+    NpyError::UnsupportedVersion { .. } => "Versión de .npy no soportada",
+    NpyError::InvalidHeader(reason) | NpyError::InvalidArchive(reason) => reason,
+    NpyError::UnsupportedDType(_) => "Tipo de elemento de .npy no soportado",
+    NpyError::Truncated { .. } => "Fichero .npy truncado",
+    NpyError::ChecksumMismatch { .. } => "El CRC32 de la entrada del .npz no coincide",
+    NpyError::MissingArray(_) => "El .npz no contiene el array",
+    NpyError::InvalidBase64 => "Texto base64 no válido",
+});
+
+impl NpyError {
+    fn write_details(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NpyError::UnsupportedVersion { major, minor } => write!(f, ": {}.{}", major, minor),
+            NpyError::UnsupportedDType(descr) | NpyError::MissingArray(descr) => write!(f, ": {}", descr),
+            NpyError::Truncated { expected, found } => {
+                write!(f, ": se esperaban {} bytes, hay {}", expected, found)
+            }
+            NpyError::ChecksumMismatch { name, expected, found } => {
+                write!(f, " {}: se esperaba {:08x}, es {:08x}", name, expected, found)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Tipo de elemento de `descr`, p. ej. `<f4` o `|u1`.
+#[derive(Clone, Copy)]
+struct Descr {
+    big_endian: bool,
+    kind: u8,
+    size: usize,
+}
+
+impl Descr {
+    fn parse(descr: &str) -> Result<Descr, NpyError> {
+        let unsupported = || NpyError::UnsupportedDType(descr.to_string());
+        let bytes = descr.as_bytes();
+        let (&order, rest) = bytes.split_first().ok_or_else(unsupported)?;
+        let (&kind, size) = rest.split_first().ok_or_else(unsupported)?;
+        let size: usize = core::str::from_utf8(size).ok().and_then(|size| size.parse().ok()).ok_or_else(unsupported)?;
+        let big_endian = match order {
+            // `=` es el orden nativo, little-endian en x86
+            b'<' | b'|' | b'=' => false,
+            b'>' => true,
+            _ => return Err(unsupported()),
+        };
+        match (kind, size) {
+            (b'f', 2 | 4 | 8) | (b'i' | b'u', 1 | 2 | 4 | 8) | (b'b', 1) => Ok(Descr { big_endian, kind, size }),
+            _ => Err(unsupported()),
+        }
+    }
+
+    /// Convierte un elemento (`size` bytes en el orden del fichero) a `f32`.
+    fn decode(self, bytes: &[u8]) -> f32 {
+        let mut word = [0; 8];
+        word[..self.size].copy_from_slice(bytes);
+        if self.big_endian {
+            word[..self.size].reverse();
+        }
+        match (self.kind, self.size) {
+            (b'f', 2) => f16::from_le_bytes([word[0], word[1]]).to_f32(),
+            (b'f', 4) => f32::from_le_bytes([word[0], word[1], word[2], word[3]]),
+            (b'f', _) => f64::from_le_bytes(word) as f32,
+            (b'i', 1) => word[0] as i8 as f32,
+            (b'i', 2) => i16::from_le_bytes([word[0], word[1]]) as f32,
+            (b'i', 4) => i32::from_le_bytes([word[0], word[1], word[2], word[3]]) as f32,
+            (b'i', _) => i64::from_le_bytes(word) as f32,
+            // Enteros sin signo y booleanos
+            _ => u64::from_le_bytes(word) as f32,
+        }
+    }
+}
+
+/// Campos del diccionario de la cabecera de un `.npy`.
+struct Header {
+    descr: String,
+    fortran_order: bool,
+    shape: Vec<usize>,
+}
+
+/// Lector del literal de Python de la cabecera, p. ej.
+/// `{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }`.
+struct HeaderParser<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> HeaderParser<'a> {
+    fn parse(text: &'a [u8]) -> Result<Header, NpyError> {
+        let mut parser = HeaderParser { text, pos: 0 };
+        let (mut descr, mut fortran_order, mut shape) = (None, None, None);
+        parser.expect(b'{')?;
+        while !parser.eat(b'}') {
+            let key = parser.string()?;
+            parser.expect(b':')?;
+            match key {
+                "descr" => descr = Some(parser.string()?.to_string()),
+                "fortran_order" => fortran_order = Some(parser.boolean()?),
+                "shape" => shape = Some(parser.tuple()?),
+                _ => return Err(NpyError::InvalidHeader("Clave desconocida en la cabecera .npy")),
+            }
+            if !parser.eat(b',') {
+                parser.expect(b'}')?;
+                break;
+            }
+        }
+        match (descr, fortran_order, shape) {
+            (Some(descr), Some(fortran_order), Some(shape)) => Ok(Header { descr, fortran_order, shape }),
+            _ => Err(NpyError::InvalidHeader("Faltan claves en la cabecera .npy")),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.text.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume `byte` (tras los espacios) si es el siguiente.
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.text.get(self.pos) == Some(&byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), NpyError> {
+        if self.eat(byte) {
+            Ok(())
+        } else {
+            Err(NpyError::InvalidHeader("Cabecera .npy mal formada"))
+        }
+    }
+
+    /// Cadena entre comillas simples o dobles, sin secuencias de escape.
+    fn string(&mut self) -> Result<&'a str, NpyError> {
+        self.skip_whitespace();
+        let quote = match self.text.get(self.pos) {
+            Some(&quote @ (b'\'' | b'"')) => quote,
+            // Los tipos estructurados se describen con una lista
+            Some(b'[') => return Err(NpyError::UnsupportedDType(String::from("estructurado"))),
+            _ => return Err(NpyError::InvalidHeader("Se esperaba una cadena en la cabecera .npy")),
+        };
+        let start = self.pos + 1;
+        let len = self.text[start..]
+            .iter()
+            .position(|&byte| byte == quote)
+            .ok_or(NpyError::InvalidHeader("Cadena sin cerrar en la cabecera .npy"))?;
+        self.pos = start + len + 1;
+        core::str::from_utf8(&self.text[start..start + len])
+            .map_err(|_| NpyError::InvalidHeader("Cabecera .npy que no es UTF-8"))
+    }
+
+    fn boolean(&mut self) -> Result<bool, NpyError> {
+        self.skip_whitespace();
+        let rest = &self.text[self.pos..];
+        let (value, len) = if rest.starts_with(b"True") {
+            (true, 4)
+        } else if rest.starts_with(b"False") {
+            (false, 5)
+        } else {
+            return Err(NpyError::InvalidHeader("fortran_order debe ser True o False"));
+        };
+        self.pos += len;
+        Ok(value)
+    }
+
+    /// Tupla de enteros: `()`, `(3,)` o `(2, 3)`.
+    fn tuple(&mut self) -> Result<Vec<usize>, NpyError> {
+        self.expect(b'(')?;
+        let mut values = Vec::new();
+        while !self.eat(b')') {
+            self.skip_whitespace();
+            let digits = self.text[self.pos..].iter().take_while(|byte| byte.is_ascii_digit()).count();
+            let value = core::str::from_utf8(&self.text[self.pos..self.pos + digits])
+                .ok()
+                .and_then(|digits| digits.parse().ok())
+                .ok_or(NpyError::InvalidHeader("Dimensión no válida en la cabecera .npy"))?;
+            self.pos += digits;
+            values.push(value);
+            if !self.eat(b',') {
+                self.expect(b')')?;
+                break;
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// `shape` como tupla de Python.
+fn shape_tuple(shape: &[usize]) -> String {
+    match shape {
+        [dim] => format!("({},)", dim),
+        _ => {
+            let dims: Vec<String> = shape.iter().map(|dim| dim.to_string()).collect();
+            format!("({})", dims.join(", "))
+        }
+    }
+}
+
+impl Tensor {
+    /// Serializa el tensor en formato `.npy` (versión 1.0, orden C), para
+    /// leerlo con `numpy.load`.
+    ///
+    /// Los `F32` se escriben como `<f4` y los `F16` como `<f2`; NumPy no
+    /// tiene `bfloat16`, así que los `BF16` se escriben como `<f4`.
+    pub fn to_npy(&self) -> TensorResult<Vec<u8>> {
+        let descr = match self.dtype() {
+            DType::F16 => "<f2",
+            DType::F32 | DType::BF16 => "<f4",
+        };
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+            descr,
+            shape_tuple(self.shape())
+        );
+        // Espacios hasta alinear los datos, más el salto de línea final
+        let prefix_len = NPY_MAGIC.len() + 4;
+        let unpadded = prefix_len + header.len() + 1;
+        let padding = (NPY_ALIGNMENT - unpadded % NPY_ALIGNMENT) % NPY_ALIGNMENT;
+        header.extend(core::iter::repeat_n(' ', padding));
+        header.push('\n');
+        let header_len = u16::try_from(header.len())
+            .map_err(|_| TensorError::InvalidArgument("La cabecera .npy no cabe en la versión 1.0"))?;
+
+        let element_size = if self.dtype() == DType::F16 { 2 } else { 4 };
+        let mut bytes = try_buffer(prefix_len + header.len() + self.len() * element_size)?;
+        bytes.extend_from_slice(NPY_MAGIC);
+        bytes.extend_from_slice(&[1, 0]);
+        bytes.extend_from_slice(&header_len.to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        for value in self.iter() {
+            match self.dtype() {
+                // Exacto: el valor viene de un `f16`
+                DType::F16 => bytes.extend_from_slice(&f16::from_f32(value).to_le_bytes()),
+                DType::F32 | DType::BF16 => bytes.extend_from_slice(&value.to_le_bytes()),
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Lee un `.npy` de las versiones 1.0 a 3.0.
+    ///
+    /// Los `<f2` dan un tensor `F16`; los demás tipos de coma flotante,
+    /// enteros y booleanos (en cualquier orden de bytes) se convierten a
+    /// `f32`. Los arrays en orden Fortran se reordenan a orden C.
+    pub fn from_npy(bytes: &[u8]) -> Result<Tensor, NpyError> {
+        if !bytes.starts_with(NPY_MAGIC) {
+            return Err(if NPY_MAGIC.starts_with(bytes) {
+                NpyError::Truncated { expected: NPY_MAGIC.len(), found: bytes.len() }
+            } else {
+                NpyError::BadMagic
+            });
+        }
+        let mut reader = ByteReader::new(bytes, |expected, found| NpyError::Truncated { expected, found });
+        reader.seek(NPY_MAGIC.len());
+        let (major, minor) = (reader.u8()?, reader.u8()?);
+        let header_len = match (major, minor) {
+            (1, 0) => reader.u16()? as usize,
+            (2 | 3, 0) => reader.u32()? as usize,
+            _ => return Err(NpyError::UnsupportedVersion { major, minor }),
+        };
+        let header = HeaderParser::parse(reader.take(header_len)?)?;
+        let descr = Descr::parse(&header.descr)?;
+
+        let len = header.shape
+            .iter()
+            .try_fold(1usize, |acc, &dim| acc.checked_mul(dim))
+            .ok_or(NpyError::InvalidHeader("Array .npy demasiado grande"))?;
+        let data_len = len.checked_mul(descr.size).ok_or(NpyError::InvalidHeader("Array .npy demasiado grande"))?;
+        let data = reader.take(data_len)?;
+        if !reader.is_empty() {
+            return Err(NpyError::InvalidHeader("Sobran bytes tras los datos del .npy"));
+        }
+
+        // En orden Fortran los datos son los de la forma invertida en orden C
+        let mut shape = header.shape.clone();
+        if header.fortran_order {
+            shape.reverse();
+        }
+        let elements = data.chunks_exact(descr.size);
+        let tensor = if (descr.kind, descr.size) == (b'f', 2) {
+            let mut values = try_buffer(len)?;
+            values.extend(elements.map(|element| f16::from_f32(descr.decode(element))));
+            Tensor::from_f16(values, &shape)?
+        } else {
+            let mut values = try_buffer(len)?;
+            values.extend(elements.map(|element| descr.decode(element)));
+            Tensor::from_vec(values, &shape)?
+        };
+        if !header.fortran_order || shape.len() < 2 {
+            return Ok(tensor);
+        }
+        let axes: Vec<usize> = (0..shape.len()).rev().collect();
+        Ok(tensor.permute(&axes)?.contiguous()?)
+    }
+}
+
+/// Archivo `.npz` (el de `numpy.savez`): un zip con un `.npy` por array.
+///
+/// Solo se admiten entradas sin comprimir, así que los de
+/// `numpy.savez_compressed` se rechazan. Al leerlo se comprueba el CRC32 de
+/// cada entrada; los arrays se convierten a `Tensor` al pedirlos.
+pub struct NpzArchive<'a> {
+    entries: Vec<(String, &'a [u8])>,
+}
+
+impl<'a> NpzArchive<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, NpyError> {
+        let invalid = NpyError::InvalidArchive;
+        let mut zip = ByteReader::new(bytes, |_, _| NpyError::InvalidArchive("Directorio central truncado"));
+        // El registro final mide 22 bytes más un comentario de hasta 64 KiB
+        let last = bytes.len().checked_sub(22).ok_or(invalid("El .npz es demasiado corto"))?;
+        let end = (last.saturating_sub(u16::MAX as usize)..=last)
+            .rev()
+            .find(|&offset| {
+                zip.seek(offset);
+                zip.u32().ok() == Some(ZIP_END_OF_DIRECTORY)
+            })
+            .ok_or(invalid("El .npz no tiene directorio central"))?;
+        // El registro final está entero: `end` es como mucho `last`
+        zip.seek(end + 10);
+        let count = zip.u16()?;
+        zip.seek(end + 16);
+        let directory = zip.u32()?;
+        if count == u16::MAX || directory == u32::MAX {
+            return Err(invalid("Los .npz con directorio zip64 no están soportados"));
+        }
+
+        let mut entries = Vec::new();
+        let mut offset = directory as usize;
+        for _ in 0..count {
+            zip.seek(offset);
+            if zip.u32().ok() != Some(ZIP_CENTRAL_HEADER) {
+                return Err(invalid("Entrada del directorio central no válida"));
+            }
+            zip.seek(offset + 8);
+            let (flags, method) = (zip.u16()?, zip.u16()?);
+            zip.seek(offset + 16);
+            let (crc, compressed, size) = (zip.u32()?, zip.u32()?, zip.u32()?);
+            let (name_len, extra_len, comment_len) = (zip.u16()? as usize, zip.u16()? as usize, zip.u16()? as usize);
+            zip.seek(offset + 42);
+            let local = zip.u32()?;
+            let (name, extra) = (zip.take(name_len)?, zip.take(extra_len)?);
+            let name = core::str::from_utf8(name).map_err(|_| invalid("Nombre de entrada que no es UTF-8"))?;
+            if flags & 1 != 0 {
+                return Err(invalid("Las entradas cifradas no están soportadas"));
+            }
+            if method != 0 {
+                return Err(invalid("Solo se admiten .npz sin comprimir (numpy.savez)"));
+            }
+
+            let (compressed, size, local) = zip64_sizes(extra, compressed, size, local)?;
+            if compressed != size {
+                return Err(invalid("Tamaños de entrada incoherentes"));
+            }
+            let data = local_data(bytes, local, size)?;
+            let found = crc32(data);
+            if found != crc {
+                return Err(NpyError::ChecksumMismatch { name: name.to_string(), expected: crc, found });
+            }
+            entries.push((name.strip_suffix(".npy").unwrap_or(name).to_string(), data));
+            offset = zip.position() + comment_len;
+        }
+        Ok(NpzArchive { entries })
+    }
+
+    /// Nombres de los arrays (los de `numpy.savez`, sin `.npy`), en el orden
+    /// del archivo.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Convierte el array `name` con `Tensor::from_npy`.
+    pub fn tensor(&self, name: &str) -> Result<Tensor, NpyError> {
+        let (_, data) = self.entries
+            .iter()
+            .find(|(entry, _)| entry == name)
+            .ok_or_else(|| NpyError::MissingArray(name.to_string()))?;
+        Tensor::from_npy(data)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Sustituye los tamaños y la posición que valen `u32::MAX` por los del
+/// campo extra zip64; NumPy lo añade a todas sus entradas.
+fn zip64_sizes(extra: &[u8], compressed: u32, size: u32, local: u32) -> Result<(usize, usize, usize), NpyError> {
+    let mut values = [size as u64, compressed as u64, local as u64];
+    let mut fields = ByteReader::new(extra, |_, _| NpyError::InvalidArchive("Campo extra del zip truncado"));
+    while !fields.is_empty() {
+        let (id, len) = (fields.u16()?, fields.u16()?);
+        let body = fields.take(len as usize)?;
+        if id == ZIP64_EXTRA {
+            // Solo aparecen, en este orden, los campos que no caben en 32 bits
+            let mut body = ByteReader::new(body, |_, _| NpyError::InvalidArchive("Campo zip64 truncado"));
+            for value in values.iter_mut().filter(|value| **value == u32::MAX as u64) {
+                *value = body.u64()?;
+            }
+        }
+    }
+    let [size, compressed, local] = values.map(|value| usize::try_from(value).unwrap_or(usize::MAX));
+    Ok((compressed, size, local))
+}
+
+/// Datos de la entrada cuya cabecera local está en `offset`.
+fn local_data(bytes: &[u8], offset: usize, size: usize) -> Result<&[u8], NpyError> {
+    let mut header = ByteReader::new(bytes, |_, _| NpyError::InvalidArchive("Cabecera local del zip no válida"));
+    header.seek(offset);
+    if header.u32()? != ZIP_LOCAL_HEADER {
+        return Err(NpyError::InvalidArchive("Cabecera local del zip no válida"));
+    }
+    header.seek(offset + 26);
+    let (name_len, extra_len) = (header.u16()? as usize, header.u16()? as usize);
+    let start = header.position() + name_len + extra_len;
+    start
+        .checked_add(size)
+        .and_then(|end| bytes.get(start..end))
+        .ok_or(NpyError::Truncated { expected: start.saturating_add(size), found: bytes.len() })
+}
+
+/// Vuelca `tensor` como `.npy` por el puerto serie, en base64, para
+/// recuperarlo en el host:
+///
+/// ```text
+/// NPY-BEGIN <nombre> <bytes> <crc32>
+/// <base64, 76 caracteres por línea>
+/// NPY-END <nombre>
+/// ```
+///
+/// Basta con unir las líneas intermedias, decodificarlas con
+/// `base64.b64decode` y pasarlas a `numpy.load(io.BytesIO(...))`.
+pub fn dump_npy(name: &str, tensor: &Tensor) -> TensorResult<()> {
+    for line in dump_lines(name, &tensor.to_npy()?)? {
+        serial_println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Líneas que `dump_npy` envía por el puerto serie para los bytes `bytes`
+/// de un `.npy`.
+pub fn dump_lines(name: &str, bytes: &[u8]) -> TensorResult<Vec<String>> {
+    let mut lines = try_buffer(bytes.len().div_ceil(DUMP_LINE_BYTES) + 2)?;
+    lines.push(format!("NPY-BEGIN {} {} {:08x}", name, bytes.len(), crc32(bytes)));
+    let mut line = [0; DUMP_LINE_BYTES / 3 * 4];
+    for chunk in bytes.chunks(DUMP_LINE_BYTES) {
+        let len = base64_encode(chunk, &mut line);
+        // El alfabeto de base64 es ASCII
+        lines.push(String::from(core::str::from_utf8(&line[..len]).unwrap_or_default()));
+    }
+    lines.push(format!("NPY-END {}", name));
+    Ok(lines)
+}
+
+/// Decodifica base64 estándar (con `=`) ignorando espacios y saltos de
+/// línea, así que acepta tanto la salida de `base64.b64encode` como las
+/// líneas de datos de `dump_lines` unidas.
+pub fn base64_decode(input: &[u8]) -> Result<Vec<u8>, NpyError> {
+    let mut bytes = try_buffer(input.len() / 4 * 3)?;
+    let (mut word, mut count, mut padding) = (0u32, 0, 0);
+    for &c in input.iter().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            // Tras un grupo con `=` no puede venir nada más
+            _ if padding > 0 && (count == 0 || c != b'=') => return Err(NpyError::InvalidBase64),
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' if count >= 2 => 0,
+            _ => return Err(NpyError::InvalidBase64),
+        };
+        padding += (c == b'=') as usize;
+        word = word << 6 | value as u32;
+        count += 1;
+        if count == 4 {
+            bytes.extend_from_slice(&word.to_be_bytes()[1..4 - padding]);
+            (word, count) = (0, 0);
+        }
+    }
+    if count != 0 {
+        return Err(NpyError::InvalidBase64);
+    }
+    Ok(bytes)
+}
+
+/// Codifica `input` en base64 estándar (con `=`) y devuelve los bytes
+/// escritos en `out`.
+pub(crate) fn base64_encode(input: &[u8], out: &mut [u8]) -> usize {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut len = 0;
+    for group in input.chunks(3) {
+        let word = group.iter().enumerate().fold(0u32, |word, (i, &byte)| word | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            out[len + i] = if i <= group.len() {
+                ALPHABET[(word >> (18 - 6 * i) & 0x3f) as usize]
+            } else {
+                b'='
+            };
+        }
+        len += 4;
+    }
+    len
+}
@@ -0,0 +1,952 @@
+use super::autograd::Var;
+use super::conv::{Conv2d, Conv2dParams, Flatten, MaxPool2d, Pool2dParams};
+use super::dtype::DType;
+use super::format::{format_error, ByteReader};
+use super::graph::{GraphModel, GraphOp};
+use super::module::{parameter_names, tape_parameters, Module};
+use super::nn::{ActivationFunction, Layer, NeuralNetwork};
+use super::tensor::{broadcast_shapes, normalize_axis, try_buffer, Tensor, TensorError, TensorResult};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use half::{bf16, f16};
+
+/// Primera versión del conjunto de operadores estándar en la que `Softmax`
+/// normaliza solo el eje `axis` en lugar de aplanar la entrada a 2D.
+const SOFTMAX_PER_AXIS_OPSET: i64 = 13;
+
+/// Errores al importar un modelo ONNX.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OnnxError {
+    /// El fichero no es un mensaje protobuf bien formado.
+    Decode(&'static str),
+    /// El mensaje se lee bien pero no describe un grafo válido.
+    InvalidModel(&'static str),
+    /// Operador sin equivalente en el kernel.
+    UnsupportedOperator(String),
+    /// Operador soportado con atributos o entradas que no lo están.
+    UnsupportedUsage { operator: String, reason: &'static str },
+    /// Código `TensorProto.DataType` que no se puede convertir a `Tensor`.
+    UnsupportedDataType(i32),
+    /// Un nodo lee un valor que no es entrada, inicializador ni salida de
+    /// un nodo anterior.
+    UnknownValue(String),
+    Tensor(TensorError),
+}
+
+format_error!(OnnxError {
+    OnnxError::Decode(reason) | OnnxError::InvalidModel(reason) => reason,
+    OnnxError::UnsupportedOperator(_) => "Operador de ONNX no soportado",
+    OnnxError::UnsupportedUsage { .. } => "Uso no soportado de un operador de ONNX",
+    OnnxError::UnsupportedDataType(_) => "Tipo de elemento de ONNX no soportado",
+    OnnxError::UnknownValue(_) => "Valor de ONNX no definido",
+});
+
+impl OnnxError {
+    fn write_details(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OnnxError::UnsupportedOperator(name) | OnnxError::UnknownValue(name) => write!(f, ": {}", name),
+            OnnxError::UnsupportedUsage { operator, reason } => write!(f, " {}: {}", operator, reason),
+            OnnxError::UnsupportedDataType(code) => write!(f, ": {}", code),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Valor de un campo protobuf según su codificación en el cable.
+#[derive(Clone, Copy)]
+enum Wire<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+impl<'a> Wire<'a> {
+    fn bytes(self) -> Result<&'a [u8], OnnxError> {
+        match self {
+            Wire::Bytes(bytes) => Ok(bytes),
+            _ => Err(OnnxError::Decode("Se esperaba un campo de longitud variable")),
+        }
+    }
+
+    fn string(self) -> Result<String, OnnxError> {
+        core::str::from_utf8(self.bytes()?)
+            .map(String::from)
+            .map_err(|_| OnnxError::Decode("Cadena protobuf que no es UTF-8"))
+    }
+
+    fn int(self) -> Result<i64, OnnxError> {
+        match self {
+            // Los negativos se codifican en complemento a dos con 10 bytes
+            Wire::Varint(value) => Ok(value as i64),
+            _ => Err(OnnxError::Decode("Se esperaba un entero varint")),
+        }
+    }
+
+    fn float(self) -> Result<f32, OnnxError> {
+        match self {
+            Wire::Fixed32(bits) => Ok(f32::from_bits(bits)),
+            _ => Err(OnnxError::Decode("Se esperaba un float de 32 bits")),
+        }
+    }
+
+    /// Añade a `out` un elemento de un `repeated int64/int32`, empaquetado
+    /// (todos en un bloque) o no.
+    fn push_ints(self, out: &mut Vec<i64>) -> Result<(), OnnxError> {
+        match self {
+            Wire::Bytes(bytes) => {
+                let mut reader = ProtoReader::open(bytes);
+                while !reader.is_empty() {
+                    out.push(reader.varint()? as i64);
+                }
+                Ok(())
+            }
+            value => {
+                out.push(value.int()?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Como `push_ints`, para `repeated float`.
+    fn push_floats(self, out: &mut Vec<f32>) -> Result<(), OnnxError> {
+        match self {
+            Wire::Bytes(bytes) => {
+                let mut reader = ProtoReader::open(bytes);
+                while !reader.is_empty() {
+                    out.push(reader.f32()?);
+                }
+                Ok(())
+            }
+            value => {
+                out.push(value.float()?);
+                Ok(())
+            }
+        }
+    }
+
+    /// Como `push_ints`, para `repeated double`.
+    fn push_doubles(self, out: &mut Vec<f64>) -> Result<(), OnnxError> {
+        match self {
+            Wire::Bytes(bytes) => {
+                let mut reader = ProtoReader::open(bytes);
+                while !reader.is_empty() {
+                    out.push(reader.f64()?);
+                }
+                Ok(())
+            }
+            Wire::Fixed64(bits) => {
+                out.push(f64::from_bits(bits));
+                Ok(())
+            }
+            _ => Err(OnnxError::Decode("Se esperaba un double de 64 bits")),
+        }
+    }
+}
+
+/// Lector de los campos de un mensaje protobuf, sin copiar los de longitud
+/// variable.
+type ProtoReader<'a> = ByteReader<'a, OnnxError>;
+
+impl<'a> ProtoReader<'a> {
+    fn open(bytes: &'a [u8]) -> Self {
+        ByteReader::new(bytes, |_, _| OnnxError::Decode("Mensaje protobuf truncado"))
+    }
+
+    fn varint(&mut self) -> Result<u64, OnnxError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(OnnxError::Decode("Varint de más de 10 bytes"))
+    }
+
+    /// Siguiente campo como `(número, valor)`, o `None` al final del mensaje.
+    fn field(&mut self) -> Result<Option<(u64, Wire<'a>)>, OnnxError> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 7 {
+            0 => Wire::Varint(self.varint()?),
+            1 => Wire::Fixed64(self.u64()?),
+            2 => {
+                let len = usize::try_from(self.varint()?).unwrap_or(usize::MAX);
+                Wire::Bytes(self.take(len)?)
+            }
+            5 => Wire::Fixed32(self.u32()?),
+            // 3 y 4 son los grupos, obsoletos y ausentes en ONNX
+            _ => return Err(OnnxError::Decode("Tipo de campo protobuf no soportado")),
+        };
+        match key >> 3 {
+            0 => Err(OnnxError::Decode("Número de campo protobuf 0")),
+            number => Ok(Some((number, value))),
+        }
+    }
+}
+
+/// Códigos de `TensorProto.DataType`.
+mod data_type {
+    pub const FLOAT: i32 = 1;
+    pub const INT32: i32 = 6;
+    pub const INT64: i32 = 7;
+    pub const FLOAT16: i32 = 10;
+    pub const DOUBLE: i32 = 11;
+    pub const BFLOAT16: i32 = 16;
+}
+
+/// Tensor de `onnx.proto`. Como en los demás mensajes, solo se leen los
+/// campos que usa la importación.
+#[derive(Default)]
+struct TensorProto<'a> {
+    name: String,
+    dims: Vec<i64>,
+    data_type: i32,
+    raw_data: Option<&'a [u8]>,
+    float_data: Vec<f32>,
+    // También guarda los bits de los `FLOAT16` y `BFLOAT16`
+    int32_data: Vec<i64>,
+    int64_data: Vec<i64>,
+    double_data: Vec<f64>,
+    external: bool,
+}
+
+impl<'a> TensorProto<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, OnnxError> {
+        let mut tensor = TensorProto::default();
+        let mut reader = ProtoReader::open(bytes);
+        while let Some((number, value)) = reader.field()? {
+            match number {
+                1 => value.push_ints(&mut tensor.dims)?,
+                2 => tensor.data_type = value.int()? as i32,
+                4 => value.push_floats(&mut tensor.float_data)?,
+                5 => value.push_ints(&mut tensor.int32_data)?,
+                7 => value.push_ints(&mut tensor.int64_data)?,
+                8 => tensor.name = value.string()?,
+                9 => tensor.raw_data = Some(value.bytes()?),
+                10 => value.push_doubles(&mut tensor.double_data)?,
+                // `data_location = EXTERNAL`
+                14 => tensor.external = value.int()? == 1,
+                _ => {}
+            }
+        }
+        Ok(tensor)
+    }
+
+    fn shape(&self) -> Result<Vec<usize>, OnnxError> {
+        self.dims
+            .iter()
+            .map(|&dim| usize::try_from(dim).map_err(|_| OnnxError::InvalidModel("Tensor con una dimensión negativa")))
+            .collect()
+    }
+
+    /// Convierte el tensor: los de coma flotante a `Tensor` (los `FLOAT16` y
+    /// `BFLOAT16` conservan su tipo) y los enteros a `Vec<i64>`.
+    fn to_value(&self) -> Result<Value, OnnxError> {
+        if self.external {
+            return Err(OnnxError::InvalidModel("Los tensores con datos externos no están soportados"));
+        }
+        let shape = self.shape()?;
+        let len = shape
+            .iter()
+            .try_fold(1usize, |acc, &dim| acc.checked_mul(dim))
+            .ok_or(OnnxError::InvalidModel("Tensor demasiado grande"))?;
+        Ok(match self.data_type {
+            data_type::FLOAT => {
+                let values = self.collect(len, &self.float_data, |&value| value, f32::from_le_bytes)?;
+                Value::Float(Tensor::from_vec(values, &shape)?)
+            }
+            data_type::DOUBLE => {
+                let values = self.collect(len, &self.double_data, |&value| value as f32, |bytes| {
+                    f64::from_le_bytes(bytes) as f32
+                })?;
+                Value::Float(Tensor::from_vec(values, &shape)?)
+            }
+            data_type::FLOAT16 => {
+                let values = self.collect(len, &self.int32_data, |&bits| f16::from_bits(bits as u16), |bytes| {
+                    f16::from_bits(u16::from_le_bytes(bytes))
+                })?;
+                Value::Float(Tensor::from_f16(values, &shape)?)
+            }
+            data_type::BFLOAT16 => {
+                let values = self.collect(len, &self.int32_data, |&bits| bf16::from_bits(bits as u16), |bytes| {
+                    bf16::from_bits(u16::from_le_bytes(bytes))
+                })?;
+                Value::Float(Tensor::from_bf16(values, &shape)?)
+            }
+            data_type::INT64 => Value::Int(self.collect(len, &self.int64_data, |&value| value, i64::from_le_bytes)?),
+            data_type::INT32 => Value::Int(self.collect(len, &self.int32_data, |&value| value, |bytes| {
+                i64::from(i32::from_le_bytes(bytes))
+            })?),
+            code => return Err(OnnxError::UnsupportedDataType(code)),
+        })
+    }
+
+    /// Los `len` elementos del tensor, de `raw_data` (elementos de `N` bytes
+    /// little-endian) si está o del campo tipado `typed`.
+    fn collect<S, T, const N: usize>(
+        &self,
+        len: usize,
+        typed: &[S],
+        convert: fn(&S) -> T,
+        decode: fn([u8; N]) -> T,
+    ) -> Result<Vec<T>, OnnxError> {
+        let found = self.raw_data.map_or(typed.len(), |raw| raw.len() / N);
+        let exact = self.raw_data.is_none_or(|raw| raw.len().is_multiple_of(N));
+        if found != len || !exact {
+            return Err(OnnxError::InvalidModel("Los datos del tensor no coinciden con su forma"));
+        }
+        let mut values = try_buffer(len)?;
+        match self.raw_data {
+            Some(raw) => values.extend(raw.chunks_exact(N).map(|chunk| {
+                let mut bytes = [0; N];
+                bytes.copy_from_slice(chunk);
+                decode(bytes)
+            })),
+            None => values.extend(typed.iter().map(convert)),
+        }
+        Ok(values)
+    }
+}
+
+#[derive(Default)]
+struct AttributeProto {
+    name: String,
+    float: Option<f32>,
+    int: Option<i64>,
+    string: Option<String>,
+    floats: Vec<f32>,
+    ints: Vec<i64>,
+}
+
+impl AttributeProto {
+    fn parse(bytes: &[u8]) -> Result<Self, OnnxError> {
+        let mut attribute = AttributeProto::default();
+        let mut reader = ProtoReader::open(bytes);
+        while let Some((number, value)) = reader.field()? {
+            match number {
+                1 => attribute.name = value.string()?,
+                2 => attribute.float = Some(value.float()?),
+                3 => attribute.int = Some(value.int()?),
+                4 => attribute.string = Some(value.string()?),
+                7 => value.push_floats(&mut attribute.floats)?,
+                8 => value.push_ints(&mut attribute.ints)?,
+                _ => {}
+            }
+        }
+        Ok(attribute)
+    }
+}
+
+#[derive(Default)]
+struct NodeProto {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    op_type: String,
+    domain: String,
+    attributes: Vec<AttributeProto>,
+}
+
+impl NodeProto {
+    fn parse(bytes: &[u8]) -> Result<Self, OnnxError> {
+        let mut node = NodeProto::default();
+        let mut reader = ProtoReader::open(bytes);
+        while let Some((number, value)) = reader.field()? {
+            match number {
+                1 => node.inputs.push(value.string()?),
+                2 => node.outputs.push(value.string()?),
+                4 => node.op_type = value.string()?,
+                5 => node.attributes.push(AttributeProto::parse(value.bytes()?)?),
+                7 => node.domain = value.string()?,
+                _ => {}
+            }
+        }
+        Ok(node)
+    }
+
+    /// Nombre de la entrada `index`; las opcionales omitidas están vacías.
+    fn input(&self, index: usize) -> Option<&str> {
+        self.inputs.get(index).map(String::as_str).filter(|name| !name.is_empty())
+    }
+
+    fn attribute(&self, name: &str) -> Option<&AttributeProto> {
+        self.attributes.iter().find(|attribute| attribute.name == name)
+    }
+
+    fn int(&self, name: &str, default: i64) -> i64 {
+        self.attribute(name).and_then(|attribute| attribute.int).unwrap_or(default)
+    }
+
+    fn float(&self, name: &str, default: f32) -> f32 {
+        self.attribute(name).and_then(|attribute| attribute.float).unwrap_or(default)
+    }
+
+    fn ints(&self, name: &str) -> Option<&[i64]> {
+        self.attribute(name).map(|attribute| attribute.ints.as_slice())
+    }
+
+    fn string(&self, name: &str) -> Option<&str> {
+        self.attribute(name).and_then(|attribute| attribute.string.as_deref())
+    }
+
+    fn unsupported(&self, reason: &'static str) -> OnnxError {
+        OnnxError::UnsupportedUsage { operator: self.op_type.clone(), reason }
+    }
+}
+
+struct GraphProto<'a> {
+    name: String,
+    nodes: Vec<NodeProto>,
+    initializers: Vec<TensorProto<'a>>,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+}
+
+impl<'a> GraphProto<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, OnnxError> {
+        let mut graph = GraphProto {
+            name: String::new(),
+            nodes: Vec::new(),
+            initializers: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        };
+        let mut reader = ProtoReader::open(bytes);
+        while let Some((number, value)) = reader.field()? {
+            match number {
+                1 => graph.nodes.push(NodeProto::parse(value.bytes()?)?),
+                2 => graph.name = value.string()?,
+                5 => graph.initializers.push(TensorProto::parse(value.bytes()?)?),
+                11 => graph.inputs.push(value_info_name(value.bytes()?)?),
+                12 => graph.outputs.push(value_info_name(value.bytes()?)?),
+                _ => {}
+            }
+        }
+        Ok(graph)
+    }
+}
+
+/// Nombre de un `ValueInfoProto`; el tipo y la forma no se usan.
+fn value_info_name(bytes: &[u8]) -> Result<String, OnnxError> {
+    let mut reader = ProtoReader::open(bytes);
+    let mut name = String::new();
+    while let Some((number, value)) = reader.field()? {
+        if number == 1 {
+            name = value.string()?;
+        }
+    }
+    Ok(name)
+}
+
+/// `ModelProto`: el grafo y la versión del conjunto de operadores estándar.
+fn parse_model(bytes: &[u8]) -> Result<(GraphProto<'_>, i64), OnnxError> {
+    let mut graph = None;
+    let mut opset = None;
+    let mut reader = ProtoReader::open(bytes);
+    while let Some((number, value)) = reader.field()? {
+        match number {
+            7 => graph = Some(GraphProto::parse(value.bytes()?)?),
+            8 => {
+                // `OperatorSetIdProto`: dominio (vacío el estándar) y versión
+                let mut domain = String::new();
+                let mut version = 0;
+                let mut entry = ProtoReader::open(value.bytes()?);
+                while let Some((number, value)) = entry.field()? {
+                    match number {
+                        1 => domain = value.string()?,
+                        2 => version = value.int()?,
+                        _ => {}
+                    }
+                }
+                if is_standard_domain(&domain) {
+                    opset = Some(version);
+                }
+            }
+            _ => {}
+        }
+    }
+    let graph = graph.ok_or(OnnxError::InvalidModel("El modelo ONNX no tiene grafo"))?;
+    let opset = opset.ok_or(OnnxError::InvalidModel("El modelo ONNX no declara la versión de sus operadores"))?;
+    Ok((graph, opset))
+}
+
+fn is_standard_domain(domain: &str) -> bool {
+    domain.is_empty() || domain == "ai.onnx"
+}
+
+/// `Add` con un operando constante, que se difunde sobre la entrada.
+struct AddConstant {
+    value: Tensor,
+}
+
+impl Module for AddConstant {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        input.add(&self.value)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        vec![&self.value]
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        parameter_names(&["value"])
+    }
+
+    fn name(&self) -> &str {
+        "Add"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        broadcast_shapes(input, self.value.shape())
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        vec![&mut self.value]
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let [value] = tape_parameters(parameters)?;
+        input.add(value)
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        self.value = self.value.to_dtype(dtype)?;
+        Ok(())
+    }
+}
+
+/// `Softmax` de ONNX. Hasta la versión 13 del conjunto de operadores la
+/// entrada se aplana a 2D en `axis` y se normaliza cada fila.
+struct Softmax {
+    axis: isize,
+    coerce_2d: bool,
+}
+
+impl Module for Softmax {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        if !self.coerce_2d {
+            return input.softmax(self.axis);
+        }
+        let axis = normalize_axis(self.axis, input.ndim())?;
+        input.flatten(axis as isize)?.softmax(-1)?.reshape(input.shape())
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        "Softmax"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        normalize_axis(self.axis, input.len())?;
+        Ok(input.to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, _parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        if !self.coerce_2d {
+            return input.softmax(self.axis);
+        }
+        let shape = input.shape();
+        let axis = normalize_axis(self.axis, shape.len())?;
+        let rows = shape[..axis].iter().product();
+        input.reshape(&[rows, shape[axis..].iter().product()])?.softmax(-1)?.reshape(&shape)
+    }
+}
+
+/// `Flatten` con un eje distinto de 1; un eje negativo cuenta desde el final
+/// (`-1` deja el último eje solo).
+struct FlattenAxis(isize);
+
+impl FlattenAxis {
+    fn axis(&self, ndim: usize) -> TensorResult<usize> {
+        let axis = if self.0 < 0 { self.0 + ndim as isize } else { self.0 };
+        if axis < 0 || axis as usize > ndim {
+            return Err(TensorError::InvalidAxis { axis: self.0, ndim });
+        }
+        Ok(axis as usize)
+    }
+}
+
+impl Module for FlattenAxis {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        let axis = self.axis(input.ndim())?;
+        input.flatten(axis as isize)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        "Flatten"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        let axis = self.axis(input.len())?;
+        Ok(vec![input[..axis].iter().product(), input[axis..].iter().product()])
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, _parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let shape = self.output_shape(&input.shape())?;
+        input.reshape(&shape)
+    }
+}
+
+/// `Reshape` con la forma constante: un 0 copia la dimensión de la entrada
+/// en esa posición (salvo con `allowzero`) y un -1 se deduce del resto.
+struct Reshape {
+    shape: Vec<i64>,
+    allow_zero: bool,
+}
+
+impl Reshape {
+    fn target(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        let mut shape = Vec::with_capacity(self.shape.len());
+        let mut inferred = None;
+        for (index, &dim) in self.shape.iter().enumerate() {
+            match dim {
+                -1 if inferred.is_none() => {
+                    inferred = Some(index);
+                    shape.push(1);
+                }
+                0 if !self.allow_zero => shape.push(
+                    *input.get(index).ok_or(TensorError::InvalidArgument("Un 0 de Reshape no tiene eje de origen"))?,
+                ),
+                dim if dim >= 0 => shape.push(dim as usize),
+                _ => return Err(TensorError::InvalidArgument("Forma de Reshape no válida")),
+            }
+        }
+        let len: usize = input.iter().product();
+        let known: usize = shape.iter().product();
+        if let Some(index) = inferred {
+            if known == 0 || !len.is_multiple_of(known) {
+                return Err(TensorError::LengthMismatch { expected: len, found: known });
+            }
+            shape[index] = len / known;
+        } else if known != len {
+            return Err(TensorError::LengthMismatch { expected: len, found: known });
+        }
+        Ok(shape)
+    }
+}
+
+impl Module for Reshape {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        let shape = self.target(input.shape())?;
+        input.reshape(&shape)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        "Reshape"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        self.target(input)
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, _parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let shape = self.target(&input.shape())?;
+        input.reshape(&shape)
+    }
+}
+
+/// Valor con nombre del grafo ONNX durante la importación.
+enum Value {
+    /// Lo calcula un nodo del `GraphModel` (o es una entrada).
+    Node,
+    /// Inicializador, o resultado de un nodo cuyas entradas son constantes.
+    Float(Tensor),
+    /// Inicializador entero, como la forma de un `Reshape`.
+    Int(Vec<i64>),
+}
+
+/// Cómo se ejecuta un nodo ONNX en el `GraphModel`.
+enum Lowered<'n> {
+    /// Un módulo aplicado a la entrada con ese nombre.
+    Module(Box<dyn Module>, &'n str),
+    /// Suma de dos valores calculados.
+    Sum(&'n str, &'n str),
+}
+
+struct Importer {
+    opset: i64,
+    values: BTreeMap<String, Value>,
+    graph: GraphModel,
+}
+
+impl Importer {
+    fn value(&self, name: &str) -> Result<&Value, OnnxError> {
+        self.values.get(name).ok_or_else(|| OnnxError::UnknownValue(name.to_string()))
+    }
+
+    /// La entrada `index` de `node` si es un tensor constante de coma flotante.
+    fn constant(&self, node: &NodeProto, index: usize) -> Result<Option<Tensor>, OnnxError> {
+        let Some(name) = node.input(index) else { return Ok(None) };
+        match self.value(name)? {
+            Value::Float(tensor) => Ok(Some(tensor.clone())),
+            Value::Node => Ok(None),
+            Value::Int(_) => Err(node.unsupported("se esperaba un tensor de coma flotante")),
+        }
+    }
+
+    /// Como `constant`, para una entrada obligatoria.
+    fn weight(&self, node: &NodeProto, index: usize) -> Result<Tensor, OnnxError> {
+        self.constant(node, index)?
+            .ok_or_else(|| node.unsupported("los pesos deben ser un inicializador"))
+    }
+
+    fn import_node(&mut self, node: &NodeProto) -> Result<(), OnnxError> {
+        if !is_standard_domain(&node.domain) {
+            let name = [node.domain.as_str(), node.op_type.as_str()].join(".");
+            return Err(OnnxError::UnsupportedOperator(name));
+        }
+        let output = match node.outputs.as_slice() {
+            [output] => output,
+            // Salida opcional `Indices` de `MaxPool` no pedida
+            [output, unused] if unused.is_empty() => output,
+            _ => return Err(node.unsupported("solo se admite una salida por nodo")),
+        };
+        let input = node.input(0).ok_or(OnnxError::InvalidModel("Nodo ONNX sin entrada"))?;
+        let lowered = match node.op_type.as_str() {
+            "Gemm" => Lowered::Module(Box::new(self.gemm(node)?), input),
+            "MatMul" => Lowered::Module(Box::new(self.matmul(node)?), input),
+            "Add" => self.add(node)?,
+            "Relu" => Lowered::Module(Box::new(ActivationFunction::ReLU), input),
+            "Sigmoid" => Lowered::Module(Box::new(ActivationFunction::Sigmoid), input),
+            "Tanh" => Lowered::Module(Box::new(ActivationFunction::Tanh), input),
+            "Softmax" => {
+                let coerce_2d = self.opset < SOFTMAX_PER_AXIS_OPSET;
+                let axis = node.int("axis", if coerce_2d { 1 } else { -1 }) as isize;
+                Lowered::Module(Box::new(Softmax { axis, coerce_2d }), input)
+            }
+            "Conv" => Lowered::Module(Box::new(self.conv(node)?), input),
+            "MaxPool" => Lowered::Module(Box::new(max_pool(node)?), input),
+            "Reshape" => Lowered::Module(Box::new(self.reshape(node)?), input),
+            "Flatten" => match node.int("axis", 1) {
+                1 => Lowered::Module(Box::new(Flatten), input),
+                axis => Lowered::Module(Box::new(FlattenAxis(axis as isize)), input),
+            },
+            other => return Err(OnnxError::UnsupportedOperator(other.to_string())),
+        };
+
+        let value = match lowered {
+            Lowered::Module(module, input) => match self.value(input)? {
+                // Con la entrada constante, el resultado también lo es
+                Value::Float(tensor) => Value::Float(module.forward(tensor.clone())?),
+                Value::Int(_) => return Err(node.unsupported("la entrada es un tensor entero")),
+                Value::Node => {
+                    self.graph.add_node(output, GraphOp::Module(module), &[input])?;
+                    Value::Node
+                }
+            },
+            Lowered::Sum(lhs, rhs) => {
+                self.graph.add_sum(output, &[lhs, rhs])?;
+                Value::Node
+            }
+        };
+        if self.values.insert(output.clone(), value).is_some() {
+            return Err(OnnxError::InvalidModel("Dos nodos ONNX escriben el mismo valor"));
+        }
+        Ok(())
+    }
+
+    /// `alpha * A * B' + beta * C` como capa densa, con `B` y `C` constantes.
+    fn gemm(&self, node: &NodeProto) -> Result<Layer, OnnxError> {
+        if node.int("transA", 0) != 0 {
+            return Err(node.unsupported("transA debe ser 0"));
+        }
+        let mut weights = self.weight(node, 1)?;
+        if weights.ndim() != 2 {
+            return Err(node.unsupported("B debe ser una matriz"));
+        }
+        if node.int("transB", 0) != 0 {
+            weights = weights.t()?.contiguous()?;
+        }
+        let alpha = node.float("alpha", 1.0);
+        if alpha != 1.0 {
+            weights = weights.mul_scalar(alpha)?;
+        }
+        let outputs = weights.shape()[1];
+        let bias = match self.constant(node, 2)? {
+            // `C` puede ser `[N]`, `[1, N]` o un escalar difundido a cada fila
+            Some(bias) if bias.len() == outputs || bias.len() == 1 => {
+                bias.reshape(&[bias.len()])?.broadcast_to(&[outputs])?.contiguous()?
+            }
+            Some(_) => return Err(node.unsupported("C debe ser igual para todas las filas")),
+            None if node.input(2).is_some() => return Err(node.unsupported("C debe ser un inicializador")),
+            None => Tensor::zeros(&[outputs])?,
+        };
+        let beta = node.float("beta", 1.0);
+        let bias = if beta != 1.0 { bias.mul_scalar(beta)? } else { bias };
+        Ok(Layer::from_parameters(weights, bias, ActivationFunction::Identity)?)
+    }
+
+    /// `A * B` con `B` constante de dos dimensiones, como capa densa sin sesgo.
+    fn matmul(&self, node: &NodeProto) -> Result<Layer, OnnxError> {
+        let weights = self.constant(node, 1)?
+            .ok_or_else(|| node.unsupported("el segundo operando debe ser un inicializador"))?;
+        if weights.ndim() != 2 {
+            return Err(node.unsupported("el segundo operando debe ser una matriz"));
+        }
+        let bias = Tensor::zeros(&[weights.shape()[1]])?;
+        Ok(Layer::from_parameters(weights, bias, ActivationFunction::Identity)?)
+    }
+
+    fn add<'n>(&self, node: &'n NodeProto) -> Result<Lowered<'n>, OnnxError> {
+        let (Some(lhs), Some(rhs)) = (node.input(0), node.input(1)) else {
+            return Err(OnnxError::InvalidModel("Add necesita dos entradas"));
+        };
+        // La suma es conmutativa: el operando constante se guarda en el módulo
+        Ok(match (self.constant(node, 0)?, self.constant(node, 1)?) {
+            (_, Some(value)) => Lowered::Module(Box::new(AddConstant { value }), lhs),
+            (Some(value), None) => Lowered::Module(Box::new(AddConstant { value }), rhs),
+            (None, None) => Lowered::Sum(lhs, rhs),
+        })
+    }
+
+    fn conv(&self, node: &NodeProto) -> Result<Conv2d, OnnxError> {
+        let weight = self.weight(node, 1)?;
+        if weight.ndim() != 4 {
+            return Err(node.unsupported("solo se admiten convoluciones 2D"));
+        }
+        if node.ints("kernel_shape").is_some_and(|kernel| kernel != [weight.shape()[2] as i64, weight.shape()[3] as i64]) {
+            return Err(OnnxError::InvalidModel("kernel_shape no coincide con los pesos de Conv"));
+        }
+        let bias = self.constant(node, 2)?;
+        if bias.is_none() && node.input(2).is_some() {
+            return Err(node.unsupported("el sesgo debe ser un inicializador"));
+        }
+        let groups = usize::try_from(node.int("group", 1)).map_err(|_| node.unsupported("group no válido"))?;
+        let params = Conv2dParams {
+            stride: pair(node, "strides", 1)?,
+            padding: padding(node)?,
+            dilation: pair(node, "dilations", 1)?,
+            groups,
+        };
+        Ok(Conv2d::from_parameters(weight, bias, params)?)
+    }
+
+    fn reshape(&self, node: &NodeProto) -> Result<Reshape, OnnxError> {
+        let name = node.input(1).ok_or(OnnxError::InvalidModel("Reshape necesita la forma"))?;
+        match self.value(name)? {
+            Value::Int(shape) => Ok(Reshape { shape: shape.clone(), allow_zero: node.int("allowzero", 0) != 0 }),
+            _ => Err(node.unsupported("la forma debe ser un inicializador entero")),
+        }
+    }
+}
+
+fn max_pool(node: &NodeProto) -> Result<MaxPool2d, OnnxError> {
+    if node.int("ceil_mode", 0) != 0 {
+        return Err(node.unsupported("ceil_mode debe ser 0"));
+    }
+    if pair(node, "dilations", 1)? != (1, 1) {
+        return Err(node.unsupported("dilations debe ser 1"));
+    }
+    let kernel = match node.ints("kernel_shape") {
+        Some(_) => pair(node, "kernel_shape", 1)?,
+        None => return Err(OnnxError::InvalidModel("MaxPool necesita kernel_shape")),
+    };
+    Ok(MaxPool2d(Pool2dParams {
+        kernel,
+        stride: pair(node, "strides", 1)?,
+        padding: padding(node)?,
+    }))
+}
+
+/// Atributo de ventana 2D (`strides`, `dilations`...), `(default, default)`
+/// si no está.
+fn pair(node: &NodeProto, name: &str, default: usize) -> Result<(usize, usize), OnnxError> {
+    match node.ints(name) {
+        None => Ok((default, default)),
+        Some(&[height, width]) if height >= 0 && width >= 0 => Ok((height as usize, width as usize)),
+        Some(_) => Err(node.unsupported("solo se admiten ventanas 2D")),
+    }
+}
+
+/// Relleno por eje a partir de `auto_pad` y `pads` (`[arriba, izquierda,
+/// abajo, derecha]`); el kernel solo rellena igual por los dos lados.
+fn padding(node: &NodeProto) -> Result<(usize, usize), OnnxError> {
+    match node.string("auto_pad").unwrap_or("NOTSET") {
+        "NOTSET" => {}
+        "VALID" => return Ok((0, 0)),
+        _ => return Err(node.unsupported("auto_pad SAME no está soportado")),
+    }
+    match node.ints("pads") {
+        None => Ok((0, 0)),
+        Some(&[top, left, bottom, right]) if top == bottom && left == right && top >= 0 && left >= 0 => {
+            Ok((top as usize, left as usize))
+        }
+        Some(&[_, _, _, _]) => Err(node.unsupported("el relleno debe ser simétrico")),
+        Some(_) => Err(node.unsupported("solo se admiten ventanas 2D")),
+    }
+}
+
+impl GraphModel {
+    /// Importa un modelo ONNX (un `ModelProto` serializado).
+    ///
+    /// Se admiten los operadores `Gemm`, `MatMul`, `Add`, `Relu`, `Sigmoid`,
+    /// `Tanh`, `Softmax`, `Conv` y `MaxPool` (2D), `Reshape` y `Flatten`;
+    /// cualquier otro da `OnnxError::UnsupportedOperator`. Los pesos de
+    /// `Gemm`, `MatMul` y `Conv` y la forma de `Reshape` deben ser
+    /// inicializadores, y los nodos cuyas entradas son todas constantes se
+    /// calculan al importar. Cada valor del grafo ONNX es un nodo con su
+    /// mismo nombre.
+    pub fn from_onnx(bytes: &[u8]) -> Result<GraphModel, OnnxError> {
+        let (proto, opset) = parse_model(bytes)?;
+        let name = if proto.name.is_empty() { "onnx" } else { proto.name.as_str() };
+        let mut importer = Importer { opset, values: BTreeMap::new(), graph: GraphModel::new(name) };
+
+        for initializer in &proto.initializers {
+            importer.values.insert(initializer.name.clone(), initializer.to_value()?);
+        }
+        // Los exportadores antiguos también declaran los inicializadores como entradas
+        for input in &proto.inputs {
+            if !importer.values.contains_key(input) {
+                importer.graph.add_input(input)?;
+                importer.values.insert(input.clone(), Value::Node);
+            }
+        }
+        // ONNX exige que los nodos estén en orden topológico
+        for node in &proto.nodes {
+            importer.import_node(node)?;
+        }
+
+        for output in &proto.outputs {
+            if !matches!(importer.value(output)?, Value::Node) {
+                return Err(OnnxError::InvalidModel("Una salida del grafo ONNX es constante"));
+            }
+        }
+        let outputs: Vec<&str> = proto.outputs.iter().map(String::as_str).collect();
+        importer.graph.set_outputs(&outputs);
+        importer.graph.build()?;
+        Ok(importer.graph)
+    }
+}
+
+impl NeuralNetwork {
+    /// Red con el grafo de un modelo ONNX de una entrada y una salida como
+    /// única capa (ver `GraphModel::from_onnx`).
+    pub fn from_onnx(bytes: &[u8]) -> Result<NeuralNetwork, OnnxError> {
+        let graph = GraphModel::from_onnx(bytes)?;
+        if graph.input_count() != 1 || graph.output_count() != 1 {
+            return Err(OnnxError::InvalidModel("La red necesita un modelo ONNX de una entrada y una salida"));
+        }
+        let mut network = NeuralNetwork::new(graph.name());
+        network.add_layer(graph)?;
+        Ok(network)
+    }
+}
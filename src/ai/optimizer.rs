@@ -0,0 +1,301 @@
+use super::tensor::{Tensor, TensorError, TensorResult};
+use crate::math;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Regla de actualización de los parámetros a partir de sus gradientes.
+///
+/// Los optimizadores con estado (momentos, medias de cuadrados) lo crean en
+/// el primer `step` y después esperan siempre los mismos parámetros, en el
+/// mismo orden (el de `NeuralNetwork::parameters`).
+pub trait Optimizer {
+    /// Actualiza cada parámetro con el gradiente de la misma posición.
+    fn step(&mut self, parameters: &mut [&mut Tensor], grads: &[Tensor]) -> TensorResult<()>;
+
+    fn learning_rate(&self) -> f32;
+
+    /// Cambia la tasa de aprendizaje, p. ej. desde un programa de tasas.
+    fn set_learning_rate(&mut self, learning_rate: f32);
+
+    /// Olvida el estado acumulado, como si no se hubiera dado ningún paso.
+    fn reset(&mut self);
+}
+
+/// Comprueba que hay un gradiente de la forma de cada parámetro.
+fn check_grads(parameters: &[&mut Tensor], grads: &[Tensor]) -> TensorResult<()> {
+    if parameters.len() != grads.len() {
+        return Err(TensorError::LengthMismatch { expected: parameters.len(), found: grads.len() });
+    }
+    for (parameter, grad) in parameters.iter().zip(grads) {
+        if parameter.shape() != grad.shape() {
+            return Err(TensorError::ShapeMismatch {
+                lhs: parameter.shape().to_vec(),
+                rhs: grad.shape().to_vec(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Prepara un búfer a cero por parámetro en el primer paso y comprueba en
+/// los siguientes que los parámetros no han cambiado.
+fn ensure_state(state: &mut Vec<Vec<f32>>, parameters: &[&mut Tensor]) -> TensorResult<()> {
+    if state.is_empty() {
+        *state = parameters.iter().map(|parameter| vec![0.0; parameter.len()]).collect();
+    }
+    if state.len() != parameters.len() {
+        return Err(TensorError::LengthMismatch { expected: state.len(), found: parameters.len() });
+    }
+    for (buffer, parameter) in state.iter().zip(parameters) {
+        if buffer.len() != parameter.len() {
+            return Err(TensorError::LengthMismatch { expected: buffer.len(), found: parameter.len() });
+        }
+    }
+    Ok(())
+}
+
+/// Sustituye `parameter` por `values`, conservando su tipo de almacenamiento.
+fn store(parameter: &mut Tensor, values: Vec<f32>) -> TensorResult<()> {
+    let dtype = parameter.dtype();
+    *parameter = Tensor::from_vec(values, parameter.shape())?.to_dtype(dtype)?;
+    Ok(())
+}
+
+/// Descenso por gradiente estocástico, con momento (clásico o de Nesterov)
+/// y decaimiento de pesos opcionales, con las fórmulas de PyTorch:
+///
+/// ```text
+/// g = ∇ + λ p
+/// v = μ v + g
+/// p = p - lr (g + μ v)   (Nesterov)
+/// p = p - lr v           (clásico; con μ = 0, SGD simple)
+/// ```
+pub struct Sgd {
+    learning_rate: f32,
+    momentum: f32,
+    nesterov: bool,
+    weight_decay: f32,
+    velocity: Vec<Vec<f32>>,
+}
+
+impl Sgd {
+    pub fn new(learning_rate: f32) -> Self {
+        Sgd { learning_rate, momentum: 0.0, nesterov: false, weight_decay: 0.0, velocity: Vec::new() }
+    }
+
+    pub fn with_momentum(self, momentum: f32, nesterov: bool) -> Self {
+        Sgd { momentum, nesterov, ..self }
+    }
+
+    /// Penalización L2 `λ p` sumada al gradiente.
+    pub fn with_weight_decay(self, weight_decay: f32) -> Self {
+        Sgd { weight_decay, ..self }
+    }
+}
+
+impl Optimizer for Sgd {
+    fn step(&mut self, parameters: &mut [&mut Tensor], grads: &[Tensor]) -> TensorResult<()> {
+        check_grads(parameters, grads)?;
+        if self.momentum != 0.0 {
+            ensure_state(&mut self.velocity, parameters)?;
+        }
+        for (i, (parameter, grad)) in parameters.iter_mut().zip(grads).enumerate() {
+            let mut values = parameter.to_vec();
+            for (j, (p, g)) in values.iter_mut().zip(grad.iter()).enumerate() {
+                let mut g = g + self.weight_decay * *p;
+                if self.momentum != 0.0 {
+                    let v = &mut self.velocity[i][j];
+                    *v = self.momentum * *v + g;
+                    g = if self.nesterov { g + self.momentum * *v } else { *v };
+                }
+                *p -= self.learning_rate * g;
+            }
+            store(parameter, values)?;
+        }
+        Ok(())
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn reset(&mut self) {
+        self.velocity.clear();
+    }
+}
+
+/// Adam, con el decaimiento de pesos acoplado (L2 sumada al gradiente) o
+/// desacoplado (AdamW, que encoge los pesos aparte de los momentos).
+pub struct Adam {
+    learning_rate: f32,
+    beta1: f32,
+    beta2: f32,
+    eps: f32,
+    weight_decay: f32,
+    decoupled: bool,
+    steps: i32,
+    m: Vec<Vec<f32>>,
+    v: Vec<Vec<f32>>,
+}
+
+impl Adam {
+    /// Adam con `β1 = 0.9`, `β2 = 0.999` y `ε = 1e-8`.
+    pub fn new(learning_rate: f32) -> Self {
+        Adam {
+            learning_rate,
+            beta1: 0.9,
+            beta2: 0.999,
+            eps: 1e-8,
+            weight_decay: 0.0,
+            decoupled: false,
+            steps: 0,
+            m: Vec::new(),
+            v: Vec::new(),
+        }
+    }
+
+    /// AdamW: en cada paso `p = p - lr λ p`, además de la actualización de Adam.
+    pub fn adamw(learning_rate: f32, weight_decay: f32) -> Self {
+        Adam { weight_decay, decoupled: true, ..Adam::new(learning_rate) }
+    }
+
+    pub fn with_betas(self, beta1: f32, beta2: f32) -> Self {
+        Adam { beta1, beta2, ..self }
+    }
+
+    /// Penalización L2 `λ p` sumada al gradiente (la de Adam original).
+    pub fn with_weight_decay(self, weight_decay: f32) -> Self {
+        Adam { weight_decay, decoupled: false, ..self }
+    }
+}
+
+impl Optimizer for Adam {
+    fn step(&mut self, parameters: &mut [&mut Tensor], grads: &[Tensor]) -> TensorResult<()> {
+        check_grads(parameters, grads)?;
+        ensure_state(&mut self.m, parameters)?;
+        ensure_state(&mut self.v, parameters)?;
+        self.steps = self.steps.saturating_add(1);
+        // Corrección del sesgo hacia cero de los momentos iniciales
+        let correction1 = 1.0 - math::powi(self.beta1, self.steps);
+        let correction2 = 1.0 - math::powi(self.beta2, self.steps);
+
+        for (i, (parameter, grad)) in parameters.iter_mut().zip(grads).enumerate() {
+            let mut values = parameter.to_vec();
+            let moments = self.m[i].iter_mut().zip(self.v[i].iter_mut());
+            for ((p, g), (m, v)) in values.iter_mut().zip(grad.iter()).zip(moments) {
+                let mut g = g;
+                if self.decoupled {
+                    *p -= self.learning_rate * self.weight_decay * *p;
+                } else {
+                    g += self.weight_decay * *p;
+                }
+                *m = self.beta1 * *m + (1.0 - self.beta1) * g;
+                *v = self.beta2 * *v + (1.0 - self.beta2) * g * g;
+                let m_hat = *m / correction1;
+                let v_hat = *v / correction2;
+                *p -= self.learning_rate * m_hat / (math::sqrt(v_hat) + self.eps);
+            }
+            store(parameter, values)?;
+        }
+        Ok(())
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn reset(&mut self) {
+        self.steps = 0;
+        self.m.clear();
+        self.v.clear();
+    }
+}
+
+/// RMSProp: divide el gradiente por la raíz de una media móvil de sus
+/// cuadrados, `s = α s + (1 - α) g²`, con momento opcional.
+pub struct RmsProp {
+    learning_rate: f32,
+    alpha: f32,
+    eps: f32,
+    momentum: f32,
+    weight_decay: f32,
+    square_avg: Vec<Vec<f32>>,
+    buffer: Vec<Vec<f32>>,
+}
+
+impl RmsProp {
+    /// RMSProp con `α = 0.99` y `ε = 1e-8`, sin momento.
+    pub fn new(learning_rate: f32) -> Self {
+        RmsProp {
+            learning_rate,
+            alpha: 0.99,
+            eps: 1e-8,
+            momentum: 0.0,
+            weight_decay: 0.0,
+            square_avg: Vec::new(),
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn with_alpha(self, alpha: f32) -> Self {
+        RmsProp { alpha, ..self }
+    }
+
+    pub fn with_momentum(self, momentum: f32) -> Self {
+        RmsProp { momentum, ..self }
+    }
+
+    /// Penalización L2 `λ p` sumada al gradiente.
+    pub fn with_weight_decay(self, weight_decay: f32) -> Self {
+        RmsProp { weight_decay, ..self }
+    }
+}
+
+impl Optimizer for RmsProp {
+    fn step(&mut self, parameters: &mut [&mut Tensor], grads: &[Tensor]) -> TensorResult<()> {
+        check_grads(parameters, grads)?;
+        ensure_state(&mut self.square_avg, parameters)?;
+        if self.momentum != 0.0 {
+            ensure_state(&mut self.buffer, parameters)?;
+        }
+        for (i, (parameter, grad)) in parameters.iter_mut().zip(grads).enumerate() {
+            let mut values = parameter.to_vec();
+            for (j, (p, g)) in values.iter_mut().zip(grad.iter()).enumerate() {
+                let g = g + self.weight_decay * *p;
+                let s = &mut self.square_avg[i][j];
+                *s = self.alpha * *s + (1.0 - self.alpha) * g * g;
+                let update = g / (math::sqrt(*s) + self.eps);
+                if self.momentum != 0.0 {
+                    let b = &mut self.buffer[i][j];
+                    *b = self.momentum * *b + update;
+                    *p -= self.learning_rate * *b;
+                } else {
+                    *p -= self.learning_rate * update;
+                }
+            }
+            store(parameter, values)?;
+        }
+        Ok(())
+    }
+
+    fn learning_rate(&self) -> f32 {
+        self.learning_rate
+    }
+
+    fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.learning_rate = learning_rate;
+    }
+
+    fn reset(&mut self) {
+        self.square_avg.clear();
+        self.buffer.clear();
+    }
+}
@@ -0,0 +1,556 @@
+use super::dtype::DType;
+use super::module::{check_last_dim, parameter_names, Module};
+use super::tensor::{normalize_axis, try_buffer, Tensor, TensorError, TensorResult};
+use alloc::string::String;
+use alloc::vec::Vec;
+use half::f16;
+
+/// Forma de mapear el rango real al rango de `i8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantScheme {
+    /// Rango simétrico `[-max|x|, max|x|]` sobre `[-127, 127]`, punto cero 0.
+    Symmetric,
+    /// Rango `[min, max]` sobre `[-128, 127]` con punto cero desplazado.
+    Asymmetric,
+}
+
+/// Con qué detalle se calculan las escalas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantGranularity {
+    /// Una única escala para todo el tensor.
+    PerTensor,
+    /// Una escala por cada posición del eje indicado (p. ej. por neurona de salida).
+    PerChannel(isize),
+}
+
+/// Tensor cuantizado a `i8`: `x ≈ scale * (q - zero_point)`.
+#[derive(Debug, Clone)]
+pub struct QuantizedTensor {
+    data: Vec<i8>,
+    shape: Vec<usize>,
+    scheme: QuantScheme,
+    axis: Option<usize>,
+    scales: Vec<f32>,
+    zero_points: Vec<i32>,
+}
+
+/// Redondeo al entero más cercano (mitades lejos de cero) sin `std`.
+pub(crate) fn round_to_i32(x: f32) -> i32 {
+    if x >= 0.0 {
+        (x + 0.5) as i32
+    } else {
+        (x - 0.5) as i32
+    }
+}
+
+/// Escala y punto cero para un rango de valores.
+fn choose_params(min: f32, max: f32, scheme: QuantScheme) -> (f32, i32) {
+    match scheme {
+        QuantScheme::Symmetric => {
+            let bound = if -min > max { -min } else { max };
+            let scale = bound / 127.0;
+            (if scale > 0.0 { scale } else { 1.0 }, 0)
+        }
+        QuantScheme::Asymmetric => {
+            // El rango debe incluir el 0 para que se represente sin error
+            let min = min.min(0.0);
+            let max = max.max(0.0);
+            let scale = (max - min) / 255.0;
+            if scale <= 0.0 {
+                return (1.0, 0);
+            }
+            let zero_point = round_to_i32(-128.0 - min / scale).clamp(-128, 127);
+            (scale, zero_point)
+        }
+    }
+}
+
+fn quantize_value(x: f32, scale: f32, zero_point: i32, scheme: QuantScheme) -> i8 {
+    let low = match scheme {
+        QuantScheme::Symmetric => -127,
+        QuantScheme::Asymmetric => -128,
+    };
+    (round_to_i32(x / scale) + zero_point).clamp(low, 127) as i8
+}
+
+impl QuantizedTensor {
+    /// Cuantiza un tensor (de cualquier tipo de almacenamiento) a `i8`.
+    pub fn quantize(
+        tensor: &Tensor,
+        scheme: QuantScheme,
+        granularity: QuantGranularity,
+    ) -> TensorResult<QuantizedTensor> {
+        let values = tensor.to_vec();
+        let shape = tensor.shape().to_vec();
+        let axis = match granularity {
+            QuantGranularity::PerTensor => None,
+            QuantGranularity::PerChannel(axis) => Some(normalize_axis(axis, shape.len())?),
+        };
+        let (channels, inner) = match axis {
+            Some(axis) => (shape[axis], shape[axis + 1..].iter().product()),
+            None => (1, values.len().max(1)),
+        };
+
+        let mut mins = try_buffer(channels)?;
+        mins.resize(channels, f32::INFINITY);
+        let mut maxs = try_buffer(channels)?;
+        maxs.resize(channels, f32::NEG_INFINITY);
+        for (i, &x) in values.iter().enumerate() {
+            let c = (i / inner) % channels;
+            mins[c] = mins[c].min(x);
+            maxs[c] = maxs[c].max(x);
+        }
+
+        let mut scales = try_buffer(channels)?;
+        let mut zero_points = try_buffer(channels)?;
+        for (&min, &max) in mins.iter().zip(&maxs) {
+            let (scale, zero_point) = if min <= max {
+                choose_params(min, max, scheme)
+            } else {
+                (1.0, 0)
+            };
+            scales.push(scale);
+            zero_points.push(zero_point);
+        }
+
+        let mut data = try_buffer(values.len())?;
+        data.extend(values.iter().enumerate().map(|(i, &x)| {
+            let c = (i / inner) % channels;
+            quantize_value(x, scales[c], zero_points[c], scheme)
+        }));
+
+        Ok(QuantizedTensor { data, shape, scheme, axis, scales, zero_points })
+    }
+
+    /// Reconstruye un tensor cuantizado a partir de sus partes (p. ej. al
+    /// leerlo de un fichero): una escala y un punto cero por canal del eje
+    /// `axis`, o uno solo si es `None`.
+    pub fn from_parts(
+        data: Vec<i8>,
+        shape: &[usize],
+        scheme: QuantScheme,
+        axis: Option<usize>,
+        scales: Vec<f32>,
+        zero_points: Vec<i32>,
+    ) -> TensorResult<QuantizedTensor> {
+        let len: usize = shape.iter().product();
+        if data.len() != len {
+            return Err(TensorError::LengthMismatch { expected: len, found: data.len() });
+        }
+        let channels = match axis {
+            Some(axis) if axis < shape.len() => shape[axis],
+            Some(axis) => return Err(TensorError::InvalidAxis { axis: axis as isize, ndim: shape.len() }),
+            None => 1,
+        };
+        for found in [scales.len(), zero_points.len()] {
+            if found != channels {
+                return Err(TensorError::LengthMismatch { expected: channels, found });
+            }
+        }
+        Ok(QuantizedTensor { data, shape: shape.to_vec(), scheme, axis, scales, zero_points })
+    }
+
+    /// Reconstruye un tensor `f32` a partir de los valores cuantizados.
+    pub fn dequantize(&self) -> TensorResult<Tensor> {
+        let inner = self.channel_inner();
+        let channels = self.scales.len();
+        let mut values = try_buffer(self.data.len())?;
+        values.extend(self.data.iter().enumerate().map(|(i, &q)| {
+            let c = (i / inner) % channels;
+            self.scales[c] * (q as i32 - self.zero_points[c]) as f32
+        }));
+        Tensor::from_vec(values, &self.shape)
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn scheme(&self) -> QuantScheme {
+        self.scheme
+    }
+
+    /// Eje de los canales, o `None` si la cuantización es por tensor.
+    pub fn axis(&self) -> Option<usize> {
+        self.axis
+    }
+
+    pub fn scales(&self) -> &[f32] {
+        &self.scales
+    }
+
+    pub fn zero_points(&self) -> &[i32] {
+        &self.zero_points
+    }
+
+    /// Valores cuantizados en orden de filas.
+    pub fn data(&self) -> &[i8] {
+        &self.data
+    }
+
+    /// Memoria ocupada por los datos y sus parámetros de cuantización.
+    pub fn size_in_bytes(&self) -> usize {
+        self.data.len() + self.scales.len() * 8
+    }
+
+    fn channel_inner(&self) -> usize {
+        match self.axis {
+            Some(axis) => self.shape[axis + 1..].iter().product(),
+            None => self.data.len().max(1),
+        }
+    }
+
+    fn channel_params(&self, channel: usize) -> (f32, i32) {
+        match self.axis {
+            Some(_) => (self.scales[channel], self.zero_points[channel]),
+            None => (self.scales[0], self.zero_points[0]),
+        }
+    }
+}
+
+/// Producto matricial de dos tensores `i8` con acumulación en `i32`.
+///
+/// `lhs` es `[m, k]` (o `[k]`) y `rhs` es `[k, n]` (o `[k]`). Las escalas
+/// pueden ser por tensor o por canal siempre que los canales sean las filas
+/// de `lhs` y las columnas de `rhs`, de modo que cada elemento del resultado
+/// se reescala una sola vez: `out[i][j] = sa[i] * sb[j] * Σ (qa - za)(qb - zb)`.
+pub fn quantized_matmul(lhs: &QuantizedTensor, rhs: &QuantizedTensor) -> TensorResult<Tensor> {
+    let (m, k) = match lhs.shape.as_slice() {
+        [k] => (1, *k),
+        [m, k] => (*m, *k),
+        other => return Err(TensorError::RankMismatch { expected: 2, found: other.len() }),
+    };
+    let (k2, n) = match rhs.shape.as_slice() {
+        [k] => (*k, 1),
+        [k, n] => (*k, *n),
+        other => return Err(TensorError::RankMismatch { expected: 2, found: other.len() }),
+    };
+    if k != k2 {
+        return Err(TensorError::ShapeMismatch { lhs: lhs.shape.clone(), rhs: rhs.shape.clone() });
+    }
+    if let Some(axis) = lhs.axis.filter(|&axis| axis != 0 || lhs.shape.len() != 2) {
+        return Err(TensorError::InvalidAxis { axis: axis as isize, ndim: lhs.shape.len() });
+    }
+    if let Some(axis) = rhs.axis.filter(|&axis| axis != 1 || rhs.shape.len() != 2) {
+        return Err(TensorError::InvalidAxis { axis: axis as isize, ndim: rhs.shape.len() });
+    }
+
+    let mut b_scales = try_buffer(n)?;
+    let mut b_zeros = try_buffer(n)?;
+    for j in 0..n {
+        let (scale, zero_point) = rhs.channel_params(j);
+        b_scales.push(scale);
+        b_zeros.push(zero_point);
+    }
+
+    let mut acc = try_buffer::<i32>(n)?;
+    acc.resize(n, 0);
+    let mut out = try_buffer(m * n)?;
+    for i in 0..m {
+        let (a_scale, a_zero) = lhs.channel_params(i);
+        acc.iter_mut().for_each(|a| *a = 0);
+        for p in 0..k {
+            let a = lhs.data[i * k + p] as i32 - a_zero;
+            if a == 0 {
+                continue;
+            }
+            let row = &rhs.data[p * n..(p + 1) * n];
+            for ((slot, &b), &b_zero) in acc.iter_mut().zip(row).zip(&b_zeros) {
+                *slot += a * (b as i32 - b_zero);
+            }
+        }
+        out.extend(acc.iter().zip(&b_scales).map(|(&sum, &b_scale)| {
+            a_scale * b_scale * sum as f32
+        }));
+    }
+
+    let mut shape = Vec::new();
+    if lhs.shape.len() > 1 {
+        shape.push(m);
+    }
+    if rhs.shape.len() > 1 {
+        shape.push(n);
+    }
+    Tensor::from_vec(out, &shape)
+}
+
+/// Elementos por bloque de los formatos de `BlockFormat`.
+pub const BLOCK_LEN: usize = 32;
+
+/// Formatos de cuantización por bloques de ggml (los de GGUF): cada bloque
+/// de `BLOCK_LEN` valores consecutivos guarda su propia escala `d` en `f16`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockFormat {
+    /// `d` y 32 enteros `i8`: `x = d * q` (34 bytes por bloque).
+    Q8_0,
+    /// `d` y 32 enteros de 4 bits sin signo: `x = d * (q - 8)` (18 bytes
+    /// por bloque). El byte `j` guarda el elemento `j` en los bits bajos y
+    /// el `j + 16` en los altos.
+    Q4_0,
+}
+
+impl BlockFormat {
+    /// Nombre en ggml.
+    pub fn name(self) -> &'static str {
+        match self {
+            BlockFormat::Q8_0 => "Q8_0",
+            BlockFormat::Q4_0 => "Q4_0",
+        }
+    }
+
+    /// Bytes de un bloque, escala incluida.
+    pub fn block_bytes(self) -> usize {
+        match self {
+            BlockFormat::Q8_0 => 2 + BLOCK_LEN,
+            BlockFormat::Q4_0 => 2 + BLOCK_LEN / 2,
+        }
+    }
+
+    /// Añade a `out` el bloque de `values` (`BLOCK_LEN` valores), con el
+    /// mismo redondeo que la implementación de referencia de ggml.
+    fn quantize_block(self, values: &[f32], out: &mut Vec<u8>) {
+        match self {
+            BlockFormat::Q8_0 => {
+                let amax = values.iter().fold(0.0f32, |amax, x| amax.max(x.abs()));
+                let d = f16::from_f32(amax / 127.0);
+                let id = if amax > 0.0 { 127.0 / amax } else { 0.0 };
+                out.extend_from_slice(&d.to_le_bytes());
+                out.extend(values.iter().map(|&x| round_to_i32(x * id) as i8 as u8));
+            }
+            BlockFormat::Q4_0 => {
+                // La escala lleva el signo del valor de mayor magnitud, que
+                // se representa exactamente como -8
+                let max = values.iter().fold(0.0f32, |max, &x| if x.abs() > max.abs() { x } else { max });
+                let d = max / -8.0;
+                let id = if d != 0.0 { 1.0 / d } else { 0.0 };
+                out.extend_from_slice(&f16::from_f32(d).to_le_bytes());
+                let nibble = |x: f32| ((x * id + 8.5) as i32).clamp(0, 15) as u8;
+                let (low, high) = values.split_at(BLOCK_LEN / 2);
+                out.extend(low.iter().zip(high).map(|(&a, &b)| nibble(a) | nibble(b) << 4));
+            }
+        }
+    }
+
+    /// Escribe en `out` los `BLOCK_LEN` valores del bloque.
+    fn dequantize_block(self, block: &[u8], out: &mut [f32]) {
+        let d = f16::from_le_bytes([block[0], block[1]]).to_f32();
+        match self {
+            BlockFormat::Q8_0 => {
+                for (slot, &q) in out.iter_mut().zip(&block[2..]) {
+                    *slot = d * q as i8 as f32;
+                }
+            }
+            BlockFormat::Q4_0 => {
+                let (low, high) = out.split_at_mut(BLOCK_LEN / 2);
+                for ((a, b), &q) in low.iter_mut().zip(high).zip(&block[2..]) {
+                    *a = d * ((q & 0x0f) as i32 - 8) as f32;
+                    *b = d * ((q >> 4) as i32 - 8) as f32;
+                }
+            }
+        }
+    }
+
+    /// Producto escalar del bloque por `x` sin decuantizarlo:
+    /// `d * Σ q·x`, aplicando la escala una sola vez.
+    fn dot_block(self, block: &[u8], x: &[f32]) -> f32 {
+        let d = f16::from_le_bytes([block[0], block[1]]).to_f32();
+        let sum = match self {
+            BlockFormat::Q8_0 => block[2..].iter().zip(x).map(|(&q, &x)| q as i8 as f32 * x).sum::<f32>(),
+            BlockFormat::Q4_0 => {
+                let (low, high) = x.split_at(BLOCK_LEN / 2);
+                block[2..].iter().zip(low).zip(high).map(|((&q, &a), &b)| {
+                    ((q & 0x0f) as i32 - 8) as f32 * a + ((q >> 4) as i32 - 8) as f32 * b
+                }).sum::<f32>()
+            }
+        };
+        d * sum
+    }
+}
+
+/// Tensor cuantizado por bloques a lo largo del último eje, cuyo tamaño
+/// debe ser múltiplo de `BLOCK_LEN`. Los bytes son los mismos que en un
+/// fichero GGUF.
+#[derive(Debug, Clone)]
+pub struct BlockQuantizedTensor {
+    format: BlockFormat,
+    shape: Vec<usize>,
+    data: Vec<u8>,
+}
+
+/// Bytes de un tensor de forma `shape` en `format`, o error si su último
+/// eje no es múltiplo de `BLOCK_LEN`.
+fn block_data_len(shape: &[usize], format: BlockFormat) -> TensorResult<usize> {
+    let Some(&inner) = shape.last() else {
+        return Err(TensorError::RankMismatch { expected: 1, found: 0 });
+    };
+    if !inner.is_multiple_of(BLOCK_LEN) {
+        return Err(TensorError::InvalidArgument("El último eje no es múltiplo del tamaño de bloque"));
+    }
+    Ok(shape.iter().product::<usize>() / BLOCK_LEN * format.block_bytes())
+}
+
+impl BlockQuantizedTensor {
+    /// Cuantiza un tensor (de cualquier tipo de almacenamiento) por bloques.
+    pub fn quantize(tensor: &Tensor, format: BlockFormat) -> TensorResult<BlockQuantizedTensor> {
+        let len = block_data_len(tensor.shape(), format)?;
+        let values = tensor.to_vec();
+        let mut data = try_buffer(len)?;
+        for block in values.chunks_exact(BLOCK_LEN) {
+            format.quantize_block(block, &mut data);
+        }
+        Ok(BlockQuantizedTensor { format, shape: tensor.shape().to_vec(), data })
+    }
+
+    /// Reconstruye el tensor a partir de sus bloques (p. ej. los de un
+    /// fichero GGUF), en orden de filas.
+    pub fn from_blocks(data: Vec<u8>, shape: &[usize], format: BlockFormat) -> TensorResult<BlockQuantizedTensor> {
+        let len = block_data_len(shape, format)?;
+        if data.len() != len {
+            return Err(TensorError::LengthMismatch { expected: len, found: data.len() });
+        }
+        Ok(BlockQuantizedTensor { format, shape: shape.to_vec(), data })
+    }
+
+    /// Reconstruye el tensor `f32` completo.
+    pub fn dequantize(&self) -> TensorResult<Tensor> {
+        Tensor::from_vec(dequantize_blocks(&self.data, self.format)?, &self.shape)
+    }
+
+    /// Producto de `input` (`[..., k]`) por la traspuesta de estos pesos
+    /// (`[n, k]`, una fila por salida, como en ggml y `torch.nn.Linear`),
+    /// decuantizando cada bloque al vuelo: los pesos nunca se expanden.
+    pub fn matmul(&self, input: &Tensor) -> TensorResult<Tensor> {
+        block_matmul(input, &self.data, &self.shape, self.format)
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn format(&self) -> BlockFormat {
+        self.format
+    }
+
+    /// Bloques en orden de filas.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Memoria ocupada por los bloques, escalas incluidas.
+    pub fn size_in_bytes(&self) -> usize {
+        self.data.len()
+    }
+}
+
+/// Decuantiza bloques consecutivos en un único vector.
+pub(crate) fn dequantize_blocks(data: &[u8], format: BlockFormat) -> TensorResult<Vec<f32>> {
+    let blocks = data.len() / format.block_bytes();
+    let mut values = try_buffer(blocks * BLOCK_LEN)?;
+    values.resize(blocks * BLOCK_LEN, 0.0);
+    for (block, out) in data.chunks_exact(format.block_bytes()).zip(values.chunks_exact_mut(BLOCK_LEN)) {
+        format.dequantize_block(block, out);
+    }
+    Ok(values)
+}
+
+/// `input [..., k] x weightsᵀ` con `weights` `[n, k]` en bloques de
+/// `format`. Solo se reserva memoria para la entrada y el resultado.
+pub(crate) fn block_matmul(input: &Tensor, data: &[u8], shape: &[usize], format: BlockFormat) -> TensorResult<Tensor> {
+    let &[n, k] = shape else {
+        return Err(TensorError::RankMismatch { expected: 2, found: shape.len() });
+    };
+    if input.shape().last() != Some(&k) {
+        return Err(TensorError::ShapeMismatch { lhs: input.shape().to_vec(), rhs: shape.to_vec() });
+    }
+    let m: usize = input.shape()[..input.shape().len() - 1].iter().product();
+    let row_bytes = k / BLOCK_LEN * format.block_bytes();
+    let values = input.to_vec();
+    let mut out = try_buffer(m * n)?;
+    for x in (0..m).map(|i| &values[i * k..(i + 1) * k]) {
+        out.extend((0..n).map(|j| {
+            data[j * row_bytes..(j + 1) * row_bytes]
+                .chunks_exact(format.block_bytes())
+                .zip(x.chunks_exact(BLOCK_LEN))
+                .map(|(block, x)| format.dot_block(block, x))
+                .sum::<f32>()
+        }));
+    }
+    let mut output_shape = input.shape()[..input.shape().len() - 1].to_vec();
+    output_shape.push(n);
+    Tensor::from_vec(out, &output_shape)
+}
+
+/// Capa densa con los pesos cuantizados por bloques (`[salida, entrada]`)
+/// y un sesgo opcional en coma flotante. Es la forma de ejecutar las
+/// proyecciones de un modelo GGUF sin expandir sus pesos.
+#[derive(Debug, Clone)]
+pub struct BlockLinear {
+    weight: BlockQuantizedTensor,
+    bias: Option<Tensor>,
+}
+
+impl BlockLinear {
+    pub fn new(weight: BlockQuantizedTensor, bias: Option<Tensor>) -> TensorResult<Self> {
+        let &[n, _] = weight.shape() else {
+            return Err(TensorError::RankMismatch { expected: 2, found: weight.shape().len() });
+        };
+        if let Some(bias) = bias.as_ref().filter(|bias| bias.shape() != [n]) {
+            return Err(TensorError::ShapeMismatch { lhs: weight.shape().to_vec(), rhs: bias.shape().to_vec() });
+        }
+        Ok(BlockLinear { weight, bias })
+    }
+
+    pub fn weight(&self) -> &BlockQuantizedTensor {
+        &self.weight
+    }
+
+    pub fn bias(&self) -> Option<&Tensor> {
+        self.bias.as_ref()
+    }
+}
+
+impl Module for BlockLinear {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        let output = self.weight.matmul(&input)?;
+        match &self.bias {
+            Some(bias) => output.add(bias),
+            None => Ok(output),
+        }
+    }
+
+    /// Los pesos por bloques no son un `Tensor`: solo el sesgo, si lo hay.
+    fn parameters(&self) -> Vec<&Tensor> {
+        self.bias.iter().collect()
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        parameter_names(if self.bias.is_some() { &["bias"] } else { &[] })
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        self.bias.iter_mut().collect()
+    }
+
+    fn name(&self) -> &str {
+        "BlockLinear"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        let shape = self.weight.shape();
+        check_last_dim(input, shape[1])?;
+        let mut output = input[..input.len() - 1].to_vec();
+        output.push(shape[0]);
+        Ok(output)
+    }
+
+    fn parameter_bytes(&self) -> usize {
+        self.weight.size_in_bytes() + self.bias.as_ref().map_or(0, |bias| bias.size_in_bytes())
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        if let Some(bias) = &mut self.bias {
+            *bias = bias.to_dtype(dtype)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use crate::math;
+use core::f32::consts::TAU;
+use spin::Mutex;
+
+/// Generador pseudoaleatorio reproducible (SplitMix64).
+///
+/// No es criptográfico; sirve para máscaras de dropout e inicialización de
+/// pesos, donde interesa poder repetir una ejecución a partir de la semilla.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub const fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Valor uniforme en `[0, 1)` con los 24 bits de mantisa de un `f32`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Valor uniforme en `[low, high)`.
+    pub fn uniform(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * self.next_f32()
+    }
+
+    /// Valor de una normal `N(mean, std²)` por Box-Muller.
+    pub fn normal(&mut self, mean: f32, std: f32) -> f32 {
+        // `1 - u` está en `(0, 1]`, así que el logaritmo es finito
+        let radius = math::sqrt(-2.0 * math::ln(1.0 - self.next_f32()));
+        mean + std * radius * math::cos(TAU * self.next_f32())
+    }
+}
+
+/// Semilla del generador global hasta la primera llamada a `manual_seed`.
+const DEFAULT_SEED: u64 = 0x5eed;
+
+static GLOBAL_RNG: Mutex<Rng> = Mutex::new(Rng::new(DEFAULT_SEED));
+
+/// Reinicia el generador global, el que usan los constructores `new` de las
+/// capas para inicializar sus pesos: con la misma semilla se obtiene el
+/// mismo modelo.
+pub fn manual_seed(seed: u64) {
+    *GLOBAL_RNG.lock() = Rng::new(seed);
+}
+
+/// Ejecuta `f` con el generador global.
+pub fn with_global_rng<R>(f: impl FnOnce(&mut Rng) -> R) -> R {
+    f(&mut GLOBAL_RNG.lock())
+}
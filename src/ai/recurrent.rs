@@ -0,0 +1,513 @@
+use super::autograd::Var;
+use super::dtype::DType;
+use super::init::Initializer;
+use super::module::{parameter_names, tape_parameters, Module};
+use super::random::with_global_rng;
+use super::tensor::{Tensor, TensorError, TensorResult};
+use crate::math;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Qué devuelve una capa recurrente.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecurrentOutput {
+    /// La salida oculta de cada paso, `[batch, seq, hidden]`.
+    Sequence,
+    /// Solo la del último paso, `[batch, hidden]`.
+    LastState,
+}
+
+/// Estado oculto de una capa recurrente al final de una secuencia.
+///
+/// Pasándolo a la siguiente llamada, una secuencia larga puede procesarse
+/// por trozos con el mismo resultado que de una vez.
+#[derive(Debug, Clone)]
+pub struct RecurrentState {
+    hidden: Tensor,
+    // Solo en LSTM
+    cell: Option<Tensor>,
+}
+
+impl RecurrentState {
+    /// Salida oculta del último paso, `[batch, hidden]`.
+    pub fn hidden(&self) -> &Tensor {
+        &self.hidden
+    }
+
+    /// Estado de la celda de una LSTM, `[batch, hidden]`.
+    pub fn cell(&self) -> Option<&Tensor> {
+        self.cell.as_ref()
+    }
+}
+
+/// Comprueba pesos `[entrada, gates * h]`, `[h, gates * h]` y sesgos
+/// `[gates * h]`, y devuelve `h`.
+fn check_parameters(weight_ih: &Tensor, weight_hh: &Tensor, biases: &[&Tensor], gates: usize) -> TensorResult<usize> {
+    let hidden = match *weight_hh.shape() {
+        [hidden, width] if width == gates * hidden => hidden,
+        [_, _] => {
+            return Err(TensorError::InvalidArgument(
+                "Los pesos recurrentes deben ser [oculta, puertas * oculta]",
+            ));
+        }
+        ref shape => return Err(TensorError::RankMismatch { expected: 2, found: shape.len() }),
+    };
+    match *weight_ih.shape() {
+        [_, width] if width == gates * hidden => {}
+        _ => {
+            return Err(TensorError::ShapeMismatch {
+                lhs: weight_ih.shape().to_vec(),
+                rhs: weight_hh.shape().to_vec(),
+            });
+        }
+    }
+    if let Some(bias) = biases.iter().find(|bias| bias.shape() != [gates * hidden]) {
+        return Err(TensorError::ShapeMismatch {
+            lhs: weight_hh.shape().to_vec(),
+            rhs: bias.shape().to_vec(),
+        });
+    }
+    Ok(hidden)
+}
+
+/// `(batch, seq)` de una entrada `[batch, seq, features]` no vacía.
+fn sequence_dims(shape: &[usize], features: usize) -> TensorResult<(usize, usize)> {
+    match *shape {
+        [_, 0, _] => Err(TensorError::EmptyReduction),
+        [batch, seq_len, f] if f == features => Ok((batch, seq_len)),
+        [_, _, _] => Err(TensorError::ShapeMismatch {
+            lhs: shape.to_vec(),
+            rhs: [features].to_vec(),
+        }),
+        _ => Err(TensorError::RankMismatch { expected: 3, found: shape.len() }),
+    }
+}
+
+/// Forma de salida de una capa recurrente de `hidden` unidades.
+fn output_shape(shape: &[usize], features: usize, hidden: usize, mode: RecurrentOutput) -> TensorResult<Vec<usize>> {
+    let (batch, seq_len) = sequence_dims(shape, features)?;
+    Ok(match mode {
+        RecurrentOutput::Sequence => [batch, seq_len, hidden].to_vec(),
+        RecurrentOutput::LastState => [batch, hidden].to_vec(),
+    })
+}
+
+/// Estado de partida: el de la llamada anterior o ceros.
+fn initial(previous: Option<&Tensor>, batch: usize, hidden: usize) -> TensorResult<Tensor> {
+    match previous {
+        Some(state) if state.shape() == [batch, hidden] => Ok(state.clone()),
+        Some(state) => Err(TensorError::ShapeMismatch {
+            lhs: state.shape().to_vec(),
+            rhs: [batch, hidden].to_vec(),
+        }),
+        None => Tensor::zeros(&[batch, hidden]),
+    }
+}
+
+/// Paso `t` de una proyección `[batch, seq, n]`, como `[batch, n]`.
+fn step(projected: &Tensor, t: usize) -> TensorResult<Tensor> {
+    projected.narrow(1, t, 1)?.squeeze(Some(1))
+}
+
+/// Puerta `index` de unas activaciones `[batch, puertas * hidden]`.
+fn gate(gates: &Tensor, index: usize, hidden: usize) -> TensorResult<Tensor> {
+    gates.narrow(1, index * hidden, hidden)
+}
+
+/// Como `step`, para una proyección en la cinta.
+fn step_tape<'t>(projected: &Var<'t>, t: usize) -> TensorResult<Var<'t>> {
+    let shape = projected.shape();
+    projected.narrow(1, t, 1)?.reshape(&[shape[0], shape[2]])
+}
+
+/// Como `gate`, para unas activaciones en la cinta.
+fn gate_tape<'t>(gates: &Var<'t>, index: usize, hidden: usize) -> TensorResult<Var<'t>> {
+    gates.narrow(1, index * hidden, hidden)
+}
+
+/// Inicialización de PyTorch para las capas recurrentes: `U(-k, k)` con
+/// `k = 1 / sqrt(h)` en todos los pesos y sesgos.
+fn initializer(hidden: usize) -> Initializer {
+    let k = 1.0 / math::sqrt(hidden.max(1) as f32);
+    Initializer::Uniform { low: -k, high: k }
+}
+
+/// Salida de la capa a partir de las salidas ocultas de cada paso.
+fn output(mode: RecurrentOutput, steps: &[Tensor], last: &Tensor) -> TensorResult<Tensor> {
+    match mode {
+        RecurrentOutput::Sequence => {
+            let steps: Vec<&Tensor> = steps.iter().collect();
+            Tensor::stack(&steps, 1)
+        }
+        RecurrentOutput::LastState => Ok(last.clone()),
+    }
+}
+
+/// Como `output`, con las salidas de cada paso en la cinta.
+fn output_tape<'t>(mode: RecurrentOutput, steps: &[Var<'t>], last: Var<'t>) -> TensorResult<Var<'t>> {
+    match mode {
+        RecurrentOutput::Sequence => {
+            let steps = steps
+                .iter()
+                .map(|step| {
+                    let shape = step.shape();
+                    step.reshape(&[shape[0], 1, shape[1]])
+                })
+                .collect::<TensorResult<Vec<_>>>()?;
+            Var::concat(&steps, 1)
+        }
+        RecurrentOutput::LastState => Ok(last),
+    }
+}
+
+/// LSTM sobre `[batch, seq, features]`, con las puertas en el orden de
+/// PyTorch (entrada, olvido, celda, salida):
+///
+/// ```text
+/// i, f, g, o = σ, σ, tanh, σ (x W_ih + h W_hh + b)
+/// c' = f c + i g
+/// h' = o tanh(c')
+/// ```
+///
+/// `b` es la suma de los dos sesgos de PyTorch (`bias_ih + bias_hh`).
+pub struct Lstm {
+    weight_ih: Tensor,
+    weight_hh: Tensor,
+    bias: Tensor,
+    output: RecurrentOutput,
+}
+
+impl Lstm {
+    /// Crea la capa con pesos y sesgo de `initializer(hidden_size)`.
+    pub fn new(input_size: usize, hidden_size: usize, output: RecurrentOutput) -> TensorResult<Self> {
+        let init = initializer(hidden_size);
+        with_global_rng(|rng| {
+            Ok(Lstm {
+                weight_ih: init.tensor(&[input_size, 4 * hidden_size], 0, 0, rng)?,
+                weight_hh: init.tensor(&[hidden_size, 4 * hidden_size], 0, 0, rng)?,
+                bias: init.tensor(&[4 * hidden_size], 0, 0, rng)?,
+                output,
+            })
+        })
+    }
+
+    /// Crea la capa a partir de pesos `[entrada, 4 h]`, `[h, 4 h]` y sesgo `[4 h]`.
+    pub fn from_parameters(
+        weight_ih: Tensor,
+        weight_hh: Tensor,
+        bias: Tensor,
+        output: RecurrentOutput,
+    ) -> TensorResult<Self> {
+        check_parameters(&weight_ih, &weight_hh, &[&bias], 4)?;
+        Ok(Lstm { weight_ih, weight_hh, bias, output })
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.weight_ih.shape()[0]
+    }
+
+    pub fn hidden_size(&self) -> usize {
+        self.weight_hh.shape()[0]
+    }
+
+    pub fn output(&self) -> RecurrentOutput {
+        self.output
+    }
+
+    pub fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        self.forward_with_state(input, None).map(|(out, _)| out)
+    }
+
+    /// Procesa la secuencia partiendo de `state` (ceros si es `None`) y
+    /// devuelve también el estado final.
+    pub fn forward_with_state(
+        &self,
+        input: Tensor,
+        state: Option<&RecurrentState>,
+    ) -> TensorResult<(Tensor, RecurrentState)> {
+        let (batch, seq_len) = sequence_dims(input.shape(), self.input_size())?;
+        let n = self.hidden_size();
+        let mut h = initial(state.map(|s| &s.hidden), batch, n)?;
+        let mut c = initial(state.and_then(|s| s.cell.as_ref()), batch, n)?;
+
+        // La proyección de la entrada no depende del estado: un solo matmul
+        // para toda la secuencia
+        let projected = input.matmul(&self.weight_ih)?.add(&self.bias)?;
+        let mut steps = Vec::new();
+        for t in 0..seq_len {
+            let gates = step(&projected, t)?.add(&h.matmul(&self.weight_hh)?)?;
+            let i = gate(&gates, 0, n)?.sigmoid()?;
+            let f = gate(&gates, 1, n)?.sigmoid()?;
+            let g = gate(&gates, 2, n)?.tanh()?;
+            let o = gate(&gates, 3, n)?.sigmoid()?;
+            c = f.mul(&c)?.add(&i.mul(&g)?)?;
+            h = o.mul(&c.tanh()?)?;
+            if self.output == RecurrentOutput::Sequence {
+                steps.push(h.clone());
+            }
+        }
+
+        let out = output(self.output, &steps, &h)?;
+        Ok((out, RecurrentState { hidden: h, cell: Some(c) }))
+    }
+
+    /// Como `forward` (partiendo de ceros), con los parámetros ya en la
+    /// cinta de `input`; el gradiente recorre la secuencia desenrollada.
+    pub fn forward_tape<'t>(
+        &self,
+        input: Var<'t>,
+        weight_ih: &Var<'t>,
+        weight_hh: &Var<'t>,
+        bias: &Var<'t>,
+    ) -> TensorResult<Var<'t>> {
+        let (batch, seq_len) = sequence_dims(&input.shape(), self.input_size())?;
+        let n = self.hidden_size();
+        let mut h = input.tape().var(Tensor::zeros(&[batch, n])?, false);
+        let mut c = h;
+
+        let projected = input.matmul(weight_ih)?.add(bias)?;
+        let mut steps = Vec::new();
+        for t in 0..seq_len {
+            let gates = step_tape(&projected, t)?.add(&h.matmul(weight_hh)?)?;
+            let i = gate_tape(&gates, 0, n)?.sigmoid()?;
+            let f = gate_tape(&gates, 1, n)?.sigmoid()?;
+            let g = gate_tape(&gates, 2, n)?.tanh()?;
+            let o = gate_tape(&gates, 3, n)?.sigmoid()?;
+            c = f.mul(&c)?.add(&i.mul(&g)?)?;
+            h = o.mul(&c.tanh()?)?;
+            if self.output == RecurrentOutput::Sequence {
+                steps.push(h);
+            }
+        }
+        output_tape(self.output, &steps, h)
+    }
+
+    pub fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        self.weight_ih = self.weight_ih.to_dtype(dtype)?;
+        self.weight_hh = self.weight_hh.to_dtype(dtype)?;
+        self.bias = self.bias.to_dtype(dtype)?;
+        Ok(())
+    }
+
+    pub fn parameter_bytes(&self) -> usize {
+        self.weight_ih.size_in_bytes() + self.weight_hh.size_in_bytes() + self.bias.size_in_bytes()
+    }
+}
+
+/// GRU sobre `[batch, seq, features]`, con las puertas en el orden de
+/// PyTorch (reinicio, actualización, candidata):
+///
+/// ```text
+/// r, z = σ (x W_ih + b_ih + h W_hh + b_hh)
+/// n = tanh(x W_in + b_in + r (h W_hn + b_hn))
+/// h' = (1 - z) n + z h
+/// ```
+pub struct Gru {
+    weight_ih: Tensor,
+    weight_hh: Tensor,
+    bias_ih: Tensor,
+    // Separado de `bias_ih`: en la candidata queda multiplicado por `r`
+    bias_hh: Tensor,
+    output: RecurrentOutput,
+}
+
+impl Gru {
+    /// Crea la capa con pesos y sesgos de `initializer(hidden_size)`.
+    pub fn new(input_size: usize, hidden_size: usize, output: RecurrentOutput) -> TensorResult<Self> {
+        let init = initializer(hidden_size);
+        with_global_rng(|rng| {
+            Ok(Gru {
+                weight_ih: init.tensor(&[input_size, 3 * hidden_size], 0, 0, rng)?,
+                weight_hh: init.tensor(&[hidden_size, 3 * hidden_size], 0, 0, rng)?,
+                bias_ih: init.tensor(&[3 * hidden_size], 0, 0, rng)?,
+                bias_hh: init.tensor(&[3 * hidden_size], 0, 0, rng)?,
+                output,
+            })
+        })
+    }
+
+    /// Crea la capa a partir de pesos `[entrada, 3 h]`, `[h, 3 h]` y sesgos `[3 h]`.
+    pub fn from_parameters(
+        weight_ih: Tensor,
+        weight_hh: Tensor,
+        bias_ih: Tensor,
+        bias_hh: Tensor,
+        output: RecurrentOutput,
+    ) -> TensorResult<Self> {
+        check_parameters(&weight_ih, &weight_hh, &[&bias_ih, &bias_hh], 3)?;
+        Ok(Gru { weight_ih, weight_hh, bias_ih, bias_hh, output })
+    }
+
+    pub fn input_size(&self) -> usize {
+        self.weight_ih.shape()[0]
+    }
+
+    pub fn hidden_size(&self) -> usize {
+        self.weight_hh.shape()[0]
+    }
+
+    pub fn output(&self) -> RecurrentOutput {
+        self.output
+    }
+
+    pub fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        self.forward_with_state(input, None).map(|(out, _)| out)
+    }
+
+    /// Procesa la secuencia partiendo de `state` (ceros si es `None`) y
+    /// devuelve también el estado final.
+    pub fn forward_with_state(
+        &self,
+        input: Tensor,
+        state: Option<&RecurrentState>,
+    ) -> TensorResult<(Tensor, RecurrentState)> {
+        let (batch, seq_len) = sequence_dims(input.shape(), self.input_size())?;
+        let n = self.hidden_size();
+        let mut h = initial(state.map(|s| &s.hidden), batch, n)?;
+
+        let projected = input.matmul(&self.weight_ih)?.add(&self.bias_ih)?;
+        let mut steps = Vec::new();
+        for t in 0..seq_len {
+            let x = step(&projected, t)?;
+            let recurrent = h.matmul(&self.weight_hh)?.add(&self.bias_hh)?;
+            let r = gate(&x, 0, n)?.add(&gate(&recurrent, 0, n)?)?.sigmoid()?;
+            let z = gate(&x, 1, n)?.add(&gate(&recurrent, 1, n)?)?.sigmoid()?;
+            let candidate = gate(&x, 2, n)?.add(&r.mul(&gate(&recurrent, 2, n)?)?)?.tanh()?;
+            // (1 - z) n + z h = n + z (h - n)
+            h = candidate.add(&z.mul(&h.sub(&candidate)?)?)?;
+            if self.output == RecurrentOutput::Sequence {
+                steps.push(h.clone());
+            }
+        }
+
+        let out = output(self.output, &steps, &h)?;
+        Ok((out, RecurrentState { hidden: h, cell: None }))
+    }
+
+    /// Como `forward` (partiendo de ceros), con los parámetros ya en la
+    /// cinta de `input`; el gradiente recorre la secuencia desenrollada.
+    pub fn forward_tape<'t>(
+        &self,
+        input: Var<'t>,
+        weight_ih: &Var<'t>,
+        weight_hh: &Var<'t>,
+        bias_ih: &Var<'t>,
+        bias_hh: &Var<'t>,
+    ) -> TensorResult<Var<'t>> {
+        let (batch, seq_len) = sequence_dims(&input.shape(), self.input_size())?;
+        let n = self.hidden_size();
+        let mut h = input.tape().var(Tensor::zeros(&[batch, n])?, false);
+
+        let projected = input.matmul(weight_ih)?.add(bias_ih)?;
+        let mut steps = Vec::new();
+        for t in 0..seq_len {
+            let x = step_tape(&projected, t)?;
+            let recurrent = h.matmul(weight_hh)?.add(bias_hh)?;
+            let r = gate_tape(&x, 0, n)?.add(&gate_tape(&recurrent, 0, n)?)?.sigmoid()?;
+            let z = gate_tape(&x, 1, n)?.add(&gate_tape(&recurrent, 1, n)?)?.sigmoid()?;
+            let candidate = gate_tape(&x, 2, n)?.add(&r.mul(&gate_tape(&recurrent, 2, n)?)?)?.tanh()?;
+            h = candidate.add(&z.mul(&h.sub(&candidate)?)?)?;
+            if self.output == RecurrentOutput::Sequence {
+                steps.push(h);
+            }
+        }
+        output_tape(self.output, &steps, h)
+    }
+
+    pub fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        self.weight_ih = self.weight_ih.to_dtype(dtype)?;
+        self.weight_hh = self.weight_hh.to_dtype(dtype)?;
+        self.bias_ih = self.bias_ih.to_dtype(dtype)?;
+        self.bias_hh = self.bias_hh.to_dtype(dtype)?;
+        Ok(())
+    }
+
+    pub fn parameter_bytes(&self) -> usize {
+        self.weight_ih.size_in_bytes()
+            + self.weight_hh.size_in_bytes()
+            + self.bias_ih.size_in_bytes()
+            + self.bias_hh.size_in_bytes()
+    }
+}
+
+impl Module for Lstm {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        Lstm::forward(self, input)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        alloc::vec![&self.weight_ih, &self.weight_hh, &self.bias]
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        parameter_names(&["weight_ih", "weight_hh", "bias"])
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        alloc::vec![&mut self.weight_ih, &mut self.weight_hh, &mut self.bias]
+    }
+
+    fn name(&self) -> &str {
+        "LSTM"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        output_shape(input, self.input_size(), self.hidden_size(), self.output)
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let [weight_ih, weight_hh, bias] = tape_parameters(parameters)?;
+        Lstm::forward_tape(self, input, weight_ih, weight_hh, bias)
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        Lstm::to_dtype(self, dtype)
+    }
+
+    fn forward_stateful(&self, input: Tensor, state: &mut Option<RecurrentState>) -> TensorResult<Tensor> {
+        let (output, next) = self.forward_with_state(input, state.as_ref())?;
+        *state = Some(next);
+        Ok(output)
+    }
+}
+
+impl Module for Gru {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        Gru::forward(self, input)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        alloc::vec![&self.weight_ih, &self.weight_hh, &self.bias_ih, &self.bias_hh]
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        parameter_names(&["weight_ih", "weight_hh", "bias_ih", "bias_hh"])
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        alloc::vec![&mut self.weight_ih, &mut self.weight_hh, &mut self.bias_ih, &mut self.bias_hh]
+    }
+
+    fn name(&self) -> &str {
+        "GRU"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        output_shape(input, self.input_size(), self.hidden_size(), self.output)
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let [weight_ih, weight_hh, bias_ih, bias_hh] = tape_parameters(parameters)?;
+        Gru::forward_tape(self, input, weight_ih, weight_hh, bias_ih, bias_hh)
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        Gru::to_dtype(self, dtype)
+    }
+
+    fn forward_stateful(&self, input: Tensor, state: &mut Option<RecurrentState>) -> TensorResult<Tensor> {
+        let (output, next) = self.forward_with_state(input, state.as_ref())?;
+        *state = Some(next);
+        Ok(output)
+    }
+}
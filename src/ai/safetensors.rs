@@ -0,0 +1,707 @@
+use super::format::{format_error, ByteReader};
+use super::module::{prefixed, Module};
+use super::nn::{Layer, NeuralNetwork};
+use super::norm::BatchNorm;
+use super::recurrent::{Gru, Lstm};
+use super::tensor::{try_buffer, Tensor, TensorError, TensorResult};
+use super::transformer::{MultiHeadAttention, TransformerBlock};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::fmt;
+use half::{bf16, f16};
+
+/// Profundidad máxima de anidamiento del JSON de la cabecera; la de un
+/// fichero válido es 3 (objeto, descripción del tensor, forma).
+const MAX_JSON_DEPTH: usize = 8;
+
+/// Errores al leer un fichero safetensors o al cargarlo en una red.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SafeTensorsError {
+    /// El fichero es más corto que lo que anuncia su cabecera.
+    Truncated { expected: usize, found: usize },
+    /// La cabecera JSON no es válida o no tiene la estructura esperada.
+    InvalidHeader(&'static str),
+    /// Tipo de elemento que no se puede convertir a `Tensor`.
+    UnsupportedDType(&'static str),
+    /// La red tiene un parámetro sin tensor con su nombre en el fichero.
+    MissingTensor(String),
+    /// El tensor del fichero no tiene la forma del parámetro.
+    ShapeMismatch { name: String, expected: Vec<usize>, found: Vec<usize> },
+    /// La red contiene una capa cuyos parámetros no se pueden sustituir.
+    UnsupportedLayer(String),
+    Tensor(TensorError),
+}
+
+format_error!(SafeTensorsError {
+    SafeTensorsError::Truncated { .. } => "Fichero safetensors truncado",
+    SafeTensorsError::InvalidHeader(reason) => reason,
+    SafeTensorsError::UnsupportedDType(_) => "Tipo de elemento de safetensors no soportado",
+    SafeTensorsError::MissingTensor(_) => "Falta un tensor en el fichero safetensors",
+    SafeTensorsError::ShapeMismatch { .. } => "El tensor no tiene la forma del parámetro",
+    SafeTensorsError::UnsupportedLayer(_) => "La capa no admite cargar parámetros",
+});
+
+impl SafeTensorsError {
+    fn write_details(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SafeTensorsError::Truncated { expected, found } => {
+                write!(f, ": se esperaban {} bytes, hay {}", expected, found)
+            }
+            SafeTensorsError::UnsupportedDType(dtype) => write!(f, ": {}", dtype),
+            SafeTensorsError::MissingTensor(name) | SafeTensorsError::UnsupportedLayer(name) => {
+                write!(f, ": {}", name)
+            }
+            SafeTensorsError::ShapeMismatch { name, expected, found } => {
+                write!(f, " {}: se esperaba {:?}, es {:?}", name, expected, found)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Tipos de elemento del formato. Se aceptan todos al leer el fichero, pero
+/// `TensorView::to_tensor` solo convierte `F32`, `F16`, `BF16` e `I8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafeDType {
+    Bool,
+    U8,
+    I8,
+    F8E4M3,
+    F8E5M2,
+    I16,
+    U16,
+    F16,
+    BF16,
+    I32,
+    U32,
+    F32,
+    F64,
+    I64,
+    U64,
+}
+
+const DTYPES: [SafeDType; 15] = [
+    SafeDType::Bool,
+    SafeDType::U8,
+    SafeDType::I8,
+    SafeDType::F8E4M3,
+    SafeDType::F8E5M2,
+    SafeDType::I16,
+    SafeDType::U16,
+    SafeDType::F16,
+    SafeDType::BF16,
+    SafeDType::I32,
+    SafeDType::U32,
+    SafeDType::F32,
+    SafeDType::F64,
+    SafeDType::I64,
+    SafeDType::U64,
+];
+
+impl SafeDType {
+    /// Nombre en la cabecera (`"F32"`, `"BF16"`...).
+    pub fn name(self) -> &'static str {
+        match self {
+            SafeDType::Bool => "BOOL",
+            SafeDType::U8 => "U8",
+            SafeDType::I8 => "I8",
+            SafeDType::F8E4M3 => "F8_E4M3",
+            SafeDType::F8E5M2 => "F8_E5M2",
+            SafeDType::I16 => "I16",
+            SafeDType::U16 => "U16",
+            SafeDType::F16 => "F16",
+            SafeDType::BF16 => "BF16",
+            SafeDType::I32 => "I32",
+            SafeDType::U32 => "U32",
+            SafeDType::F32 => "F32",
+            SafeDType::F64 => "F64",
+            SafeDType::I64 => "I64",
+            SafeDType::U64 => "U64",
+        }
+    }
+
+    /// Tamaño en bytes de un elemento.
+    pub fn size(self) -> usize {
+        match self {
+            SafeDType::Bool | SafeDType::U8 | SafeDType::I8 | SafeDType::F8E4M3 | SafeDType::F8E5M2 => 1,
+            SafeDType::I16 | SafeDType::U16 | SafeDType::F16 | SafeDType::BF16 => 2,
+            SafeDType::I32 | SafeDType::U32 | SafeDType::F32 => 4,
+            SafeDType::F64 | SafeDType::I64 | SafeDType::U64 => 8,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<SafeDType> {
+        DTYPES.into_iter().find(|dtype| dtype.name() == name)
+    }
+}
+
+/// Tipos que `cast_slice` puede leer directamente de los bytes del fichero.
+///
+/// # Safety
+///
+/// Solo para tipos sin relleno en los que cualquier patrón de bits es un
+/// valor válido. El trait es privado del módulo, así que no se puede
+/// implementar para otros tipos desde fuera.
+unsafe trait PlainData: Copy {}
+
+// SAFETY: números de 4 y 2 bytes sin relleno; todo patrón de bits es válido
+unsafe impl PlainData for f32 {}
+unsafe impl PlainData for f16 {}
+unsafe impl PlainData for bf16 {}
+
+/// Reinterpreta `bytes` como elementos de `T` si están alineados y el
+/// procesador es little-endian, como el formato.
+fn cast_slice<T: PlainData>(bytes: &[u8]) -> Option<&[T]> {
+    if cfg!(target_endian = "big") {
+        return None;
+    }
+    // SAFETY: `T: PlainData` garantiza que cualquier patrón de bits es un `T`
+    // válido, y `align_to` solo reinterpreta la parte alineada del slice.
+    let (prefix, values, suffix) = unsafe { bytes.align_to::<T>() };
+    (prefix.is_empty() && suffix.is_empty()).then_some(values)
+}
+
+/// Tensor de un fichero safetensors, sin copiar sus datos.
+#[derive(Debug, Clone)]
+pub struct TensorView<'a> {
+    name: String,
+    dtype: SafeDType,
+    shape: Vec<usize>,
+    data: &'a [u8],
+}
+
+impl<'a> TensorView<'a> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn dtype(&self) -> SafeDType {
+        self.dtype
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Bytes del tensor dentro del fichero.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / self.dtype.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Los datos como `f32`, sin copia, si el tipo es `F32` y el búfer está
+    /// alineado a 4 bytes.
+    pub fn as_f32(&self) -> Option<&'a [f32]> {
+        (self.dtype == SafeDType::F32).then(|| cast_slice(self.data)).flatten()
+    }
+
+    /// Como `as_f32`, para `F16`.
+    pub fn as_f16(&self) -> Option<&'a [f16]> {
+        (self.dtype == SafeDType::F16).then(|| cast_slice(self.data)).flatten()
+    }
+
+    /// Como `as_f32`, para `BF16`.
+    pub fn as_bf16(&self) -> Option<&'a [bf16]> {
+        (self.dtype == SafeDType::BF16).then(|| cast_slice(self.data)).flatten()
+    }
+
+    /// Copia el tensor a un `Tensor` del mismo tipo (`F32`, `F16` o `BF16`);
+    /// los `I8` se convierten a `f32`. Si los datos están alineados se copian
+    /// de una vez; si no, elemento a elemento.
+    pub fn to_tensor(&self) -> Result<Tensor, SafeTensorsError> {
+        let len = self.len();
+        Ok(match self.dtype {
+            SafeDType::F32 => Tensor::from_vec(self.collect(self.as_f32(), f32::from_le_bytes)?, &self.shape)?,
+            SafeDType::F16 => {
+                let values = self.collect(self.as_f16(), |bytes| f16::from_bits(u16::from_le_bytes(bytes)))?;
+                Tensor::from_f16(values, &self.shape)?
+            }
+            SafeDType::BF16 => {
+                let values = self.collect(self.as_bf16(), |bytes| bf16::from_bits(u16::from_le_bytes(bytes)))?;
+                Tensor::from_bf16(values, &self.shape)?
+            }
+            SafeDType::I8 => {
+                let mut values = try_buffer(len)?;
+                values.extend(self.data.iter().map(|&byte| byte as i8 as f32));
+                Tensor::from_vec(values, &self.shape)?
+            }
+            dtype => return Err(SafeTensorsError::UnsupportedDType(dtype.name())),
+        })
+    }
+
+    /// Copia `aligned` si existe o decodifica cada elemento de `N` bytes.
+    fn collect<T: Copy, const N: usize>(
+        &self,
+        aligned: Option<&[T]>,
+        decode: fn([u8; N]) -> T,
+    ) -> TensorResult<Vec<T>> {
+        let mut values = try_buffer(self.len())?;
+        match aligned {
+            Some(slice) => values.extend_from_slice(slice),
+            None => values.extend(self.data.chunks_exact(N).map(|chunk| {
+                let mut bytes = [0; N];
+                bytes.copy_from_slice(chunk);
+                decode(bytes)
+            })),
+        }
+        Ok(values)
+    }
+}
+
+impl TensorView<'static> {
+    /// Como `to_tensor`, pero si el fichero vive tanto como el kernel y los
+    /// datos `F32`, `F16` o `BF16` están alineados, el tensor los lee
+    /// directamente de él sin copiarlos (ver `Tensor::from_static`).
+    pub fn to_static_tensor(&self) -> Result<Tensor, SafeTensorsError> {
+        let borrowed = match self.dtype {
+            SafeDType::F32 => self.as_f32().map(|values| Tensor::from_static(values, &self.shape)),
+            SafeDType::F16 => self.as_f16().map(|values| Tensor::from_static_f16(values, &self.shape)),
+            SafeDType::BF16 => self.as_bf16().map(|values| Tensor::from_static_bf16(values, &self.shape)),
+            _ => None,
+        };
+        match borrowed {
+            Some(tensor) => Ok(tensor?),
+            None => self.to_tensor(),
+        }
+    }
+}
+
+/// Fichero safetensors: una cabecera JSON con el tipo, la forma y la
+/// posición de cada tensor, seguida de sus datos en bruto:
+///
+/// ```text
+/// longitud de la cabecera u64 | {"nombre": {"dtype": "F32", "shape": [2, 3],
+///                                "data_offsets": [inicio, fin]}, ...} | datos
+/// ```
+///
+/// Los tensores son vistas sobre `bytes`: leer el fichero no copia los
+/// pesos.
+pub struct SafeTensors<'a> {
+    tensors: Vec<TensorView<'a>>,
+    metadata: Vec<(String, String)>,
+}
+
+impl<'a> SafeTensors<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, SafeTensorsError> {
+        let mut reader = ByteReader::new(bytes, |expected, found| SafeTensorsError::Truncated { expected, found });
+        let header_len = usize::try_from(reader.u64()?).unwrap_or(usize::MAX);
+        let header = core::str::from_utf8(reader.take(header_len)?)
+            .map_err(|_| SafeTensorsError::InvalidHeader("La cabecera safetensors no es UTF-8"))?;
+        let data = &bytes[reader.position()..];
+
+        let Json::Object(entries) = JsonParser::new(header).document()? else {
+            return Err(SafeTensorsError::InvalidHeader("La cabecera safetensors no es un objeto"));
+        };
+        let mut tensors = Vec::new();
+        let mut metadata = Vec::new();
+        for (name, value) in entries {
+            if name == "__metadata__" {
+                let Json::Object(pairs) = value else {
+                    return Err(SafeTensorsError::InvalidHeader("Los metadatos deben ser un objeto"));
+                };
+                for (key, value) in pairs {
+                    let Json::String(value) = value else {
+                        return Err(SafeTensorsError::InvalidHeader("Los metadatos deben ser cadenas"));
+                    };
+                    metadata.push((key, value));
+                }
+            } else {
+                tensors.push(TensorView::describe(name, value, data)?);
+            }
+        }
+        Ok(SafeTensors { tensors, metadata })
+    }
+
+    /// Tensores en el orden de la cabecera.
+    pub fn tensors(&self) -> &[TensorView<'a>] {
+        &self.tensors
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TensorView<'a>> {
+        self.tensors.iter().find(|tensor| tensor.name == name)
+    }
+
+    /// Pares de `__metadata__`, p. ej. `("format", "pt")`.
+    pub fn metadata(&self) -> &[(String, String)] {
+        &self.metadata
+    }
+
+    pub fn len(&self) -> usize {
+        self.tensors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tensors.is_empty()
+    }
+}
+
+impl<'a> TensorView<'a> {
+    /// Interpreta la descripción `{"dtype", "shape", "data_offsets"}` de un
+    /// tensor y comprueba que sus datos caben en `data` y encajan con la forma.
+    fn describe(name: String, value: Json, data: &'a [u8]) -> Result<Self, SafeTensorsError> {
+        let invalid = SafeTensorsError::InvalidHeader;
+        let Json::Object(fields) = value else {
+            return Err(invalid("Cada tensor debe describirse con un objeto"));
+        };
+        let field = |key: &str| fields.iter().find(|(name, _)| name == key).map(|(_, value)| value);
+        let dtype = match field("dtype") {
+            Some(Json::String(dtype)) => {
+                SafeDType::from_name(dtype).ok_or(invalid("Tipo de elemento de safetensors desconocido"))?
+            }
+            _ => return Err(invalid("Falta el tipo de un tensor")),
+        };
+        let shape = field("shape").and_then(Json::as_usizes).ok_or(invalid("Falta la forma de un tensor"))?;
+        let (start, end) = match field("data_offsets").and_then(Json::as_usizes).as_deref() {
+            Some(&[start, end]) if start <= end => (start, end),
+            _ => return Err(invalid("Posición de los datos de un tensor no válida")),
+        };
+        let bytes = shape
+            .iter()
+            .try_fold(dtype.size(), |acc, &dim| acc.checked_mul(dim))
+            .ok_or(invalid("Tensor demasiado grande"))?;
+        if end - start != bytes {
+            return Err(invalid("Los datos de un tensor no encajan con su forma"));
+        }
+        let data = data.get(start..end).ok_or(SafeTensorsError::Truncated {
+            expected: end,
+            found: data.len(),
+        })?;
+        Ok(TensorView { name, dtype, shape, data })
+    }
+}
+
+/// Valor JSON de la cabecera. Los números se guardan como texto y solo se
+/// interpretan como enteros no negativos, los únicos que usa el formato; de
+/// los booleanos basta con saber que son válidos.
+enum Json {
+    Null,
+    Bool,
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    fn as_usizes(&self) -> Option<Vec<usize>> {
+        match self {
+            Json::Array(items) => items.iter().map(Json::as_usize).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// Analizador descendente recursivo de JSON, con la profundidad limitada
+/// para no agotar la pila del kernel.
+struct JsonParser<'a> {
+    text: &'a [u8],
+    position: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn new(text: &'a str) -> Self {
+        JsonParser { text: text.as_bytes(), position: 0 }
+    }
+
+    /// Un valor y, como mucho, espacios de relleno detrás.
+    fn document(&mut self) -> Result<Json, SafeTensorsError> {
+        let value = self.value(0)?;
+        self.skip_whitespace();
+        if self.position != self.text.len() {
+            return Err(SafeTensorsError::InvalidHeader("Hay datos tras el JSON de la cabecera"));
+        }
+        Ok(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.position).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), SafeTensorsError> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(SafeTensorsError::InvalidHeader("JSON de la cabecera mal formado"));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, SafeTensorsError> {
+        if !self.text[self.position..].starts_with(word.as_bytes()) {
+            return Err(SafeTensorsError::InvalidHeader("JSON de la cabecera mal formado"));
+        }
+        self.position += word.len();
+        Ok(value)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, SafeTensorsError> {
+        if depth > MAX_JSON_DEPTH {
+            return Err(SafeTensorsError::InvalidHeader("JSON de la cabecera demasiado anidado"));
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b't') => self.literal("true", Json::Bool),
+            Some(b'f') => self.literal("false", Json::Bool),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.position;
+                while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+                    self.position += 1;
+                }
+                let text = core::str::from_utf8(&self.text[start..self.position]).unwrap_or_default();
+                Ok(Json::Number(text.to_string()))
+            }
+            _ => Err(SafeTensorsError::InvalidHeader("JSON de la cabecera mal formado")),
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Json, SafeTensorsError> {
+        self.expect(b'{')?;
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Json::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(b':')?;
+            entries.push((key, self.value(depth + 1)?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(entries));
+                }
+                _ => return Err(SafeTensorsError::InvalidHeader("JSON de la cabecera mal formado")),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Json, SafeTensorsError> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(SafeTensorsError::InvalidHeader("JSON de la cabecera mal formado")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, SafeTensorsError> {
+        let malformed = SafeTensorsError::InvalidHeader("Cadena JSON de la cabecera mal formada");
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let byte = self.peek().ok_or(malformed.clone())?;
+            self.position += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = self.peek().ok_or(malformed.clone())?;
+                    self.position += 1;
+                    let decoded = match escape {
+                        b'"' | b'\\' | b'/' => escape as char,
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape().ok_or(malformed.clone())?,
+                        _ => return Err(malformed),
+                    };
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(decoded.encode_utf8(&mut buffer).as_bytes());
+                }
+                _ => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| malformed)
+    }
+
+    /// `\uXXXX`, incluidos los pares suplentes (`\uD83D\uDE00`).
+    fn unicode_escape(&mut self) -> Option<char> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high);
+        }
+        if self.text.get(self.position..self.position + 2) != Some(b"\\u") {
+            return None;
+        }
+        self.position += 2;
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return None;
+        }
+        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits = self.text.get(self.position..self.position + 4)?;
+        self.position += 4;
+        digits.iter().try_fold(0, |acc, &digit| Some(acc * 16 + (digit as char).to_digit(16)?))
+    }
+}
+
+/// Disposición de los pesos de las capas densas en el fichero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightLayout {
+    /// `[entrada, salida]`, la de `Layer`.
+    Native,
+    /// `[salida, entrada]`, la de `nn.Linear` de PyTorch: los pesos de las
+    /// capas densas (también las de la atención y los bloques de
+    /// transformer) se trasponen al cargarlos. Las `Lstm` y `Gru` se leen
+    /// con los nombres de `nn.LSTM`/`nn.GRU` de una capa (`weight_ih_l0`,
+    /// `bias_hh_l0`...), también traspuestos; el sesgo único de la `Lstm` es
+    /// la suma de `bias_ih_l0` y `bias_hh_l0`.
+    PyTorch,
+}
+
+impl NeuralNetwork {
+    /// Sustituye los parámetros de la red por los tensores de `file` con
+    /// el mismo nombre (ver `named_parameters`), comprobando su forma; si
+    /// falta alguno o no encaja, la red no cambia.
+    ///
+    /// Los tensores conservan el tipo del fichero (`f32`, `f16` o `bf16`;
+    /// los `i8` pasan a `f32`). En las `BatchNorm` también se cargan
+    /// `running_mean` y `running_var` si están. Devuelve los nombres del
+    /// fichero que no se han usado (p. ej. `num_batches_tracked`).
+    ///
+    /// Los pesos se copian al heap; para evitarlo con un fichero enlazado en
+    /// el kernel, `load_static_safetensors`.
+    pub fn load_safetensors(&mut self, file: &SafeTensors, layout: WeightLayout) -> Result<Vec<String>, SafeTensorsError> {
+        self.load_views(file, layout, TensorView::to_tensor)
+    }
+
+    /// Como `load_safetensors`, pero los parámetros cuyo tensor está alineado
+    /// en el fichero lo leen directamente de `file` sin copiarlo (salvo los
+    /// que hay que trasponer con `WeightLayout::PyTorch`). Si después se
+    /// entrena o se cambia el tipo de la red, esos parámetros pasan al heap.
+    pub fn load_static_safetensors(
+        &mut self,
+        file: &SafeTensors<'static>,
+        layout: WeightLayout,
+    ) -> Result<Vec<String>, SafeTensorsError> {
+        self.load_views(file, layout, TensorView::to_static_tensor)
+    }
+
+    fn load_views<'a>(
+        &mut self,
+        file: &SafeTensors<'a>,
+        layout: WeightLayout,
+        convert: fn(&TensorView<'a>) -> Result<Tensor, SafeTensorsError>,
+    ) -> Result<Vec<String>, SafeTensorsError> {
+        let mut used = vec![false; file.len()];
+        let mut take = |name: &str, expected: &[usize]| -> Result<Tensor, SafeTensorsError> {
+            let position = file
+                .tensors
+                .iter()
+                .position(|tensor| tensor.name == name)
+                .ok_or_else(|| SafeTensorsError::MissingTensor(name.to_string()))?;
+            let view = &file.tensors[position];
+            if view.shape() != expected {
+                return Err(SafeTensorsError::ShapeMismatch {
+                    name: name.to_string(),
+                    expected: expected.to_vec(),
+                    found: view.shape().to_vec(),
+                });
+            }
+            used[position] = true;
+            convert(view)
+        };
+
+        let mut values = Vec::new();
+        let mut running_stats = Vec::new();
+        for (index, layer) in self.layers_mut().iter_mut().enumerate() {
+            // Una capa sin `parameters_mut` (p. ej. un `Module` propio) no se puede cargar
+            if layer.parameters_mut().len() != layer.parameters().len() {
+                return Err(SafeTensorsError::UnsupportedLayer(layer.name().to_string()));
+            }
+            let layer: &dyn Module = layer.as_ref();
+            let any: &dyn Any = layer;
+            let dense = any.is::<Layer>() || any.is::<MultiHeadAttention>() || any.is::<TransformerBlock>();
+            let recurrent = any.is::<Lstm>() || any.is::<Gru>();
+            let prefix = index.to_string();
+            for (name, parameter) in prefixed(&prefix, layer.parameter_names()).iter().zip(layer.parameters()) {
+                let shape = parameter.shape();
+                if layout == WeightLayout::PyTorch && recurrent {
+                    let pytorch = format!("{}_l0", name);
+                    values.push(match shape.len() {
+                        2 => take(&pytorch, &[shape[1], shape[0]])?.t()?.contiguous()?,
+                        _ if name.ends_with(".bias") => {
+                            let bias_ih = take(&format!("{}.bias_ih_l0", prefix), shape)?;
+                            bias_ih.add(&take(&format!("{}.bias_hh_l0", prefix), shape)?)?
+                        }
+                        _ => take(&pytorch, shape)?,
+                    });
+                } else if layout == WeightLayout::PyTorch && dense && shape.len() == 2 && name.ends_with("weight") {
+                    values.push(take(name, &[shape[1], shape[0]])?.t()?.contiguous()?);
+                } else {
+                    values.push(take(name, shape)?);
+                }
+            }
+            if let Some(norm) = any.downcast_ref::<BatchNorm>() {
+                let (mean, var) = (format!("{}.running_mean", prefix), format!("{}.running_var", prefix));
+                if file.get(&mean).is_some() || file.get(&var).is_some() {
+                    let shape = norm.gamma().shape();
+                    running_stats.push((index, take(&mean, shape)?, take(&var, shape)?));
+                }
+            }
+        }
+
+        self.set_parameters(&values)?;
+        for (index, mean, var) in running_stats {
+            let layer: &mut dyn Any = self.layers_mut()[index].as_mut();
+            if let Some(norm) = layer.downcast_mut::<BatchNorm>() {
+                norm.set_running_stats(mean, var)?;
+            }
+        }
+        Ok(file
+            .tensors
+            .iter()
+            .zip(used)
+            .filter(|(_, used)| !used)
+            .map(|(tensor, _)| tensor.name.clone())
+            .collect())
+    }
+}
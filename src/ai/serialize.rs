@@ -0,0 +1,730 @@
+use super::conv::{AvgPool2d, Conv2d, Conv2dParams, Flatten, GlobalAvgPool2d, MaxPool2d, Pool2dParams};
+use super::dtype::DType;
+use super::format::{format_error, ByteReader};
+use super::module::Module;
+use super::nn::{ActivationFunction, Layer, LayerWeights, NeuralNetwork};
+use super::norm::{BatchNorm, Dropout, LayerNorm, RmsNorm};
+use super::quant::{QuantScheme, QuantizedTensor};
+use super::recurrent::{Gru, Lstm, RecurrentOutput};
+use super::tensor::{Tensor, TensorError};
+use super::transformer::{Embedding, MultiHeadAttention, PositionalEncoding, TransformerBlock};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::any::Any;
+use core::fmt;
+use half::{bf16, f16};
+
+/// Firma de los ficheros de modelo.
+const MAGIC: [u8; 4] = *b"RAIM";
+
+/// Versión del formato que escribe `serialize` y la única que lee `deserialize`.
+pub const MODEL_FORMAT_VERSION: u16 = 1;
+
+/// Firma, versión, banderas (reservadas, a cero) y longitud del cuerpo.
+const HEADER_LEN: usize = 12;
+
+/// CRC32 de la cabecera y el cuerpo.
+const TRAILER_LEN: usize = 4;
+
+/// Errores al leer o escribir un modelo en el formato binario nativo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelFormatError {
+    /// Los datos no empiezan por la firma `RAIM`.
+    BadMagic,
+    /// El fichero es de otra versión del formato.
+    UnsupportedVersion { found: u16, supported: u16 },
+    /// Faltan bytes respecto a la longitud que anuncia la cabecera.
+    Truncated { expected: usize, found: usize },
+    /// El CRC32 guardado no coincide con el de los datos.
+    ChecksumMismatch { expected: u32, found: u32 },
+    /// La red contiene una capa que el formato no sabe describir (un
+    /// `Module` propio, un `GraphModel`...); lleva su nombre.
+    UnsupportedLayer(String),
+    /// El CRC es correcto pero el contenido no es coherente.
+    Corrupt(&'static str),
+    /// Los tensores leídos no encajan en la capa que describen.
+    Tensor(TensorError),
+}
+
+format_error!(ModelFormatError {
+    ModelFormatError::BadMagic => "No es un fichero de modelo (firma incorrecta)",
+    ModelFormatError::UnsupportedVersion { .. } => "Versión del formato de modelo no soportada",
+    ModelFormatError::Truncated { .. } => "Fichero de modelo truncado",
+    ModelFormatError::ChecksumMismatch { .. } => "El CRC32 del modelo no coincide",
+    ModelFormatError::UnsupportedLayer(_) => "Capa no soportada por el formato de modelo",
+    ModelFormatError::Corrupt(reason) => reason,
+});
+
+impl ModelFormatError {
+    fn write_details(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ModelFormatError::UnsupportedVersion { found, supported } => {
+                write!(f, ": {} (se admite la {})", found, supported)
+            }
+            ModelFormatError::Truncated { expected, found } => {
+                write!(f, ": se esperaban {} bytes, hay {}", expected, found)
+            }
+            ModelFormatError::ChecksumMismatch { expected, found } => {
+                write!(f, ": se esperaba {:#010x}, es {:#010x}", expected, found)
+            }
+            ModelFormatError::UnsupportedLayer(name) => write!(f, ": {}", name),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// CRC32 de IEEE 802.3 (el de zip y PNG), bit a bit para no ocupar 1 KiB
+/// de tabla.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Etiquetas de los descriptores de capa.
+mod tag {
+    pub const DENSE: u8 = 1;
+    pub const ACTIVATION: u8 = 2;
+    pub const CONV2D: u8 = 3;
+    pub const MAX_POOL2D: u8 = 4;
+    pub const AVG_POOL2D: u8 = 5;
+    pub const GLOBAL_AVG_POOL2D: u8 = 6;
+    pub const FLATTEN: u8 = 7;
+    pub const LAYER_NORM: u8 = 8;
+    pub const RMS_NORM: u8 = 9;
+    pub const BATCH_NORM: u8 = 10;
+    pub const DROPOUT: u8 = 11;
+    pub const EMBEDDING: u8 = 12;
+    pub const POSITIONAL_ENCODING: u8 = 13;
+    pub const ATTENTION: u8 = 14;
+    pub const TRANSFORMER_BLOCK: u8 = 15;
+    pub const LSTM: u8 = 16;
+    pub const GRU: u8 = 17;
+}
+
+/// Tipos de los bloques de pesos.
+mod blob {
+    pub const F32: u8 = 0;
+    pub const F16: u8 = 1;
+    pub const BF16: u8 = 2;
+    pub const QUANTIZED: u8 = 3;
+}
+
+const ACTIVATIONS: [ActivationFunction; 7] = [
+    ActivationFunction::ReLU,
+    ActivationFunction::Sigmoid,
+    ActivationFunction::Tanh,
+    ActivationFunction::Softmax,
+    ActivationFunction::Identity,
+    ActivationFunction::Gelu,
+    ActivationFunction::Silu,
+];
+
+fn activation_code(activation: ActivationFunction) -> u8 {
+    ACTIVATIONS.iter().position(|&a| a == activation).unwrap_or(0) as u8
+}
+
+/// Búfer de escritura en little-endian.
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Tamaños y longitudes, que el formato guarda en 32 bits.
+    fn len(&mut self, value: usize) -> Result<(), ModelFormatError> {
+        let value = u32::try_from(value)
+            .map_err(|_| TensorError::InvalidArgument("Tamaño que no cabe en 32 bits"))?;
+        self.u32(value);
+        Ok(())
+    }
+
+    fn pair(&mut self, pair: (usize, usize)) -> Result<(), ModelFormatError> {
+        self.len(pair.0)?;
+        self.len(pair.1)
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+}
+
+/// Lector sobre el cuerpo, ya validado por el CRC: quedarse sin bytes
+/// significa que los descriptores no son coherentes.
+type Reader<'a> = ByteReader<'a, ModelFormatError>;
+
+impl<'a> Reader<'a> {
+    fn open(body: &'a [u8]) -> Self {
+        ByteReader::new(body, |_, _| ModelFormatError::Corrupt("El contenido del modelo acaba antes de tiempo"))
+    }
+
+    fn len(&mut self) -> Result<usize, ModelFormatError> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn pair(&mut self) -> Result<(usize, usize), ModelFormatError> {
+        Ok((self.len()?, self.len()?))
+    }
+
+    fn bool(&mut self) -> Result<bool, ModelFormatError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ModelFormatError::Corrupt("Valor booleano no válido")),
+        }
+    }
+
+    /// Bytes de `count` elementos de `size` bytes, comprobando que existen
+    /// antes de reservar memoria para ellos.
+    fn elements(&mut self, count: usize, size: usize) -> Result<&'a [u8], ModelFormatError> {
+        let len = count
+            .checked_mul(size)
+            .ok_or(ModelFormatError::Corrupt("Tensor demasiado grande"))?;
+        self.take(len)
+    }
+}
+
+/// Configuración de una capa, sin sus pesos.
+enum Descriptor {
+    Dense,
+    Activation,
+    Conv2d { params: Conv2dParams, bias: bool },
+    MaxPool2d(Pool2dParams),
+    AvgPool2d(Pool2dParams),
+    GlobalAvgPool2d,
+    Flatten,
+    LayerNorm { eps: f32 },
+    RmsNorm { eps: f32 },
+    BatchNorm { eps: f32, momentum: f32 },
+    Dropout { probability: f32 },
+    Embedding,
+    PositionalEncoding,
+    Attention { heads: usize, causal: bool, rotary: bool },
+    TransformerBlock { heads: usize, causal: bool, rotary: bool, eps: (f32, f32) },
+    Lstm(RecurrentOutput),
+    Gru(RecurrentOutput),
+}
+
+/// Bloque de pesos: un tensor en su tipo de almacenamiento o cuantizado.
+enum Blob {
+    Float(Tensor),
+    Quantized(QuantizedTensor),
+}
+
+impl Blob {
+    fn tensor(self) -> Result<Tensor, ModelFormatError> {
+        match self {
+            Blob::Float(tensor) => Ok(tensor),
+            Blob::Quantized(_) => Err(ModelFormatError::Corrupt("Tensor cuantizado donde no se admite")),
+        }
+    }
+}
+
+/// Las tres secciones del cuerpo mientras se recorren las capas.
+#[derive(Default)]
+struct Sections {
+    layers: usize,
+    descriptors: Writer,
+    activations: Writer,
+    activation_count: usize,
+    blobs: Writer,
+    blob_count: usize,
+}
+
+impl Sections {
+    fn activation(&mut self, activation: ActivationFunction) {
+        self.activations.u8(activation_code(activation));
+        self.activation_count += 1;
+    }
+
+    fn shape(&mut self, shape: &[usize]) -> Result<(), ModelFormatError> {
+        let ndim = u8::try_from(shape.len())
+            .map_err(|_| TensorError::InvalidArgument("Demasiadas dimensiones"))?;
+        self.blobs.u8(ndim);
+        for &dim in shape {
+            self.blobs.len(dim)?;
+        }
+        Ok(())
+    }
+
+    fn tensor(&mut self, tensor: &Tensor) -> Result<(), ModelFormatError> {
+        let dtype = tensor.dtype();
+        self.blobs.u8(match dtype {
+            DType::F32 => blob::F32,
+            DType::F16 => blob::F16,
+            DType::BF16 => blob::BF16,
+        });
+        self.shape(tensor.shape())?;
+        // Los valores ya son representables en su tipo: la conversión
+        // desde `f32` devuelve exactamente los bits almacenados
+        for value in tensor.iter() {
+            match dtype {
+                DType::F32 => self.blobs.f32(value),
+                DType::F16 => self.blobs.u16(f16::from_f32(value).to_bits()),
+                DType::BF16 => self.blobs.u16(bf16::from_f32(value).to_bits()),
+            }
+        }
+        self.blob_count += 1;
+        Ok(())
+    }
+
+    fn quantized(&mut self, tensor: &QuantizedTensor) -> Result<(), ModelFormatError> {
+        self.blobs.u8(blob::QUANTIZED);
+        self.shape(tensor.shape())?;
+        self.blobs.u8(match tensor.scheme() {
+            QuantScheme::Symmetric => 0,
+            QuantScheme::Asymmetric => 1,
+        });
+        self.blobs.i32(tensor.axis().map_or(-1, |axis| axis as i32));
+        self.blobs.len(tensor.scales().len())?;
+        for &scale in tensor.scales() {
+            self.blobs.f32(scale);
+        }
+        for &zero_point in tensor.zero_points() {
+            self.blobs.i32(zero_point);
+        }
+        self.blobs.bytes.extend(tensor.data().iter().map(|&value| value as u8));
+        self.blob_count += 1;
+        Ok(())
+    }
+
+    /// Capa densa sin descriptor propio (dentro de la atención o del bloque).
+    fn dense(&mut self, layer: &Layer) -> Result<(), ModelFormatError> {
+        self.activation(layer.activation());
+        match layer.weights() {
+            LayerWeights::Float(weights) => self.tensor(weights)?,
+            LayerWeights::Quantized(weights) => self.quantized(weights)?,
+        }
+        self.tensor(layer.bias())
+    }
+
+    fn tensors(&mut self, tensors: &[&Tensor]) -> Result<(), ModelFormatError> {
+        tensors.iter().try_for_each(|tensor| self.tensor(tensor))
+    }
+
+    fn pool(&mut self, tag: u8, params: &Pool2dParams) -> Result<(), ModelFormatError> {
+        self.descriptors.u8(tag);
+        self.descriptors.pair(params.kernel)?;
+        self.descriptors.pair(params.stride)?;
+        self.descriptors.pair(params.padding)
+    }
+
+    fn recurrent(&mut self, tag: u8, output: RecurrentOutput, parameters: &[&Tensor]) -> Result<(), ModelFormatError> {
+        self.descriptors.u8(tag);
+        self.descriptors.bool(output == RecurrentOutput::LastState);
+        self.tensors(parameters)
+    }
+
+    /// Añade la descripción y los pesos de `layer` a las secciones.
+    fn layer(&mut self, layer: &dyn Module) -> Result<(), ModelFormatError> {
+        let any: &dyn Any = layer;
+        if let Some(dense) = any.downcast_ref::<Layer>() {
+            self.descriptors.u8(tag::DENSE);
+            self.dense(dense)?;
+        } else if let Some(&activation) = any.downcast_ref::<ActivationFunction>() {
+            self.descriptors.u8(tag::ACTIVATION);
+            self.activation(activation);
+        } else if let Some(conv) = any.downcast_ref::<Conv2d>() {
+            let params = conv.params();
+            self.descriptors.u8(tag::CONV2D);
+            self.descriptors.pair(params.stride)?;
+            self.descriptors.pair(params.padding)?;
+            self.descriptors.pair(params.dilation)?;
+            self.descriptors.len(params.groups)?;
+            self.descriptors.bool(conv.bias().is_some());
+            self.tensor(conv.weight())?;
+            if let Some(bias) = conv.bias() {
+                self.tensor(bias)?;
+            }
+        } else if let Some(pool) = any.downcast_ref::<MaxPool2d>() {
+            self.pool(tag::MAX_POOL2D, &pool.0)?;
+        } else if let Some(pool) = any.downcast_ref::<AvgPool2d>() {
+            self.pool(tag::AVG_POOL2D, &pool.0)?;
+        } else if any.is::<GlobalAvgPool2d>() {
+            self.descriptors.u8(tag::GLOBAL_AVG_POOL2D);
+        } else if any.is::<Flatten>() {
+            self.descriptors.u8(tag::FLATTEN);
+        } else if let Some(norm) = any.downcast_ref::<LayerNorm>() {
+            self.descriptors.u8(tag::LAYER_NORM);
+            self.descriptors.f32(norm.eps());
+            self.tensors(&[norm.gamma(), norm.beta()])?;
+        } else if let Some(norm) = any.downcast_ref::<RmsNorm>() {
+            self.descriptors.u8(tag::RMS_NORM);
+            self.descriptors.f32(norm.eps());
+            self.tensor(norm.gamma())?;
+        } else if let Some(norm) = any.downcast_ref::<BatchNorm>() {
+            self.descriptors.u8(tag::BATCH_NORM);
+            self.descriptors.f32(norm.eps());
+            self.descriptors.f32(norm.momentum());
+            self.tensors(&[norm.gamma(), norm.beta(), &norm.running_mean(), &norm.running_var()])?;
+        } else if let Some(dropout) = any.downcast_ref::<Dropout>() {
+            self.descriptors.u8(tag::DROPOUT);
+            self.descriptors.f32(dropout.probability());
+        } else if let Some(embedding) = any.downcast_ref::<Embedding>() {
+            self.descriptors.u8(tag::EMBEDDING);
+            self.tensor(embedding.weight())?;
+        } else if any.is::<PositionalEncoding>() {
+            self.descriptors.u8(tag::POSITIONAL_ENCODING);
+        } else if let Some(attention) = any.downcast_ref::<MultiHeadAttention>() {
+            self.descriptors.u8(tag::ATTENTION);
+            self.descriptors.len(attention.heads())?;
+            self.descriptors.bool(attention.is_causal());
+            self.descriptors.bool(attention.is_rotary());
+            attention.projections().into_iter().try_for_each(|projection| self.dense(projection))?;
+        } else if let Some(block) = any.downcast_ref::<TransformerBlock>() {
+            let attention = block.attention();
+            self.descriptors.u8(tag::TRANSFORMER_BLOCK);
+            self.descriptors.len(attention.heads())?;
+            self.descriptors.bool(attention.is_causal());
+            self.descriptors.bool(attention.is_rotary());
+            self.descriptors.f32(block.attention_norm().eps());
+            self.descriptors.f32(block.ffn_norm().eps());
+            self.tensors(&[block.attention_norm().gamma(), block.attention_norm().beta()])?;
+            attention.projections().into_iter().try_for_each(|projection| self.dense(projection))?;
+            self.tensors(&[block.ffn_norm().gamma(), block.ffn_norm().beta()])?;
+            self.dense(block.ffn_up())?;
+            self.dense(block.ffn_down())?;
+        } else if let Some(lstm) = any.downcast_ref::<Lstm>() {
+            self.recurrent(tag::LSTM, lstm.output(), &layer.parameters())?;
+        } else if let Some(gru) = any.downcast_ref::<Gru>() {
+            self.recurrent(tag::GRU, gru.output(), &layer.parameters())?;
+        } else {
+            return Err(ModelFormatError::UnsupportedLayer(layer.name().to_string()));
+        }
+        self.layers += 1;
+        Ok(())
+    }
+}
+
+/// Lee las secciones del cuerpo en orden y reconstruye las capas.
+struct Parser<'a> {
+    activations: core::slice::Iter<'a, u8>,
+    blobs: alloc::vec::IntoIter<Blob>,
+}
+
+impl Parser<'_> {
+    fn activation(&mut self) -> Result<ActivationFunction, ModelFormatError> {
+        let code = *self.activations.next().ok_or(ModelFormatError::Corrupt("Faltan activaciones"))?;
+        ACTIVATIONS
+            .get(code as usize)
+            .copied()
+            .ok_or(ModelFormatError::Corrupt("Código de activación desconocido"))
+    }
+
+    fn blob(&mut self) -> Result<Blob, ModelFormatError> {
+        self.blobs.next().ok_or(ModelFormatError::Corrupt("Faltan bloques de pesos"))
+    }
+
+    fn tensor(&mut self) -> Result<Tensor, ModelFormatError> {
+        self.blob()?.tensor()
+    }
+
+    fn dense(&mut self) -> Result<Layer, ModelFormatError> {
+        let activation = self.activation()?;
+        let weights = self.blob()?;
+        let bias = self.tensor()?;
+        Ok(match weights {
+            Blob::Float(weights) => Layer::from_parameters(weights, bias, activation)?,
+            Blob::Quantized(weights) => Layer::from_quantized(weights, bias, activation)?,
+        })
+    }
+
+    fn layer_norm(&mut self, eps: f32) -> Result<LayerNorm, ModelFormatError> {
+        let gamma = self.tensor()?;
+        Ok(LayerNorm::from_parameters(gamma, self.tensor()?, eps)?)
+    }
+
+    fn attention(&mut self, heads: usize, causal: bool, rotary: bool) -> Result<MultiHeadAttention, ModelFormatError> {
+        let (query, key, value, output) = (self.dense()?, self.dense()?, self.dense()?, self.dense()?);
+        let mut attention = MultiHeadAttention::from_parameters(query, key, value, output, heads, causal)?;
+        attention.set_rotary(rotary);
+        Ok(attention)
+    }
+
+    fn build(&mut self, descriptor: Descriptor) -> Result<Box<dyn Module>, ModelFormatError> {
+        Ok(match descriptor {
+            Descriptor::Dense => Box::new(self.dense()?),
+            Descriptor::Activation => Box::new(self.activation()?),
+            Descriptor::Conv2d { params, bias } => {
+                let weight = self.tensor()?;
+                let bias = if bias { Some(self.tensor()?) } else { None };
+                Box::new(Conv2d::from_parameters(weight, bias, params)?)
+            }
+            Descriptor::MaxPool2d(params) => Box::new(MaxPool2d(params)),
+            Descriptor::AvgPool2d(params) => Box::new(AvgPool2d(params)),
+            Descriptor::GlobalAvgPool2d => Box::new(GlobalAvgPool2d),
+            Descriptor::Flatten => Box::new(Flatten),
+            Descriptor::LayerNorm { eps } => Box::new(self.layer_norm(eps)?),
+            Descriptor::RmsNorm { eps } => Box::new(RmsNorm::from_parameters(self.tensor()?, eps)?),
+            Descriptor::BatchNorm { eps, momentum } => {
+                let (gamma, beta, mean, var) = (self.tensor()?, self.tensor()?, self.tensor()?, self.tensor()?);
+                let mut norm = BatchNorm::from_parameters(gamma, beta, mean, var, eps)?;
+                norm.set_momentum(momentum);
+                Box::new(norm)
+            }
+            Descriptor::Dropout { probability } => Box::new(Dropout::new(probability, 0)?),
+            Descriptor::Embedding => Box::new(Embedding::from_parameters(self.tensor()?)?),
+            Descriptor::PositionalEncoding => Box::new(PositionalEncoding),
+            Descriptor::Attention { heads, causal, rotary } => Box::new(self.attention(heads, causal, rotary)?),
+            Descriptor::TransformerBlock { heads, causal, rotary, eps } => {
+                let attention_norm = self.layer_norm(eps.0)?;
+                let attention = self.attention(heads, causal, rotary)?;
+                let ffn_norm = self.layer_norm(eps.1)?;
+                let (ffn_up, ffn_down) = (self.dense()?, self.dense()?);
+                Box::new(TransformerBlock::from_parameters(attention_norm, attention, ffn_norm, ffn_up, ffn_down)?)
+            }
+            Descriptor::Lstm(output) => {
+                let (weight_ih, weight_hh, bias) = (self.tensor()?, self.tensor()?, self.tensor()?);
+                Box::new(Lstm::from_parameters(weight_ih, weight_hh, bias, output)?)
+            }
+            Descriptor::Gru(output) => {
+                let (weight_ih, weight_hh) = (self.tensor()?, self.tensor()?);
+                let (bias_ih, bias_hh) = (self.tensor()?, self.tensor()?);
+                Box::new(Gru::from_parameters(weight_ih, weight_hh, bias_ih, bias_hh, output)?)
+            }
+        })
+    }
+}
+
+fn read_pool(reader: &mut Reader) -> Result<Pool2dParams, ModelFormatError> {
+    Ok(Pool2dParams { kernel: reader.pair()?, stride: reader.pair()?, padding: reader.pair()? })
+}
+
+fn read_recurrent_output(reader: &mut Reader) -> Result<RecurrentOutput, ModelFormatError> {
+    Ok(if reader.bool()? { RecurrentOutput::LastState } else { RecurrentOutput::Sequence })
+}
+
+fn read_descriptor(reader: &mut Reader) -> Result<Descriptor, ModelFormatError> {
+    Ok(match reader.u8()? {
+        tag::DENSE => Descriptor::Dense,
+        tag::ACTIVATION => Descriptor::Activation,
+        tag::CONV2D => {
+            let params = Conv2dParams {
+                stride: reader.pair()?,
+                padding: reader.pair()?,
+                dilation: reader.pair()?,
+                groups: reader.len()?,
+            };
+            Descriptor::Conv2d { params, bias: reader.bool()? }
+        }
+        tag::MAX_POOL2D => Descriptor::MaxPool2d(read_pool(reader)?),
+        tag::AVG_POOL2D => Descriptor::AvgPool2d(read_pool(reader)?),
+        tag::GLOBAL_AVG_POOL2D => Descriptor::GlobalAvgPool2d,
+        tag::FLATTEN => Descriptor::Flatten,
+        tag::LAYER_NORM => Descriptor::LayerNorm { eps: reader.f32()? },
+        tag::RMS_NORM => Descriptor::RmsNorm { eps: reader.f32()? },
+        tag::BATCH_NORM => Descriptor::BatchNorm { eps: reader.f32()?, momentum: reader.f32()? },
+        tag::DROPOUT => Descriptor::Dropout { probability: reader.f32()? },
+        tag::EMBEDDING => Descriptor::Embedding,
+        tag::POSITIONAL_ENCODING => Descriptor::PositionalEncoding,
+        tag::ATTENTION => Descriptor::Attention {
+            heads: reader.len()?,
+            causal: reader.bool()?,
+            rotary: reader.bool()?,
+        },
+        tag::TRANSFORMER_BLOCK => Descriptor::TransformerBlock {
+            heads: reader.len()?,
+            causal: reader.bool()?,
+            rotary: reader.bool()?,
+            eps: (reader.f32()?, reader.f32()?),
+        },
+        tag::LSTM => Descriptor::Lstm(read_recurrent_output(reader)?),
+        tag::GRU => Descriptor::Gru(read_recurrent_output(reader)?),
+        _ => return Err(ModelFormatError::Corrupt("Tipo de capa desconocido")),
+    })
+}
+
+fn read_shape(reader: &mut Reader) -> Result<(Vec<usize>, usize), ModelFormatError> {
+    let ndim = reader.u8()? as usize;
+    let shape = (0..ndim).map(|_| reader.len()).collect::<Result<Vec<_>, _>>()?;
+    let len = shape
+        .iter()
+        .try_fold(1usize, |acc, &dim| acc.checked_mul(dim))
+        .ok_or(ModelFormatError::Corrupt("Tensor demasiado grande"))?;
+    Ok((shape, len))
+}
+
+fn read_blob(reader: &mut Reader) -> Result<Blob, ModelFormatError> {
+    let kind = reader.u8()?;
+    let (shape, len) = read_shape(reader)?;
+    let halves = |bytes: &[u8]| -> Vec<u16> {
+        bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect()
+    };
+    Ok(match kind {
+        blob::F32 => {
+            let bytes = reader.elements(len, 4)?;
+            let values = bytes
+                .chunks_exact(4)
+                .map(|word| f32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+                .collect();
+            Blob::Float(Tensor::from_vec(values, &shape)?)
+        }
+        blob::F16 => {
+            let values = halves(reader.elements(len, 2)?).into_iter().map(f16::from_bits).collect();
+            Blob::Float(Tensor::from_f16(values, &shape)?)
+        }
+        blob::BF16 => {
+            let values = halves(reader.elements(len, 2)?).into_iter().map(bf16::from_bits).collect();
+            Blob::Float(Tensor::from_bf16(values, &shape)?)
+        }
+        blob::QUANTIZED => {
+            let scheme = match reader.u8()? {
+                0 => QuantScheme::Symmetric,
+                1 => QuantScheme::Asymmetric,
+                _ => return Err(ModelFormatError::Corrupt("Esquema de cuantización desconocido")),
+            };
+            let axis = usize::try_from(reader.i32()?).ok();
+            let channels = reader.len()?;
+            let scales = reader.elements(channels, 4)?;
+            let scales = scales.chunks_exact(4).map(|w| f32::from_le_bytes([w[0], w[1], w[2], w[3]])).collect();
+            let zero_points = reader.elements(channels, 4)?;
+            let zero_points = zero_points.chunks_exact(4).map(|w| i32::from_le_bytes([w[0], w[1], w[2], w[3]])).collect();
+            let data = reader.elements(len, 1)?.iter().map(|&byte| byte as i8).collect();
+            Blob::Quantized(QuantizedTensor::from_parts(data, &shape, scheme, axis, scales, zero_points)?)
+        }
+        _ => return Err(ModelFormatError::Corrupt("Tipo de bloque de pesos desconocido")),
+    })
+}
+
+/// Formato binario nativo de los modelos, todo en little-endian:
+///
+/// ```text
+/// cabecera     "RAIM" | versión u16 | banderas u16 | longitud del cuerpo u32
+/// cuerpo       nombre (u32 + UTF-8)
+///              nº de capas u32       | descriptores (etiqueta u8 + configuración)
+///              nº de activaciones u32 | códigos u8
+///              nº de bloques u32     | bloques de pesos
+/// cola         CRC32 de cabecera y cuerpo
+/// ```
+///
+/// Cada bloque de pesos lleva su tipo (`f32`, `f16`, `bf16` o `i8`
+/// cuantizado, con sus escalas y puntos cero), el número de dimensiones
+/// (u8) y cada dimensión (u32), seguidos de los datos. Las capas compuestas
+/// (atención, bloque de transformer) consumen varias activaciones y bloques
+/// en un orden fijo.
+impl NeuralNetwork {
+    /// Codifica la red en el formato binario nativo.
+    ///
+    /// Falla con `UnsupportedLayer` si alguna capa no es de la biblioteca.
+    /// El generador de un `Dropout` no se guarda: al leerlo empieza con
+    /// semilla 0.
+    pub fn serialize(&self) -> Result<Vec<u8>, ModelFormatError> {
+        let mut sections = Sections::default();
+        for layer in self.layers() {
+            sections.layer(layer.as_ref())?;
+        }
+
+        let mut body = Writer::default();
+        body.len(self.name().len())?;
+        body.bytes.extend_from_slice(self.name().as_bytes());
+        body.len(sections.layers)?;
+        body.bytes.extend_from_slice(&sections.descriptors.bytes);
+        body.len(sections.activation_count)?;
+        body.bytes.extend_from_slice(&sections.activations.bytes);
+        body.len(sections.blob_count)?;
+        body.bytes.extend_from_slice(&sections.blobs.bytes);
+
+        let mut file = Writer::default();
+        file.bytes.extend_from_slice(&MAGIC);
+        file.u16(MODEL_FORMAT_VERSION);
+        file.u16(0);
+        file.len(body.bytes.len())?;
+        file.bytes.extend_from_slice(&body.bytes);
+        let crc = crc32(&file.bytes);
+        file.u32(crc);
+        Ok(file.bytes)
+    }
+
+    /// Reconstruye una red escrita con `serialize`.
+    ///
+    /// Comprueba la firma, la versión, la longitud y el CRC antes de
+    /// interpretar el contenido; la red vuelve en modo de evaluación y con
+    /// ejecución en coma flotante.
+    pub fn deserialize(bytes: &[u8]) -> Result<NeuralNetwork, ModelFormatError> {
+        // Hasta tener la cabecera completa solo se sabe que faltan bytes
+        let mut file = ByteReader::new(bytes, |_, found| ModelFormatError::Truncated {
+            expected: HEADER_LEN + TRAILER_LEN,
+            found,
+        });
+        let magic = file.take(MAGIC.len())?;
+        let version = file.u16()?;
+        let _flags = file.u16()?;
+        let body_len = file.u32()? as usize;
+        if magic != MAGIC {
+            return Err(ModelFormatError::BadMagic);
+        }
+        if version != MODEL_FORMAT_VERSION {
+            return Err(ModelFormatError::UnsupportedVersion { found: version, supported: MODEL_FORMAT_VERSION });
+        }
+        let total = HEADER_LEN.saturating_add(body_len).saturating_add(TRAILER_LEN);
+        if bytes.len() < total {
+            return Err(ModelFormatError::Truncated { expected: total, found: bytes.len() });
+        }
+        if bytes.len() > total {
+            return Err(ModelFormatError::Corrupt("Hay datos después del CRC del modelo"));
+        }
+        let body = file.take(body_len)?;
+        let expected = file.u32()?;
+        let found = crc32(&bytes[..HEADER_LEN + body_len]);
+        if expected != found {
+            return Err(ModelFormatError::ChecksumMismatch { expected, found });
+        }
+
+        let mut reader = Reader::open(body);
+        let name_len = reader.len()?;
+        let name = core::str::from_utf8(reader.take(name_len)?)
+            .map_err(|_| ModelFormatError::Corrupt("El nombre del modelo no es UTF-8"))?;
+        let layer_count = reader.len()?;
+        let mut descriptors = Vec::new();
+        for _ in 0..layer_count {
+            descriptors.push(read_descriptor(&mut reader)?);
+        }
+        let activation_count = reader.len()?;
+        let activations = reader.elements(activation_count, 1)?;
+        let blob_count = reader.len()?;
+        let mut blobs = Vec::new();
+        for _ in 0..blob_count {
+            blobs.push(read_blob(&mut reader)?);
+        }
+        if !reader.is_empty() {
+            return Err(ModelFormatError::Corrupt("Sobran bytes al final del cuerpo del modelo"));
+        }
+
+        let mut parser = Parser { activations: activations.iter(), blobs: blobs.into_iter() };
+        let mut network = NeuralNetwork::new(name);
+        for descriptor in descriptors {
+            network.add_module(parser.build(descriptor)?)?;
+        }
+        if parser.activations.len() != 0 || parser.blobs.len() != 0 {
+            return Err(ModelFormatError::Corrupt("Sobran activaciones o pesos sin capa"));
+        }
+        Ok(network)
+    }
+}
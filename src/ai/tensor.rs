@@ -1,66 +1,1160 @@
+use alloc::collections::TryReserveError;
+use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
-use core::ops::{Add, Mul};
-use ndarray::{Array, ArrayD, IxDyn};
+use core::fmt;
+use half::{bf16, f16};
+use super::dtype::{Buffer, DType, Storage, UPCAST_BLOCK};
+use super::gemm::{gemm_strided, GemmKernel};
+use crate::math;
+use core::ops::Range;
 
+/// Errores de las operaciones con tensores.
+///
+/// Las operaciones que dependen de datos externos (por ejemplo, una petición
+/// a `/ai/predict`) devuelven estos errores en lugar de entrar en pánico.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TensorError {
+    /// Las formas no son compatibles (ni iguales ni difundibles).
+    ShapeMismatch { lhs: Vec<usize>, rhs: Vec<usize> },
+    /// El número de dimensiones no es el esperado por la operación.
+    RankMismatch { expected: usize, found: usize },
+    /// El número de elementos no coincide con la forma pedida.
+    LengthMismatch { expected: usize, found: usize },
+    /// No hay memoria suficiente en el heap para el resultado.
+    OutOfMemory { bytes: usize },
+    /// El eje indicado no existe en el tensor.
+    InvalidAxis { axis: isize, ndim: usize },
+    /// Un índice se sale de la dimensión correspondiente.
+    IndexOutOfBounds { index: usize, len: usize },
+    /// Operación sin elementos de entrada: máximo de un eje vacío,
+    /// concatenación de una lista vacía...
+    EmptyReduction,
+    /// Parámetro de la operación fuera de su dominio (paso 0, grupos que
+    /// no dividen los canales...); lleva la descripción concreta.
+    InvalidArgument(&'static str),
+}
+
+impl TensorError {
+    /// Descripción breve y estática del error.
+    pub fn message(&self) -> &'static str {
+        match self {
+            TensorError::ShapeMismatch { .. } => "Formas de tensor incompatibles",
+            TensorError::RankMismatch { .. } => "Número de dimensiones incorrecto",
+            TensorError::LengthMismatch { .. } => "Número de elementos incorrecto para la forma",
+            TensorError::OutOfMemory { .. } => "Memoria insuficiente para el tensor",
+            TensorError::InvalidAxis { .. } => "Eje de tensor no válido",
+            TensorError::IndexOutOfBounds { .. } => "Índice fuera de rango",
+            TensorError::EmptyReduction => "Operación sin elementos de entrada",
+            TensorError::InvalidArgument(reason) => reason,
+        }
+    }
+}
+
+impl fmt::Display for TensorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TensorError::ShapeMismatch { lhs, rhs } => {
+                write!(f, "{}: {:?} y {:?}", self.message(), lhs, rhs)
+            }
+            TensorError::RankMismatch { expected, found } => {
+                write!(f, "{}: se esperaban {}, hay {}", self.message(), expected, found)
+            }
+            TensorError::LengthMismatch { expected, found } => {
+                write!(f, "{}: se esperaban {}, hay {}", self.message(), expected, found)
+            }
+            TensorError::OutOfMemory { bytes } => {
+                write!(f, "{}: {} bytes", self.message(), bytes)
+            }
+            TensorError::InvalidAxis { axis, ndim } => {
+                write!(f, "{}: {} con {} dimensiones", self.message(), axis, ndim)
+            }
+            TensorError::IndexOutOfBounds { index, len } => {
+                write!(f, "{}: {} con longitud {}", self.message(), index, len)
+            }
+            TensorError::EmptyReduction | TensorError::InvalidArgument(_) => f.write_str(self.message()),
+        }
+    }
+}
+
+pub type TensorResult<T> = Result<T, TensorError>;
+
+/// `sqrt(2/π)`, el factor de la aproximación de GELU.
+pub(crate) const GELU_SCALE: f32 = core::f32::consts::FRAC_2_SQRT_PI * core::f32::consts::FRAC_1_SQRT_2;
+
+/// Reserva un búfer de `len` elementos sin abortar si el heap está agotado.
+pub(crate) fn try_buffer<T>(len: usize) -> TensorResult<Vec<T>> {
+    let bytes = len.saturating_mul(core::mem::size_of::<T>());
+    let mut buffer = Vec::new();
+    buffer.try_reserve_exact(len)
+        .map_err(|_: TryReserveError| TensorError::OutOfMemory { bytes })?;
+    Ok(buffer)
+}
+
+/// Número de elementos de una forma, detectando desbordamientos.
+pub(crate) fn checked_len(shape: &[usize]) -> TensorResult<usize> {
+    shape.iter()
+        .try_fold(1usize, |acc, &d| acc.checked_mul(d))
+        .ok_or(TensorError::OutOfMemory { bytes: usize::MAX })
+}
+
+/// Convierte un eje que puede ser negativo (contado desde el final) en índice.
+pub fn normalize_axis(axis: isize, ndim: usize) -> TensorResult<usize> {
+    let resolved = if axis < 0 { axis + ndim as isize } else { axis };
+    if resolved < 0 || resolved as usize >= ndim {
+        return Err(TensorError::InvalidAxis { axis, ndim });
+    }
+    Ok(resolved as usize)
+}
+
+/// Forma resultante de difundir dos formas según las reglas de NumPy.
+///
+/// Las dimensiones se alinean por la derecha; cada par debe ser igual o
+/// contener un 1.
+pub fn broadcast_shapes(lhs: &[usize], rhs: &[usize]) -> TensorResult<Vec<usize>> {
+    let ndim = lhs.len().max(rhs.len());
+    let mut shape = vec![0; ndim];
+    for i in 0..ndim {
+        let a = if i < ndim - lhs.len() { 1 } else { lhs[i - (ndim - lhs.len())] };
+        let b = if i < ndim - rhs.len() { 1 } else { rhs[i - (ndim - rhs.len())] };
+        shape[i] = match (a, b) {
+            (a, b) if a == b => a,
+            (1, b) => b,
+            (a, 1) => a,
+            _ => {
+                return Err(TensorError::ShapeMismatch {
+                    lhs: lhs.to_vec(),
+                    rhs: rhs.to_vec(),
+                })
+            }
+        };
+    }
+    Ok(shape)
+}
+
+/// Tensor n-dimensional con almacenamiento propio del kernel.
+///
+/// Los datos viven en un búfer compartido (`Arc<Storage>`) y el tensor
+/// describe cómo recorrerlo mediante `shape`, `strides` y `offset`. Varias
+/// vistas pueden compartir el mismo búfer sin copiarlo; las operaciones
+/// aritméticas siempre producen un tensor nuevo y contiguo de tipo `f32`,
+/// aunque las entradas estén almacenadas en media precisión.
 #[derive(Debug, Clone)]
 pub struct Tensor {
-    data: ArrayD<f32>,
+    storage: Arc<Storage>,
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+    offset: usize,
+}
+
+/// Calcula los strides de un tensor contiguo en orden de filas (row-major).
+pub fn contiguous_strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![0; shape.len()];
+    let mut stride = 1;
+    for (dim, s) in shape.iter().zip(strides.iter_mut()).rev() {
+        *s = stride;
+        stride *= *dim;
+    }
+    strides
 }
 
 impl Tensor {
-    pub fn zeros(shape: &[usize]) -> Self {
-        let data = Array::zeros(IxDyn(shape));
-        Tensor { data }
-    }
-    
-    pub fn ones(shape: &[usize]) -> Self {
-        let data = Array::ones(IxDyn(shape));
-        Tensor { data }
-    }
-    
-    pub fn from_vec(values: Vec<f32>, shape: &[usize]) -> Self {
-        let data = Array::from_shape_vec(IxDyn(shape), values)
-            .expect("Error al crear tensor desde vector");
-        Tensor { data }
-    }
-    
-    pub fn shape(&self) -> Vec<usize> {
-        self.data.shape().to_vec()
-    }
-    
-    pub fn matmul(&self, other: &Tensor) -> Tensor {
-        // Implementación simplificada para demostración
-        // En un sistema real, usaría BLAS o una implementación optimizada
-        let result = self.data.dot(&other.data);
-        Tensor { data: result.into_dyn() }
-    }
-    
-    pub fn add(&self, other: &Tensor) -> Tensor {
-        let result = &self.data + &other.data;
-        Tensor { data: result }
-    }
-    
-    pub fn relu(&self) -> Tensor {
-        let result = self.data.map(|&x| if x > 0.0 { x } else { 0.0 });
-        Tensor { data: result }
-    }
-    
-    pub fn sigmoid(&self) -> Tensor {
-        let result = self.data.map(|&x| 1.0 / (1.0 + (-x).exp()));
-        Tensor { data: result }
-    }
-    
-    pub fn tanh(&self) -> Tensor {
-        let result = self.data.map(|&x| x.tanh());
-        Tensor { data: result }
-    }
-    
-    pub fn softmax(&self) -> Tensor {
-        // Implementación simplificada para demostración
-        let max_val = self.data.fold(f32::MIN, |a, &b| a.max(b));
-        let exp = self.data.map(|&x| (x - max_val).exp());
-        let sum = exp.sum();
-        let result = exp.map(|&x| x / sum);
-        Tensor { data: result }
-    }
-}
\ No newline at end of file
+    fn from_storage(storage: Vec<f32>, shape: &[usize]) -> Self {
+        Self::from_typed_storage(Storage::F32(storage.into()), shape)
+    }
+
+    fn from_typed_storage(storage: Storage, shape: &[usize]) -> Self {
+        Tensor {
+            storage: Arc::new(storage),
+            shape: shape.to_vec(),
+            strides: contiguous_strides(shape),
+            offset: 0,
+        }
+    }
+
+    pub fn zeros(shape: &[usize]) -> TensorResult<Self> {
+        Self::full(shape, 0.0)
+    }
+
+    pub fn ones(shape: &[usize]) -> TensorResult<Self> {
+        Self::full(shape, 1.0)
+    }
+
+    /// Crea un tensor con todos los elementos iguales a `value`.
+    pub fn full(shape: &[usize], value: f32) -> TensorResult<Self> {
+        let len = checked_len(shape)?;
+        let mut data = try_buffer(len)?;
+        data.resize(len, value);
+        Ok(Self::from_storage(data, shape))
+    }
+
+    pub fn from_vec(values: Vec<f32>, shape: &[usize]) -> TensorResult<Self> {
+        let len = checked_len(shape)?;
+        if values.len() != len {
+            return Err(TensorError::LengthMismatch { expected: len, found: values.len() });
+        }
+        Ok(Self::from_storage(values, shape))
+    }
+
+    /// Crea un tensor almacenado en `f16`.
+    pub fn from_f16(values: Vec<f16>, shape: &[usize]) -> TensorResult<Self> {
+        let len = checked_len(shape)?;
+        if values.len() != len {
+            return Err(TensorError::LengthMismatch { expected: len, found: values.len() });
+        }
+        Ok(Self::from_typed_storage(Storage::F16(values.into()), shape))
+    }
+
+    /// Crea un tensor almacenado en `bf16`.
+    pub fn from_bf16(values: Vec<bf16>, shape: &[usize]) -> TensorResult<Self> {
+        let len = checked_len(shape)?;
+        if values.len() != len {
+            return Err(TensorError::LengthMismatch { expected: len, found: values.len() });
+        }
+        Ok(Self::from_typed_storage(Storage::BF16(values.into()), shape))
+    }
+
+    /// Crea un tensor `f32` que lee directamente `values` sin copiarlos (p. ej.
+    /// pesos enlazados en la imagen del kernel); se copian al heap solo si
+    /// se escribe en él con `set`.
+    pub fn from_static(values: &'static [f32], shape: &[usize]) -> TensorResult<Self> {
+        Self::from_static_storage(Storage::F32(Buffer::Static(values)), shape)
+    }
+
+    /// Como `from_static`, almacenado en `f16`.
+    pub fn from_static_f16(values: &'static [f16], shape: &[usize]) -> TensorResult<Self> {
+        Self::from_static_storage(Storage::F16(Buffer::Static(values)), shape)
+    }
+
+    /// Como `from_static`, almacenado en `bf16`.
+    pub fn from_static_bf16(values: &'static [bf16], shape: &[usize]) -> TensorResult<Self> {
+        Self::from_static_storage(Storage::BF16(Buffer::Static(values)), shape)
+    }
+
+    fn from_static_storage(storage: Storage, shape: &[usize]) -> TensorResult<Self> {
+        let len = checked_len(shape)?;
+        if storage.len() != len {
+            return Err(TensorError::LengthMismatch { expected: len, found: storage.len() });
+        }
+        Ok(Self::from_typed_storage(storage, shape))
+    }
+
+    /// Si el tensor lee sus datos de un búfer prestado (`from_static`) en
+    /// vez de uno propio en el heap.
+    pub fn is_static(&self) -> bool {
+        self.storage.is_static()
+    }
+
+    /// Crea un tensor de dimensión cero con un único valor.
+    pub fn scalar(value: f32) -> Self {
+        Self::from_storage(vec![value], &[])
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn dtype(&self) -> DType {
+        self.storage.dtype()
+    }
+
+    /// Memoria que ocupan los elementos visibles del tensor.
+    pub fn size_in_bytes(&self) -> usize {
+        self.len() * self.dtype().size()
+    }
+
+    /// Copia el tensor a un almacenamiento contiguo del tipo indicado.
+    ///
+    /// Si ya tiene ese tipo y es contiguo, comparte el almacenamiento.
+    pub fn to_dtype(&self, dtype: DType) -> TensorResult<Tensor> {
+        if dtype == self.dtype() {
+            return self.contiguous();
+        }
+        let mut values = try_buffer(self.len())?;
+        values.extend(self.iter());
+        let storage = Storage::from_f32(&values, dtype)?;
+        Ok(Self::from_typed_storage(storage, &self.shape))
+    }
+
+    pub fn strides(&self) -> &[usize] {
+        &self.strides
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Número de dimensiones del tensor.
+    pub fn ndim(&self) -> usize {
+        self.shape.len()
+    }
+
+    /// Número total de elementos.
+    pub fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Indica si los elementos están dispuestos en orden de filas sin huecos.
+    pub fn is_contiguous(&self) -> bool {
+        let mut expected = 1;
+        for (&dim, &stride) in self.shape.iter().zip(self.strides.iter()).rev() {
+            if dim != 1 && stride != expected {
+                return false;
+            }
+            expected *= dim;
+        }
+        true
+    }
+
+    /// Indica si dos tensores comparten el mismo búfer de almacenamiento.
+    pub fn shares_storage(&self, other: &Tensor) -> bool {
+        Arc::ptr_eq(&self.storage, &other.storage)
+    }
+
+    /// Devuelve los datos como slice cuando el tensor es `f32` y contiguo.
+    pub fn as_slice(&self) -> Option<&[f32]> {
+        match self.storage.as_f32() {
+            Some(data) if self.is_contiguous() => Some(&data[self.offset..self.offset + self.len()]),
+            _ => None,
+        }
+    }
+
+    /// Lee el elemento en el índice multidimensional dado.
+    pub fn get(&self, index: &[usize]) -> Option<f32> {
+        if index.len() != self.shape.len() {
+            return None;
+        }
+        let mut offset = self.offset;
+        for ((&i, &dim), &stride) in index.iter().zip(&self.shape).zip(&self.strides) {
+            if i >= dim {
+                return None;
+            }
+            offset += i * stride;
+        }
+        Some(self.storage.get(offset))
+    }
+
+    /// Escribe un elemento, copiando el búfer si está compartido con otra vista.
+    pub fn set(&mut self, index: &[usize], value: f32) -> Option<()> {
+        if index.len() != self.shape.len() {
+            return None;
+        }
+        let mut offset = self.offset;
+        for ((&i, &dim), &stride) in index.iter().zip(&self.shape).zip(&self.strides) {
+            if i >= dim {
+                return None;
+            }
+            offset += i * stride;
+        }
+        Arc::make_mut(&mut self.storage).set(offset, value);
+        Some(())
+    }
+
+    /// Crea una vista sobre el mismo almacenamiento con otra geometría.
+    ///
+    /// Falla si hay un stride por dimensión distinto o si la vista se
+    /// saldría del búfer.
+    pub fn as_strided(&self, shape: &[usize], strides: &[usize], offset: usize) -> TensorResult<Tensor> {
+        if shape.len() != strides.len() {
+            return Err(TensorError::LengthMismatch { expected: shape.len(), found: strides.len() });
+        }
+        if shape.iter().all(|&d| d > 0) {
+            let last = shape.iter()
+                .zip(strides)
+                .try_fold(offset, |acc, (&d, &s)| (d - 1).checked_mul(s)?.checked_add(acc))
+                .unwrap_or(usize::MAX);
+            if last >= self.storage.len() {
+                return Err(TensorError::IndexOutOfBounds { index: last, len: self.storage.len() });
+            }
+        }
+        Ok(Tensor {
+            storage: Arc::clone(&self.storage),
+            shape: shape.to_vec(),
+            strides: strides.to_vec(),
+            offset,
+        })
+    }
+
+    /// Itera los elementos en orden lógico, sea o no contiguo el tensor.
+    ///
+    /// Los elementos se leen por bloques del último eje con `upcast_into`,
+    /// así que `map`, `zip_with` y las activaciones convierten `f16`/`bf16`
+    /// a `f32` por bloques en vez de elemento a elemento.
+    pub fn iter(&self) -> TensorIter<'_> {
+        let ndim = self.shape.len();
+        let (lane_len, lane_stride) = match ndim {
+            0 => (1, 1),
+            _ => (self.shape[ndim - 1], self.strides[ndim - 1]),
+        };
+        let outer = ndim.saturating_sub(1);
+        TensorIter {
+            storage: &self.storage,
+            lanes: OffsetIter::new(&self.shape[..outer], &self.strides[..outer], self.offset),
+            lane_base: 0,
+            lane_len,
+            lane_stride,
+            lane_pos: lane_len,
+            block: [0.0; UPCAST_BLOCK],
+            block_len: 0,
+            block_pos: 0,
+            remaining: self.len(),
+        }
+    }
+
+    /// Copia los elementos en orden lógico a un vector nuevo.
+    pub fn to_vec(&self) -> Vec<f32> {
+        match self.as_slice() {
+            Some(slice) => slice.to_vec(),
+            None => self.iter().collect(),
+        }
+    }
+
+    /// Devuelve un tensor contiguo del mismo tipo; si ya lo es, comparte el
+    /// almacenamiento.
+    pub fn contiguous(&self) -> TensorResult<Tensor> {
+        if self.is_contiguous() {
+            Ok(self.clone())
+        } else {
+            let offsets = OffsetIter::new(&self.shape, &self.strides, self.offset);
+            let storage = self.storage.gather(offsets, self.len())?;
+            Ok(Self::from_typed_storage(storage, &self.shape))
+        }
+    }
+
+    /// Vista de `self` expandida a `shape` usando strides nulos (broadcast).
+    pub fn broadcast_to(&self, shape: &[usize]) -> TensorResult<Tensor> {
+        let mismatch = || TensorError::ShapeMismatch {
+            lhs: self.shape.clone(),
+            rhs: shape.to_vec(),
+        };
+        if shape.len() < self.shape.len() {
+            return Err(mismatch());
+        }
+        let lead = shape.len() - self.shape.len();
+        let mut strides = vec![0; shape.len()];
+        for (i, (&dim, &stride)) in self.shape.iter().zip(&self.strides).enumerate() {
+            if dim == shape[lead + i] {
+                strides[lead + i] = stride;
+            } else if dim != 1 {
+                return Err(mismatch());
+            }
+        }
+        Ok(Tensor {
+            storage: Arc::clone(&self.storage),
+            shape: shape.to_vec(),
+            strides,
+            offset: self.offset,
+        })
+    }
+
+    /// Cambia la forma sin copiar cuando la geometría actual lo permite;
+    /// en caso contrario copia los datos a un tensor contiguo.
+    pub fn reshape(&self, shape: &[usize]) -> TensorResult<Tensor> {
+        let len = checked_len(shape)?;
+        if len != self.len() {
+            return Err(TensorError::LengthMismatch { expected: self.len(), found: len });
+        }
+        match view_strides(&self.shape, &self.strides, shape) {
+            Some(strides) => Ok(Tensor {
+                storage: Arc::clone(&self.storage),
+                shape: shape.to_vec(),
+                strides,
+                offset: self.offset,
+            }),
+            None => {
+                let offsets = OffsetIter::new(&self.shape, &self.strides, self.offset);
+                let storage = self.storage.gather(offsets, len)?;
+                Ok(Self::from_typed_storage(storage, shape))
+            }
+        }
+    }
+
+    /// Reordena los ejes (vista sin copia). `dims[i]` es el eje original
+    /// que pasa a ocupar la posición `i`.
+    pub fn permute(&self, dims: &[usize]) -> TensorResult<Tensor> {
+        if dims.len() != self.ndim() {
+            return Err(TensorError::RankMismatch { expected: self.ndim(), found: dims.len() });
+        }
+        let mut seen = vec![false; dims.len()];
+        for &d in dims {
+            if d >= dims.len() || seen[d] {
+                return Err(TensorError::InvalidAxis { axis: d as isize, ndim: self.ndim() });
+            }
+            seen[d] = true;
+        }
+        Ok(Tensor {
+            storage: Arc::clone(&self.storage),
+            shape: dims.iter().map(|&d| self.shape[d]).collect(),
+            strides: dims.iter().map(|&d| self.strides[d]).collect(),
+            offset: self.offset,
+        })
+    }
+
+    /// Intercambia dos ejes (vista sin copia).
+    pub fn transpose(&self, dim0: isize, dim1: isize) -> TensorResult<Tensor> {
+        let dim0 = normalize_axis(dim0, self.ndim())?;
+        let dim1 = normalize_axis(dim1, self.ndim())?;
+        let mut view = self.clone();
+        view.shape.swap(dim0, dim1);
+        view.strides.swap(dim0, dim1);
+        Ok(view)
+    }
+
+    /// Traspuesta de una matriz (vista sin copia).
+    pub fn t(&self) -> TensorResult<Tensor> {
+        if self.ndim() != 2 {
+            return Err(TensorError::RankMismatch { expected: 2, found: self.ndim() });
+        }
+        self.transpose(0, 1)
+    }
+
+    /// Vista de las posiciones `range` del eje `axis` tomadas cada `step`.
+    pub fn slice(&self, axis: isize, range: Range<usize>, step: usize) -> TensorResult<Tensor> {
+        let axis = normalize_axis(axis, self.ndim())?;
+        let len = self.shape[axis];
+        if range.start > range.end || range.end > len {
+            return Err(TensorError::IndexOutOfBounds { index: range.end.max(range.start), len });
+        }
+        if step == 0 {
+            return Err(TensorError::InvalidArgument("El paso de slice debe ser mayor que 0"));
+        }
+        let mut view = self.clone();
+        view.shape[axis] = (range.end - range.start).div_ceil(step);
+        view.strides[axis] = self.strides[axis] * step;
+        view.offset = self.offset + range.start * self.strides[axis];
+        Ok(view)
+    }
+
+    /// Vista de `length` posiciones consecutivas del eje `axis` a partir de `start`.
+    pub fn narrow(&self, axis: isize, start: usize, length: usize) -> TensorResult<Tensor> {
+        self.slice(axis, start..start.saturating_add(length), 1)
+    }
+
+    /// Elimina el eje `axis` si tiene tamaño 1, o todos los de tamaño 1 con `None`.
+    pub fn squeeze(&self, axis: Option<isize>) -> TensorResult<Tensor> {
+        let mut view = self.clone();
+        match axis {
+            Some(axis) => {
+                let axis = normalize_axis(axis, self.ndim())?;
+                if self.shape[axis] != 1 {
+                    return Err(TensorError::ShapeMismatch {
+                        lhs: self.shape.clone(),
+                        rhs: vec![1],
+                    });
+                }
+                view.shape.remove(axis);
+                view.strides.remove(axis);
+            }
+            None => {
+                view.shape.clear();
+                view.strides.clear();
+                for (&dim, &stride) in self.shape.iter().zip(&self.strides) {
+                    if dim != 1 {
+                        view.shape.push(dim);
+                        view.strides.push(stride);
+                    }
+                }
+            }
+        }
+        Ok(view)
+    }
+
+    /// Inserta un eje de tamaño 1 en la posición `axis`.
+    pub fn unsqueeze(&self, axis: isize) -> TensorResult<Tensor> {
+        let axis = normalize_axis(axis, self.ndim() + 1)?;
+        let stride = if axis < self.ndim() {
+            self.strides[axis] * self.shape[axis]
+        } else {
+            1
+        };
+        let mut view = self.clone();
+        view.shape.insert(axis, 1);
+        view.strides.insert(axis, stride);
+        Ok(view)
+    }
+
+    /// Concatena tensores a lo largo de un eje existente (copia).
+    ///
+    /// Todas las formas deben coincidir salvo en `axis`.
+    pub fn concat(tensors: &[&Tensor], axis: isize) -> TensorResult<Tensor> {
+        let first = match tensors.first() {
+            Some(first) => first,
+            None => return Err(TensorError::EmptyReduction),
+        };
+        let axis = normalize_axis(axis, first.ndim())?;
+        let mut shape = first.shape.clone();
+        shape[axis] = 0;
+        for t in tensors {
+            let compatible = t.ndim() == first.ndim()
+                && t.shape.iter()
+                    .zip(&first.shape)
+                    .enumerate()
+                    .all(|(i, (a, b))| i == axis || a == b);
+            if !compatible {
+                return Err(TensorError::ShapeMismatch {
+                    lhs: first.shape.clone(),
+                    rhs: t.shape.clone(),
+                });
+            }
+            shape[axis] += t.shape[axis];
+        }
+
+        let len = checked_len(&shape)?;
+        let mut data = try_buffer(len)?;
+        data.resize(len, 0.0);
+
+        // Cada tensor ocupa un tramo de `inner` elementos dentro de cada
+        // bloque exterior del resultado.
+        let out_inner: usize = shape[axis..].iter().product();
+        let mut start = 0;
+        for t in tensors {
+            let inner: usize = t.shape[axis..].iter().product();
+            for (i, value) in t.iter().enumerate() {
+                data[(i / inner) * out_inner + start + i % inner] = value;
+            }
+            start += inner;
+        }
+        Ok(Self::from_storage(data, &shape))
+    }
+
+    /// Apila tensores de igual forma a lo largo de un eje nuevo (copia).
+    pub fn stack(tensors: &[&Tensor], axis: isize) -> TensorResult<Tensor> {
+        let mut expanded = Vec::with_capacity(tensors.len());
+        for t in tensors {
+            if t.shape != tensors[0].shape {
+                return Err(TensorError::ShapeMismatch {
+                    lhs: tensors[0].shape.clone(),
+                    rhs: t.shape.clone(),
+                });
+            }
+            expanded.push(t.unsqueeze(axis)?);
+        }
+        let refs: Vec<&Tensor> = expanded.iter().collect();
+        Self::concat(&refs, axis)
+    }
+
+    /// Divide el eje `axis` en trozos de `size` posiciones (el último puede
+    /// ser menor). Los trozos son vistas que comparten el almacenamiento.
+    pub fn split(&self, axis: isize, size: usize) -> TensorResult<Vec<Tensor>> {
+        let dim = self.shape[normalize_axis(axis, self.ndim())?];
+        if size == 0 {
+            return Err(TensorError::InvalidArgument("El tamaño de los trozos de split debe ser mayor que 0"));
+        }
+        let mut parts = Vec::with_capacity(dim.div_ceil(size));
+        let mut start = 0;
+        while start < dim {
+            let length = size.min(dim - start);
+            parts.push(self.narrow(axis, start, length)?);
+            start += length;
+        }
+        Ok(parts)
+    }
+
+    /// Aplica `f` a cada elemento y devuelve un tensor contiguo nuevo.
+    pub fn map<F: Fn(f32) -> f32>(&self, f: F) -> TensorResult<Tensor> {
+        let mut data = try_buffer(self.len())?;
+        data.extend(self.iter().map(f));
+        Ok(Self::from_storage(data, &self.shape))
+    }
+
+    /// Combina dos tensores elemento a elemento con difusión (broadcasting).
+    pub fn zip_with<F: Fn(f32, f32) -> f32>(&self, other: &Tensor, f: F) -> TensorResult<Tensor> {
+        let shape = broadcast_shapes(&self.shape, &other.shape)?;
+        let lhs = self.broadcast_to(&shape)?;
+        let rhs = other.broadcast_to(&shape)?;
+        let mut data = try_buffer(checked_len(&shape)?)?;
+        data.extend(lhs.iter().zip(rhs.iter()).map(|(a, b)| f(a, b)));
+        Ok(Self::from_storage(data, &shape))
+    }
+
+    pub fn add(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, |a, b| a + b)
+    }
+
+    pub fn sub(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, |a, b| a - b)
+    }
+
+    pub fn mul(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, |a, b| a * b)
+    }
+
+    pub fn div(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, |a, b| a / b)
+    }
+
+    pub fn pow(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, math::powf)
+    }
+
+    pub fn maximum(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, f32::max)
+    }
+
+    pub fn minimum(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, f32::min)
+    }
+
+    // Las comparaciones devuelven una máscara con 1.0 donde se cumple y 0.0 donde no.
+
+    pub fn equal(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, |a, b| mask(a == b))
+    }
+
+    pub fn not_equal(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, |a, b| mask(a != b))
+    }
+
+    pub fn less(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, |a, b| mask(a < b))
+    }
+
+    pub fn less_equal(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, |a, b| mask(a <= b))
+    }
+
+    pub fn greater(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, |a, b| mask(a > b))
+    }
+
+    pub fn greater_equal(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.zip_with(other, |a, b| mask(a >= b))
+    }
+
+    pub fn add_scalar(&self, value: f32) -> TensorResult<Tensor> {
+        self.map(|x| x + value)
+    }
+
+    pub fn mul_scalar(&self, value: f32) -> TensorResult<Tensor> {
+        self.map(|x| x * value)
+    }
+
+    /// Producto matricial con la semántica de `numpy.matmul`.
+    ///
+    /// Los operandos de una dimensión se tratan como vector fila (izquierda)
+    /// o columna (derecha) y esa dimensión se elimina del resultado. Las
+    /// dimensiones anteriores a las dos últimas se difunden como lotes.
+    pub fn matmul(&self, other: &Tensor) -> TensorResult<Tensor> {
+        self.matmul_with(other, GemmKernel::detect())
+    }
+
+    /// `matmul` con un microkernel concreto (por ejemplo, para compararlos).
+    pub fn matmul_with(&self, other: &Tensor, kernel: GemmKernel) -> TensorResult<Tensor> {
+        if self.ndim() == 0 || other.ndim() == 0 {
+            return Err(TensorError::RankMismatch { expected: 1, found: 0 });
+        }
+
+        // Vistas de al menos dos dimensiones sobre ambos operandos
+        let a = if self.ndim() == 1 {
+            self.as_strided(&[1, self.shape[0]], &[0, self.strides[0]], self.offset)?
+        } else {
+            self.clone()
+        };
+        let b = if other.ndim() == 1 {
+            other.as_strided(&[other.shape[0], 1], &[other.strides[0], 0], other.offset)?
+        } else {
+            other.clone()
+        };
+
+        let (a_batch, a_mat) = a.shape.split_at(a.ndim() - 2);
+        let (b_batch, b_mat) = b.shape.split_at(b.ndim() - 2);
+        let (m, k, n) = (a_mat[0], a_mat[1], b_mat[1]);
+        if k != b_mat[0] {
+            return Err(TensorError::ShapeMismatch {
+                lhs: self.shape.clone(),
+                rhs: other.shape.clone(),
+            });
+        }
+
+        let batch = broadcast_shapes(a_batch, b_batch)?;
+        let mut full = batch.clone();
+        full.extend_from_slice(&[m, k]);
+        let a = a.broadcast_to(&full)?;
+        full.truncate(batch.len());
+        full.extend_from_slice(&[k, n]);
+        let b = b.broadcast_to(&full)?;
+
+        let batches = checked_len(&batch)?;
+        let len = checked_len(&[batches, m, n])?;
+        let mut out = try_buffer(len)?;
+        out.resize(len, 0.0);
+
+        let nb = batch.len();
+        if len > 0 {
+            let a_offsets = OffsetIter::new(&batch, &a.strides[..nb], a.offset);
+            let b_offsets = OffsetIter::new(&batch, &b.strides[..nb], b.offset);
+            for ((a_off, b_off), block) in a_offsets.zip(b_offsets).zip(out.chunks_mut(m * n)) {
+                gemm_strided(
+                    kernel,
+                    m, n, k,
+                    &a.storage, a_off, a.strides[nb], a.strides[nb + 1],
+                    &b.storage, b_off, b.strides[nb], b.strides[nb + 1],
+                    block,
+                )?;
+            }
+        }
+
+        let mut shape = batch;
+        if self.ndim() > 1 {
+            shape.push(m);
+        }
+        if other.ndim() > 1 {
+            shape.push(n);
+        }
+        Ok(Self::from_storage(out, &shape))
+    }
+
+    pub fn relu(&self) -> TensorResult<Tensor> {
+        self.map(|x| if x > 0.0 { x } else { 0.0 })
+    }
+
+    pub fn sigmoid(&self) -> TensorResult<Tensor> {
+        self.map(math::sigmoid)
+    }
+
+    pub fn tanh(&self) -> TensorResult<Tensor> {
+        self.map(math::tanh)
+    }
+
+    /// GELU aproximada: `0.5 x (1 + tanh(sqrt(2/π) (x + 0.044715 x³)))`.
+    pub fn gelu(&self) -> TensorResult<Tensor> {
+        self.map(|x| 0.5 * x * (1.0 + math::tanh(GELU_SCALE * (x + 0.044_715 * x * x * x))))
+    }
+
+    /// SiLU: `x * sigmoid(x)`.
+    pub fn silu(&self) -> TensorResult<Tensor> {
+        self.map(|x| x * math::sigmoid(x))
+    }
+
+    /// Softmax normalizado a lo largo de `axis` (p. ej. `-1` para las clases
+    /// de una salida `[batch, clases]`).
+    pub fn softmax(&self, axis: isize) -> TensorResult<Tensor> {
+        self.map_lanes(axis, |lane, out| {
+            let max_val = lane.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let mut sum = 0.0;
+            for (o, &x) in out.iter_mut().zip(lane) {
+                *o = math::exp(x - max_val);
+                sum += *o;
+            }
+            for o in out.iter_mut() {
+                *o /= sum;
+            }
+        })
+    }
+
+    /// Logaritmo del softmax, calculado de forma estable como `x - max - ln(Σ e^(x - max))`.
+    pub fn log_softmax(&self, axis: isize) -> TensorResult<Tensor> {
+        self.map_lanes(axis, |lane, out| {
+            let max_val = lane.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let sum: f32 = lane.iter().map(|&x| math::exp(x - max_val)).sum();
+            let log_sum = math::ln(sum);
+            for (o, &x) in out.iter_mut().zip(lane) {
+                *o = x - max_val - log_sum;
+            }
+        })
+    }
+
+    /// Suma de todos los elementos.
+    pub fn sum_all(&self) -> f32 {
+        self.iter().sum()
+    }
+
+    /// Media de todos los elementos.
+    pub fn mean_all(&self) -> f32 {
+        self.sum_all() / self.len() as f32
+    }
+
+    pub fn sum(&self, axis: isize, keepdim: bool) -> TensorResult<Tensor> {
+        self.reduce(axis, keepdim, |lane| Ok(lane.iter().sum()))
+    }
+
+    pub fn mean(&self, axis: isize, keepdim: bool) -> TensorResult<Tensor> {
+        self.reduce(axis, keepdim, |lane| {
+            Ok(lane.iter().sum::<f32>() / lane.len() as f32)
+        })
+    }
+
+    /// Varianza poblacional (sin corrección de Bessel) a lo largo de `axis`.
+    pub fn var(&self, axis: isize, keepdim: bool) -> TensorResult<Tensor> {
+        self.reduce(axis, keepdim, |lane| Ok(variance(lane)))
+    }
+
+    pub fn max(&self, axis: isize, keepdim: bool) -> TensorResult<Tensor> {
+        self.reduce(axis, keepdim, |lane| Ok(lane[arg_extreme(lane, |a, b| a > b)?]))
+    }
+
+    pub fn min(&self, axis: isize, keepdim: bool) -> TensorResult<Tensor> {
+        self.reduce(axis, keepdim, |lane| Ok(lane[arg_extreme(lane, |a, b| a < b)?]))
+    }
+
+    /// Posición del máximo a lo largo de `axis`, expresada como `f32`.
+    ///
+    /// En caso de empate se devuelve la primera posición.
+    pub fn argmax(&self, axis: isize, keepdim: bool) -> TensorResult<Tensor> {
+        self.reduce(axis, keepdim, |lane| Ok(arg_extreme(lane, |a, b| a > b)? as f32))
+    }
+
+    /// Posición del mínimo a lo largo de `axis`, expresada como `f32`.
+    pub fn argmin(&self, axis: isize, keepdim: bool) -> TensorResult<Tensor> {
+        self.reduce(axis, keepdim, |lane| Ok(arg_extreme(lane, |a, b| a < b)? as f32))
+    }
+
+    /// Selecciona las posiciones `indices` del eje `axis` (copia del mismo tipo).
+    pub fn index_select(&self, axis: isize, indices: &[usize]) -> TensorResult<Tensor> {
+        let axis = normalize_axis(axis, self.ndim())?;
+        let len = self.shape[axis];
+        if let Some(&index) = indices.iter().find(|&&i| i >= len) {
+            return Err(TensorError::IndexOutOfBounds { index, len });
+        }
+
+        let mut shape = self.shape.clone();
+        shape[axis] = indices.len();
+        let outer = &self.shape[..axis];
+        let inner = &self.shape[axis + 1..];
+        let offsets = OffsetIter::new(outer, &self.strides[..axis], self.offset)
+            .flat_map(|base| {
+                indices.iter().flat_map(move |&index| {
+                    let start = base + index * self.strides[axis];
+                    OffsetIter::new(inner, &self.strides[axis + 1..], start)
+                })
+            });
+        let storage = self.storage.gather(offsets, checked_len(&shape)?)?;
+        Ok(Self::from_typed_storage(storage, &shape))
+    }
+
+    /// Reduce cada fila a lo largo de `axis` a un único valor.
+    fn reduce<F>(&self, axis: isize, keepdim: bool, f: F) -> TensorResult<Tensor>
+    where
+        F: Fn(&[f32]) -> TensorResult<f32>,
+    {
+        let axis = normalize_axis(axis, self.ndim())?;
+        let (outer_shape, outer_strides) = self.without_axis(axis);
+        let mut data = try_buffer(checked_len(&outer_shape)?)?;
+        let mut lane = try_buffer(self.shape[axis])?;
+        for base in OffsetIter::new(&outer_shape, &outer_strides, self.offset) {
+            self.read_lane(base, axis, &mut lane);
+            data.push(f(&lane)?);
+        }
+
+        let mut shape = outer_shape;
+        if keepdim {
+            shape.insert(axis, 1);
+        }
+        Ok(Self::from_storage(data, &shape))
+    }
+
+    /// Transforma cada fila a lo largo de `axis` conservando la forma.
+    fn map_lanes<F>(&self, axis: isize, f: F) -> TensorResult<Tensor>
+    where
+        F: Fn(&[f32], &mut [f32]),
+    {
+        let axis = normalize_axis(axis, self.ndim())?;
+        let len = checked_len(&self.shape)?;
+        let mut data = try_buffer(len)?;
+        data.resize(len, 0.0);
+
+        let out_strides = contiguous_strides(&self.shape);
+        let (outer_shape, outer_strides) = self.without_axis(axis);
+        let out_outer: Vec<usize> = out_strides.iter()
+            .enumerate()
+            .filter(|&(i, _)| i != axis)
+            .map(|(_, &s)| s)
+            .collect();
+
+        let n = self.shape[axis];
+        let mut lane = try_buffer(n)?;
+        let mut result = try_buffer(n)?;
+        result.resize(n, 0.0);
+        let bases = OffsetIter::new(&outer_shape, &outer_strides, self.offset);
+        let out_bases = OffsetIter::new(&outer_shape, &out_outer, 0);
+        for (base, out_base) in bases.zip(out_bases) {
+            self.read_lane(base, axis, &mut lane);
+            f(&lane, &mut result);
+            for (i, &value) in result.iter().enumerate() {
+                data[out_base + i * out_strides[axis]] = value;
+            }
+        }
+        Ok(Self::from_storage(data, &self.shape))
+    }
+
+    /// Forma y strides del tensor sin el eje `axis`.
+    fn without_axis(&self, axis: usize) -> (Vec<usize>, Vec<usize>) {
+        let mut shape = self.shape.clone();
+        let mut strides = self.strides.clone();
+        shape.remove(axis);
+        strides.remove(axis);
+        (shape, strides)
+    }
+
+    fn read_lane(&self, base: usize, axis: usize, lane: &mut Vec<f32>) {
+        let stride = self.strides[axis];
+        lane.resize(self.shape[axis], 0.0);
+        self.storage.upcast_into(base, stride, lane);
+    }
+}
+
+fn variance(values: &[f32]) -> f32 {
+    let n = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / n;
+    values.iter().map(|&x| (x - mean) * (x - mean)).sum::<f32>() / n
+}
+
+/// Posición del primer elemento que gana todas las comparaciones `better`.
+fn arg_extreme<F: Fn(f32, f32) -> bool>(values: &[f32], better: F) -> TensorResult<usize> {
+    if values.is_empty() {
+        return Err(TensorError::EmptyReduction);
+    }
+    let mut best = 0;
+    for (i, &x) in values.iter().enumerate().skip(1) {
+        if better(x, values[best]) {
+            best = i;
+        }
+    }
+    Ok(best)
+}
+
+fn mask(condition: bool) -> f32 {
+    if condition { 1.0 } else { 0.0 }
+}
+
+/// Strides para ver `old_shape` con la forma `new_shape` sin copiar, si existen.
+///
+/// Agrupa los ejes originales en tramos que son contiguos entre sí y
+/// comprueba que la nueva forma puede repartirse sobre esos tramos.
+fn view_strides(old_shape: &[usize], old_strides: &[usize], new_shape: &[usize]) -> Option<Vec<usize>> {
+    let mut new_strides = vec![0; new_shape.len()];
+    if old_shape.iter().product::<usize>() == 0 || old_shape.is_empty() {
+        return Some(contiguous_strides(new_shape));
+    }
+
+    let mut view_d = new_shape.len() as isize - 1;
+    let mut chunk_base_stride = *old_strides.last().unwrap();
+    let mut tensor_numel = 1;
+    let mut view_numel = 1;
+    for tensor_d in (0..old_shape.len()).rev() {
+        tensor_numel *= old_shape[tensor_d];
+        let chunk_ends = tensor_d == 0
+            || (old_shape[tensor_d - 1] != 1
+                && old_strides[tensor_d - 1] != tensor_numel * chunk_base_stride);
+        if chunk_ends {
+            while view_d >= 0 && (view_numel < tensor_numel || new_shape[view_d as usize] == 1) {
+                new_strides[view_d as usize] = view_numel * chunk_base_stride;
+                view_numel *= new_shape[view_d as usize];
+                view_d -= 1;
+            }
+            if view_numel != tensor_numel {
+                return None;
+            }
+            if tensor_d > 0 {
+                chunk_base_stride = old_strides[tensor_d - 1];
+                tensor_numel = 1;
+                view_numel = 1;
+            }
+        }
+    }
+    if view_d != -1 {
+        return None;
+    }
+    Some(new_strides)
+}
+
+/// Recorre los desplazamientos en el almacenamiento de una vista strided.
+struct OffsetIter {
+    shape: Vec<usize>,
+    strides: Vec<usize>,
+    index: Vec<usize>,
+    offset: usize,
+    remaining: usize,
+}
+
+impl OffsetIter {
+    fn new(shape: &[usize], strides: &[usize], offset: usize) -> Self {
+        OffsetIter {
+            shape: shape.to_vec(),
+            strides: strides.to_vec(),
+            index: vec![0; shape.len()],
+            offset,
+            remaining: shape.iter().product(),
+        }
+    }
+}
+
+impl Iterator for OffsetIter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let current = self.offset;
+        self.remaining -= 1;
+
+        // Avanzar el índice como un contador con acarreo desde el último eje
+        for axis in (0..self.shape.len()).rev() {
+            self.index[axis] += 1;
+            self.offset += self.strides[axis];
+            if self.index[axis] < self.shape[axis] {
+                break;
+            }
+            self.offset -= self.strides[axis] * self.shape[axis];
+            self.index[axis] = 0;
+        }
+        Some(current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// Iterador por valor sobre los elementos de un tensor.
+pub struct TensorIter<'a> {
+    storage: &'a Storage,
+    // Inicio de cada fila del último eje
+    lanes: OffsetIter,
+    lane_base: usize,
+    lane_len: usize,
+    lane_stride: usize,
+    lane_pos: usize,
+    // Elementos ya convertidos a `f32` de la fila actual
+    block: [f32; UPCAST_BLOCK],
+    block_len: usize,
+    block_pos: usize,
+    remaining: usize,
+}
+
+impl<'a> Iterator for TensorIter<'a> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.remaining == 0 {
+            return None;
+        }
+        if self.block_pos == self.block_len {
+            if self.lane_pos == self.lane_len {
+                self.lane_base = self.lanes.next()?;
+                self.lane_pos = 0;
+            }
+            let len = (self.lane_len - self.lane_pos).min(UPCAST_BLOCK);
+            let start = self.lane_base + self.lane_pos * self.lane_stride;
+            self.storage.upcast_into(start, self.lane_stride, &mut self.block[..len]);
+            self.lane_pos += len;
+            self.block_len = len;
+            self.block_pos = 0;
+        }
+        let value = self.block[self.block_pos];
+        self.block_pos += 1;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for TensorIter<'a> {}
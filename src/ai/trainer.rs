@@ -0,0 +1,435 @@
+use super::loss::Loss;
+use super::nn::NeuralNetwork;
+use super::optimizer::Optimizer;
+use super::random::Rng;
+use super::tensor::{Tensor, TensorError, TensorResult};
+use crate::{math, serial_println};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::f32::consts::PI;
+
+/// Evolución de la tasa de aprendizaje a lo largo de las épocas, a partir
+/// de la tasa con la que se creó el optimizador.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LrSchedule {
+    Constant,
+    /// Multiplica la tasa por `gamma` cada `step_size` épocas.
+    Step { step_size: usize, gamma: f32 },
+    /// Baja de la tasa base a `min_lr` siguiendo medio coseno en `epochs`
+    /// épocas y se queda en `min_lr` después.
+    Cosine { epochs: usize, min_lr: f32 },
+    /// Sube linealmente hasta la tasa base en `epochs` épocas y a partir de
+    /// ahí sigue `then`, contando desde el final del calentamiento.
+    Warmup { epochs: usize, then: Box<LrSchedule> },
+}
+
+impl LrSchedule {
+    /// Tasa para la época `epoch` (desde 0) con tasa base `base`.
+    pub fn learning_rate(&self, base: f32, epoch: usize) -> f32 {
+        match self {
+            LrSchedule::Constant => base,
+            LrSchedule::Step { step_size, gamma } => {
+                let decays = epoch / (*step_size).max(1);
+                base * math::powi(*gamma, decays.min(i32::MAX as usize) as i32)
+            }
+            LrSchedule::Cosine { epochs, min_lr } => {
+                let progress = epoch.min(*epochs) as f32 / (*epochs).max(1) as f32;
+                min_lr + (base - min_lr) * 0.5 * (1.0 + math::cos(PI * progress))
+            }
+            LrSchedule::Warmup { epochs, then } => {
+                if epoch < *epochs {
+                    base * (epoch + 1) as f32 / *epochs as f32
+                } else {
+                    then.learning_rate(base, epoch - epochs)
+                }
+            }
+        }
+    }
+}
+
+/// Recorte de los gradientes antes de cada actualización.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientClip {
+    /// Limita cada componente a `[-v, v]`.
+    Value(f32),
+    /// Escala todos los gradientes a la vez para que su norma L2 conjunta
+    /// no pase de este valor.
+    Norm(f32),
+}
+
+/// Recorta `grads` y devuelve su norma L2 conjunta antes del recorte.
+pub fn clip_gradients(grads: &mut [Tensor], clip: GradientClip) -> TensorResult<f32> {
+    let norm = math::sqrt(grads.iter().map(|grad| grad.iter().map(|g| g * g).sum::<f32>()).sum());
+    match clip {
+        GradientClip::Value(limit) => {
+            for grad in grads.iter_mut() {
+                *grad = grad.map(|g| g.clamp(-limit, limit))?;
+            }
+        }
+        GradientClip::Norm(max_norm) if norm > max_norm => {
+            let scale = max_norm / norm;
+            for grad in grads.iter_mut() {
+                *grad = grad.mul_scalar(scale)?;
+            }
+        }
+        GradientClip::Norm(_) => {}
+    }
+    Ok(norm)
+}
+
+/// Métrica que vigila la parada temprana, medida sobre el conjunto de
+/// validación (o el de entrenamiento si no hay).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// La pérdida; mejor cuanto menor.
+    Loss,
+    /// Fracción de aciertos; mejor cuanto mayor. Con una sola salida se
+    /// umbraliza en 0.5; con varias se compara el máximo de cada fila.
+    Accuracy,
+}
+
+impl Metric {
+    fn improves(self, value: f32, best: f32, min_delta: f32) -> bool {
+        match self {
+            Metric::Loss => value < best - min_delta,
+            Metric::Accuracy => value > best + min_delta,
+        }
+    }
+}
+
+/// Detiene el entrenamiento si `metric` no mejora al menos `min_delta`
+/// durante `patience` épocas seguidas, y restaura los pesos de la mejor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EarlyStopping {
+    pub metric: Metric,
+    pub patience: usize,
+    pub min_delta: f32,
+}
+
+/// Ejemplos de entrada y objetivo; el eje 0 recorre los ejemplos.
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    inputs: Tensor,
+    targets: Tensor,
+}
+
+impl Dataset {
+    pub fn new(inputs: Tensor, targets: Tensor) -> TensorResult<Self> {
+        match (inputs.shape().first(), targets.shape().first()) {
+            (Some(a), Some(b)) if a == b => Ok(Dataset { inputs, targets }),
+            (Some(_), Some(_)) => Err(TensorError::ShapeMismatch {
+                lhs: inputs.shape().to_vec(),
+                rhs: targets.shape().to_vec(),
+            }),
+            _ => Err(TensorError::RankMismatch { expected: 1, found: 0 }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inputs.shape()[0]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn inputs(&self) -> &Tensor {
+        &self.inputs
+    }
+
+    pub fn targets(&self) -> &Tensor {
+        &self.targets
+    }
+
+    /// Lote con los ejemplos `indices`.
+    pub fn batch(&self, indices: &[usize]) -> TensorResult<(Tensor, Tensor)> {
+        Ok((self.inputs.index_select(0, indices)?, self.targets.index_select(0, indices)?))
+    }
+}
+
+/// Configuración de `Trainer`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainerConfig {
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub schedule: LrSchedule,
+    pub clip: Option<GradientClip>,
+    pub early_stopping: Option<EarlyStopping>,
+    /// Épocas entre copias de los pesos en memoria; 0 para no guardarlas.
+    pub checkpoint_every: usize,
+    /// Semilla para barajar los ejemplos en cada época; `None` los recorre
+    /// en orden.
+    pub shuffle_seed: Option<u64>,
+    /// Épocas entre mensajes de progreso por el puerto serie; 0 para no
+    /// informar.
+    pub log_every: usize,
+}
+
+impl Default for TrainerConfig {
+    fn default() -> Self {
+        TrainerConfig {
+            epochs: 10,
+            batch_size: 32,
+            schedule: LrSchedule::Constant,
+            clip: None,
+            early_stopping: None,
+            checkpoint_every: 0,
+            shuffle_seed: None,
+            log_every: 1,
+        }
+    }
+}
+
+/// Copia de los pesos de un modelo al final de una época.
+///
+/// Los tensores comparten el búfer con los que tenía el modelo entonces; el
+/// optimizador crea tensores nuevos al actualizar, así que la copia no
+/// cambia aunque el entrenamiento continúe.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub epoch: usize,
+    pub metric: f32,
+    pub parameters: Vec<Tensor>,
+    /// Estado no entrenable (`NeuralNetwork::buffers`), como las
+    /// estadísticas acumuladas de `BatchNorm`.
+    pub buffers: Vec<Tensor>,
+}
+
+/// Resultados de una época.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EpochStats {
+    pub epoch: usize,
+    pub learning_rate: f32,
+    /// Media de la pérdida de los lotes, ponderada por su tamaño.
+    pub train_loss: f32,
+    /// Pérdida sobre el conjunto de validación, si lo hay, y precisión si
+    /// además la pérdida es de clasificación (una entropía cruzada).
+    pub validation_loss: Option<f32>,
+    pub validation_accuracy: Option<f32>,
+}
+
+/// Resumen de `Trainer::fit`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingReport {
+    pub history: Vec<EpochStats>,
+    /// Época con la mejor métrica de parada temprana, si se vigila alguna.
+    pub best_epoch: Option<usize>,
+    pub stopped_early: bool,
+}
+
+/// Entrena un `NeuralNetwork` por épocas sobre un `Dataset`.
+pub struct Trainer {
+    loss: Loss,
+    optimizer: Box<dyn Optimizer>,
+    config: TrainerConfig,
+    base_learning_rate: f32,
+    checkpoint: Option<Checkpoint>,
+    best: Option<Checkpoint>,
+}
+
+impl Trainer {
+    /// La tasa de aprendizaje con la que se creó `optimizer` es la base del
+    /// programa de tasas.
+    pub fn new(loss: Loss, optimizer: impl Optimizer + 'static, config: TrainerConfig) -> Self {
+        Trainer {
+            loss,
+            base_learning_rate: optimizer.learning_rate(),
+            optimizer: Box::new(optimizer),
+            config,
+            checkpoint: None,
+            best: None,
+        }
+    }
+
+    pub fn config(&self) -> &TrainerConfig {
+        &self.config
+    }
+
+    pub fn optimizer(&self) -> &dyn Optimizer {
+        self.optimizer.as_ref()
+    }
+
+    /// Última copia periódica de los pesos.
+    pub fn checkpoint(&self) -> Option<&Checkpoint> {
+        self.checkpoint.as_ref()
+    }
+
+    /// Pesos de la época con la mejor métrica de parada temprana.
+    pub fn best(&self) -> Option<&Checkpoint> {
+        self.best.as_ref()
+    }
+
+    /// Entrena `network` con `train` durante `config.epochs` épocas (o
+    /// hasta la parada temprana). La métrica de parada se mide en
+    /// `validation` o, si no hay, en `train`.
+    ///
+    /// El modelo queda en modo evaluación y, si la parada temprana está
+    /// activa, con los pesos y el estado no entrenable de la mejor época.
+    pub fn fit(
+        &mut self,
+        network: &mut NeuralNetwork,
+        train: &Dataset,
+        validation: Option<&Dataset>,
+    ) -> TensorResult<TrainingReport> {
+        if self.config.batch_size == 0 {
+            return Err(TensorError::InvalidArgument("El tamaño de lote debe ser mayor que cero"));
+        }
+        if train.is_empty() {
+            return Err(TensorError::EmptyReduction);
+        }
+        let mut rng = self.config.shuffle_seed.map(Rng::new);
+        let mut order: Vec<usize> = (0..train.len()).collect();
+        let mut report = TrainingReport { history: Vec::new(), best_epoch: None, stopped_early: false };
+        let mut stale = 0;
+        self.best = None;
+
+        for epoch in 0..self.config.epochs {
+            let learning_rate = self.config.schedule.learning_rate(self.base_learning_rate, epoch);
+            self.optimizer.set_learning_rate(learning_rate);
+            if let Some(rng) = rng.as_mut() {
+                shuffle(&mut order, rng);
+            }
+
+            network.set_training(true);
+            let result = self.train_epoch(network, train, &order);
+            network.set_training(false);
+            let train_loss = result?;
+
+            let (validation_loss, validation_accuracy) = match validation {
+                Some(data) => {
+                    let (loss, accuracy) = self.evaluate(network, data)?;
+                    (Some(loss), Some(accuracy).filter(|_| classifies(self.loss)))
+                }
+                None => (None, None),
+            };
+            let stats = EpochStats { epoch, learning_rate, train_loss, validation_loss, validation_accuracy };
+            report.history.push(stats);
+
+            if self.config.log_every != 0 && (epoch + 1) % self.config.log_every == 0 {
+                log_epoch(&stats, self.config.epochs);
+            }
+
+            if self.config.checkpoint_every != 0 && (epoch + 1) % self.config.checkpoint_every == 0 {
+                self.checkpoint = Some(snapshot(network, epoch, train_loss));
+            }
+
+            if let Some(stopping) = self.config.early_stopping {
+                let value = match (stopping.metric, validation_loss, validation_accuracy) {
+                    (Metric::Loss, Some(loss), _) => loss,
+                    (Metric::Accuracy, _, Some(accuracy)) => accuracy,
+                    (Metric::Loss, None, _) => train_loss,
+                    (Metric::Accuracy, _, None) => self.evaluate(network, validation.unwrap_or(train))?.1,
+                };
+                let improved = match &self.best {
+                    Some(best) => stopping.metric.improves(value, best.metric, stopping.min_delta),
+                    None => true,
+                };
+                if improved {
+                    self.best = Some(snapshot(network, epoch, value));
+                    report.best_epoch = Some(epoch);
+                    stale = 0;
+                } else {
+                    stale += 1;
+                    if stale >= stopping.patience {
+                        report.stopped_early = true;
+                        serial_println!("Parada temprana en la época {}", epoch + 1);
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let Some(best) = &self.best {
+            network.set_parameters(&best.parameters)?;
+            network.set_buffers(&best.buffers)?;
+        }
+        Ok(report)
+    }
+
+    /// Una pasada por los lotes de `train` en el orden `order`; devuelve la
+    /// pérdida media por ejemplo.
+    fn train_epoch(&mut self, network: &mut NeuralNetwork, train: &Dataset, order: &[usize]) -> TensorResult<f32> {
+        let mut total = 0.0;
+        for indices in order.chunks(self.config.batch_size) {
+            let (input, target) = train.batch(indices)?;
+            let (loss, mut grads) = network.gradients(input, &target, self.loss)?;
+            if let Some(clip) = self.config.clip {
+                clip_gradients(&mut grads, clip)?;
+            }
+            network.apply_gradients(&grads, self.optimizer.as_mut())?;
+            total += loss * indices.len() as f32;
+        }
+        Ok(total / order.len() as f32)
+    }
+
+    /// Pérdida y precisión de `network` sobre `data`.
+    pub fn evaluate(&self, network: &NeuralNetwork, data: &Dataset) -> TensorResult<(f32, f32)> {
+        let prediction = network.forward(data.inputs().clone())?;
+        let (loss, _) = self.loss.evaluate(&prediction, data.targets())?;
+        Ok((loss, accuracy(&prediction, data.targets())?))
+    }
+}
+
+/// Fracción de filas acertadas (ver `Metric::Accuracy`).
+pub fn accuracy(prediction: &Tensor, target: &Tensor) -> TensorResult<f32> {
+    if prediction.shape() != target.shape() {
+        return Err(TensorError::ShapeMismatch {
+            lhs: prediction.shape().to_vec(),
+            rhs: target.shape().to_vec(),
+        });
+    }
+    let hits = match prediction.shape().last() {
+        None => return Err(TensorError::RankMismatch { expected: 1, found: 0 }),
+        Some(0) => return Err(TensorError::EmptyReduction),
+        Some(1) => prediction
+            .iter()
+            .zip(target.iter())
+            .filter(|&(p, t)| (p >= 0.5) == (t >= 0.5))
+            .count(),
+        Some(_) => {
+            let predicted = prediction.argmax(-1, false)?;
+            let expected = target.argmax(-1, false)?;
+            predicted.iter().zip(expected.iter()).filter(|&(p, t)| p == t).count()
+        }
+    };
+    let rows = prediction.len() / prediction.shape()[prediction.ndim() - 1];
+    Ok(hits as f32 / rows.max(1) as f32)
+}
+
+fn classifies(loss: Loss) -> bool {
+    matches!(loss, Loss::BinaryCrossEntropy | Loss::CategoricalCrossEntropy)
+}
+
+/// Baraja `order` (Fisher-Yates).
+fn shuffle(order: &mut [usize], rng: &mut Rng) {
+    for i in (1..order.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+}
+
+fn snapshot(network: &NeuralNetwork, epoch: usize, metric: f32) -> Checkpoint {
+    Checkpoint {
+        epoch,
+        metric,
+        parameters: network.parameters().into_iter().cloned().collect(),
+        buffers: network.buffers(),
+    }
+}
+
+fn log_epoch(stats: &EpochStats, epochs: usize) {
+    match (stats.validation_loss, stats.validation_accuracy) {
+        (Some(loss), Some(accuracy)) => serial_println!(
+            "Época {}/{}: pérdida {:.4}, validación {:.4} ({:.1}% aciertos), lr {:.2e}",
+            stats.epoch + 1, epochs, stats.train_loss, loss, accuracy * 100.0, stats.learning_rate
+        ),
+        (Some(loss), None) => serial_println!(
+            "Época {}/{}: pérdida {:.4}, validación {:.4}, lr {:.2e}",
+            stats.epoch + 1, epochs, stats.train_loss, loss, stats.learning_rate
+        ),
+        (None, _) => serial_println!(
+            "Época {}/{}: pérdida {:.4}, lr {:.2e}",
+            stats.epoch + 1, epochs, stats.train_loss, stats.learning_rate
+        ),
+    }
+}
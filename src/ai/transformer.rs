@@ -0,0 +1,694 @@
+use super::autograd::Var;
+use super::dtype::DType;
+use super::init::Initializer;
+use super::module::{parameter_names, prefixed, split_parameters, tape_parameters, Module};
+use super::nn::{ActivationFunction, Layer};
+use super::norm::LayerNorm;
+use super::quant::QuantScheme;
+use super::random::with_global_rng;
+use super::tensor::{try_buffer, Tensor, TensorError, TensorResult};
+use crate::math;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Posiciones máximas de las codificaciones posicionales: más allá, los
+/// ángulos superan el rango de `math::sin` y `math::cos`.
+pub const MAX_POSITIONS: usize = 8192;
+
+/// Base de las frecuencias, la del artículo original y de RoPE.
+const POSITION_BASE: f32 = 10_000.0;
+
+/// Épsilon de las `LayerNorm` de `TransformerBlock::new`.
+const BLOCK_NORM_EPS: f32 = 1e-5;
+
+/// Frecuencia angular `base^(-2i / dim)` del par `i`.
+fn frequency(pair: usize, dim: usize) -> f32 {
+    1.0 / math::powf(POSITION_BASE, (2 * pair) as f32 / dim as f32)
+}
+
+fn check_positions(end: usize) -> TensorResult<()> {
+    if end > MAX_POSITIONS {
+        return Err(TensorError::InvalidArgument("Secuencia más larga que el máximo de posiciones"));
+    }
+    Ok(())
+}
+
+/// Identificadores de token guardados como `f32` a índices de fila.
+fn token_indices(ids: &Tensor) -> TensorResult<Vec<usize>> {
+    let mut indices = try_buffer(ids.len())?;
+    for id in ids.iter() {
+        // También rechaza NaN, que no es igual a ningún entero
+        if id < 0.0 || id != (id as usize) as f32 {
+            return Err(TensorError::InvalidArgument(
+                "Los identificadores de token deben ser enteros no negativos",
+            ));
+        }
+        indices.push(id as usize);
+    }
+    Ok(indices)
+}
+
+/// Tabla de embeddings `[vocabulario, dim]`: cada identificador de token
+/// selecciona una fila.
+pub struct Embedding {
+    weight: Tensor,
+}
+
+impl Embedding {
+    /// Crea la tabla con valores de `N(0, 1)` del generador global.
+    pub fn new(vocab_size: usize, dim: usize) -> TensorResult<Self> {
+        let init = Initializer::Normal { mean: 0.0, std: 1.0 };
+        Ok(Embedding { weight: with_global_rng(|rng| init.tensor(&[vocab_size, dim], vocab_size, dim, rng))? })
+    }
+
+    pub fn from_parameters(weight: Tensor) -> TensorResult<Self> {
+        if weight.ndim() != 2 {
+            return Err(TensorError::RankMismatch { expected: 2, found: weight.ndim() });
+        }
+        Ok(Embedding { weight })
+    }
+
+    pub fn weight(&self) -> &Tensor {
+        &self.weight
+    }
+
+    pub fn vocab_size(&self) -> usize {
+        self.weight.shape()[0]
+    }
+
+    pub fn dim(&self) -> usize {
+        self.weight.shape()[1]
+    }
+
+    /// `ids` contiene identificadores enteros guardados como `f32` (p. ej.
+    /// `[batch, seq]`); el resultado añade un último eje de tamaño `dim`.
+    pub fn forward(&self, ids: Tensor) -> TensorResult<Tensor> {
+        let indices = token_indices(&ids)?;
+        let mut shape = ids.shape().to_vec();
+        shape.push(self.dim());
+        self.weight.index_select(0, &indices)?.reshape(&shape)
+    }
+
+    /// Como `forward`, con la tabla `weight` ya en la cinta. Los
+    /// identificadores no reciben gradiente; el de cada fila es la suma del
+    /// de las posiciones que la seleccionan.
+    pub fn forward_tape<'t>(&self, ids: Var<'t>, weight: &Var<'t>) -> TensorResult<Var<'t>> {
+        let indices = token_indices(&ids.value())?;
+        let mut shape = ids.shape();
+        shape.push(self.dim());
+        weight.index_select(0, &indices)?.reshape(&shape)
+    }
+
+    pub fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        self.weight = self.weight.to_dtype(dtype)?;
+        Ok(())
+    }
+
+    pub fn parameter_bytes(&self) -> usize {
+        self.weight.size_in_bytes()
+    }
+}
+
+/// Codificación posicional sinusoidal `[seq_len, dim]` de *Attention is all
+/// you need*: `PE[p, 2i] = sin(p ω_i)` y `PE[p, 2i + 1] = cos(p ω_i)`, con
+/// `ω_i = 10000^(-2i / dim)`.
+pub fn sinusoidal_positions(seq_len: usize, dim: usize) -> TensorResult<Tensor> {
+    check_positions(seq_len)?;
+    let mut values = try_buffer(seq_len * dim)?;
+    for position in 0..seq_len {
+        values.extend((0..dim).map(|column| {
+            let angle = position as f32 * frequency(column / 2, dim);
+            if column % 2 == 0 { math::sin(angle) } else { math::cos(angle) }
+        }));
+    }
+    Tensor::from_vec(values, &[seq_len, dim])
+}
+
+/// Suma la codificación sinusoidal a una entrada `[..., seq, dim]`.
+pub fn add_sinusoidal_positions(input: &Tensor) -> TensorResult<Tensor> {
+    input.add(&positions_for(input.shape())?)
+}
+
+/// Codificación sinusoidal para una entrada de forma `[..., seq, dim]`.
+fn positions_for(shape: &[usize]) -> TensorResult<Tensor> {
+    if shape.len() < 2 {
+        return Err(TensorError::RankMismatch { expected: 2, found: shape.len() });
+    }
+    sinusoidal_positions(shape[shape.len() - 2], shape[shape.len() - 1])
+}
+
+/// Capa que suma la codificación sinusoidal (`add_sinusoidal_positions`).
+#[derive(Debug, Clone, Copy)]
+pub struct PositionalEncoding;
+
+/// Codificación posicional rotatoria (RoPE) sobre `[..., seq, dim]` con
+/// `dim` par.
+///
+/// Cada par `(x_i, x_{i + dim/2})` de la posición `p` se rota un ángulo
+/// `(offset + p) ω_i` (la convención por mitades de GPT-NeoX y LLaMA).
+/// `offset` es la posición del primer elemento, para secuencias que
+/// continúan otra anterior.
+pub fn apply_rotary(input: &Tensor, offset: usize) -> TensorResult<Tensor> {
+    let (cos, sin) = rotary_tables(input.shape(), offset)?;
+    let half = cos.shape()[1];
+    let x1 = input.narrow(-1, 0, half)?;
+    let x2 = input.narrow(-1, half, half)?;
+    let rotated1 = x1.mul(&cos)?.sub(&x2.mul(&sin)?)?;
+    let rotated2 = x1.mul(&sin)?.add(&x2.mul(&cos)?)?;
+    Tensor::concat(&[&rotated1, &rotated2], -1)
+}
+
+/// Como `apply_rotary` desde la posición 0, para una entrada en la cinta.
+fn apply_rotary_tape<'t>(input: &Var<'t>) -> TensorResult<Var<'t>> {
+    let (cos, sin) = rotary_tables(&input.shape(), 0)?;
+    let half = cos.shape()[1];
+    let tape = input.tape();
+    let (cos, sin) = (tape.var(cos, false), tape.var(sin, false));
+    let x1 = input.narrow(-1, 0, half)?;
+    let x2 = input.narrow(-1, half, half)?;
+    let rotated1 = x1.mul(&cos)?.sub(&x2.mul(&sin)?)?;
+    let rotated2 = x1.mul(&sin)?.add(&x2.mul(&cos)?)?;
+    Var::concat(&[rotated1, rotated2], -1)
+}
+
+/// Cosenos y senos `[seq, dim / 2]` de RoPE para una entrada de forma
+/// `[..., seq, dim]` que empieza en la posición `offset`.
+fn rotary_tables(shape: &[usize], offset: usize) -> TensorResult<(Tensor, Tensor)> {
+    if shape.len() < 2 {
+        return Err(TensorError::RankMismatch { expected: 2, found: shape.len() });
+    }
+    let (seq_len, dim) = (shape[shape.len() - 2], shape[shape.len() - 1]);
+    if dim % 2 != 0 {
+        return Err(TensorError::InvalidArgument("RoPE necesita una dimensión par"));
+    }
+    check_positions(offset + seq_len)?;
+
+    let half = dim / 2;
+    let mut cos = try_buffer(seq_len * half)?;
+    let mut sin = try_buffer(seq_len * half)?;
+    for position in offset..offset + seq_len {
+        for pair in 0..half {
+            let angle = position as f32 * frequency(pair, dim);
+            cos.push(math::cos(angle));
+            sin.push(math::sin(angle));
+        }
+    }
+    Ok((Tensor::from_vec(cos, &[seq_len, half])?, Tensor::from_vec(sin, &[seq_len, half])?))
+}
+
+/// Máscara aditiva `[lq, lk]`: 0 donde la consulta puede atender a la clave
+/// y `-inf` en el resto.
+fn causal_mask(lq: usize, lk: usize) -> TensorResult<Tensor> {
+    if lq > lk {
+        return Err(TensorError::InvalidArgument(
+            "La atención causal necesita al menos tantas claves como consultas",
+        ));
+    }
+    let mut values = try_buffer(lq * lk)?;
+    for i in 0..lq {
+        let visible = i + lk - lq;
+        values.extend((0..lk).map(|j| if j <= visible { 0.0 } else { f32::NEG_INFINITY }));
+    }
+    Tensor::from_vec(values, &[lq, lk])
+}
+
+/// Atención por producto escalar escalado, `softmax(q kᵀ / sqrt(d) + máscara) v`.
+///
+/// `q` es `[..., lq, d]`, `k` `[..., lk, d]` y `v` `[..., lk, dv]`; las
+/// dimensiones anteriores se difunden como en `matmul`. Con `causal`, las
+/// consultas son las últimas `lq` posiciones de la secuencia y la `i` solo
+/// ve las claves `j <= i + lk - lq`.
+pub fn scaled_dot_product_attention(q: &Tensor, k: &Tensor, v: &Tensor, causal: bool) -> TensorResult<Tensor> {
+    if q.ndim() < 2 || k.ndim() < 2 {
+        return Err(TensorError::RankMismatch { expected: 2, found: q.ndim().min(k.ndim()) });
+    }
+    let d = q.shape()[q.ndim() - 1];
+    let scores = q.matmul(&k.transpose(-2, -1)?)?.mul_scalar(1.0 / math::sqrt(d as f32))?;
+    let scores = if causal {
+        let shape = scores.shape();
+        scores.add(&causal_mask(shape[shape.len() - 2], shape[shape.len() - 1])?)?
+    } else {
+        scores
+    };
+    scores.softmax(-1)?.matmul(v)
+}
+
+/// Como `scaled_dot_product_attention`, con los operandos en la cinta.
+fn scaled_dot_product_attention_tape<'t>(q: &Var<'t>, k: &Var<'t>, v: &Var<'t>, causal: bool) -> TensorResult<Var<'t>> {
+    let shape = q.shape();
+    let d = shape[shape.len() - 1];
+    let scores = q.matmul(&k.transpose(-2, -1)?)?.mul_scalar(1.0 / math::sqrt(d as f32))?;
+    let scores = if causal {
+        let shape = scores.shape();
+        let mask = causal_mask(shape[shape.len() - 2], shape[shape.len() - 1])?;
+        scores.add(&scores.tape().var(mask, false))?
+    } else {
+        scores
+    };
+    scores.softmax(-1)?.matmul(v)
+}
+
+/// Atención multicabeza sobre `[batch, seq, dim]` (o `[seq, dim]`).
+///
+/// Las proyecciones son capas densas `[dim, dim]` sin activación; cada
+/// cabeza atiende con `dim / heads` componentes y sus salidas se
+/// concatenan antes de la proyección final.
+pub struct MultiHeadAttention {
+    query: Layer,
+    key: Layer,
+    value: Layer,
+    output: Layer,
+    heads: usize,
+    causal: bool,
+    rotary: bool,
+}
+
+impl MultiHeadAttention {
+    pub fn new(dim: usize, heads: usize, causal: bool) -> TensorResult<Self> {
+        let projection = || Layer::new(dim, dim, ActivationFunction::Identity);
+        Self::from_parameters(projection()?, projection()?, projection()?, projection()?, heads, causal)
+    }
+
+    /// Crea la atención a partir de las cuatro proyecciones ya entrenadas,
+    /// todas `[dim, dim]`.
+    pub fn from_parameters(
+        query: Layer,
+        key: Layer,
+        value: Layer,
+        output: Layer,
+        heads: usize,
+        causal: bool,
+    ) -> TensorResult<Self> {
+        let shape = query.weights().shape().to_vec();
+        for other in [&key, &value, &output] {
+            if other.weights().shape() != shape {
+                return Err(TensorError::ShapeMismatch {
+                    lhs: shape,
+                    rhs: other.weights().shape().to_vec(),
+                });
+            }
+        }
+        if shape[0] != shape[1] {
+            return Err(TensorError::InvalidArgument("Las proyecciones de la atención deben ser cuadradas"));
+        }
+        if heads == 0 || !shape[0].is_multiple_of(heads) {
+            return Err(TensorError::InvalidArgument(
+                "El número de cabezas debe dividir la dimensión del modelo",
+            ));
+        }
+        Ok(MultiHeadAttention { query, key, value, output, heads, causal, rotary: false })
+    }
+
+    pub fn dim(&self) -> usize {
+        self.query.weights().shape()[0]
+    }
+
+    /// Proyecciones de consulta, clave, valor y salida, en ese orden.
+    pub fn projections(&self) -> [&Layer; 4] {
+        [&self.query, &self.key, &self.value, &self.output]
+    }
+
+    pub fn heads(&self) -> usize {
+        self.heads
+    }
+
+    pub fn is_causal(&self) -> bool {
+        self.causal
+    }
+
+    /// Aplica RoPE a consultas y claves de cada cabeza (desactivado al crearla).
+    pub fn set_rotary(&mut self, rotary: bool) {
+        self.rotary = rotary;
+    }
+
+    pub fn is_rotary(&self) -> bool {
+        self.rotary
+    }
+
+    /// `[batch, seq, dim]` a `[batch, heads, seq, dim / heads]`.
+    fn split_heads(&self, x: Tensor, batch: usize, seq_len: usize) -> TensorResult<Tensor> {
+        let head_dim = self.dim() / self.heads;
+        x.reshape(&[batch, seq_len, self.heads, head_dim])?.permute(&[0, 2, 1, 3])
+    }
+
+    /// `(batch, seq)` de una entrada `[batch, seq, dim]` o `[seq, dim]`.
+    fn sequence_dims(&self, shape: &[usize]) -> TensorResult<(usize, usize)> {
+        match *shape {
+            [batch, seq_len, dim] if dim == self.dim() => Ok((batch, seq_len)),
+            [seq_len, dim] if dim == self.dim() => Ok((1, seq_len)),
+            _ => Err(TensorError::ShapeMismatch {
+                lhs: shape.to_vec(),
+                rhs: self.query.weights().shape().to_vec(),
+            }),
+        }
+    }
+
+    pub fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        let (batch, seq_len) = self.sequence_dims(input.shape())?;
+        let x = input.reshape(&[batch, seq_len, self.dim()])?;
+
+        let mut q = self.split_heads(self.query.forward(x.clone())?, batch, seq_len)?;
+        let mut k = self.split_heads(self.key.forward(x.clone())?, batch, seq_len)?;
+        let v = self.split_heads(self.value.forward(x)?, batch, seq_len)?;
+        if self.rotary {
+            q = apply_rotary(&q, 0)?;
+            k = apply_rotary(&k, 0)?;
+        }
+
+        let heads = scaled_dot_product_attention(&q, &k, &v, self.causal)?;
+        let merged = heads.permute(&[0, 2, 1, 3])?.reshape(&[batch, seq_len, self.dim()])?;
+        self.output.forward(merged)?.reshape(input.shape())
+    }
+
+    /// Como `forward`, con los parámetros de las cuatro proyecciones (en el
+    /// orden de `parameters`) ya en la cinta de `input`.
+    pub fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let shape = input.shape();
+        let (batch, seq_len) = self.sequence_dims(&shape)?;
+        let counts = self.projections().map(|layer| layer.parameters().len());
+        let [query, key, value, output] = split_parameters(parameters, counts)?;
+        let x = input.reshape(&[batch, seq_len, self.dim()])?;
+
+        let head_dim = self.dim() / self.heads;
+        let split_heads = |x: Var<'t>| x.reshape(&[batch, seq_len, self.heads, head_dim])?.permute(&[0, 2, 1, 3]);
+        let mut q = split_heads(self.query.forward_tape(x, query)?)?;
+        let mut k = split_heads(self.key.forward_tape(x, key)?)?;
+        let v = split_heads(self.value.forward_tape(x, value)?)?;
+        if self.rotary {
+            q = apply_rotary_tape(&q)?;
+            k = apply_rotary_tape(&k)?;
+        }
+
+        let heads = scaled_dot_product_attention_tape(&q, &k, &v, self.causal)?;
+        let merged = heads.permute(&[0, 2, 1, 3])?.reshape(&[batch, seq_len, self.dim()])?;
+        self.output.forward_tape(merged, output)?.reshape(&shape)
+    }
+
+    pub fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        for layer in [&mut self.query, &mut self.key, &mut self.value, &mut self.output] {
+            layer.to_dtype(dtype)?;
+        }
+        Ok(())
+    }
+
+    /// Cuantiza a `i8` las cuatro proyecciones.
+    pub fn quantize(&mut self, scheme: QuantScheme) -> TensorResult<()> {
+        for layer in [&mut self.query, &mut self.key, &mut self.value, &mut self.output] {
+            layer.quantize(scheme)?;
+        }
+        Ok(())
+    }
+
+    pub fn parameter_bytes(&self) -> usize {
+        [&self.query, &self.key, &self.value, &self.output]
+            .iter()
+            .map(|layer| layer.parameter_bytes())
+            .sum()
+    }
+}
+
+/// Bloque de transformer con normalización previa (como GPT-2):
+/// `x + atención(norm(x))` seguido de `x + ffn(norm(x))`, donde la red
+/// *feed-forward* es una capa densa con GELU y otra de vuelta a `dim`.
+pub struct TransformerBlock {
+    attention_norm: LayerNorm,
+    attention: MultiHeadAttention,
+    ffn_norm: LayerNorm,
+    ffn_up: Layer,
+    ffn_down: Layer,
+}
+
+impl TransformerBlock {
+    pub fn new(dim: usize, heads: usize, hidden: usize, causal: bool) -> TensorResult<Self> {
+        Ok(TransformerBlock {
+            attention_norm: LayerNorm::new(&[dim], BLOCK_NORM_EPS)?,
+            attention: MultiHeadAttention::new(dim, heads, causal)?,
+            ffn_norm: LayerNorm::new(&[dim], BLOCK_NORM_EPS)?,
+            ffn_up: Layer::new(dim, hidden, ActivationFunction::Gelu)?,
+            ffn_down: Layer::new(hidden, dim, ActivationFunction::Identity)?,
+        })
+    }
+
+    /// Compone el bloque a partir de sus partes ya entrenadas; `ffn_up` debe
+    /// ser `[dim, oculta]` y `ffn_down` `[oculta, dim]`.
+    pub fn from_parameters(
+        attention_norm: LayerNorm,
+        attention: MultiHeadAttention,
+        ffn_norm: LayerNorm,
+        ffn_up: Layer,
+        ffn_down: Layer,
+    ) -> TensorResult<Self> {
+        let up = ffn_up.weights().shape();
+        let down = ffn_down.weights().shape();
+        if up[0] != attention.dim() || up[1] != down[0] || down[1] != attention.dim() {
+            return Err(TensorError::ShapeMismatch { lhs: up.to_vec(), rhs: down.to_vec() });
+        }
+        Ok(TransformerBlock { attention_norm, attention, ffn_norm, ffn_up, ffn_down })
+    }
+
+    pub fn attention_norm(&self) -> &LayerNorm {
+        &self.attention_norm
+    }
+
+    pub fn attention(&self) -> &MultiHeadAttention {
+        &self.attention
+    }
+
+    pub fn attention_mut(&mut self) -> &mut MultiHeadAttention {
+        &mut self.attention
+    }
+
+    pub fn ffn_norm(&self) -> &LayerNorm {
+        &self.ffn_norm
+    }
+
+    pub fn ffn_up(&self) -> &Layer {
+        &self.ffn_up
+    }
+
+    pub fn ffn_down(&self) -> &Layer {
+        &self.ffn_down
+    }
+
+    pub fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        let attended = self.attention.forward(self.attention_norm.forward(input.clone())?)?;
+        let x = input.add(&attended)?;
+        let hidden = self.ffn_up.forward(self.ffn_norm.forward(x.clone())?)?;
+        x.add(&self.ffn_down.forward(hidden)?)
+    }
+
+    /// Como `forward`, con los parámetros (en el orden de `parameters`) ya
+    /// en la cinta de `input`.
+    pub fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let counts = [
+            self.attention_norm.parameters().len(),
+            self.attention.parameters().len(),
+            self.ffn_norm.parameters().len(),
+            self.ffn_up.parameters().len(),
+            self.ffn_down.parameters().len(),
+        ];
+        let [attention_norm, attention, ffn_norm, ffn_up, ffn_down] = split_parameters(parameters, counts)?;
+        let normalized = Module::forward_tape(&self.attention_norm, input, attention_norm)?;
+        let x = input.add(&self.attention.forward_tape(normalized, attention)?)?;
+        let normalized = Module::forward_tape(&self.ffn_norm, x, ffn_norm)?;
+        let hidden = self.ffn_up.forward_tape(normalized, ffn_up)?;
+        x.add(&self.ffn_down.forward_tape(hidden, ffn_down)?)
+    }
+
+    pub fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        self.attention_norm.to_dtype(dtype)?;
+        self.attention.to_dtype(dtype)?;
+        self.ffn_norm.to_dtype(dtype)?;
+        self.ffn_up.to_dtype(dtype)?;
+        self.ffn_down.to_dtype(dtype)
+    }
+
+    /// Cuantiza a `i8` las proyecciones de la atención y del perceptrón; las
+    /// normalizaciones siguen en coma flotante.
+    pub fn quantize(&mut self, scheme: QuantScheme) -> TensorResult<()> {
+        self.attention.quantize(scheme)?;
+        self.ffn_up.quantize(scheme)?;
+        self.ffn_down.quantize(scheme)
+    }
+
+    pub fn parameter_bytes(&self) -> usize {
+        self.attention_norm.parameter_bytes()
+            + self.attention.parameter_bytes()
+            + self.ffn_norm.parameter_bytes()
+            + self.ffn_up.parameter_bytes()
+            + self.ffn_down.parameter_bytes()
+    }
+}
+
+impl Module for Embedding {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        Embedding::forward(self, input)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        alloc::vec![&self.weight]
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        parameter_names(&["weight"])
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        alloc::vec![&mut self.weight]
+    }
+
+    fn name(&self) -> &str {
+        "Embedding"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        let mut output = input.to_vec();
+        output.push(self.dim());
+        Ok(output)
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let [weight] = tape_parameters(parameters)?;
+        Embedding::forward_tape(self, input, weight)
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        Embedding::to_dtype(self, dtype)
+    }
+}
+
+impl Module for PositionalEncoding {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        add_sinusoidal_positions(&input)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        "PositionalEncoding"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        if input.len() < 2 {
+            return Err(TensorError::RankMismatch { expected: 2, found: input.len() });
+        }
+        check_positions(input[input.len() - 2])?;
+        Ok(input.to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, _parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        let positions = positions_for(&input.shape())?;
+        input.add(&input.tape().var(positions, false))
+    }
+}
+
+impl Module for MultiHeadAttention {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        MultiHeadAttention::forward(self, input)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        [&self.query, &self.key, &self.value, &self.output]
+            .into_iter()
+            .flat_map(|layer| layer.parameters())
+            .collect()
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        [("query", &self.query), ("key", &self.key), ("value", &self.value), ("output", &self.output)]
+            .into_iter()
+            .flat_map(|(prefix, layer)| prefixed(prefix, layer.parameter_names()))
+            .collect()
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        [&mut self.query, &mut self.key, &mut self.value, &mut self.output]
+            .into_iter()
+            .flat_map(|layer| layer.parameters_mut())
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        "MultiHeadAttention"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        self.sequence_dims(input)?;
+        Ok(input.to_vec())
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        MultiHeadAttention::forward_tape(self, input, parameters)
+    }
+
+    fn parameter_bytes(&self) -> usize {
+        MultiHeadAttention::parameter_bytes(self)
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        MultiHeadAttention::to_dtype(self, dtype)
+    }
+
+    fn quantize(&mut self, scheme: QuantScheme) -> TensorResult<()> {
+        MultiHeadAttention::quantize(self, scheme)
+    }
+}
+
+impl Module for TransformerBlock {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        TransformerBlock::forward(self, input)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        let mut parameters = self.attention_norm.parameters();
+        parameters.extend(self.attention.parameters());
+        parameters.extend(self.ffn_norm.parameters());
+        parameters.extend(self.ffn_up.parameters());
+        parameters.extend(self.ffn_down.parameters());
+        parameters
+    }
+
+    fn parameter_names(&self) -> Vec<String> {
+        let mut names = prefixed("attention_norm", self.attention_norm.parameter_names());
+        names.extend(prefixed("attention", self.attention.parameter_names()));
+        names.extend(prefixed("ffn_norm", self.ffn_norm.parameter_names()));
+        names.extend(prefixed("ffn_up", self.ffn_up.parameter_names()));
+        names.extend(prefixed("ffn_down", self.ffn_down.parameter_names()));
+        names
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Tensor> {
+        let mut parameters = self.attention_norm.parameters_mut();
+        parameters.extend(self.attention.parameters_mut());
+        parameters.extend(self.ffn_norm.parameters_mut());
+        parameters.extend(self.ffn_up.parameters_mut());
+        parameters.extend(self.ffn_down.parameters_mut());
+        parameters
+    }
+
+    fn name(&self) -> &str {
+        "TransformerBlock"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        self.attention.output_shape(input)
+    }
+
+    fn forward_tape<'t>(&self, input: Var<'t>, parameters: &[Var<'t>]) -> TensorResult<Var<'t>> {
+        TransformerBlock::forward_tape(self, input, parameters)
+    }
+
+    fn parameter_bytes(&self) -> usize {
+        TransformerBlock::parameter_bytes(self)
+    }
+
+    fn to_dtype(&mut self, dtype: DType) -> TensorResult<()> {
+        TransformerBlock::to_dtype(self, dtype)
+    }
+
+    fn quantize(&mut self, scheme: QuantScheme) -> TensorResult<()> {
+        TransformerBlock::quantize(self, scheme)
+    }
+}
@@ -2,9 +2,11 @@ mod http;
 mod websocket;
 mod rest;
 
+use crate::ai::{base64_decode, NpyError, Tensor};
 use crate::println;
 use lazy_static::lazy_static;
 use spin::Mutex;
+use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 
@@ -67,6 +69,12 @@ impl ApiSubsystem {
             handler: ai_predict_handler,
         });
         
+        self.register_endpoint(ApiEndpoint {
+            path: String::from("/ai/npy"),
+            method: HttpMethod::POST,
+            handler: ai_npy_handler,
+        });
+        
         self.initialized = true;
         
         println!("Subsistema de API inicializado");
@@ -117,6 +125,39 @@ fn status_handler(_request: &ApiRequest) -> ApiResponse {
     }
 }
 
+// Recibe un `.npy` (tal cual o en base64) y devuelve el tensor leído como
+// `.npy` en orden C, para comprobar desde Python cómo lo interpreta el núcleo
+fn ai_npy_handler(request: &ApiRequest) -> ApiResponse {
+    let body = request.body.as_deref().unwrap_or_default();
+    
+    // `HttpServer::parse_request` solo admite cuerpos UTF-8, así que lo
+    // normal es recibirlo en base64
+    let tensor = if body.starts_with(b"\x93NUMPY") {
+        Tensor::from_npy(body)
+    } else {
+        base64_decode(body).and_then(|bytes| Tensor::from_npy(&bytes))
+    };
+    
+    match tensor.and_then(|tensor| tensor.to_npy().map_err(NpyError::from)) {
+        Ok(npy) => {
+            let mut headers = Vec::new();
+            headers.push((String::from("Content-Type"), String::from("application/octet-stream")));
+            headers.push((String::from("Content-Length"), format!("{}", npy.len())));
+            
+            ApiResponse {
+                status: 200,
+                headers,
+                body: Some(npy),
+            }
+        }
+        Err(error) => ApiResponse {
+            status: 400,
+            headers: Vec::new(),
+            body: Some(format!("{}", error).into_bytes()),
+        },
+    }
+}
+
 fn ai_predict_handler(request: &ApiRequest) -> ApiResponse {
     // En un sistema real, esto pasaría los datos al subsistema de IA
     // y devolvería los resultados de la predicción
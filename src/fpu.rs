@@ -0,0 +1,203 @@
+use core::arch::asm;
+use core::arch::x86_64::{__cpuid, __cpuid_count};
+use core::sync::atomic::{AtomicU32, Ordering};
+use x86_64::registers::control::{Cr0, Cr0Flags, Cr4, Cr4Flags};
+use x86_64::registers::xcontrol::{XCr0, XCr0Flags};
+use crate::println;
+
+/// Tamaño reservado para el área de XSAVE (x87 + SSE + AVX ocupan 832 bytes).
+const XSAVE_AREA_SIZE: usize = 1024;
+
+const FEATURE_SSE: u32 = 1 << 0;
+const FEATURE_SSE2: u32 = 1 << 1;
+const FEATURE_FXSR: u32 = 1 << 2;
+const FEATURE_XSAVE: u32 = 1 << 3;
+const FEATURE_AVX: u32 = 1 << 4;
+const FEATURE_AVX2: u32 = 1 << 5;
+const FEATURE_FMA: u32 = 1 << 6;
+
+/// Extensiones habilitadas por `init` (no solo presentes en la CPU).
+static ENABLED: AtomicU32 = AtomicU32::new(0);
+
+/// Extensiones SIMD que el kernel puede usar con seguridad.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimdFeatures {
+    pub sse: bool,
+    pub sse2: bool,
+    pub fxsr: bool,
+    pub xsave: bool,
+    pub avx: bool,
+    pub avx2: bool,
+    pub fma: bool,
+}
+
+impl SimdFeatures {
+    fn from_bits(bits: u32) -> Self {
+        SimdFeatures {
+            sse: bits & FEATURE_SSE != 0,
+            sse2: bits & FEATURE_SSE2 != 0,
+            fxsr: bits & FEATURE_FXSR != 0,
+            xsave: bits & FEATURE_XSAVE != 0,
+            avx: bits & FEATURE_AVX != 0,
+            avx2: bits & FEATURE_AVX2 != 0,
+            fma: bits & FEATURE_FMA != 0,
+        }
+    }
+}
+
+/// Consulta CPUID para saber qué extensiones ofrece el procesador.
+fn detect() -> u32 {
+    let mut bits = 0;
+    let leaf1 = __cpuid(1);
+    let max_leaf = __cpuid(0).eax;
+    let leaf7_ebx = if max_leaf >= 7 { __cpuid_count(7, 0).ebx } else { 0 };
+
+    let checks = [
+        (leaf1.edx & (1 << 25), FEATURE_SSE),
+        (leaf1.edx & (1 << 26), FEATURE_SSE2),
+        (leaf1.edx & (1 << 24), FEATURE_FXSR),
+        (leaf1.ecx & (1 << 26), FEATURE_XSAVE),
+        (leaf1.ecx & (1 << 28), FEATURE_AVX),
+        (leaf1.ecx & (1 << 12), FEATURE_FMA),
+        (leaf7_ebx & (1 << 5), FEATURE_AVX2),
+    ];
+    for (present, feature) in checks {
+        if present != 0 {
+            bits |= feature;
+        }
+    }
+    bits
+}
+
+/// Habilita la FPU, SSE y, si la CPU lo admite, AVX mediante XSAVE.
+///
+/// Debe ser lo primero que haga el kernel: los manejadores de interrupción
+/// guardan el estado SIMD con FXSAVE/XSAVE, que requieren CR4.OSFXSR/OSXSAVE,
+/// y con la característica `simd` cualquier función puede usar registros
+/// SSE (el bootloader ya activa SSE, pero no XSAVE ni AVX).
+pub fn init() {
+    let detected = detect();
+    let required = FEATURE_SSE | FEATURE_SSE2 | FEATURE_FXSR;
+    if detected & required != required {
+        println!("FPU: la CPU no admite SSE2/FXSR; SIMD deshabilitado");
+        return;
+    }
+
+    let mut enabled = required;
+    unsafe {
+        // Sin emulación de x87 ni fallos de "dispositivo no disponible"
+        let mut cr0 = Cr0::read();
+        cr0.remove(Cr0Flags::EMULATE_COPROCESSOR | Cr0Flags::TASK_SWITCHED);
+        cr0.insert(Cr0Flags::MONITOR_COPROCESSOR | Cr0Flags::NUMERIC_ERROR);
+        Cr0::write(cr0);
+
+        let mut cr4 = Cr4::read();
+        cr4.insert(Cr4Flags::OSFXSR | Cr4Flags::OSXMMEXCPT_ENABLE);
+        if detected & FEATURE_XSAVE != 0 {
+            cr4.insert(Cr4Flags::OSXSAVE);
+        }
+        Cr4::write(cr4);
+
+        if detected & FEATURE_XSAVE != 0 {
+            enabled |= FEATURE_XSAVE;
+            let mut xcr0 = XCr0Flags::X87 | XCr0Flags::SSE;
+            if detected & FEATURE_AVX != 0 {
+                xcr0 |= XCr0Flags::AVX;
+            }
+            XCr0::write(xcr0);
+
+            // CPUID.(0Dh,0).EBX es el tamaño del área para los componentes activos
+            let area_size = __cpuid_count(0xd, 0).ebx as usize;
+            if detected & FEATURE_AVX != 0 && area_size <= XSAVE_AREA_SIZE {
+                enabled |= FEATURE_AVX | (detected & (FEATURE_AVX2 | FEATURE_FMA));
+            } else if detected & FEATURE_AVX != 0 {
+                XCr0::write(XCr0Flags::X87 | XCr0Flags::SSE);
+            }
+        }
+
+        asm!("fninit", options(nomem, nostack));
+    }
+
+    ENABLED.store(enabled, Ordering::SeqCst);
+    let features = SimdFeatures::from_bits(enabled);
+    println!(
+        "FPU: SSE2 habilitado (XSAVE: {}, AVX: {}, AVX2: {}, FMA: {})",
+        features.xsave, features.avx, features.avx2, features.fma
+    );
+}
+
+/// Extensiones habilitadas; todas a `false` antes de `init`.
+pub fn features() -> SimdFeatures {
+    SimdFeatures::from_bits(ENABLED.load(Ordering::Relaxed))
+}
+
+/// Copia del estado x87/SSE/AVX de la CPU.
+///
+/// Sirve tanto para los manejadores de interrupción como para guardar el
+/// contexto de una tarea en un futuro cambio de contexto.
+#[repr(C, align(64))]
+pub struct FpuState {
+    area: [u8; XSAVE_AREA_SIZE],
+}
+
+impl FpuState {
+    /// Estado vacío; restaurarlo deja la FPU en su estado inicial.
+    pub const fn new() -> Self {
+        FpuState { area: [0; XSAVE_AREA_SIZE] }
+    }
+
+    /// Guarda los registros con XSAVE si está habilitado, o con FXSAVE.
+    #[inline(always)]
+    pub fn save(&mut self) {
+        let enabled = ENABLED.load(Ordering::Relaxed);
+        let area = self.area.as_mut_ptr();
+        unsafe {
+            if enabled & FEATURE_XSAVE != 0 {
+                asm!("xsave64 [{}]", in(reg) area, in("eax") u32::MAX, in("edx") u32::MAX,
+                    options(nostack, preserves_flags));
+            } else if enabled & FEATURE_FXSR != 0 {
+                asm!("fxsave64 [{}]", in(reg) area, options(nostack, preserves_flags));
+            }
+        }
+    }
+
+    /// Restaura los registros guardados con `save`.
+    #[inline(always)]
+    pub fn restore(&self) {
+        let enabled = ENABLED.load(Ordering::Relaxed);
+        let area = self.area.as_ptr();
+        unsafe {
+            if enabled & FEATURE_XSAVE != 0 {
+                asm!("xrstor64 [{}]", in(reg) area, in("eax") u32::MAX, in("edx") u32::MAX,
+                    options(nostack, preserves_flags));
+            } else if enabled & FEATURE_FXSR != 0 {
+                asm!("fxrstor64 [{}]", in(reg) area, options(nostack, preserves_flags));
+            }
+        }
+    }
+}
+
+/// Guarda el estado SIMD al crearse y lo restaura al destruirse.
+///
+/// Los manejadores de interrupción compilados con la característica `simd`
+/// lo crean al entrar para no corromper los registros vectoriales del código
+/// interrumpido (por ejemplo, un kernel de matmul a medias).
+pub struct FpuGuard {
+    state: FpuState,
+}
+
+impl FpuGuard {
+    #[inline(always)]
+    pub fn save() -> Self {
+        let mut state = FpuState::new();
+        state.save();
+        FpuGuard { state }
+    }
+}
+
+impl Drop for FpuGuard {
+    #[inline(always)]
+    fn drop(&mut self) {
+        self.state.restore();
+    }
+}
@@ -5,9 +5,16 @@ use crate::println;
 pub extern "x86-interrupt" fn breakpoint_handler(
     stack_frame: InterruptStackFrame)
 {
+    #[cfg(feature = "simd")]
+    let _fpu = crate::fpu::FpuGuard::save();
+
     println!("EXCEPCIÓN: BREAKPOINT\n{:#?}", stack_frame);
 }
 
+// Los manejadores de doble falta y fallo de página no crean un `FpuGuard`:
+// terminan en `panic!` y nunca vuelven al código interrumpido, así que no
+// hay estado SIMD que preservar (y la pila IST de la doble falta es pequeña
+// para el área de XSAVE).
 pub extern "x86-interrupt" fn double_fault_handler(
     stack_frame: InterruptStackFrame, _error_code: u64) -> !
 {
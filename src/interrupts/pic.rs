@@ -31,6 +31,9 @@ impl InterruptIndex {
 pub extern "x86-interrupt" fn timer_interrupt_handler(
     _stack_frame: InterruptStackFrame)
 {
+    #[cfg(feature = "simd")]
+    let _fpu = crate::fpu::FpuGuard::save();
+
     unsafe {
         PICS.lock().notify_end_of_interrupt(InterruptIndex::Timer.as_u8());
     }
@@ -48,6 +51,9 @@ pub extern "x86-interrupt" fn keyboard_interrupt_handler(
 {
     use x86_64::instructions::port::Port;
 
+    #[cfg(feature = "simd")]
+    let _fpu = crate::fpu::FpuGuard::save();
+
     let mut port = Port::new(0x60);
     let scancode: u8 = unsafe { port.read() };
     
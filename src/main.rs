@@ -9,7 +9,11 @@
 extern crate alloc;
 
 mod vga_buffer;
+mod serial;
 mod gdt;
+mod fpu;
+mod tsc;
+mod math;
 mod interrupts;
 mod memory;
 mod allocator;
@@ -28,10 +32,13 @@ static ALLOCATOR: allocator::LockedHeapAllocator = allocator::LockedHeapAllocato
 entry_point!(kernel_main);
 
 fn kernel_main(boot_info: &'static BootInfo) -> ! {
+    // La FPU va primero: formatear texto ya puede usar registros x87/SSE
+    fpu::init();
     println!("RustAI-OS: Inicializando...");
     
     // Inicializar componentes del sistema
     gdt::init();
+    tsc::init();
     interrupts::init();
     
     // Configurar gestor de memoria
@@ -0,0 +1,304 @@
+// Funciones de `f32` que `core` no incluye (en `std` vienen de la libm del
+// sistema). Se calculan con reducción de rango y polinomios; el error máximo
+// de cada una se comprueba en `tests/math_accuracy.rs` frente a una
+// referencia en `f64`.
+
+/// `ln(2)` dividido en una parte alta con los bits bajos a cero (el
+/// producto `k * LN2_HI` es exacto para `|k| < 2^9`) y el resto.
+const LN2_HI: f32 = 0.693_145_75;
+const LN2_LO: f32 = 1.428_606_8e-6;
+const LOG2_E: f32 = core::f32::consts::LOG2_E;
+
+/// Por encima, `exp` desborda a infinito.
+const EXP_OVERFLOW: f32 = 88.722_84;
+/// Por debajo, `exp` es menor que el menor subnormal y da 0.
+const EXP_UNDERFLOW: f32 = -103.972_08;
+
+/// `2^k` para `-126 <= k <= 127`.
+fn pow2(k: i32) -> f32 {
+    f32::from_bits(((k + 127) as u32) << 23)
+}
+
+/// `y * 2^k` sin pasar por valores intermedios fuera de rango.
+fn scale(y: f32, k: i32) -> f32 {
+    if k > 127 {
+        y * pow2(127) * pow2(k - 127)
+    } else if k < -126 {
+        y * pow2(k + 100) * pow2(-100)
+    } else {
+        y * pow2(k)
+    }
+}
+
+/// Reduce `x = k ln 2 + r` con `|r| <= ln(2) / 2`.
+fn reduce_ln2(x: f32) -> (i32, f32) {
+    let t = x * LOG2_E;
+    let k = if t >= 0.0 { (t + 0.5) as i32 } else { (t - 0.5) as i32 };
+    let kf = k as f32;
+    (k, (x - kf * LN2_HI) - kf * LN2_LO)
+}
+
+/// `e^r - 1` para `|r| <= ln(2) / 2` con la serie de Taylor hasta `r^9`
+/// (el primer término omitido es menor que `2^-36 * r`).
+fn expm1_reduced(r: f32) -> f32 {
+    let p = 1.0 / 362_880.0;
+    let p = p * r + 1.0 / 40_320.0;
+    let p = p * r + 1.0 / 5_040.0;
+    let p = p * r + 1.0 / 720.0;
+    let p = p * r + 1.0 / 120.0;
+    let p = p * r + 1.0 / 24.0;
+    let p = p * r + 1.0 / 6.0;
+    let p = p * r + 0.5;
+    r + r * r * p
+}
+
+/// `e^x`. Error máximo: 1 ULP (los resultados subnormales pueden perder
+/// algún bit más por el doble redondeo al escalar).
+pub fn exp(x: f32) -> f32 {
+    if x.is_nan() {
+        return x;
+    }
+    if x > EXP_OVERFLOW {
+        return f32::INFINITY;
+    }
+    if x < EXP_UNDERFLOW {
+        return 0.0;
+    }
+    let (k, r) = reduce_ln2(x);
+    scale(1.0 + expm1_reduced(r), k)
+}
+
+/// `e^x - 1`, preciso también cuando `x` es cercano a 0. Error máximo: 2 ULP.
+pub fn expm1(x: f32) -> f32 {
+    if x.is_nan() {
+        return x;
+    }
+    if x > EXP_OVERFLOW {
+        return f32::INFINITY;
+    }
+    if x < -18.0 {
+        // e^x está por debajo de medio ULP de 1
+        return -1.0;
+    }
+    let (k, r) = reduce_ln2(x);
+    let u = expm1_reduced(r);
+    if k > 24 {
+        // -1 ya no afecta al resultado
+        scale(1.0 + u, k) - 1.0
+    } else {
+        // e^x - 1 = 2^k u + (2^k - 1), donde el segundo término es exacto
+        scale(u, k) + (pow2(k) - 1.0)
+    }
+}
+
+/// Logaritmo natural; `NaN` para negativos y `-inf` para cero. Error
+/// máximo: 1 ULP en todo el rango (subnormales incluidos).
+pub fn ln(x: f32) -> f32 {
+    if x.is_nan() || x < 0.0 {
+        return f32::NAN;
+    }
+    if x == 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    if x == f32::INFINITY {
+        return x;
+    }
+
+    // x = m * 2^e con m en [sqrt(2)/2, sqrt(2))
+    let mut bits = x.to_bits();
+    let mut e = 0i32;
+    if bits < 0x0080_0000 {
+        // Subnormal: se normaliza multiplicando por 2^25
+        bits = (x * pow2(25)).to_bits();
+        e -= 25;
+    }
+    e += ((bits >> 23) as i32) - 127;
+    let mut m = f32::from_bits((bits & 0x007f_ffff) | 0x3f80_0000);
+    if m > core::f32::consts::SQRT_2 {
+        m *= 0.5;
+        e += 1;
+    }
+
+    // ln(1 + f) = 2 atanh(s) con s = f / (2 + f), |s| < 0.172. Como en
+    // fdlibm, se reescribe como f - (f²/2 - s (f²/2 + R)) para que el
+    // término dominante `f` no arrastre el error de la división.
+    let f = m - 1.0;
+    let s = f / (2.0 + f);
+    let z = s * s;
+    let r = 2.0 / 9.0;
+    let r = r * z + 2.0 / 7.0;
+    let r = r * z + 2.0 / 5.0;
+    let r = (r * z + 2.0 / 3.0) * z;
+    let hfsq = 0.5 * f * f;
+
+    let ef = e as f32;
+    ef * LN2_HI + (f - (hfsq - (s * (hfsq + r) + ef * LN2_LO)))
+}
+
+/// Tangente hiperbólica: `expm1(2|x|) / (expm1(2|x|) + 2)` con el signo de `x`.
+/// Error máximo: 3 ULP.
+pub fn tanh(x: f32) -> f32 {
+    let a = x.abs();
+    if a > 10.0 {
+        // tanh(10) = 1 - 4e-9, que se redondea a 1
+        return if x > 0.0 { 1.0 } else { -1.0 };
+    }
+    let t = expm1(2.0 * a);
+    let y = t / (t + 2.0);
+    if x < 0.0 { -y } else { y }
+}
+
+/// Función logística `1 / (1 + e^-x)`. Error máximo: 3 ULP.
+pub fn sigmoid(x: f32) -> f32 {
+    if x >= 0.0 {
+        1.0 / (1.0 + exp(-x))
+    } else {
+        // e^x / (1 + e^x) evita desbordar e^-x
+        let e = exp(x);
+        e / (1.0 + e)
+    }
+}
+
+/// Raíz cuadrada redondeada correctamente, calculada bit a bit como en
+/// `e_sqrtf.c` de fdlibm (el target base no tiene `sqrtss`).
+pub fn sqrt(x: f32) -> f32 {
+    if x.is_nan() || x < 0.0 {
+        return f32::NAN;
+    }
+    if x == 0.0 || x == f32::INFINITY {
+        return x;
+    }
+
+    let mut ix = x.to_bits() as i32;
+    let mut m = ix >> 23;
+    if m == 0 {
+        // Subnormal: se desplaza la mantisa hasta que tenga el bit implícito
+        let mut i = 0;
+        while ix & 0x0080_0000 == 0 {
+            ix <<= 1;
+            i += 1;
+        }
+        m -= i - 1;
+    }
+    m -= 127;
+    ix = (ix & 0x007f_ffff) | 0x0080_0000;
+    if m & 1 != 0 {
+        ix += ix;
+    }
+    m >>= 1;
+
+    ix += ix;
+    let mut q = 0;
+    let mut s = 0;
+    let mut r = 0x0100_0000;
+    while r != 0 {
+        let t = s + r;
+        if t <= ix {
+            s = t + r;
+            ix -= t;
+            q += r;
+        }
+        ix += ix;
+        r >>= 1;
+    }
+    // Resto distinto de cero: redondeo al más cercano con el bit sobrante
+    if ix != 0 {
+        q += q & 1;
+    }
+    f32::from_bits(((q >> 1) + 0x3f00_0000 + (m << 23)) as u32)
+}
+
+/// `x^y`. Con exponente entero se usa multiplicación por cuadrados
+/// (exacta para potencias pequeñas); en otro caso `exp(y ln x)`, cuyo error
+/// crece con `|y ln x|`.
+pub fn powf(x: f32, y: f32) -> f32 {
+    if y == 0.0 || x == 1.0 {
+        return 1.0;
+    }
+    if x.is_nan() || y.is_nan() {
+        return f32::NAN;
+    }
+    if y.abs() < 16_777_216.0 && y == (y as i32) as f32 {
+        return powi(x, y as i32);
+    }
+    if x < 0.0 {
+        return f32::NAN;
+    }
+    exp(y * ln(x))
+}
+
+/// `x^n` por multiplicación de cuadrados.
+pub fn powi(x: f32, n: i32) -> f32 {
+    let mut base = x;
+    let mut exponent = n.unsigned_abs();
+    let mut result = 1.0;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result *= base;
+        }
+        base *= base;
+        exponent >>= 1;
+    }
+    if n < 0 { 1.0 / result } else { result }
+}
+
+/// `π/4` en `f64` dividido en una parte alta de 29 bits (el producto por
+/// `j < 2^14` es exacto) y el resto.
+const PI4_HI: f64 = 0.785_398_162_901_401_5;
+const PI4_LO: f64 = 4.960_467_898_402_702e-10;
+
+/// Por encima, `j` no cabe en los 14 bits que hacen exacto `j * PI4_HI`;
+/// `sin` y `cos` devuelven `NaN`.
+const TRIG_LIMIT: f32 = 8192.0;
+
+/// Reduce `|x| = j π/4 + r` con `j` par y `|r| <= π/4`.
+///
+/// La resta se hace en `f64`: en `f32` (como en `sinf.c` de Cephes) se
+/// pierden los bits bajos de `r` cerca de los ceros de la función y el error
+/// relativo crece con `x`.
+fn reduce_pi4(a: f32) -> (u32, f32) {
+    let mut j = (a * (4.0 / core::f32::consts::PI)) as u32;
+    j += j & 1;
+    let y = j as f64;
+    let r = (a as f64 - y * PI4_HI) - y * PI4_LO;
+    (j & 7, r as f32)
+}
+
+/// `sin(r)` para `|r| <= π/4`.
+fn sin_reduced(r: f32) -> f32 {
+    let z = r * r;
+    let p = (-1.951_529_6e-4 * z + 8.332_161e-3) * z - 1.666_665_5e-1;
+    r + r * z * p
+}
+
+/// `cos(r)` para `|r| <= π/4`.
+fn cos_reduced(r: f32) -> f32 {
+    let z = r * r;
+    let p = (2.443_315_7e-5 * z - 1.388_731_6e-3) * z + 4.166_664_6e-2;
+    1.0 - 0.5 * z + z * z * p
+}
+
+/// Seno. Error máximo: 2 ULP para `|x| <= 8192`; fuera de ese rango
+/// devuelve `NaN`.
+pub fn sin(x: f32) -> f32 {
+    let a = x.abs();
+    if x.is_nan() || a > TRIG_LIMIT {
+        return f32::NAN;
+    }
+    let (j, r) = reduce_pi4(a);
+    let y = if j & 2 == 0 { sin_reduced(r) } else { cos_reduced(r) };
+    // sin(-x) = -sin(x); los octantes 4 a 7 cambian el signo
+    if (j > 3) != (x < 0.0) { -y } else { y }
+}
+
+/// Coseno, con el mismo rango y error que `sin`.
+pub fn cos(x: f32) -> f32 {
+    let a = x.abs();
+    if x.is_nan() || a > TRIG_LIMIT {
+        return f32::NAN;
+    }
+    let (j, r) = reduce_pi4(a);
+    let y = if j & 2 == 0 { cos_reduced(r) } else { sin_reduced(r) };
+    // cos(j π/4 + r) con j = 2: -sin(r); j = 4: -cos(r); j = 6: sin(r)
+    if j == 2 || j == 4 { -y } else { y }
+}
@@ -0,0 +1,70 @@
+use core::arch::x86_64::_rdtsc;
+use core::sync::atomic::{AtomicU64, Ordering};
+use x86_64::instructions::port::Port;
+use crate::println;
+
+/// Frecuencia de entrada del PIT (8253/8254) en Hz.
+const PIT_FREQUENCY: u64 = 1_193_182;
+/// La calibración dura 1/CALIBRATION_DIVISOR segundos (10 ms).
+const CALIBRATION_DIVISOR: u64 = 100;
+/// Lecturas del puerto 0x61 antes de dar el PIT por ausente. Cada lectura de
+/// un puerto ISA tarda cerca de 1 µs, así que son unos segundos como mínimo.
+const MAX_CALIBRATION_POLLS: u32 = 10_000_000;
+
+/// Ciclos del TSC por segundo; 0 hasta que `init` lo calibra.
+static FREQUENCY: AtomicU64 = AtomicU64::new(0);
+
+/// Contador de marca de tiempo (ciclos desde el arranque de la CPU).
+#[inline]
+pub fn read() -> u64 {
+    unsafe { _rdtsc() }
+}
+
+/// Calibra el TSC contra el canal 2 del PIT, que no genera interrupciones.
+///
+/// Si la salida del canal 2 no llega a subir (PIT ausente o mal emulado), el
+/// TSC se queda sin calibrar y `cycles_to_nanos` devuelve 0.
+pub fn init() {
+    let mut control: Port<u8> = Port::new(0x61);
+    let mut command: Port<u8> = Port::new(0x43);
+    let mut channel2: Port<u8> = Port::new(0x42);
+    let latch = PIT_FREQUENCY / CALIBRATION_DIVISOR;
+
+    let cycles = unsafe {
+        // Puerta del canal 2 abierta y altavoz desconectado
+        let value = control.read();
+        control.write((value & !0x02) | 0x01);
+        // Canal 2, byte bajo y alto, modo 0 (la salida sube al llegar a cero)
+        command.write(0b1011_0000);
+        channel2.write(latch as u8);
+        channel2.write((latch >> 8) as u8);
+
+        let start = read();
+        let mut polls = 0;
+        while control.read() & 0x20 == 0 {
+            polls += 1;
+            if polls == MAX_CALIBRATION_POLLS {
+                println!("TSC: el PIT no responde, sin calibrar");
+                return;
+            }
+        }
+        read() - start
+    };
+
+    let frequency = cycles * CALIBRATION_DIVISOR;
+    FREQUENCY.store(frequency, Ordering::Relaxed);
+    println!("TSC: {} MHz", frequency / 1_000_000);
+}
+
+/// Ciclos del TSC por segundo (0 si no se ha calibrado).
+pub fn frequency() -> u64 {
+    FREQUENCY.load(Ordering::Relaxed)
+}
+
+/// Convierte ciclos del TSC a nanosegundos; 0 si no se ha calibrado.
+pub fn cycles_to_nanos(cycles: u64) -> u64 {
+    match frequency() {
+        0 => 0,
+        frequency => (cycles as u128 * 1_000_000_000 / frequency as u128) as u64,
+    }
+}
@@ -0,0 +1,302 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::vec;
+use alloc::vec::Vec;
+use rustai_os::ai::{
+    check_gradients, manual_seed, BatchNorm, Conv2d, Conv2dParams, Dropout, Embedding, GraphModel, Gru,
+    LayerNorm, Lstm, Module, MultiHeadAttention, Pool2dParams, RecurrentOutput, RmsNorm, Tape, Tensor,
+    TensorResult, TransformerBlock, Var,
+};
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::{assert_close, assert_tensor_close};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+// Valores deterministas en [-1, 1], lejos de 0 para que ReLU sea derivable
+fn input(shape: &[usize], seed: usize) -> Tensor {
+    let len = shape.iter().product::<usize>();
+    let values = (0..len)
+        .map(|i| {
+            let x = ((i * 7 + seed) % 13) as f32 / 6.0 - 1.0;
+            if x.abs() < 0.1 { 0.5 } else { x }
+        })
+        .collect();
+    Tensor::from_vec(values, shape).unwrap()
+}
+
+fn check<F>(f: F, inputs: &[Tensor])
+where
+    F: for<'t> Fn(&[Var<'t>]) -> TensorResult<Var<'t>>,
+{
+    let error = check_gradients(f, inputs, 1e-2).unwrap();
+    assert!(error < 5e-3, "error relativo {}", error);
+}
+
+#[test_case]
+fn elementwise_ops_match_numerical_gradients() {
+    // Suma con difusión del sesgo y producto elemento a elemento
+    check(
+        |v| Ok(v[0].mul(&v[1])?.add(&v[2])?.tanh()?.sum_all()),
+        &[input(&[2, 3], 0), input(&[2, 3], 5), input(&[3], 2)],
+    );
+    check(|v| Ok(v[0].relu()?.mul(&v[0])?.sigmoid()?.mean_all()), &[input(&[4, 2], 1)]);
+    // softmax sobre cada eje, ponderada para que el gradiente no se anule
+    for axis in [0, -1] {
+        check(
+            move |v| v[0].softmax(axis)?.mul(&v[1])?.sum(1, false)?.mean(0, false),
+            &[input(&[3, 4], 3), input(&[3, 4], 8)],
+        );
+    }
+    check(
+        |v| Ok(v[0].transpose(0, 1)?.mul(&v[1])?.sum(0, true)?.sum_all()),
+        &[input(&[2, 3], 4), input(&[3, 2], 9)],
+    );
+}
+
+#[test_case]
+fn matmul_gradients_cover_vectors_and_batches() {
+    for (a, b) in [
+        (&[2, 3][..], &[3, 4][..]),
+        (&[3][..], &[3, 2][..]),
+        (&[2, 3][..], &[3][..]),
+        (&[2, 2, 3][..], &[3, 2][..]),
+        (&[2, 2, 3][..], &[1, 3, 2][..]),
+    ] {
+        check(|v| Ok(v[0].matmul(&v[1])?.tanh()?.sum_all()), &[input(a, 1), input(b, 6)]);
+    }
+
+    // Perceptrón de dos capas con salida softmax
+    check(
+        |v| {
+            let hidden = v[0].matmul(&v[1])?.add(&v[2])?.relu()?;
+            let logits = hidden.matmul(&v[3])?.add(&v[4])?;
+            Ok(logits.softmax(-1)?.mul(&v[5])?.sum_all())
+        },
+        &[
+            input(&[2, 3], 0),
+            input(&[3, 4], 3),
+            input(&[4], 7),
+            input(&[4, 2], 2),
+            input(&[2], 4),
+            input(&[2, 2], 11),
+        ],
+    );
+}
+
+#[test_case]
+fn backward_accumulates_into_leaves() {
+    let tape = Tape::new();
+    let x = tape.var(Tensor::from_vec(vec![1.0, -2.0, 3.0], &[3]).unwrap(), true);
+    let c = tape.var(Tensor::full(&[3], 2.0).unwrap(), false);
+    let y = x.mul(&x).unwrap();
+    let loss = y.mul(&c).unwrap().sum_all();
+    assert!(loss.requires_grad() && !c.requires_grad());
+    assert_eq!(loss.shape(), vec![0usize; 0]);
+
+    // d/dx Σ 2 x² = 4 x
+    loss.backward().unwrap();
+    assert_close(&x.grad().unwrap(), &[4.0, -8.0, 12.0], 1e-5);
+    assert!(c.grad().is_none());
+    // Los gradientes intermedios no se conservan
+    assert!(y.grad().is_none());
+
+    // Una segunda pasada suma su gradiente al anterior
+    loss.backward().unwrap();
+    assert_close(&x.grad().unwrap(), &[8.0, -16.0, 24.0], 1e-5);
+    tape.zero_grad();
+    assert!(x.grad().is_none());
+
+    assert!(y.backward().is_err());
+    let other = Tape::new();
+    assert!(x.add(&other.var(Tensor::ones(&[3]).unwrap(), true)).is_err());
+}
+
+#[test_case]
+fn shape_ops_match_numerical_gradients() {
+    check(
+        |v| Ok(v[0].sub(&v[1])?.mul_scalar(-1.5)?.add_scalar(0.25)?.tanh()?.sum_all()),
+        &[input(&[2, 3], 1), input(&[3], 4)],
+    );
+    check(
+        |v| Ok(v[0].permute(&[2, 0, 1])?.reshape(&[4, 6])?.mul(&v[1])?.sum_all()),
+        &[input(&[2, 3, 4], 2), input(&[4, 6], 7)],
+    );
+    // Los tramos de `narrow` se recombinan con `concat` en otro orden
+    check(
+        |v| {
+            let head = v[0].narrow(1, 0, 1)?;
+            let tail = v[0].narrow(-1, 1, 2)?;
+            Ok(Var::concat(&[tail, v[1], head], 1)?.mul(&v[2])?.sum_all())
+        },
+        &[input(&[2, 3], 3), input(&[2, 2], 5), input(&[2, 5], 8)],
+    );
+    // Las filas repetidas acumulan gradiente
+    check(
+        |v| Ok(v[0].index_select(0, &[2, 0, 2, 1])?.mul(&v[1])?.sum_all()),
+        &[input(&[3, 2], 6), input(&[4, 2], 1)],
+    );
+    check(|v| Ok(v[0].index_select(1, &[1, 1])?.tanh()?.sum_all()), &[input(&[2, 3], 9)]);
+}
+
+#[test_case]
+fn gelu_silu_and_rsqrt_match_numerical_gradients() {
+    check(|v| Ok(v[0].mul_scalar(2.0)?.gelu()?.mul(&v[1])?.sum_all()), &[input(&[3, 4], 2), input(&[3, 4], 5)]);
+    check(|v| Ok(v[0].mul_scalar(2.0)?.silu()?.mul(&v[1])?.sum_all()), &[input(&[3, 4], 4), input(&[3, 4], 9)]);
+    check(|v| Ok(v[0].mul(&v[0])?.add_scalar(0.5)?.rsqrt()?.sum_all()), &[input(&[2, 3], 7)]);
+}
+
+#[test_case]
+fn conv_and_pool_match_numerical_gradients() {
+    for (params, x, weight) in [
+        // Grupos, paso, relleno y dilatación distintos en cada eje
+        (
+            Conv2dParams { stride: (2, 1), padding: (1, 1), dilation: (1, 2), groups: 2 },
+            input(&[1, 4, 5, 6], 1),
+            input(&[4, 2, 3, 2], 3),
+        ),
+        // Convolución en profundidad con multiplicador 2
+        (
+            Conv2dParams { padding: (1, 1), groups: 2, ..Conv2dParams::default() },
+            input(&[2, 2, 4, 4], 5),
+            input(&[4, 1, 3, 3], 2),
+        ),
+    ] {
+        check(move |v| Ok(v[0].conv2d(&v[1], params)?.tanh()?.sum_all()), &[x, weight]);
+    }
+
+    // Valores distintos en cada ventana, para que el máximo no empate
+    let distinct = Tensor::from_vec((0..32).map(|i| ((i * 5) % 32) as f32 / 8.0 - 2.0).collect(), &[1, 2, 4, 4]).unwrap();
+    let params = Pool2dParams { kernel: (2, 3), stride: (2, 1), padding: (1, 1) };
+    check(|v| Ok(v[0].max_pool2d(params)?.mul(&v[1])?.sum_all()), &[distinct.clone(), input(&[1, 2, 3, 4], 4)]);
+    check(|v| Ok(v[0].avg_pool2d(params)?.mul(&v[1])?.sum_all()), &[distinct, input(&[1, 2, 3, 4], 6)]);
+}
+
+/// Comprueba que `forward_tape` da lo mismo que `forward` y que sus
+/// gradientes respecto a la entrada y a los parámetros son los numéricos.
+fn check_module(module: &dyn Module, x: Tensor) {
+    let tape = Tape::new();
+    let parameters: Vec<Var> = module.parameters().into_iter().map(|p| tape.var(p.clone(), false)).collect();
+    let output = module.forward_tape(tape.var(x.clone(), false), &parameters).unwrap();
+    assert_tensor_close(&output.value(), &module.forward(x.clone()).unwrap(), 1e-5);
+
+    // Ponderada para que el gradiente de las normalizaciones no se anule
+    let weights = input(&output.shape(), 5);
+    let mut inputs = vec![x];
+    inputs.extend(module.parameters().into_iter().cloned());
+    check(
+        |v| {
+            let output = module.forward_tape(v[0], &v[1..])?;
+            Ok(output.mul(&v[0].tape().var(weights.clone(), false))?.sum_all())
+        },
+        &inputs,
+    );
+}
+
+#[test_case]
+fn normalization_tapes_match_numerical_gradients() {
+    check_module(&LayerNorm::from_parameters(input(&[3, 2], 1), input(&[3, 2], 2), 1e-5).unwrap(), input(&[2, 3, 2], 3));
+    check_module(&RmsNorm::from_parameters(input(&[4], 4), 1e-5).unwrap(), input(&[3, 4], 6));
+
+    let stats = |seed| input(&[3], seed).map(|x| x * x + 0.5).unwrap();
+    let mut batch_norm = BatchNorm::from_parameters(input(&[3], 1), input(&[3], 2), input(&[3], 3), stats(4), 1e-5).unwrap();
+    check_module(&batch_norm, input(&[2, 3, 2], 5));
+    // En entrenamiento el gradiente pasa por las estadísticas del lote, que
+    // también se acumulan
+    batch_norm.set_training(true);
+    let before = batch_norm.running_mean();
+    check_module(&batch_norm, input(&[4, 3], 7));
+    assert!(common::max_abs_diff(&before, &batch_norm.running_mean()) > 0.0);
+}
+
+#[test_case]
+fn dropout_tape_reuses_the_forward_mask() {
+    let mut dropout = Dropout::new(0.5, 7).unwrap();
+    let tape = Tape::new();
+    let x = tape.var(Tensor::ones(&[64]).unwrap(), true);
+    assert_close(&dropout.forward_tape(x).unwrap().value(), &[1.0; 64], 0.0);
+
+    dropout.set_training(true);
+    let expected = Dropout::new(0.5, 7).map(|mut d| {
+        d.set_training(true);
+        d.forward(Tensor::ones(&[64]).unwrap()).unwrap()
+    });
+    let output = dropout.forward_tape(x).unwrap();
+    assert_tensor_close(&output.value(), &expected.unwrap(), 0.0);
+    // El gradiente es la propia máscara
+    output.sum_all().backward().unwrap();
+    assert_tensor_close(&x.grad().unwrap(), &output.value(), 0.0);
+}
+
+#[test_case]
+fn sequence_module_tapes_match_numerical_gradients() {
+    manual_seed(11);
+    check_module(&Conv2d::new(2, 3, (2, 2), Conv2dParams { padding: (1, 0), ..Conv2dParams::default() }).unwrap(), input(&[1, 2, 3, 3], 2));
+
+    // Los identificadores no tienen gradiente: solo se comprueba la tabla
+    let embedding = Embedding::from_parameters(input(&[4, 3], 1)).unwrap();
+    let ids = Tensor::from_vec(vec![2.0, 0.0, 2.0, 3.0], &[2, 2]).unwrap();
+    check(
+        |v| {
+            let ids = v[0].tape().var(ids.clone(), false);
+            Ok(embedding.forward_tape(ids, &v[0])?.mul(&v[1])?.sum_all())
+        },
+        &[embedding.weight().clone(), input(&[2, 2, 3], 4)],
+    );
+    let tape = Tape::new();
+    let weight = tape.var(embedding.weight().clone(), false);
+    let output = embedding.forward_tape(tape.var(ids.clone(), false), &weight).unwrap();
+    assert_tensor_close(&output.value(), &embedding.forward(ids).unwrap(), 0.0);
+
+    let mut attention = MultiHeadAttention::new(4, 2, true).unwrap();
+    attention.set_rotary(true);
+    check_module(&attention, input(&[1, 3, 4], 3));
+    check_module(&TransformerBlock::new(4, 2, 6, true).unwrap(), input(&[3, 4], 8));
+
+    for output in [RecurrentOutput::Sequence, RecurrentOutput::LastState] {
+        check_module(&Lstm::new(2, 3, output).unwrap(), input(&[2, 3, 2], 1));
+        check_module(&Gru::new(2, 3, output).unwrap(), input(&[2, 3, 2], 5));
+    }
+
+    // Conexión residual y concatenación: `out = [proj(x) + x, x]`
+    let mut graph = GraphModel::new("residual");
+    graph.add_input("x").unwrap();
+    graph.add_module("norm", RmsNorm::new(&[2], 1e-5).unwrap(), "x").unwrap();
+    graph.add_module("proj", LayerNorm::new(&[2], 1e-5).unwrap(), "norm").unwrap();
+    graph.add_sum("sum", &["proj", "x"]).unwrap();
+    graph.add_concat("out", &["sum", "x"], -1).unwrap();
+    graph.set_outputs(&["out"]);
+    graph.build().unwrap();
+    check_module(&graph, input(&[3, 2], 2));
+}
@@ -0,0 +1,43 @@
+//! Utilidades compartidas por los tests de integración.
+
+#![allow(dead_code)]
+
+use alloc::vec::Vec;
+use rustai_os::ai::Tensor;
+
+/// Compara elemento a elemento con los valores esperados, en orden lógico.
+pub fn assert_close(actual: &Tensor, expected: &[f32], tolerance: f32) {
+    let values = actual.to_vec();
+    assert_eq!(values.len(), expected.len());
+    for (a, e) in values.iter().zip(expected) {
+        assert!((a - e).abs() <= tolerance, "{} != {}", a, e);
+    }
+}
+
+/// Como `assert_close`, pero contra otro tensor de la misma forma.
+pub fn assert_tensor_close(actual: &Tensor, expected: &Tensor, tolerance: f32) {
+    assert_eq!(actual.shape(), expected.shape());
+    assert_close(actual, &expected.to_vec(), tolerance);
+}
+
+pub fn max_abs_diff(a: &Tensor, b: &Tensor) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs()).fold(0.0, f32::max)
+}
+
+/// Valores pseudoaleatorios reproducibles en `[-scale, scale)`.
+pub fn pseudo_random(len: usize, seed: u32, scale: f32) -> Vec<f32> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            ((state >> 8) as f32 / (1u32 << 23) as f32 - 1.0) * scale
+        })
+        .collect()
+}
+
+/// Tensor determinista con valores en `[-0.5, 0.5)` que no se repiten en
+/// posiciones vecinas.
+pub fn ramp(shape: &[usize]) -> Tensor {
+    let len = shape.iter().product();
+    Tensor::from_vec((0..len).map(|i| ((i * 7) % 11) as f32 / 11.0 - 0.5).collect(), shape).unwrap()
+}
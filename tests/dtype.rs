@@ -0,0 +1,115 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::vec;
+use alloc::vec::Vec;
+use rustai_os::ai::{DType, GemmKernel, Tensor};
+use rustai_os::math;
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::assert_close;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+#[test_case]
+fn to_dtype_roundtrips_representable_values() {
+    let values = vec![0.5, -1.25, 3.0, 1024.0, -0.0078125, 0.0];
+    let t = Tensor::from_vec(values.clone(), &[2, 3]).unwrap();
+    for dtype in [DType::F16, DType::BF16] {
+        let half = t.to_dtype(dtype).unwrap();
+        assert_eq!((half.dtype(), half.shape(), half.size_in_bytes()), (dtype, &[2, 3][..], 12));
+        assert_eq!(half.to_vec(), values);
+        let back = half.to_dtype(DType::F32).unwrap();
+        assert_eq!((back.dtype(), back.to_vec()), (DType::F32, values.clone()));
+        // Sin cambio de tipo se comparte el almacenamiento
+        assert!(half.to_dtype(dtype).unwrap().shares_storage(&half));
+        // Las vistas se convierten en orden lógico
+        assert_eq!(half.t().unwrap().to_dtype(DType::F32).unwrap().to_vec(), t.t().unwrap().to_vec());
+    }
+}
+
+#[test_case]
+fn f16_and_bf16_round_differently() {
+    // 1 + 2⁻⁹ y 1 + 3·2⁻⁹ caben en los 10 bits de mantisa de f16, no en los
+    // 7 de bf16; 70000 desborda f16; 1e-6 es subnormal en f16
+    let (one_ulp, three_ulp) = (1.0 + 1.0 / 512.0, 1.0 + 3.0 / 512.0);
+    let t = Tensor::from_vec(vec![one_ulp, three_ulp, 70_000.0, 1e-6], &[4]).unwrap();
+    let f16 = t.to_dtype(DType::F16).unwrap().to_vec();
+    assert_eq!(f16[..2], [one_ulp, three_ulp]);
+    assert_eq!(f16[2], f32::INFINITY);
+    assert_eq!(f16[3], 17.0 / 16_777_216.0);
+    let bf16 = t.to_dtype(DType::BF16).unwrap().to_vec();
+    assert_eq!(bf16[..3], [1.0, 1.007_812_5, 70_144.0]);
+    assert_eq!(bf16[3], 134.0 / 128.0 / 1_048_576.0);
+}
+
+#[test_case]
+fn matmul_mixes_storage_types() {
+    let a = Tensor::from_vec(vec![0.5, -1.0, 2.0, 1.5, 0.25, -0.75], &[2, 3]).unwrap();
+    let b = Tensor::from_vec(vec![1.0, 2.0, -0.5, 0.125, 3.0, -1.0], &[3, 2]).unwrap();
+    let expected = [7.0, -1.125, -0.875, 3.781_25];
+    let (a16, b16) = (a.to_dtype(DType::F16).unwrap(), b.to_dtype(DType::BF16).unwrap());
+    for kernel in [GemmKernel::Scalar, GemmKernel::Sse2, GemmKernel::Avx2Fma] {
+        for (lhs, rhs) in [(&a16, &b16), (&a, &b16), (&a16, &b)] {
+            let out = lhs.matmul_with(rhs, kernel).unwrap();
+            assert_eq!(out.dtype(), DType::F32);
+            assert_close(&out, &expected, 0.0);
+        }
+        // Operando traspuesto: `bᵀ` es `[2, 3]` con stride 1 entre filas
+        let out = b16.t().unwrap().matmul_with(&a16.t().unwrap(), kernel).unwrap();
+        assert_close(&out, &[7.0, -0.875, -1.125, 3.781_25], 0.0);
+    }
+}
+
+#[test_case]
+fn elementwise_ops_upcast_half_views() {
+    // Filas de 100 elementos: más de un bloque de conversión por fila
+    let values: Vec<f32> = (0..300).map(|i| (i as f32 - 150.0) / 16.0).collect();
+    let t = Tensor::from_vec(values, &[3, 100]).unwrap().to_dtype(DType::F16).unwrap();
+    for view in [t.narrow(1, 10, 80).unwrap(), t.t().unwrap()] {
+        // Lectura elemento a elemento como referencia
+        let (rows, columns) = (view.shape()[0], view.shape()[1]);
+        let reference: Vec<f32> = (0..rows * columns)
+            .map(|i| view.get(&[i / columns, i % columns]).unwrap())
+            .collect();
+        assert_eq!(view.to_vec(), reference);
+
+        let relu: Vec<f32> = reference.iter().map(|&x| x.max(0.0)).collect();
+        assert_close(&view.relu().unwrap(), &relu, 0.0);
+        let sigmoid: Vec<f32> = reference.iter().map(|&x| math::sigmoid(x)).collect();
+        assert_close(&view.sigmoid().unwrap(), &sigmoid, 0.0);
+        let tanh: Vec<f32> = reference.iter().map(|&x| math::tanh(x)).collect();
+        assert_close(&view.tanh().unwrap(), &tanh, 0.0);
+        let doubled: Vec<f32> = reference.iter().map(|&x| 2.0 * x).collect();
+        assert_close(&view.add(&view.to_dtype(DType::BF16).unwrap()).unwrap(), &doubled, 0.0);
+    }
+}
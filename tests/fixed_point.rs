@@ -0,0 +1,214 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::vec::Vec;
+use rustai_os::ai::{
+    ActivationFunction, ExecutionMode, FixedFormat, FixedLayer, FixedTensor, Layer, NeuralNetwork, Tensor,
+    TensorError,
+};
+use rustai_os::math::{exp, sigmoid};
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::{max_abs_diff, pseudo_random};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+/// Capa 1 → `outputs` con pesos dados y sesgo nulo.
+fn scalar_layer(weights: &[f32], activation: ActivationFunction) -> Layer {
+    let n = weights.len();
+    let weights = Tensor::from_vec(weights.to_vec(), &[1, n]).unwrap();
+    Layer::from_parameters(weights, Tensor::zeros(&[n]).unwrap(), activation).unwrap()
+}
+
+/// Entradas `i / 64` en `[-12, 12]`, exactas en Q16.16.
+fn sweep() -> Tensor {
+    let values: Vec<f32> = (-768..=768).map(|i| i as f32 / 64.0).collect();
+    let len = values.len();
+    Tensor::from_vec(values, &[len, 1]).unwrap()
+}
+
+#[test_case]
+fn fixed_layers_track_float_layers() {
+    // Pesos y entradas pequeños para que la preactivación quepa en Q15. La
+    // tolerancia cubre el redondeo de 16 productos a medio bit menos
+    // significativo y el error de las tablas de la sigmoide y la exponencial.
+    const TOLERANCE: f32 = 1e-3;
+    let activations = [
+        ActivationFunction::ReLU,
+        ActivationFunction::Sigmoid,
+        ActivationFunction::Tanh,
+        ActivationFunction::Softmax,
+        ActivationFunction::Identity,
+        ActivationFunction::Gelu,
+        ActivationFunction::Silu,
+    ];
+    let weights = Tensor::from_vec(pseudo_random(16 * 8, 1, 0.05), &[16, 8]).unwrap();
+    let bias = Tensor::from_vec(pseudo_random(8, 2, 0.1), &[8]).unwrap();
+    let input = Tensor::from_vec(pseudo_random(4 * 16, 3, 1.0), &[4, 16]).unwrap();
+
+    for format in [FixedFormat::Q15, FixedFormat::Q16_16] {
+        for activation in activations {
+            let layer = Layer::from_parameters(weights.clone(), bias.clone(), activation).unwrap();
+            let expected = layer.forward(input.clone()).unwrap();
+
+            let fixed = FixedLayer::from_layer(&layer, format).unwrap();
+            let output = fixed.forward(&FixedTensor::from_tensor(&input, format).unwrap()).unwrap();
+            assert_eq!(output.format(), format);
+            assert_eq!(output.shape(), &[4, 8]);
+            assert!(max_abs_diff(&expected, &output.to_tensor().unwrap()) < TOLERANCE);
+        }
+    }
+}
+
+#[test_case]
+fn lookup_tables_stay_within_documented_error() {
+    // Las tablas interpolan con un error de unos 6e-5 y se suma medio bit
+    // menos significativo por el redondeo de la salida a Q16.16. Fuera de
+    // [-8, 8] la sigmoide se satura a 0 o 1.
+    const TOLERANCE: f32 = 7e-5;
+    const SATURATED_TOLERANCE: f32 = 3.4e-4;
+    let input = FixedTensor::from_tensor(&sweep(), FixedFormat::Q16_16).unwrap();
+
+    let layer = scalar_layer(&[1.0], ActivationFunction::Sigmoid);
+    let output = FixedLayer::from_layer(&layer, FixedFormat::Q16_16).unwrap()
+        .forward(&input).unwrap()
+        .to_tensor().unwrap();
+    for (x, y) in sweep().iter().zip(output.iter()) {
+        let tolerance = if x.abs() < 8.0 { TOLERANCE } else { SATURATED_TOLERANCE };
+        assert!((y - sigmoid(x)).abs() < tolerance);
+    }
+
+    // softmax([0, x]) = [1, exp(x)] / (1 + exp(x)), con exp(x) de la tabla.
+    // El error de hasta 5e-4 de la exponencial cerca de 0 se reduce al
+    // normalizar, pero queda por encima del de la sigmoide
+    const SOFTMAX_TOLERANCE: f32 = 1.5e-4;
+    let layer = scalar_layer(&[0.0, 1.0], ActivationFunction::Softmax);
+    let output = FixedLayer::from_layer(&layer, FixedFormat::Q16_16).unwrap()
+        .forward(&input).unwrap()
+        .to_tensor().unwrap();
+    for (x, row) in sweep().iter().zip(output.to_vec().chunks(2)) {
+        let e = exp(x);
+        assert!((row[0] - 1.0 / (1.0 + e)).abs() < SOFTMAX_TOLERANCE);
+        assert!((row[1] - e / (1.0 + e)).abs() < SOFTMAX_TOLERANCE);
+    }
+}
+
+#[test_case]
+fn network_switches_between_float_and_fixed_execution() {
+    let mut network = NeuralNetwork::new("fixed");
+    let first = Tensor::from_vec(pseudo_random(6 * 5, 4, 0.5), &[6, 5]).unwrap();
+    let second = Tensor::from_vec(pseudo_random(5 * 3, 5, 0.5), &[5, 3]).unwrap();
+    network.add_layer(Layer::from_parameters(first, Tensor::zeros(&[5]).unwrap(), ActivationFunction::Tanh).unwrap()).unwrap();
+    network.add_layer(Layer::from_parameters(second, Tensor::zeros(&[3]).unwrap(), ActivationFunction::Sigmoid).unwrap()).unwrap();
+    let input = Tensor::from_vec(pseudo_random(2 * 6, 6, 1.0), &[2, 6]).unwrap();
+
+    assert_eq!(network.execution_mode(), ExecutionMode::Float);
+    let float_output = network.forward(input.clone()).unwrap();
+
+    network.set_execution_mode(ExecutionMode::Fixed(FixedFormat::Q16_16)).unwrap();
+    assert_eq!(network.execution_mode(), ExecutionMode::Fixed(FixedFormat::Q16_16));
+    let fixed_output = network.forward(input.clone()).unwrap();
+    assert_eq!(fixed_output.shape(), float_output.shape());
+    assert!(max_abs_diff(&float_output, &fixed_output) < 1e-3);
+
+    let fixed = network
+        .forward_fixed(FixedTensor::from_tensor(&input, FixedFormat::Q16_16).unwrap())
+        .unwrap();
+    assert_eq!(fixed.to_tensor().unwrap().to_vec(), fixed_output.to_vec());
+
+    network.set_execution_mode(ExecutionMode::Float).unwrap();
+    assert_eq!(network.forward(input).unwrap().to_vec(), float_output.to_vec());
+}
+
+#[test_case]
+fn add_layer_reports_fixed_point_conversion_failure() {
+    let mut network = NeuralNetwork::new("fixed");
+    network.set_execution_mode(ExecutionMode::Fixed(FixedFormat::Q16_16)).unwrap();
+    let weights = Tensor::from_vec(pseudo_random(3 * 4, 7, 0.5), &[3, 4]).unwrap();
+    network.add_layer(Layer::from_parameters(weights, Tensor::zeros(&[4]).unwrap(), ActivationFunction::Tanh).unwrap()).unwrap();
+
+    // Vistas con stride 0: la capa ocupa un elemento, pero su copia en coma
+    // fija no cabe en memoria
+    let one = Tensor::zeros(&[1]).unwrap();
+    let weights = one.as_strided(&[4, 1 << 60], &[0, 0], 0).unwrap();
+    let bias = one.as_strided(&[1 << 60], &[0], 0).unwrap();
+    let huge = Layer::from_parameters(weights, bias, ActivationFunction::Identity).unwrap();
+    assert!(matches!(network.add_layer(huge), Err(TensorError::OutOfMemory { .. })));
+
+    // La capa queda añadida y el modelo sigue en modo coma fija
+    assert_eq!(network.layers().len(), 2);
+    assert_eq!(network.execution_mode(), ExecutionMode::Fixed(FixedFormat::Q16_16));
+}
+
+#[test_case]
+fn fixed_point_values_saturate() {
+    let values = Tensor::from_vec(alloc::vec![2.0, -3.0, 0.5, 40000.0], &[4]).unwrap();
+
+    let q15 = FixedTensor::from_tensor(&values, FixedFormat::Q15).unwrap();
+    assert_eq!(q15.data(), &[i16::MAX as i32, i16::MIN as i32, 1 << 14, i16::MAX as i32]);
+
+    let q16 = FixedTensor::from_tensor(&values, FixedFormat::Q16_16).unwrap();
+    assert_eq!(q16.data(), &[2 << 16, -3 << 16, 1 << 15, i32::MAX]);
+    assert_eq!(q16.to_format(FixedFormat::Q15).unwrap().data(), q15.data());
+
+    // La preactivación 0.9 + 0.9 se sale de Q15 y se queda en el máximo
+    let layer = Layer::from_parameters(
+        Tensor::from_vec(alloc::vec![0.9, 0.9], &[2, 1]).unwrap(),
+        Tensor::zeros(&[1]).unwrap(),
+        ActivationFunction::Identity,
+    ).unwrap();
+    let input = FixedTensor::from_tensor(&Tensor::from_vec(alloc::vec![0.99, 0.99], &[2]).unwrap(), FixedFormat::Q15).unwrap();
+    let output = FixedLayer::from_layer(&layer, FixedFormat::Q15).unwrap().forward(&input).unwrap();
+    assert_eq!(output.data(), &[i16::MAX as i32]);
+}
+
+#[test_case]
+fn fixed_layers_accept_empty_dimensions() {
+    // Sin entradas, la salida es la activación del sesgo
+    let layer = Layer::from_parameters(
+        Tensor::zeros(&[0, 2]).unwrap(),
+        Tensor::from_vec(alloc::vec![0.0, 1.0], &[2]).unwrap(),
+        ActivationFunction::Identity,
+    ).unwrap();
+    let input = FixedTensor::from_tensor(&Tensor::zeros(&[3, 0]).unwrap(), FixedFormat::Q16_16).unwrap();
+    let output = FixedLayer::from_layer(&layer, FixedFormat::Q16_16).unwrap().forward(&input).unwrap();
+    assert_eq!(output.shape(), &[3, 2]);
+    assert_eq!(output.data(), &[0, 1 << 16, 0, 1 << 16, 0, 1 << 16]);
+
+    // Sin salidas, filas vacías
+    let layer = Layer::from_parameters(Tensor::zeros(&[3, 0]).unwrap(), Tensor::zeros(&[0]).unwrap(), ActivationFunction::Softmax).unwrap();
+    let input = FixedTensor::from_tensor(&Tensor::ones(&[2, 3]).unwrap(), FixedFormat::Q15).unwrap();
+    let output = FixedLayer::from_layer(&layer, FixedFormat::Q15).unwrap().forward(&input).unwrap();
+    assert_eq!(output.shape(), &[2, 0]);
+    assert!(output.data().is_empty());
+}
@@ -0,0 +1,226 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use rustai_os::ai::{
+    BlockFormat, BlockLinear, BlockQuantizedTensor, DType, GgmlType, GgufError, GgufFile, GgufValue, Module, Tensor,
+};
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::{assert_tensor_close, ramp};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+/// Escritor de ficheros GGUF v3 con alineación de 32 bytes para las pruebas.
+#[derive(Default)]
+struct Gguf {
+    metadata: Vec<u8>,
+    metadata_count: u64,
+    infos: Vec<u8>,
+    data: Vec<u8>,
+    tensor_count: u64,
+}
+
+impl Gguf {
+    fn key(mut self, key: &str, value_type: u32, value: &[u8]) -> Self {
+        self.metadata.extend_from_slice(&string(key));
+        self.metadata.extend_from_slice(&value_type.to_le_bytes());
+        self.metadata.extend_from_slice(value);
+        self.metadata_count += 1;
+        self
+    }
+
+    /// Tensor con las dimensiones `ne` de ggml (la contigua primero).
+    fn tensor(mut self, name: &str, ne: &[u64], ggml_type: u32, data: &[u8]) -> Self {
+        self.infos.extend_from_slice(&string(name));
+        self.infos.extend_from_slice(&(ne.len() as u32).to_le_bytes());
+        ne.iter().for_each(|dim| self.infos.extend_from_slice(&dim.to_le_bytes()));
+        self.infos.extend_from_slice(&ggml_type.to_le_bytes());
+        self.data.resize(self.data.len().div_ceil(32) * 32, 0);
+        self.infos.extend_from_slice(&(self.data.len() as u64).to_le_bytes());
+        self.data.extend_from_slice(data);
+        self.tensor_count += 1;
+        self
+    }
+
+    fn build(self) -> Vec<u8> {
+        let mut bytes = b"GGUF".to_vec();
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&self.tensor_count.to_le_bytes());
+        bytes.extend_from_slice(&self.metadata_count.to_le_bytes());
+        bytes.extend_from_slice(&self.metadata);
+        bytes.extend_from_slice(&self.infos);
+        bytes.resize(bytes.len().div_ceil(32) * 32, 0);
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+}
+
+fn string(value: &str) -> Vec<u8> {
+    let mut bytes = (value.len() as u64).to_le_bytes().to_vec();
+    bytes.extend_from_slice(value.as_bytes());
+    bytes
+}
+
+#[test_case]
+fn metadata_and_tensor_infos_are_read_without_copies() {
+    let mut tokens = 8u32.to_le_bytes().to_vec();
+    tokens.extend_from_slice(&3u64.to_le_bytes());
+    ["<s>", "hola", "mundo"].iter().for_each(|token| tokens.extend_from_slice(&string(token)));
+    let embeddings: Vec<u8> = ramp(&[8]).iter().flat_map(|x| x.to_le_bytes()).collect();
+    let norm: Vec<u8> = [1.0f32, 0.5, -2.0].iter().flat_map(|&x| half::f16::from_f32(x).to_le_bytes()).collect();
+    let bytes = Gguf::default()
+        .key("general.architecture", 8, &string("llama"))
+        .key("llama.block_count", 4, &2u32.to_le_bytes())
+        .key("llama.rope.freq_base", 6, &10000.0f32.to_le_bytes())
+        .key("tokenizer.ggml.tokens", 9, &tokens)
+        .key("tokenizer.ggml.add_bos_token", 7, &[1])
+        .tensor("token_embd.weight", &[4, 2], 0, &embeddings)
+        .tensor("output_norm.weight", &[3], 1, &norm)
+        .tensor("blk.0.ffn_up.weight", &[256, 2], 12, &[])
+        .build();
+
+    let file = GgufFile::parse(&bytes).unwrap();
+    assert_eq!((file.version(), file.alignment(), file.len()), (3, 32, 3));
+    assert_eq!(file.architecture(), Some("llama"));
+    assert_eq!(file.value("llama.block_count").and_then(GgufValue::as_u64), Some(2));
+    assert_eq!(file.value("llama.rope.freq_base").and_then(GgufValue::as_f32), Some(10000.0));
+    assert_eq!(file.value("tokenizer.ggml.add_bos_token"), Some(&GgufValue::Bool(true)));
+    let tokens = file.value("tokenizer.ggml.tokens").and_then(GgufValue::as_array).unwrap();
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens.iter().filter_map(|token| token.as_str()).collect::<Vec<_>>(), vec!["<s>", "hola", "mundo"]);
+    assert_eq!(tokens.get(1), Some(GgufValue::String("hola")));
+    assert_eq!(file.metadata()[0].0, "general.architecture");
+
+    // Las dimensiones de ggml van al revés que la forma
+    let embeddings = file.get("token_embd.weight").unwrap();
+    assert_eq!((embeddings.shape(), embeddings.ggml_type()), (&[2, 4][..], GgmlType::F32));
+    assert_eq!(embeddings.data().as_ptr(), bytes[bytes.len() - 64..].as_ptr());
+    assert_eq!(embeddings.to_tensor().unwrap().to_vec(), ramp(&[8]).to_vec());
+    let norm = file.get("output_norm.weight").unwrap().to_tensor().unwrap();
+    assert_eq!((norm.dtype(), norm.to_vec()), (DType::F16, vec![1.0, 0.5, -2.0]));
+
+    // Q4_K: se conserva la descripción, pero no se sabe leer
+    let unknown = file.get("blk.0.ffn_up.weight").unwrap();
+    assert_eq!((unknown.ggml_type(), unknown.shape(), unknown.data().len()), (GgmlType::Other(12), &[2, 256][..], 0));
+    let error = unknown.to_tensor().unwrap_err();
+    assert_eq!(format!("{}", error), "Tipo de tensor de GGUF no soportado blk.0.ffn_up.weight: 12");
+}
+
+#[test_case]
+fn block_formats_follow_the_ggml_reference() {
+    // Q4_0 toma como -8 el valor de mayor magnitud: d = -16 / -8 = 2
+    let values: Vec<f32> = (0..32).map(|i| i as f32 - 16.0).collect();
+    let tensor = Tensor::from_vec(values.clone(), &[32]).unwrap();
+    let q4 = BlockQuantizedTensor::quantize(&tensor, BlockFormat::Q4_0).unwrap();
+    assert_eq!(q4.size_in_bytes(), 18);
+    assert_eq!(&q4.data()[..3], &[0x00, 0x40, 0x80]);
+    let restored = q4.dequantize().unwrap().to_vec();
+    for (x, y) in values.iter().zip(&restored) {
+        assert!((x - y).abs() <= 1.0, "{} -> {}", x, y);
+    }
+    assert_eq!((restored[0], restored[31]), (-16.0, 14.0));
+
+    let q8 = BlockQuantizedTensor::quantize(&tensor, BlockFormat::Q8_0).unwrap();
+    assert_eq!(q8.size_in_bytes(), 34);
+    assert_eq!((q8.data()[2] as i8, q8.data()[33] as i8), (-127, 119));
+    assert_tensor_close(&q8.dequantize().unwrap(), &tensor, 16.0 / 254.0 + 1e-3);
+
+    let odd = Tensor::zeros(&[2, 40]).unwrap();
+    assert!(BlockQuantizedTensor::quantize(&odd, BlockFormat::Q8_0).is_err());
+    assert!(BlockQuantizedTensor::from_blocks(vec![0; 33], &[32], BlockFormat::Q8_0).is_err());
+    let zeros = BlockQuantizedTensor::quantize(&Tensor::zeros(&[32]).unwrap(), BlockFormat::Q4_0).unwrap();
+    assert_eq!(zeros.dequantize().unwrap().to_vec(), vec![0.0; 32]);
+}
+
+#[test_case]
+fn block_matmul_matches_the_dequantized_weights() {
+    let weights = ramp(&[3, 64]);
+    let input = Tensor::from_vec(ramp(&[2 * 64]).to_vec().into_iter().rev().collect(), &[2, 64]).unwrap();
+    let bias = Tensor::from_vec(vec![0.5, 0.0, -0.5], &[3]).unwrap();
+    for (format, ggml_type) in [(BlockFormat::Q8_0, 8), (BlockFormat::Q4_0, 2)] {
+        let quantized = BlockQuantizedTensor::quantize(&weights, format).unwrap();
+        assert_eq!(quantized.size_in_bytes(), 3 * 2 * format.block_bytes());
+        let expected = input.matmul(&quantized.dequantize().unwrap().t().unwrap()).unwrap();
+        assert_tensor_close(&quantized.matmul(&input).unwrap(), &expected, 1e-4);
+
+        // Directamente sobre los bloques del fichero
+        let bytes = Gguf::default().tensor("w", &[64, 3], ggml_type, quantized.data()).build();
+        let file = GgufFile::parse(&bytes).unwrap();
+        let tensor = file.get("w").unwrap();
+        assert_eq!(tensor.ggml_type().block_format(), Some(format));
+        assert_eq!(tensor.matmul(&input).unwrap().to_vec(), quantized.matmul(&input).unwrap().to_vec());
+        assert_eq!(tensor.to_tensor().unwrap().to_vec(), quantized.dequantize().unwrap().to_vec());
+
+        let layer = BlockLinear::new(tensor.to_block_tensor().unwrap(), Some(bias.clone())).unwrap();
+        assert_eq!(layer.output_shape(&[5, 64]).unwrap(), vec![5, 3]);
+        assert_eq!(layer.parameter_bytes(), quantized.size_in_bytes() + 12);
+        assert_eq!(layer.parameter_names(), vec!["bias"]);
+        let row = Tensor::from_vec(input.to_vec()[..64].to_vec(), &[64]).unwrap();
+        let output = layer.forward(row).unwrap();
+        let first = expected.narrow(0, 0, 1).unwrap().reshape(&[3]).unwrap();
+        assert_tensor_close(&output, &first.add(&bias).unwrap(), 1e-4);
+    }
+    let quantized = BlockQuantizedTensor::quantize(&weights, BlockFormat::Q8_0).unwrap();
+    assert!(quantized.matmul(&Tensor::zeros(&[2, 32]).unwrap()).is_err());
+    assert!(BlockLinear::new(quantized, Some(Tensor::zeros(&[4]).unwrap())).is_err());
+}
+
+#[test_case]
+fn malformed_gguf_files_are_rejected() {
+    let bytes = Gguf::default().key("general.name", 8, &string("tiny")).tensor("w", &[2], 0, &[0; 8]).build();
+    assert!(GgufFile::parse(&bytes).is_ok());
+    assert_eq!(GgufFile::parse(b"GGML\x03\x00\x00\x00").err(), Some(GgufError::BadMagic));
+    let mut old = bytes.clone();
+    old[4] = 1;
+    assert_eq!(GgufFile::parse(&old).err(), Some(GgufError::UnsupportedVersion(1)));
+    assert_eq!(
+        GgufFile::parse(&bytes[..bytes.len() - 1]).err(),
+        Some(GgufError::Truncated { expected: bytes.len(), found: bytes.len() - 1 })
+    );
+    assert!(matches!(GgufFile::parse(&bytes[..30]), Err(GgufError::Truncated { .. })));
+
+    // Un array que anuncia más elementos de los que hay
+    let mut huge = 4u32.to_le_bytes().to_vec();
+    huge.extend_from_slice(&u64::MAX.to_le_bytes());
+    let bytes = Gguf::default().key("a", 9, &huge).build();
+    assert!(matches!(GgufFile::parse(&bytes), Err(GgufError::Truncated { .. })));
+    let bytes = Gguf::default().key("a", 13, &[0; 4]).build();
+    assert!(matches!(GgufFile::parse(&bytes), Err(GgufError::Invalid(_))));
+    let bytes = Gguf::default().key("general.alignment", 4, &24u32.to_le_bytes()).build();
+    assert!(matches!(GgufFile::parse(&bytes), Err(GgufError::Invalid(_))));
+    let bytes = Gguf::default().tensor("w", &[40, 2], 8, &[0; 34 * 2]).build();
+    assert!(matches!(GgufFile::parse(&bytes), Err(GgufError::Invalid(_))));
+}
@@ -0,0 +1,187 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use core::f64::consts::{FRAC_PI_2, LN_2};
+use core::panic::PanicInfo;
+use rustai_os::math;
+use rustai_os::test_panic_handler;
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    test_main();
+
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+/// Puntos de cada barrido; bastan para cruzar muchas veces cada tramo de
+/// la reducción de rango sin alargar demasiado la prueba en QEMU.
+const SWEEP_POINTS: u32 = 20_000;
+
+/// `e^x` de referencia en `f64`: reducción por `ln 2` y 20 términos de Taylor.
+fn exp_ref(x: f64) -> f64 {
+    let t = x / LN_2;
+    let k = if t >= 0.0 { (t + 0.5) as i64 } else { (t - 0.5) as i64 };
+    let r = x - k as f64 * LN_2;
+    let (mut term, mut sum) = (1.0, 1.0);
+    for i in 1..20 {
+        term *= r / i as f64;
+        sum += term;
+    }
+    sum * f64::from_bits(((k + 1023) as u64) << 52)
+}
+
+/// `ln x` de referencia en `f64` con la serie de `atanh`.
+fn ln_ref(x: f64) -> f64 {
+    let bits = x.to_bits();
+    let e = ((bits >> 52) as i64) - 1023;
+    let m = f64::from_bits((bits & ((1 << 52) - 1)) | (1023 << 52));
+    let s = (m - 1.0) / (m + 1.0);
+    let (mut power, mut sum) = (s, 0.0);
+    for i in 0..40 {
+        sum += power / (2 * i + 1) as f64;
+        power *= s * s;
+    }
+    2.0 * sum + e as f64 * LN_2
+}
+
+fn tanh_ref(x: f64) -> f64 {
+    if x.abs() < 1e-3 {
+        // Serie de Taylor para evitar la cancelación de e^2x - 1
+        return x - x * x * x / 3.0 + 2.0 * x * x * x * x * x / 15.0;
+    }
+    let t = exp_ref(2.0 * x);
+    (t - 1.0) / (t + 1.0)
+}
+
+/// `sin` y `cos` de referencia en `f64`: reducción por `π/2` y Taylor.
+/// La resta pierde unos `|x| * 2^-53`, despreciable en los rangos probados.
+fn sin_cos_ref(x: f64) -> (f64, f64) {
+    let t = x / FRAC_PI_2;
+    let k = if t >= 0.0 { (t + 0.5) as i64 } else { (t - 0.5) as i64 };
+    let r = x - k as f64 * FRAC_PI_2;
+    let (mut sin, mut cos) = (0.0, 0.0);
+    let mut term = 1.0;
+    for i in 0..24 {
+        if i % 2 == 0 {
+            cos += term;
+        } else {
+            sin += term;
+        }
+        // r^n / n! con signo alterno cada dos términos
+        let step = if i % 2 == 0 { r } else { -r };
+        term *= step / (i + 1) as f64;
+    }
+    match k.rem_euclid(4) {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    }
+}
+
+/// Distancia en ULPs de `f32` entre `value` y la referencia exacta.
+fn ulp_error(value: f32, reference: f64) -> f64 {
+    let rounded = reference as f32;
+    if value == rounded {
+        return 0.0;
+    }
+    let magnitude = rounded.abs().max(f32::MIN_POSITIVE);
+    let ulp = f32::from_bits(magnitude.to_bits() + 1) as f64 - magnitude as f64;
+    (value as f64 - reference).abs() / ulp
+}
+
+/// Error máximo de `f` frente a `reference` en una rejilla uniforme de `[lo, hi]`.
+fn max_ulp_error(lo: f32, hi: f32, f: fn(f32) -> f32, reference: fn(f64) -> f64) -> f64 {
+    (0..=SWEEP_POINTS)
+        .map(|i| {
+            let x = lo + (hi - lo) * (i as f32 / SWEEP_POINTS as f32);
+            ulp_error(f(x), reference(x as f64))
+        })
+        .fold(0.0, f64::max)
+}
+
+#[test_case]
+fn exp_is_within_one_ulp() {
+    assert!(max_ulp_error(-87.0, 88.0, math::exp, exp_ref) <= 1.0);
+    assert!(max_ulp_error(-1.0, 1.0, math::exp, exp_ref) <= 1.0);
+    assert_eq!(math::exp(0.0), 1.0);
+    assert_eq!(math::exp(100.0), f32::INFINITY);
+    assert_eq!(math::exp(-200.0), 0.0);
+    assert!(math::exp(f32::NAN).is_nan());
+}
+
+#[test_case]
+fn expm1_is_accurate_near_zero() {
+    assert!(max_ulp_error(-20.0, 20.0, math::expm1, |x| exp_ref(x) - 1.0) <= 2.0);
+    assert!(max_ulp_error(-1e-3, 1e-3, math::expm1, |x| {
+        x + x * x / 2.0 + x * x * x / 6.0 + x * x * x * x / 24.0
+    }) <= 2.0);
+}
+
+#[test_case]
+fn ln_is_within_one_ulp() {
+    assert!(max_ulp_error(0.5, 2.0, math::ln, ln_ref) <= 1.0);
+    assert!(max_ulp_error(1e-30, 1e30, math::ln, ln_ref) <= 1.0);
+    // Subnormales y extremos por patrones de bits
+    for bits in (1..0x7f80_0000u32).step_by(0x7f80_0000 / SWEEP_POINTS as usize) {
+        let x = f32::from_bits(bits);
+        assert!(ulp_error(math::ln(x), ln_ref(x as f64)) <= 1.0, "ln({})", x);
+    }
+    assert_eq!(math::ln(1.0), 0.0);
+    assert_eq!(math::ln(0.0), f32::NEG_INFINITY);
+    assert!(math::ln(-1.0).is_nan());
+}
+
+#[test_case]
+fn tanh_and_sigmoid_are_within_three_ulp() {
+    assert!(max_ulp_error(-10.0, 10.0, math::tanh, tanh_ref) <= 3.0);
+    assert!(max_ulp_error(-0.5, 0.5, math::tanh, tanh_ref) <= 3.0);
+    assert!(max_ulp_error(-80.0, 80.0, math::sigmoid, |x| 1.0 / (1.0 + exp_ref(-x))) <= 3.0);
+    assert_eq!(math::tanh(20.0), 1.0);
+    assert_eq!(math::tanh(-20.0), -1.0);
+    assert_eq!(math::sigmoid(-200.0), 0.0);
+}
+
+#[test_case]
+fn sqrt_is_correctly_rounded() {
+    for bits in (1..0x7f80_0000u32).step_by(0x7f80_0000 / SWEEP_POINTS as usize) {
+        let x = f32::from_bits(bits);
+        let y = math::sqrt(x);
+        // El cuadrado de un f32 es exacto en f64: ningún vecino puede estar más cerca
+        let error = |v: f32| (v as f64 * v as f64 - x as f64).abs();
+        assert!(error(y) <= error(f32::from_bits(y.to_bits() + 1)), "sqrt({})", x);
+        assert!(error(y) <= error(f32::from_bits(y.to_bits() - 1)), "sqrt({})", x);
+    }
+    assert_eq!(math::sqrt(16.0), 4.0);
+    assert!(math::sqrt(-1.0).is_nan());
+}
+
+#[test_case]
+fn powf_handles_integer_and_real_exponents() {
+    assert_eq!(math::powf(2.0, 10.0), 1024.0);
+    assert_eq!(math::powf(-2.0, 3.0), -8.0);
+    assert_eq!(math::powf(4.0, -1.0), 0.25);
+    assert!((math::powf(2.0, 0.5) - core::f32::consts::SQRT_2).abs() < 1e-6);
+    assert!(math::powf(-2.0, 0.5).is_nan());
+}
+
+#[test_case]
+fn sin_and_cos_are_within_two_ulp() {
+    let sin_ref = |x| sin_cos_ref(x).0;
+    let cos_ref = |x| sin_cos_ref(x).1;
+    assert!(max_ulp_error(-4.0, 4.0, math::sin, sin_ref) <= 2.0);
+    assert!(max_ulp_error(-4.0, 4.0, math::cos, cos_ref) <= 2.0);
+    assert!(max_ulp_error(-100.0, 100.0, math::sin, sin_ref) <= 2.0);
+    assert!(max_ulp_error(-100.0, 100.0, math::cos, cos_ref) <= 2.0);
+    assert_eq!(math::sin(0.0), 0.0);
+    assert_eq!(math::cos(0.0), 1.0);
+    assert!(math::sin(1e5).is_nan());
+}
@@ -0,0 +1,188 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::vec;
+use alloc::vec::Vec;
+use rustai_os::ai::{
+    crc32, manual_seed, ActivationFunction, BatchNorm, Conv2d, Conv2dParams, DType, Dropout,
+    Embedding, Flatten, GlobalAvgPool2d, Gru, Layer, LayerNorm, LayerWeights, Lstm, MaxPool2d,
+    ModelFormatError, Module, NeuralNetwork, Pool2dParams, PositionalEncoding, QuantScheme,
+    RecurrentOutput, RmsNorm, Tensor, TensorResult, TransformerBlock, MODEL_FORMAT_VERSION,
+};
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::ramp;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+/// Serializa, vuelve a leer y comprueba que la copia calcula lo mismo y se
+/// serializa igual.
+fn assert_roundtrip(network: &NeuralNetwork, input: Tensor) -> NeuralNetwork {
+    let bytes = network.serialize().unwrap();
+    let copy = NeuralNetwork::deserialize(&bytes).unwrap();
+    assert_eq!(copy.name(), network.name());
+    assert_eq!(copy.layers().len(), network.layers().len());
+    assert_eq!(copy.serialize().unwrap(), bytes);
+    let expected = network.forward(input.clone()).unwrap();
+    let found = copy.forward(input).unwrap();
+    assert_eq!(found.shape(), expected.shape());
+    assert_eq!(found.to_vec(), expected.to_vec());
+    copy
+}
+
+#[test_case]
+fn crc32_matches_reference_value() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+}
+
+#[test_case]
+fn networks_roundtrip_with_identical_outputs() {
+    manual_seed(21);
+    let mut mlp = NeuralNetwork::new("mlp");
+    mlp.add_layer(Layer::new(4, 8, ActivationFunction::Gelu).unwrap()).unwrap();
+    mlp.add_layer(LayerNorm::new(&[8], 1e-5).unwrap()).unwrap();
+    mlp.add_layer(BatchNorm::new(8, 1e-3, 0.2).unwrap()).unwrap();
+    mlp.add_layer(Dropout::new(0.25, 7).unwrap()).unwrap();
+    mlp.add_layer(RmsNorm::new(&[8], 1e-6).unwrap()).unwrap();
+    mlp.add_layer(Layer::new(8, 3, ActivationFunction::Softmax).unwrap()).unwrap();
+    assert_roundtrip(&mlp, ramp(&[2, 4]));
+
+    let mut cnn = NeuralNetwork::new("cnn");
+    let params = Conv2dParams { padding: (1, 1), groups: 2, ..Conv2dParams::default() };
+    cnn.add_layer(Conv2d::new(2, 4, (3, 3), params).unwrap()).unwrap();
+    cnn.add_layer(ActivationFunction::ReLU).unwrap();
+    cnn.add_layer(MaxPool2d(Pool2dParams::new((2, 2)))).unwrap();
+    cnn.add_layer(GlobalAvgPool2d).unwrap();
+    cnn.add_layer(Flatten).unwrap();
+    cnn.add_layer(Layer::new(4, 2, ActivationFunction::Identity).unwrap()).unwrap();
+    assert_roundtrip(&cnn, ramp(&[1, 2, 4, 4]));
+
+    let mut transformer = NeuralNetwork::new("transformer");
+    transformer.add_layer(Embedding::new(10, 8).unwrap()).unwrap();
+    transformer.add_layer(PositionalEncoding).unwrap();
+    let mut block = TransformerBlock::new(8, 2, 16, true).unwrap();
+    block.attention_mut().set_rotary(true);
+    transformer.add_layer(block).unwrap();
+    transformer.add_layer(Lstm::new(8, 4, RecurrentOutput::Sequence).unwrap()).unwrap();
+    transformer.add_layer(Gru::new(4, 3, RecurrentOutput::LastState).unwrap()).unwrap();
+    let ids = Tensor::from_vec(vec![1.0, 4.0, 9.0, 0.0, 2.0, 2.0], &[2, 3]).unwrap();
+    assert_roundtrip(&transformer, ids);
+}
+
+#[test_case]
+fn quantized_and_half_precision_weights_are_kept() {
+    manual_seed(22);
+    let mut network = NeuralNetwork::new("compact");
+    network.add_layer(Layer::new(6, 5, ActivationFunction::Tanh).unwrap()).unwrap();
+    network.add_layer(Layer::new(5, 2, ActivationFunction::Sigmoid).unwrap()).unwrap();
+    let float_size = network.serialize().unwrap().len();
+
+    network.quantize(QuantScheme::Asymmetric).unwrap();
+    let copy = assert_roundtrip(&network, ramp(&[3, 6]));
+    let layer: &dyn core::any::Any = copy.layers()[0].as_ref();
+    match layer.downcast_ref::<Layer>().unwrap().weights() {
+        LayerWeights::Quantized(weights) => assert_eq!(weights.scheme(), QuantScheme::Asymmetric),
+        LayerWeights::Float(_) => panic!("los pesos cuantizados se leen como float"),
+    }
+    assert!(network.serialize().unwrap().len() < float_size);
+
+    let mut half = NeuralNetwork::new("half");
+    half.add_layer(Layer::new(6, 5, ActivationFunction::ReLU).unwrap()).unwrap();
+    half.to_dtype(DType::F16).unwrap();
+    half.add_layer(Layer::new(5, 2, ActivationFunction::Identity).unwrap()).unwrap();
+    let copy = assert_roundtrip(&half, ramp(&[3, 6]));
+    assert_eq!(copy.parameters()[0].dtype(), DType::F16);
+    assert_eq!(copy.parameters()[2].dtype(), DType::F32);
+}
+
+// Módulo propio, que el formato no sabe describir
+struct Doubler;
+
+impl Module for Doubler {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        Ok(input.mul_scalar(2.0).unwrap())
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        Vec::new()
+    }
+
+    fn name(&self) -> &str {
+        "Doubler"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        Ok(input.to_vec())
+    }
+}
+
+#[test_case]
+fn damaged_files_are_rejected_with_descriptive_errors() {
+    manual_seed(23);
+    let mut network = NeuralNetwork::new("xor");
+    network.add_layer(Layer::new(2, 4, ActivationFunction::ReLU).unwrap()).unwrap();
+    network.add_layer(Layer::new(4, 1, ActivationFunction::Sigmoid).unwrap()).unwrap();
+    let bytes = network.serialize().unwrap();
+
+    for len in [0, 5, bytes.len() / 2, bytes.len() - 1] {
+        match NeuralNetwork::deserialize(&bytes[..len]) {
+            Err(ModelFormatError::Truncated { found, .. }) => assert_eq!(found, len),
+            other => panic!("truncado a {} bytes: {:?}", len, other.err()),
+        }
+    }
+
+    let mut damaged = bytes.clone();
+    damaged[0] = b'X';
+    assert_eq!(NeuralNetwork::deserialize(&damaged).err(), Some(ModelFormatError::BadMagic));
+
+    let mut damaged = bytes.clone();
+    damaged[4..6].copy_from_slice(&(MODEL_FORMAT_VERSION + 1).to_le_bytes());
+    assert_eq!(
+        NeuralNetwork::deserialize(&damaged).err(),
+        Some(ModelFormatError::UnsupportedVersion { found: MODEL_FORMAT_VERSION + 1, supported: MODEL_FORMAT_VERSION })
+    );
+
+    let mut damaged = bytes.clone();
+    let middle = damaged.len() / 2;
+    damaged[middle] ^= 0x10;
+    let error = NeuralNetwork::deserialize(&damaged).err().unwrap();
+    assert!(matches!(error, ModelFormatError::ChecksumMismatch { .. }));
+    assert!(alloc::format!("{}", error).starts_with("El CRC32 del modelo no coincide"));
+
+    network.add_layer(Doubler).unwrap();
+    assert_eq!(
+        network.serialize().err(),
+        Some(ModelFormatError::UnsupportedLayer("Doubler".into()))
+    );
+}
@@ -0,0 +1,132 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::vec;
+use rustai_os::ai::{
+    ActivationFunction, GraphModel, InferenceEngine, Layer, Module, NeuralNetwork, Tensor,
+};
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::assert_close;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+// Capa densa `n -> n` que multiplica por `factor`
+fn scaled_identity(n: usize, factor: f32) -> Layer {
+    let mut weights = Tensor::zeros(&[n, n]).unwrap();
+    for i in 0..n {
+        weights.set(&[i, i], factor).unwrap();
+    }
+    Layer::from_parameters(weights, Tensor::zeros(&[n]).unwrap(), ActivationFunction::Identity).unwrap()
+}
+
+fn sample() -> Tensor {
+    Tensor::from_vec(vec![1.0, -2.0, 3.0, 0.5, 0.0, -1.0], &[2, 3]).unwrap()
+}
+
+#[test_case]
+fn residual_and_concat_merges() {
+    let mut graph = GraphModel::new("residual");
+    graph.add_input("x").unwrap();
+    graph.add_module("double", scaled_identity(3, 2.0), "x").unwrap();
+    graph.add_sum("residual", &["x", "double"]).unwrap();
+    graph.add_module("relu", ActivationFunction::ReLU, "x").unwrap();
+    graph.add_concat("merged", &["residual", "relu"], -1).unwrap();
+    graph.set_outputs(&["merged", "residual"]);
+    graph.build().unwrap();
+
+    let order = graph.execution_order();
+    let position = |name| order.iter().position(|&n| n == name).unwrap();
+    assert!(position("x") < position("double"));
+    assert!(position("double") < position("residual"));
+    assert!(position("relu") < position("merged"));
+
+    let outputs = graph.forward_many(vec![sample()]).unwrap();
+    assert_eq!(outputs.len(), 2);
+    assert_eq!(outputs[0].shape(), &[2, 6]);
+    assert_close(&outputs[0], &[3.0, -6.0, 9.0, 1.0, 0.0, 3.0, 1.5, 0.0, -3.0, 0.5, 0.0, 0.0], 1e-6);
+    assert_close(&outputs[1], &[3.0, -6.0, 9.0, 1.5, 0.0, -3.0], 1e-6);
+    assert_eq!(graph.output_shapes(&[&[2, 3]]).unwrap(), vec![vec![2, 6], vec![2, 3]]);
+    assert!(graph.output_shapes(&[&[2, 4]]).is_err());
+}
+
+#[test_case]
+fn multiple_inputs_forward_edges_and_validation() {
+    let mut graph = GraphModel::new("two-inputs");
+    // Los nodos pueden nombrar entradas que se añaden después
+    graph.add_sum("sum", &["a", "scaled"]).unwrap();
+    graph.add_module("scaled", scaled_identity(3, -1.0), "b").unwrap();
+    graph.add_module("unused", ActivationFunction::Tanh, "a").unwrap();
+    graph.add_input("a").unwrap();
+    graph.add_input("b").unwrap();
+    graph.set_outputs(&["sum"]);
+    assert!(graph.forward_many(vec![sample(), sample()]).is_err());
+    graph.build().unwrap();
+
+    // Los nodos de los que no depende ninguna salida no se ejecutan
+    assert!(!graph.execution_order().contains(&"unused"));
+    let b = Tensor::ones(&[2, 3]).unwrap();
+    let out = graph.forward_many(vec![sample(), b]).unwrap();
+    assert_close(&out[0], &[0.0, -3.0, 2.0, -0.5, -1.0, -2.0], 1e-6);
+    assert!(graph.forward_many(vec![sample()]).is_err());
+
+    assert!(graph.add_input("a").is_err());
+    assert!(graph.add_sum("alone", &["a"]).is_err());
+    graph.add_module("loop1", ActivationFunction::ReLU, "loop2").unwrap();
+    graph.add_module("loop2", ActivationFunction::ReLU, "loop1").unwrap();
+    graph.set_outputs(&["loop2"]);
+    assert!(graph.build().is_err());
+    graph.set_outputs(&["missing"]);
+    assert!(graph.build().is_err());
+    assert!(!graph.is_built());
+}
+
+#[test_case]
+fn graph_runs_as_a_module() {
+    let mut graph = GraphModel::new("block");
+    graph.add_input("x").unwrap();
+    graph.add_module("dense", scaled_identity(3, 0.5), "x").unwrap();
+    graph.add_sum("out", &["x", "dense"]).unwrap();
+    graph.set_outputs(&["out"]);
+    graph.build().unwrap();
+    assert_eq!(graph.parameters().len(), 2);
+    assert_eq!(graph.output_shape(&[2, 3]).unwrap(), vec![2, 3]);
+
+    let mut network = NeuralNetwork::new("graph");
+    network.add_layer(graph).unwrap();
+    network.add_layer(ActivationFunction::ReLU).unwrap();
+    let mut engine = InferenceEngine::new();
+    engine.load_model(network);
+    let out = engine.predict(sample()).unwrap();
+    assert_close(&out, &[1.5, 0.0, 4.5, 0.75, 0.0, 0.0], 1e-6);
+}
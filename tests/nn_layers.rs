@@ -0,0 +1,433 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::vec;
+use rustai_os::ai::{
+    apply_rotary, manual_seed, scaled_dot_product_attention, sinusoidal_positions,
+    ActivationFunction, BatchNorm, Conv2d, Conv2dParams, Dropout, Embedding, Flatten,
+    GlobalAvgPool2d, Gru, InferenceEngine, Initializer, Layer, LayerNorm, LayerWeights, Lstm,
+    MaxPool2d, Module, MultiHeadAttention, NetworkState, NeuralNetwork, Pool2dParams,
+    RecurrentOutput, RmsNorm, Rng, Tensor, TensorResult, TransformerBlock,
+};
+use alloc::vec::Vec;
+use rustai_os::math;
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::assert_close;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+// Dos muestras con la misma forma y distinta escala
+fn batch() -> Tensor {
+    Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0, 2.0, 4.0, 6.0, 8.0], &[2, 4]).unwrap()
+}
+
+#[test_case]
+fn layer_norm_and_rms_norm_normalize_each_row() {
+    // (x - 2.5) / sqrt(1.25) en ambas filas
+    let expected = [-1.341_64, -0.447_21, 0.447_21, 1.341_64];
+    let out = LayerNorm::new(&[4], 1e-6).unwrap().forward(batch()).unwrap();
+    assert_eq!(out.shape(), &[2, 4]);
+    assert_close(&out.narrow(0, 0, 1).unwrap(), &expected, 1e-4);
+    assert_close(&out.narrow(0, 1, 1).unwrap(), &expected, 1e-4);
+
+    // Fila 0: x / sqrt(30 / 4)
+    let rms = RmsNorm::new(&[4], 1e-6).unwrap().forward(batch()).unwrap();
+    assert_close(&rms.narrow(0, 0, 1).unwrap(), &[0.365_15, 0.730_30, 1.095_45, 1.460_59], 1e-4);
+    assert!(LayerNorm::new(&[3], 1e-6).unwrap().forward(batch()).is_err());
+}
+
+#[test_case]
+fn norm_parameters_are_validated() {
+    let gamma = Tensor::ones(&[4]).unwrap();
+    assert!(RmsNorm::from_parameters(gamma.clone(), 1e-6).is_ok());
+    assert!(RmsNorm::from_parameters(gamma.clone(), 0.0).is_err());
+    assert!(RmsNorm::from_parameters(gamma.clone(), f32::NAN).is_err());
+    assert!(RmsNorm::from_parameters(Tensor::ones(&[]).unwrap(), 1e-6).is_err());
+    assert!(RmsNorm::from_parameters(Tensor::ones(&[0]).unwrap(), 1e-6).is_err());
+
+    assert!(LayerNorm::from_parameters(gamma.clone(), Tensor::zeros(&[3]).unwrap(), 1e-6).is_err());
+    assert!(LayerNorm::from_parameters(gamma.clone(), Tensor::zeros(&[4]).unwrap(), -1.0).is_err());
+    assert!(LayerNorm::new(&[4], 0.0).is_err());
+    assert!(RmsNorm::new(&[], 1e-6).is_err());
+}
+
+#[test_case]
+fn batch_norm_uses_batch_stats_only_in_training() {
+    let mut norm = BatchNorm::new(4, 1e-6, 0.1).unwrap();
+    // Estadísticas iniciales: media 0, varianza 1
+    assert_close(&norm.forward(batch()).unwrap(), &batch().to_vec(), 1e-4);
+
+    norm.set_training(true);
+    let out = norm.forward(batch()).unwrap();
+    // Cada canal tiene dos valores: quedan en -1 y 1
+    assert_close(&out, &[-1.0, -1.0, -1.0, -1.0, 1.0, 1.0, 1.0, 1.0], 1e-3);
+    // Media del canal 0 = 1.5, varianza insesgada = 0.5
+    assert_close(&norm.running_mean(), &[0.15, 0.3, 0.45, 0.6], 1e-6);
+    assert_close(&norm.running_var(), &[0.95, 1.1, 1.35, 1.7], 1e-6);
+}
+
+#[test_case]
+fn dropout_is_identity_in_eval_and_seeded_in_training() {
+    let mut dropout = Dropout::new(0.5, 42).unwrap();
+    assert_close(&dropout.forward(batch()).unwrap(), &batch().to_vec(), 0.0);
+
+    dropout.set_training(true);
+    let out = dropout.forward(Tensor::ones(&[1000]).unwrap()).unwrap();
+    let kept = out.iter().filter(|&x| x > 0.0).count();
+    assert!(kept > 400 && kept < 600, "{}", kept);
+    // Los elementos que se conservan se escalan por 1 / (1 - p)
+    assert!(out.iter().all(|x| x == 0.0 || x == 2.0));
+
+    // Misma semilla, misma máscara
+    let mut again = Dropout::new(0.5, 42).unwrap();
+    again.set_training(true);
+    assert_close(&again.forward(Tensor::ones(&[1000]).unwrap()).unwrap(), &out.to_vec(), 0.0);
+    assert!(Dropout::new(1.0, 0).is_err());
+}
+
+#[test_case]
+fn network_propagates_training_mode() {
+    let mut network = NeuralNetwork::new("norm");
+    network.add_layer(Layer::new(4, 4, ActivationFunction::Tanh).unwrap()).unwrap();
+    network.add_layer(Dropout::new(0.9, 1).unwrap()).unwrap();
+    network.add_layer(LayerNorm::new(&[4], 1e-5).unwrap()).unwrap();
+    assert!(!network.is_training());
+    let eval = network.forward(batch()).unwrap();
+    network.set_training(true);
+    let train = network.forward(batch()).unwrap();
+    network.set_training(false);
+    assert_close(&network.forward(batch()).unwrap(), &eval.to_vec(), 0.0);
+    assert_eq!(train.shape(), &[2, 4]);
+}
+
+#[test_case]
+fn gelu_and_silu_match_reference_values() {
+    let x = Tensor::from_vec(vec![-2.0, -1.0, 0.0, 1.0, 3.0], &[5]).unwrap();
+    assert_close(&x.gelu().unwrap(), &[-0.045_40, -0.158_81, 0.0, 0.841_19, 2.996_36], 1e-5);
+    assert_close(&x.silu().unwrap(), &[-0.238_41, -0.268_94, 0.0, 0.731_06, 2.857_72], 1e-5);
+    assert_close(&ActivationFunction::Identity.apply(&x).unwrap(), &x.to_vec(), 0.0);
+}
+
+#[test_case]
+fn embedding_and_positional_encodings() {
+    let weight = Tensor::from_vec(vec![0.0, 1.0, 10.0, 11.0, 20.0, 21.0], &[3, 2]).unwrap();
+    let embedding = Embedding::from_parameters(weight).unwrap();
+    let ids = Tensor::from_vec(vec![2.0, 0.0, 1.0, 1.0], &[2, 2]).unwrap();
+    let out = embedding.forward(ids).unwrap();
+    assert_eq!(out.shape(), &[2, 2, 2]);
+    assert_close(&out, &[20.0, 21.0, 0.0, 1.0, 10.0, 11.0, 10.0, 11.0], 0.0);
+    assert!(embedding.forward(Tensor::from_vec(vec![3.0], &[1]).unwrap()).is_err());
+    assert!(embedding.forward(Tensor::from_vec(vec![0.5], &[1]).unwrap()).is_err());
+
+    // ω = 1 y 1/100 para dim = 4
+    let pe = sinusoidal_positions(2, 4).unwrap();
+    assert_close(&pe, &[0.0, 1.0, 0.0, 1.0, 0.841_47, 0.540_30, 0.010_00, 0.999_95], 1e-5);
+
+    // RoPE conserva la norma y el producto de consulta y clave solo
+    // depende de la distancia entre sus posiciones
+    let q = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], &[1, 4]).unwrap();
+    let k = Tensor::from_vec(vec![0.5, -1.0, 2.0, 1.0], &[1, 4]).unwrap();
+    let norm = |t: &Tensor| t.mul(t).unwrap().sum_all();
+    assert!((norm(&apply_rotary(&q, 7).unwrap()) - norm(&q)).abs() < 1e-4);
+    let score = |m, n| apply_rotary(&q, m).unwrap().mul(&apply_rotary(&k, n).unwrap()).unwrap().sum_all();
+    assert!((score(5, 2) - score(13, 10)).abs() < 1e-4);
+    assert!((score(0, 0) - q.mul(&k).unwrap().sum_all()).abs() < 1e-6);
+}
+
+#[test_case]
+fn causal_attention_ignores_future_positions() {
+    // Con consultas nulas los pesos son uniformes sobre las claves visibles
+    let q = Tensor::zeros(&[3, 2]).unwrap();
+    let k = Tensor::from_vec(vec![1.0, 0.0, 0.0, 1.0, 1.0, 1.0], &[3, 2]).unwrap();
+    let v = Tensor::from_vec(vec![3.0, 0.0, 6.0, 3.0, 0.0, 9.0], &[3, 2]).unwrap();
+    let full = scaled_dot_product_attention(&q, &k, &v, false).unwrap();
+    assert_close(&full, &[3.0, 4.0, 3.0, 4.0, 3.0, 4.0], 1e-5);
+    let causal = scaled_dot_product_attention(&q, &k, &v, true).unwrap();
+    assert_close(&causal, &[3.0, 0.0, 4.5, 1.5, 3.0, 4.0], 1e-5);
+
+    // Una sola consulta (la última posición) ve todas las claves
+    let last = scaled_dot_product_attention(&q.narrow(0, 2, 1).unwrap(), &k, &v, true).unwrap();
+    assert_close(&last, &[3.0, 4.0], 1e-5);
+    assert!(scaled_dot_product_attention(&k, &q.narrow(0, 0, 2).unwrap(), &v, true).is_err());
+}
+
+#[test_case]
+fn transformer_block_is_residual_and_causal() {
+    // Con los pesos a cero, atención y feed-forward aportan 0 y el bloque
+    // deja pasar la entrada por las conexiones residuales
+    let x = Tensor::from_vec((0..24).map(|i| i as f32 * 0.1).collect(), &[2, 3, 4]).unwrap();
+    let zeros = |input, output| {
+        let mut rng = Rng::new(0);
+        Layer::with_initializer(input, output, ActivationFunction::Identity, Initializer::Zeros, &mut rng).unwrap()
+    };
+    let attention =
+        MultiHeadAttention::from_parameters(zeros(4, 4), zeros(4, 4), zeros(4, 4), zeros(4, 4), 2, true)
+            .unwrap();
+    let norm = || LayerNorm::new(&[4], 1e-5).unwrap();
+    let block = TransformerBlock::from_parameters(norm(), attention, norm(), zeros(4, 8), zeros(8, 4)).unwrap();
+    assert_close(&block.forward(x.clone()).unwrap(), &x.to_vec(), 1e-6);
+    assert!(TransformerBlock::new(4, 3, 8, true).is_err());
+
+    let identity = || {
+        let mut eye = Tensor::zeros(&[4, 4]).unwrap();
+        for i in 0..4 {
+            eye.set(&[i, i], 1.0).unwrap();
+        }
+        Layer::from_parameters(eye, Tensor::zeros(&[4]).unwrap(), ActivationFunction::Identity).unwrap()
+    };
+    let mut attention =
+        MultiHeadAttention::from_parameters(identity(), identity(), identity(), identity(), 2, true)
+            .unwrap();
+    attention.set_rotary(true);
+    let mut network = NeuralNetwork::new("transformer");
+    network.add_layer(attention).unwrap();
+    let out = network.forward(x.clone()).unwrap();
+    assert_eq!(out.shape(), &[2, 3, 4]);
+    // La primera posición solo se atiende a sí misma
+    assert_close(&out.narrow(1, 0, 1).unwrap(), &x.narrow(1, 0, 1).unwrap().to_vec(), 1e-5);
+    // Cambiar la última posición no altera las anteriores
+    let changed = Tensor::concat(&[&x.narrow(1, 0, 2).unwrap(), &Tensor::ones(&[2, 1, 4]).unwrap()], 1).unwrap();
+    let again = network.forward(changed).unwrap();
+    assert_close(&again.narrow(1, 0, 2).unwrap(), &out.narrow(1, 0, 2).unwrap().to_vec(), 1e-6);
+}
+
+#[test_case]
+fn initializers_are_seeded_and_scaled() {
+    // Misma semilla, mismos pesos
+    let sample = |init: Initializer, shape: &[usize], seed| {
+        init.tensor(shape, shape[0], shape[1], &mut Rng::new(seed)).unwrap()
+    };
+    let a = sample(Initializer::XavierUniform, &[100, 50], 7);
+    assert_close(&sample(Initializer::XavierUniform, &[100, 50], 7), &a.to_vec(), 0.0);
+    assert!(sample(Initializer::XavierUniform, &[100, 50], 8).to_vec() != a.to_vec());
+
+    // Xavier uniforme: U(-a, a) con a = sqrt(6 / 150) = 0.2
+    let max = a.iter().fold(0.0f32, |max, x| max.max(x.abs()));
+    assert!(max <= 0.2 && max > 0.19, "{}", max);
+
+    // He normal: media 0 y varianza 2 / fan_in
+    let he = sample(Initializer::HeNormal, &[256, 128], 1);
+    let mean = he.sum_all() / he.len() as f32;
+    let variance = he.iter().map(|x| (x - mean) * (x - mean)).sum::<f32>() / he.len() as f32;
+    assert!(mean.abs() < 0.002, "{}", mean);
+    assert!((variance * 128.0 - 1.0).abs() < 0.05, "{}", variance);
+
+    let normal = sample(Initializer::Normal { mean: 3.0, std: 0.5 }, &[100, 100], 2);
+    assert!((normal.sum_all() / 10_000.0 - 3.0).abs() < 0.02);
+    let uniform = sample(Initializer::Uniform { low: 1.0, high: 2.0 }, &[10, 10], 3);
+    assert!(uniform.iter().all(|x| (1.0..2.0).contains(&x)));
+
+    // Las capas se inicializan desde el generador global
+    let weights = |layer: &Layer| match layer.weights() {
+        LayerWeights::Float(weights) => weights.to_vec(),
+        LayerWeights::Quantized(_) => unreachable!(),
+    };
+    manual_seed(42);
+    let layer = Layer::new(16, 8, ActivationFunction::ReLU).unwrap();
+    assert!(weights(&layer).iter().any(|&w| w != 0.0));
+    assert_close(layer.bias(), &[0.0; 8], 0.0);
+    assert!(weights(&Layer::new(16, 8, ActivationFunction::ReLU).unwrap()) != weights(&layer));
+    manual_seed(42);
+    assert_eq!(weights(&Layer::new(16, 8, ActivationFunction::ReLU).unwrap()), weights(&layer));
+}
+
+fn vector(values: &[f32]) -> Tensor {
+    Tensor::from_vec(values.to_vec(), &[values.len()]).unwrap()
+}
+
+// Pesos deterministas en [-0.5, 0.5] para las pruebas de flujo
+fn pseudo_weights(shape: &[usize], seed: usize) -> Tensor {
+    let len = shape.iter().product::<usize>();
+    let values = (0..len).map(|i| ((i * 7 + seed) % 11) as f32 / 10.0 - 0.5).collect();
+    Tensor::from_vec(values, shape).unwrap()
+}
+
+#[test_case]
+fn lstm_and_gru_match_scalar_recurrence() {
+    let xs = [1.0, -0.5, 2.0];
+    let input = Tensor::from_vec(xs.to_vec(), &[1, 3, 1]).unwrap();
+
+    let lstm = Lstm::from_parameters(
+        Tensor::from_vec(vec![0.5, -0.3, 0.8, 0.2], &[1, 4]).unwrap(),
+        Tensor::from_vec(vec![0.1, 0.4, -0.6, 0.3], &[1, 4]).unwrap(),
+        vector(&[0.0, 1.0, 0.1, -0.2]),
+        RecurrentOutput::Sequence,
+    ).unwrap();
+    let (mut h, mut c) = (0.0, 0.0);
+    let mut expected = [0.0; 3];
+    for (x, e) in xs.iter().zip(&mut expected) {
+        let i = math::sigmoid(0.5 * x + 0.1 * h);
+        let f = math::sigmoid(-0.3 * x + 0.4 * h + 1.0);
+        let g = math::tanh(0.8 * x - 0.6 * h + 0.1);
+        let o = math::sigmoid(0.2 * x + 0.3 * h - 0.2);
+        c = f * c + i * g;
+        h = o * math::tanh(c);
+        *e = h;
+    }
+    let out = lstm.forward(input.clone()).unwrap();
+    assert_eq!(out.shape(), &[1, 3, 1]);
+    assert_close(&out, &expected, 1e-6);
+
+    let gru = Gru::from_parameters(
+        Tensor::from_vec(vec![0.3, -0.7, 0.9], &[1, 3]).unwrap(),
+        Tensor::from_vec(vec![0.5, 0.2, -0.4], &[1, 3]).unwrap(),
+        vector(&[0.1, 0.0, -0.1]),
+        vector(&[0.0, 0.3, 0.2]),
+        RecurrentOutput::LastState,
+    ).unwrap();
+    let mut h = 0.0;
+    for x in xs {
+        let r = math::sigmoid(0.3 * x + 0.1 + 0.5 * h);
+        let z = math::sigmoid(-0.7 * x + 0.2 * h + 0.3);
+        let n = math::tanh(0.9 * x - 0.1 + r * (-0.4 * h + 0.2));
+        h = (1.0 - z) * n + z * h;
+    }
+    let out = gru.forward(input).unwrap();
+    assert_eq!(out.shape(), &[1, 1]);
+    assert_close(&out, &[h], 1e-6);
+
+    let bad_recurrent = Tensor::zeros(&[1, 3]).unwrap();
+    let bias = vector(&[0.0; 4]);
+    assert!(Lstm::from_parameters(Tensor::zeros(&[1, 4]).unwrap(), bad_recurrent, bias, RecurrentOutput::Sequence).is_err());
+    assert!(lstm.forward(Tensor::zeros(&[1, 3]).unwrap()).is_err());
+}
+
+#[test_case]
+fn recurrent_state_streams_across_calls() {
+    let lstm = Lstm::from_parameters(
+        pseudo_weights(&[3, 8], 1),
+        pseudo_weights(&[2, 8], 2),
+        pseudo_weights(&[8], 3),
+        RecurrentOutput::Sequence,
+    ).unwrap();
+    let gru = Gru::from_parameters(
+        pseudo_weights(&[2, 6], 4),
+        pseudo_weights(&[2, 6], 5),
+        pseudo_weights(&[6], 6),
+        pseudo_weights(&[6], 7),
+        RecurrentOutput::LastState,
+    ).unwrap();
+    let mut network = NeuralNetwork::new("sensores");
+    network.add_layer(lstm).unwrap();
+    network.add_layer(gru).unwrap();
+
+    // [batch = 2, seq = 5, features = 3]
+    let sequence = pseudo_weights(&[2, 5, 3], 8);
+    let whole = network.forward(sequence.clone()).unwrap();
+    assert_eq!(whole.shape(), &[2, 2]);
+
+    // Procesar por trozos con estado da el mismo resultado final
+    let mut state = NetworkState::new();
+    network.forward_stateful(sequence.narrow(1, 0, 2).unwrap(), &mut state).unwrap();
+    let chunked = network.forward_stateful(sequence.narrow(1, 2, 3).unwrap(), &mut state).unwrap();
+    assert_close(&chunked, &whole.to_vec(), 1e-6);
+    assert_eq!(state.layer(0).unwrap().hidden().shape(), &[2, 2]);
+    assert!(state.layer(0).unwrap().cell().is_some());
+    assert!(state.layer(1).unwrap().cell().is_none());
+
+    // Un lote de otro tamaño no encaja con el estado guardado
+    assert!(network.forward_stateful(pseudo_weights(&[1, 1, 3], 9), &mut state).is_err());
+
+    // Cada sesión del motor lleva su propio estado
+    let mut engine = InferenceEngine::new();
+    engine.load_model(network);
+    let a = engine.open_session().unwrap();
+    let b = engine.open_session().unwrap();
+    engine.predict_session(a, sequence.narrow(1, 0, 2).unwrap()).unwrap();
+    engine.predict_session(b, sequence.narrow(1, 0, 4).unwrap()).unwrap();
+    let from_a = engine.predict_session(a, sequence.narrow(1, 2, 3).unwrap()).unwrap();
+    assert_close(&from_a, &whole.to_vec(), 1e-6);
+
+    engine.reset_session(b).unwrap();
+    assert_close(&engine.predict_session(b, sequence.clone()).unwrap(), &whole.to_vec(), 1e-6);
+    assert_close(&engine.predict(sequence).unwrap(), &whole.to_vec(), 1e-6);
+    engine.close_session(a).unwrap();
+    assert!(engine.predict_session(a, Tensor::zeros(&[2, 1, 3]).unwrap()).is_err());
+}
+
+// Módulo definido fuera de `ai`: multiplica por un factor entrenable
+struct Scale {
+    factor: Tensor,
+}
+
+impl Module for Scale {
+    fn forward(&self, input: Tensor) -> TensorResult<Tensor> {
+        input.mul(&self.factor)
+    }
+
+    fn parameters(&self) -> Vec<&Tensor> {
+        vec![&self.factor]
+    }
+
+    fn name(&self) -> &str {
+        "Scale"
+    }
+
+    fn output_shape(&self, input: &[usize]) -> TensorResult<Vec<usize>> {
+        Ok(input.to_vec())
+    }
+}
+
+#[test_case]
+fn network_composes_heterogeneous_modules() {
+    let mut network = NeuralNetwork::new("cnn");
+    network.add_layer(Conv2d::new(1, 4, (3, 3), Conv2dParams { padding: (1, 1), ..Conv2dParams::default() }).unwrap()).unwrap();
+    network.add_layer(ActivationFunction::ReLU).unwrap();
+    network.add_layer(MaxPool2d(Pool2dParams::new((2, 2)))).unwrap();
+    network.add_layer(GlobalAvgPool2d).unwrap();
+    network.add_layer(Flatten).unwrap();
+    network.add_layer(Layer::new(4, 3, ActivationFunction::Softmax).unwrap()).unwrap();
+    network.add_layer(Scale { factor: Tensor::full(&[3], 2.0).unwrap() }).unwrap();
+
+    let names: Vec<&str> = network.layers().iter().map(|layer| layer.name()).collect();
+    assert_eq!(names, ["Conv2d", "ReLU", "MaxPool2d", "GlobalAvgPool2d", "Flatten", "Dense", "Scale"]);
+    // Pesos y sesgo de Conv2d y Dense, más el factor de Scale
+    assert_eq!(network.parameters().len(), 5);
+    assert_eq!(network.parameter_bytes(), (36 + 4 + 12 + 3 + 3) * 4);
+
+    assert_eq!(network.output_shape(&[2, 1, 8, 8]).unwrap(), vec![2, 3]);
+    assert!(network.output_shape(&[2, 3, 8, 8]).is_err());
+    let out = network.forward(Tensor::ones(&[2, 1, 8, 8]).unwrap()).unwrap();
+    assert_eq!(out.shape(), &[2, 3]);
+    // Cada fila es una distribución de softmax escalada por 2
+    for row in 0..2 {
+        let probabilities = out.narrow(0, row, 1).unwrap();
+        assert!(probabilities.iter().all(|p| p > 0.0));
+        assert!((probabilities.sum_all() - 2.0).abs() < 1e-5);
+    }
+
+    let recurrent = Lstm::new(3, 5, RecurrentOutput::LastState).unwrap();
+    assert_eq!(recurrent.output_shape(&[4, 7, 3]).unwrap(), vec![4, 5]);
+    assert_eq!(Embedding::new(10, 8).unwrap().output_shape(&[2, 6]).unwrap(), vec![2, 6, 8]);
+}
@@ -0,0 +1,270 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use rustai_os::ai::{base64_decode, crc32, dump_lines, dump_npy, DType, NpyError, NpzArchive, Tensor};
+use rustai_os::api::{ApiSubsystem, HttpServer};
+use rustai_os::{allocator, memory, test_panic_handler};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+/// `.npy` de versión 1.0 con la cabecera `header` (sin relleno) y `data`.
+fn npy(header: &str, data: &[u8]) -> Vec<u8> {
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u16 + 1).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.push(b'\n');
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+/// Zip con las entradas dadas tal cual, declaradas con el método `method`
+/// (0 = sin comprimir).
+fn zip(entries: &[(&str, Vec<u8>)], method: u16) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in entries {
+        let offset = bytes.len() as u32;
+        let sizes = [crc32(data), data.len() as u32, data.len() as u32];
+        bytes.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        bytes.extend_from_slice(&[20, 0, 0, 0]);
+        bytes.extend_from_slice(&method.to_le_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        sizes.iter().for_each(|value| bytes.extend_from_slice(&value.to_le_bytes()));
+        bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&[0; 2]);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(data);
+
+        directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        directory.extend_from_slice(&[20, 0, 20, 0, 0, 0]);
+        directory.extend_from_slice(&method.to_le_bytes());
+        directory.extend_from_slice(&[0; 4]);
+        sizes.iter().for_each(|value| directory.extend_from_slice(&value.to_le_bytes()));
+        directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        directory.extend_from_slice(&[0; 12]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+    let start = bytes.len() as u32;
+    bytes.extend_from_slice(&directory);
+    bytes.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&start.to_le_bytes());
+    bytes.extend_from_slice(&[0; 2]);
+    bytes
+}
+
+#[test_case]
+fn written_files_have_numpy_headers_and_roundtrip() {
+    let tensor = Tensor::from_vec(vec![1.0, -2.0, 3.5, 0.0, 0.25, 6.0], &[2, 3]).unwrap();
+    let bytes = tensor.to_npy().unwrap();
+    assert!(bytes.starts_with(b"\x93NUMPY\x01\x00"));
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    assert!((10 + header_len).is_multiple_of(64));
+    let header = core::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+    assert!(header.starts_with("{'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }"));
+    assert!(header.ends_with(" \n"));
+    assert_eq!(bytes.len(), 10 + header_len + 6 * 4);
+    assert_eq!(Tensor::from_npy(&bytes).unwrap().to_vec(), tensor.to_vec());
+
+    // Una vista traspuesta se escribe en su orden lógico
+    let transposed = tensor.t().unwrap();
+    let copy = Tensor::from_npy(&transposed.to_npy().unwrap()).unwrap();
+    assert_eq!(copy.shape(), &[3, 2]);
+    assert_eq!(copy.to_vec(), transposed.to_vec());
+
+    let half = tensor.to_dtype(DType::F16).unwrap();
+    let copy = Tensor::from_npy(&half.to_npy().unwrap()).unwrap();
+    assert_eq!((copy.dtype(), copy.to_vec()), (DType::F16, half.to_vec()));
+    let brain = tensor.to_dtype(DType::BF16).unwrap();
+    let copy = Tensor::from_npy(&brain.to_npy().unwrap()).unwrap();
+    assert_eq!((copy.dtype(), copy.to_vec()), (DType::F32, brain.to_vec()));
+
+    for (tensor, shape) in [(Tensor::scalar(7.0), "()"), (Tensor::zeros(&[3]).unwrap(), "(3,)")] {
+        let bytes = tensor.to_npy().unwrap();
+        let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        let header = core::str::from_utf8(&bytes[10..10 + header_len]).unwrap();
+        assert!(header.contains(&format!("'shape': {}, }}", shape)), "{}", header);
+        assert_eq!(Tensor::from_npy(&bytes).unwrap().shape(), tensor.shape());
+    }
+    dump_npy("roundtrip", &tensor).unwrap();
+}
+
+#[test_case]
+fn dump_lines_are_base64_split_every_57_bytes() {
+    // Vectores de la RFC 4648, con colas de 1 y 2 bytes
+    for (input, encoded) in [
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ] {
+        let begin = format!("NPY-BEGIN v {} {:08x}", input.len(), crc32(input.as_bytes()));
+        let lines = dump_lines("v", input.as_bytes()).unwrap();
+        assert_eq!(lines, [begin, String::from(encoded), String::from("NPY-END v")]);
+    }
+    assert_eq!(dump_lines("v", &[]).unwrap(), ["NPY-BEGIN v 0 00000000", "NPY-END v"]);
+
+    // 57 bytes llenan una línea de 76 caracteres; el siguiente abre otra
+    let bytes: Vec<u8> = (0..58).collect();
+    let lines = dump_lines("t", &bytes).unwrap();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[1], "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4vMDEyMzQ1Njc4");
+    assert_eq!(lines[2], "OQ==");
+
+    let bytes: Vec<u8> = (0..114).collect();
+    let lines = dump_lines("t", &bytes).unwrap();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[2], "OTo7PD0+P0BBQkNERUZHSElKS0xNTk9QUVJTVFVWV1hZWltcXV5fYGFiY2RlZmdoaWprbG1ub3Bx");
+}
+
+#[test_case]
+fn base64_decode_reads_dump_lines() {
+    for (decoded, input) in [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foobar", "Zm9v\r\nYmFy\n")] {
+        assert_eq!(base64_decode(input.as_bytes()).unwrap(), decoded.as_bytes());
+    }
+    for input in ["Zg=", "Z===", "Zg==Zg==", "Zm9v!", "Zg=a"] {
+        assert_eq!(base64_decode(input.as_bytes()), Err(NpyError::InvalidBase64), "{}", input);
+    }
+
+    // Las líneas de datos de un volcado, unidas, son el `.npy` original
+    let bytes: Vec<u8> = (0..=255).collect();
+    let lines = dump_lines("t", &bytes).unwrap();
+    let text = lines[1..lines.len() - 1].join("\n");
+    assert_eq!(base64_decode(text.as_bytes()).unwrap(), bytes);
+}
+
+#[test_case]
+fn npy_endpoint_returns_the_parsed_tensor() {
+    let mut api = ApiSubsystem::new();
+    api.initialize();
+    let post = |body: &str| {
+        let raw = format!("POST /ai/npy HTTP/1.1\r\nContent-Type: text/plain\r\n\r\n{}", body);
+        HttpServer::format_response(&api.handle_request(HttpServer::parse_request(raw.as_bytes()).unwrap()))
+    };
+
+    // Un array en orden Fortran vuelve en orden C
+    let data: Vec<u8> = [0.0f32, 3.0, 1.0, 4.0, 2.0, 5.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let fortran = npy("{'descr': '<f4', 'fortran_order': True, 'shape': (2, 3), }", &data);
+    let lines = dump_lines("t", &fortran).unwrap();
+    let response = post(&lines[1..lines.len() - 1].join("\r\n"));
+    let expected = Tensor::from_vec(vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0], &[2, 3]).unwrap().to_npy().unwrap();
+    assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert!(response.ends_with(&expected));
+
+    assert!(post("no es base64").starts_with(b"HTTP/1.1 400 "));
+    assert!(post("AAAA").starts_with(b"HTTP/1.1 400 "));
+}
+
+#[test_case]
+fn reads_byte_orders_integer_types_and_fortran_order() {
+    // np.arange(6, dtype='<f8').reshape(2, 3, order='F'): columnas contiguas
+    let data: Vec<u8> = [0.0f64, 3.0, 1.0, 4.0, 2.0, 5.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let bytes = npy("{'descr': '<f8', 'fortran_order': True, 'shape': (2, 3), }", &data);
+    let tensor = Tensor::from_npy(&bytes).unwrap();
+    assert_eq!(tensor.shape(), &[2, 3]);
+    assert_eq!(tensor.to_vec(), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert!(tensor.is_contiguous());
+
+    let data: Vec<u8> = [-1i32, 70000].iter().flat_map(|v| v.to_be_bytes()).collect();
+    let bytes = npy("{\"descr\": \">i4\", \"fortran_order\": False, \"shape\": (2,)}", &data);
+    assert_eq!(Tensor::from_npy(&bytes).unwrap().to_vec(), vec![-1.0, 70000.0]);
+
+    let bytes = npy("{'shape': (1, 3), 'fortran_order': False, 'descr': '|u1'}", &[0, 128, 255]);
+    assert_eq!(Tensor::from_npy(&bytes).unwrap().to_vec(), vec![0.0, 128.0, 255.0]);
+    let bytes = npy("{'descr': '|b1', 'fortran_order': False, 'shape': (2,), }", &[1, 0]);
+    assert_eq!(Tensor::from_npy(&bytes).unwrap().to_vec(), vec![1.0, 0.0]);
+
+    // Versión 2.0: longitud de la cabecera de 32 bits
+    let header = "{'descr': '<i8', 'fortran_order': False, 'shape': (1,), }\n";
+    let mut bytes = b"\x93NUMPY\x02\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(&(-5i64).to_le_bytes());
+    assert_eq!(Tensor::from_npy(&bytes).unwrap().to_vec(), vec![-5.0]);
+}
+
+#[test_case]
+fn malformed_npy_files_are_rejected() {
+    let valid = Tensor::ones(&[2, 2]).unwrap().to_npy().unwrap();
+    assert_eq!(Tensor::from_npy(b"PK\x03\x04 no es npy").err(), Some(NpyError::BadMagic));
+    assert_eq!(
+        Tensor::from_npy(&valid[..valid.len() - 1]).err(),
+        Some(NpyError::Truncated { expected: valid.len(), found: valid.len() - 1 })
+    );
+    let mut future = valid.clone();
+    future[6] = 4;
+    assert_eq!(Tensor::from_npy(&future).err(), Some(NpyError::UnsupportedVersion { major: 4, minor: 0 }));
+
+    let complex = npy("{'descr': '<c8', 'fortran_order': False, 'shape': (1,), }", &[0; 8]);
+    let error = Tensor::from_npy(&complex).err().unwrap();
+    assert_eq!(error, NpyError::UnsupportedDType(String::from("<c8")));
+    assert_eq!(format!("{}", error), "Tipo de elemento de .npy no soportado: <c8");
+    let structured = npy("{'descr': [('x', '<f4')], 'fortran_order': False, 'shape': (1,), }", &[0; 4]);
+    assert!(matches!(Tensor::from_npy(&structured), Err(NpyError::UnsupportedDType(_))));
+    let missing = npy("{'descr': '<f4', 'shape': (1,), }", &[0; 4]);
+    assert!(matches!(Tensor::from_npy(&missing), Err(NpyError::InvalidHeader(_))));
+}
+
+#[test_case]
+fn stored_npz_archives_are_read_and_checked() {
+    let weights = Tensor::from_vec(vec![0.5, -1.5, 2.0, 4.0], &[2, 2]).unwrap();
+    let bias = Tensor::from_vec(vec![0.1, 0.2], &[2]).unwrap();
+    let entries = [
+        ("weights.npy", weights.to_npy().unwrap()),
+        ("bias.npy", bias.to_npy().unwrap()),
+    ];
+    let bytes = zip(&entries, 0);
+    let archive = NpzArchive::parse(&bytes).unwrap();
+    assert_eq!(archive.len(), 2);
+    assert_eq!(archive.names().collect::<Vec<_>>(), vec!["weights", "bias"]);
+    assert_eq!(archive.tensor("weights").unwrap().to_vec(), weights.to_vec());
+    assert_eq!(archive.tensor("bias").unwrap().shape(), &[2]);
+    assert_eq!(archive.tensor("gamma").err(), Some(NpyError::MissingArray(String::from("gamma"))));
+
+    let mut damaged = bytes.clone();
+    let last = entries[0].1.len() + 30 + "weights.npy".len() - 1;
+    damaged[last] ^= 0x01;
+    assert!(matches!(NpzArchive::parse(&damaged), Err(NpyError::ChecksumMismatch { ref name, .. }) if name == "weights.npy"));
+
+    // Deflate, como `numpy.savez_compressed`
+    assert!(matches!(NpzArchive::parse(&zip(&entries, 8)), Err(NpyError::InvalidArchive(_))));
+    assert!(matches!(NpzArchive::parse(&bytes[..bytes.len() - 4]), Err(NpyError::InvalidArchive(_))));
+}
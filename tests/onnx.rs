@@ -0,0 +1,283 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use rustai_os::ai::{
+    Conv2dParams, GraphModel, InferenceEngine, NeuralNetwork, OnnxError, Pool2dParams, Tensor,
+};
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::{assert_tensor_close, ramp};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+/// Codificador protobuf mínimo para escribir los modelos de las pruebas.
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn varint(mut self, mut value: u64) -> Self {
+        while value >= 0x80 {
+            self.0.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+        self
+    }
+
+    fn int(self, field: u64, value: i64) -> Self {
+        self.varint(field << 3).varint(value as u64)
+    }
+
+    fn float(mut self, field: u64, value: f32) -> Self {
+        self = self.varint(field << 3 | 5);
+        self.0.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn bytes(mut self, field: u64, bytes: &[u8]) -> Self {
+        self = self.varint(field << 3 | 2).varint(bytes.len() as u64);
+        self.0.extend_from_slice(bytes);
+        self
+    }
+
+    fn string(self, field: u64, value: &str) -> Self {
+        self.bytes(field, value.as_bytes())
+    }
+
+    fn message(self, field: u64, message: Message) -> Self {
+        self.bytes(field, &message.0)
+    }
+}
+
+/// Inicializador `FLOAT` con los datos en `raw_data`.
+fn tensor(name: &str, dims: &[usize], values: &[f32]) -> Message {
+    let raw: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+    dims.iter()
+        .fold(Message::default(), |message, &dim| message.int(1, dim as i64))
+        .int(2, 1)
+        .string(8, name)
+        .bytes(9, &raw)
+}
+
+/// Inicializador `FLOAT` con los datos en `float_data` empaquetado.
+fn typed_tensor(name: &str, dims: &[usize], values: &[f32]) -> Message {
+    let packed: Vec<u8> = values.iter().flat_map(|value| value.to_le_bytes()).collect();
+    dims.iter()
+        .fold(Message::default(), |message, &dim| message.int(1, dim as i64))
+        .int(2, 1)
+        .bytes(4, &packed)
+        .string(8, name)
+}
+
+/// Inicializador `INT64` de una dimensión, en `int64_data` empaquetado.
+fn int_tensor(name: &str, values: &[i64]) -> Message {
+    let packed = values.iter().fold(Message::default(), |message, &value| message.varint(value as u64));
+    Message::default().int(1, values.len() as i64).int(2, 7).bytes(7, &packed.0).string(8, name)
+}
+
+fn int_attribute(name: &str, value: i64) -> Message {
+    Message::default().string(1, name).int(3, value).int(20, 2)
+}
+
+fn float_attribute(name: &str, value: f32) -> Message {
+    Message::default().string(1, name).float(2, value).int(20, 1)
+}
+
+fn ints_attribute(name: &str, values: &[i64]) -> Message {
+    let packed = values.iter().fold(Message::default(), |message, &value| message.varint(value as u64));
+    Message::default().string(1, name).bytes(8, &packed.0).int(20, 7)
+}
+
+fn node(op: &str, inputs: &[&str], output: &str, attributes: Vec<Message>) -> Message {
+    let message = inputs.iter().fold(Message::default(), |message, input| message.string(1, input));
+    attributes.into_iter().fold(message.string(2, output).string(4, op), |message, attribute| {
+        message.message(5, attribute)
+    })
+}
+
+/// `ModelProto` con un grafo llamado `name` y el conjunto de operadores
+/// estándar en la versión `opset`.
+fn model(name: &str, opset: i64, nodes: Vec<Message>, initializers: Vec<Message>, inputs: &[&str], outputs: &[&str]) -> Vec<u8> {
+    let value_info = |name: &str| Message::default().string(1, name);
+    let mut graph = Message::default().string(2, name);
+    for node in nodes {
+        graph = graph.message(1, node);
+    }
+    for initializer in initializers {
+        graph = graph.message(5, initializer);
+    }
+    for input in inputs {
+        graph = graph.message(11, value_info(input));
+    }
+    for output in outputs {
+        graph = graph.message(12, value_info(output));
+    }
+    Message::default()
+        .int(1, 8)
+        .message(8, Message::default().int(2, opset))
+        .message(7, graph)
+        .0
+}
+
+#[test_case]
+fn dense_classifier_runs_like_the_kernel_ops() {
+    let w = ramp(&[4, 3]);
+    let c = Tensor::from_vec(vec![0.1, -0.2, 0.3, 0.0], &[4]).unwrap();
+    let m = Tensor::from_vec(ramp(&[8]).to_vec().into_iter().rev().collect(), &[4, 2]).unwrap();
+    let b = Tensor::from_vec(vec![0.5, -0.5], &[2]).unwrap();
+    let bytes = model(
+        "mlp",
+        13,
+        vec![
+            node("Gemm", &["x", "w", "c"], "h", vec![int_attribute("transB", 1), float_attribute("alpha", 0.5)]),
+            node("Relu", &["h"], "r", vec![]),
+            node("MatMul", &["r", "m"], "z", vec![]),
+            node("Add", &["z", "b"], "logits", vec![]),
+            node("Softmax", &["logits"], "y", vec![]),
+        ],
+        vec![
+            tensor("w", &[4, 3], &w.to_vec()),
+            tensor("c", &[4], &c.to_vec()),
+            tensor("m", &[4, 2], &m.to_vec()),
+            typed_tensor("b", &[2], &b.to_vec()),
+        ],
+        // Como los exportadores antiguos, declara un inicializador como entrada
+        &["x", "w"],
+        &["y"],
+    );
+
+    let mut engine = InferenceEngine::new();
+    assert_eq!(engine.load_onnx(&bytes), Ok(0));
+    assert_eq!(engine.get_model_names(), vec![String::from("mlp")]);
+
+    let x = ramp(&[2, 3]);
+    let expected = x.matmul(&w.t().unwrap()).unwrap().mul_scalar(0.5).unwrap().add(&c).unwrap().relu().unwrap();
+    let expected = expected.matmul(&m).unwrap().add(&b).unwrap().softmax(-1).unwrap();
+    assert_tensor_close(&engine.predict(x).unwrap(), &expected, 1e-5);
+}
+
+#[test_case]
+fn convolutional_graph_with_residual_sum_and_folded_constants() {
+    let kernel = ramp(&[2, 1, 3, 3]);
+    let kernel_bias = Tensor::from_vec(vec![0.25, -0.25], &[2]).unwrap();
+    let dense = ramp(&[8, 3]);
+    let bytes = model(
+        "cnn",
+        11,
+        vec![
+            node("Conv", &["x", "k", "kb"], "conv", vec![
+                ints_attribute("kernel_shape", &[3, 3]),
+                ints_attribute("pads", &[1, 1, 1, 1]),
+            ]),
+            node("Relu", &["conv"], "relu", vec![]),
+            node("Add", &["conv", "relu"], "sum", vec![]),
+            node("MaxPool", &["sum"], "pool", vec![
+                ints_attribute("kernel_shape", &[2, 2]),
+                ints_attribute("strides", &[2, 2]),
+            ]),
+            node("Flatten", &["pool"], "flat", vec![]),
+            node("Reshape", &["flat", "shape"], "rows", vec![]),
+            // Solo depende de inicializadores: se calcula al importar
+            node("Relu", &["raw_bias"], "bias", vec![]),
+            node("Gemm", &["rows", "dense", "bias"], "logits", vec![]),
+            node("Sigmoid", &["logits"], "y", vec![]),
+        ],
+        vec![
+            tensor("k", &[2, 1, 3, 3], &kernel.to_vec()),
+            tensor("kb", &[2], &kernel_bias.to_vec()),
+            int_tensor("shape", &[0, -1]),
+            tensor("raw_bias", &[3], &[-1.0, 0.5, 2.0]),
+            tensor("dense", &[8, 3], &dense.to_vec()),
+        ],
+        &["x"],
+        &["y"],
+    );
+
+    let graph = GraphModel::from_onnx(&bytes).unwrap();
+    assert_eq!(graph.execution_order(), vec!["x", "conv", "relu", "sum", "pool", "flat", "rows", "logits", "y"]);
+    assert_eq!(graph.output_shapes(&[&[1, 1, 4, 4]]).unwrap(), vec![vec![1, 3]]);
+
+    let x = ramp(&[1, 1, 4, 4]);
+    let params = Conv2dParams { padding: (1, 1), ..Conv2dParams::default() };
+    let conv = x.conv2d(&kernel, Some(&kernel_bias), params).unwrap();
+    let pooled = conv.add(&conv.relu().unwrap()).unwrap().max_pool2d(Pool2dParams::new((2, 2))).unwrap();
+    let bias = Tensor::from_vec(vec![0.0, 0.5, 2.0], &[3]).unwrap();
+    let expected = pooled.flatten(1).unwrap().matmul(&dense).unwrap().add(&bias).unwrap().sigmoid().unwrap();
+    let network = NeuralNetwork::from_onnx(&bytes).unwrap();
+    assert_eq!(network.name(), "cnn");
+    assert_tensor_close(&network.forward(x).unwrap(), &expected, 1e-5);
+}
+
+#[test_case]
+fn unsupported_operators_and_malformed_files_are_reported() {
+    let single = |op_node: Message, initializers: Vec<Message>| model("g", 13, vec![op_node], initializers, &["x"], &["y"]);
+
+    let lstm = single(node("LSTM", &["x"], "y", vec![]), vec![]);
+    let error = GraphModel::from_onnx(&lstm).err().unwrap();
+    assert_eq!(error, OnnxError::UnsupportedOperator(String::from("LSTM")));
+    assert_eq!(format!("{}", error), "Operador de ONNX no soportado: LSTM");
+    assert_eq!(InferenceEngine::new().load_onnx(&lstm).err(), Some(error));
+
+    let custom = single(node("FusedConv", &["x"], "y", vec![]).string(7, "com.microsoft"), vec![]);
+    assert_eq!(
+        GraphModel::from_onnx(&custom).err(),
+        Some(OnnxError::UnsupportedOperator(String::from("com.microsoft.FusedConv")))
+    );
+
+    let asymmetric = single(
+        node("Conv", &["x", "k"], "y", vec![ints_attribute("pads", &[0, 0, 1, 1])]),
+        vec![tensor("k", &[1, 1, 2, 2], &[1.0; 4])],
+    );
+    assert_eq!(
+        GraphModel::from_onnx(&asymmetric).err(),
+        Some(OnnxError::UnsupportedUsage { operator: String::from("Conv"), reason: "el relleno debe ser simétrico" })
+    );
+
+    let dangling = single(node("Relu", &["missing"], "y", vec![]), vec![]);
+    assert_eq!(GraphModel::from_onnx(&dangling).err(), Some(OnnxError::UnknownValue(String::from("missing"))));
+
+    let relu = single(node("Relu", &["x"], "y", vec![]), vec![]);
+    assert!(GraphModel::from_onnx(&relu).is_ok());
+    assert_eq!(
+        GraphModel::from_onnx(&relu[..relu.len() - 3]).err(),
+        Some(OnnxError::Decode("Mensaje protobuf truncado"))
+    );
+
+    let two_inputs = model("g", 13, vec![node("Add", &["a", "b"], "y", vec![])], vec![], &["a", "b"], &["y"]);
+    assert!(GraphModel::from_onnx(&two_inputs).is_ok());
+    assert!(matches!(NeuralNetwork::from_onnx(&two_inputs), Err(OnnxError::InvalidModel(_))));
+}
@@ -0,0 +1,154 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::vec::Vec;
+use rustai_os::ai::{
+    quantized_matmul, ActivationFunction, Layer, NeuralNetwork, QuantGranularity, QuantScheme,
+    QuantizedTensor, Tensor, TransformerBlock,
+};
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::{max_abs_diff, pseudo_random};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+fn max_abs(t: &Tensor) -> f32 {
+    t.iter().map(f32::abs).fold(0.0, f32::max)
+}
+
+#[test_case]
+fn symmetric_roundtrip_error_is_half_a_step() {
+    let t = Tensor::from_vec(pseudo_random(256, 1, 1.0), &[16, 16]).unwrap();
+    let q = QuantizedTensor::quantize(&t, QuantScheme::Symmetric, QuantGranularity::PerTensor).unwrap();
+    assert_eq!(q.zero_points(), &[0]);
+    let error = max_abs_diff(&t, &q.dequantize().unwrap());
+    assert!(error <= q.scales()[0] / 2.0 + 1e-6);
+}
+
+#[test_case]
+fn asymmetric_uses_full_range_for_positive_data() {
+    let values: Vec<f32> = pseudo_random(128, 2, 1.0).iter().map(|x| x + 1.0).collect();
+    let t = Tensor::from_vec(values, &[128]).unwrap();
+    let asym = QuantizedTensor::quantize(&t, QuantScheme::Asymmetric, QuantGranularity::PerTensor).unwrap();
+    let sym = QuantizedTensor::quantize(&t, QuantScheme::Symmetric, QuantGranularity::PerTensor).unwrap();
+    // Con datos positivos el esquema asimétrico aprovecha los 256 niveles
+    assert!(asym.scales()[0] < sym.scales()[0] * 0.6);
+    let error = max_abs_diff(&t, &asym.dequantize().unwrap());
+    assert!(error <= asym.scales()[0] / 2.0 + 1e-6);
+    // El cero sigue siendo representable exactamente
+    let zero = Tensor::zeros(&[1]).unwrap();
+    let z = QuantizedTensor::quantize(&zero, QuantScheme::Asymmetric, QuantGranularity::PerTensor).unwrap();
+    assert_eq!(z.dequantize().unwrap().to_vec(), [0.0]);
+}
+
+#[test_case]
+fn per_channel_scales_follow_each_column() {
+    // La segunda columna es cien veces menor que la primera
+    let mut values = pseudo_random(64, 3, 1.0);
+    for pair in values.chunks_mut(2) {
+        pair[1] *= 0.01;
+    }
+    let t = Tensor::from_vec(values, &[32, 2]).unwrap();
+    let per_tensor = QuantizedTensor::quantize(&t, QuantScheme::Symmetric, QuantGranularity::PerTensor).unwrap();
+    let per_channel = QuantizedTensor::quantize(&t, QuantScheme::Symmetric, QuantGranularity::PerChannel(1)).unwrap();
+    assert_eq!(per_channel.scales().len(), 2);
+
+    let small = t.narrow(1, 1, 1).unwrap();
+    let err_tensor = max_abs_diff(&small, &per_tensor.dequantize().unwrap().narrow(1, 1, 1).unwrap());
+    let err_channel = max_abs_diff(&small, &per_channel.dequantize().unwrap().narrow(1, 1, 1).unwrap());
+    assert!(err_channel * 10.0 < err_tensor);
+}
+
+#[test_case]
+fn quantized_matmul_matches_f32_matmul() {
+    let a = Tensor::from_vec(pseudo_random(8 * 32, 4, 1.0), &[8, 32]).unwrap();
+    let b = Tensor::from_vec(pseudo_random(32 * 16, 5, 1.0), &[32, 16]).unwrap();
+    let reference = a.matmul(&b).unwrap();
+
+    let qa = QuantizedTensor::quantize(&a, QuantScheme::Asymmetric, QuantGranularity::PerTensor).unwrap();
+    for granularity in [QuantGranularity::PerTensor, QuantGranularity::PerChannel(1)] {
+        let qb = QuantizedTensor::quantize(&b, QuantScheme::Symmetric, granularity).unwrap();
+        let result = quantized_matmul(&qa, &qb).unwrap();
+        assert_eq!(result.shape(), &[8, 16]);
+        // Error relativo al mayor valor de la referencia por debajo del 2 %
+        assert!(max_abs_diff(&reference, &result) < 0.02 * max_abs(&reference));
+    }
+}
+
+#[test_case]
+fn quantized_layer_tracks_float_layer() {
+    let weights = Tensor::from_vec(pseudo_random(24 * 10, 6, 1.0), &[24, 10]).unwrap();
+    let bias = Tensor::from_vec(pseudo_random(10, 7, 1.0), &[10]).unwrap();
+    let input = Tensor::from_vec(pseudo_random(4 * 24, 8, 1.0), &[4, 24]).unwrap();
+
+    let float_layer = Layer::from_parameters(weights.clone(), bias.clone(), ActivationFunction::Tanh).unwrap();
+    let mut quantized_layer = Layer::from_parameters(weights, bias, ActivationFunction::Tanh).unwrap();
+    quantized_layer.quantize(QuantScheme::Symmetric).unwrap();
+
+    let expected = float_layer.forward(input.clone()).unwrap();
+    let actual = quantized_layer.forward(input).unwrap();
+    assert!(max_abs_diff(&expected, &actual) < 0.03);
+}
+
+#[test_case]
+fn quantized_layer_flattens_leading_dims() {
+    let weights = Tensor::from_vec(pseudo_random(24 * 10, 9, 1.0), &[24, 10]).unwrap();
+    let bias = Tensor::from_vec(pseudo_random(10, 10, 1.0), &[10]).unwrap();
+    let input = Tensor::from_vec(pseudo_random(2 * 3 * 24, 11, 1.0), &[2, 3, 24]).unwrap();
+
+    let float_layer = Layer::from_parameters(weights.clone(), bias.clone(), ActivationFunction::Tanh).unwrap();
+    let mut quantized_layer = Layer::from_parameters(weights, bias, ActivationFunction::Tanh).unwrap();
+    quantized_layer.quantize(QuantScheme::Symmetric).unwrap();
+
+    let expected = float_layer.forward(input.clone()).unwrap();
+    let actual = quantized_layer.forward(input).unwrap();
+    assert_eq!(actual.shape(), &[2, 3, 10]);
+    assert!(max_abs_diff(&expected, &actual) < 0.03);
+}
+
+#[test_case]
+fn network_quantize_reaches_nested_layers() {
+    let input = Tensor::from_vec(pseudo_random(4 * 16, 12, 1.0), &[4, 16]).unwrap();
+    let mut network = NeuralNetwork::new("transformer");
+    network.add_layer(TransformerBlock::new(16, 2, 32, true).unwrap()).unwrap();
+
+    let expected = network.forward(input.clone()).unwrap();
+    let float_bytes = network.parameter_bytes();
+    network.quantize(QuantScheme::Symmetric).unwrap();
+
+    // Las seis proyecciones pasan de 4 bytes por peso a 1
+    assert!(network.parameter_bytes() * 2 < float_bytes);
+    let actual = network.forward(input).unwrap();
+    assert_eq!(actual.shape(), &[4, 16]);
+    assert!(max_abs_diff(&expected, &actual) < 0.1);
+}
@@ -0,0 +1,315 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use half::{bf16, f16};
+use rustai_os::ai::{
+    manual_seed, ActivationFunction, BatchNorm, DType, Gru, Layer, Lstm, NeuralNetwork, RecurrentOutput,
+    SafeDType, SafeTensors, SafeTensorsError, Tensor, TransformerBlock, WeightLayout,
+};
+use rustai_os::math;
+use rustai_os::{allocator, memory, test_panic_handler};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+fn f32_bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+/// Fichero safetensors con los tensores dados, con la cabecera rellenada a
+/// múltiplo de 8 como hace la biblioteca de Python.
+fn safetensors(tensors: &[(&str, &str, &[usize], Vec<u8>)]) -> Vec<u8> {
+    let mut header = String::from("{\"__metadata__\":{\"format\":\"pt\"}");
+    let mut offset = 0;
+    for (name, dtype, shape, data) in tensors {
+        header += &format!(
+            ",\"{}\":{{\"dtype\":\"{}\",\"shape\":{:?},\"data_offsets\":[{},{}]}}",
+            name, dtype, shape, offset, offset + data.len()
+        );
+        offset += data.len();
+    }
+    header.push('}');
+    while header.len() % 8 != 0 {
+        header.push(' ');
+    }
+    let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+    bytes.extend_from_slice(header.as_bytes());
+    for (_, _, _, data) in tensors {
+        bytes.extend_from_slice(data);
+    }
+    bytes
+}
+
+/// Exporta los parámetros de `network` con sus nombres, en `f32`.
+fn export(network: &NeuralNetwork) -> Vec<u8> {
+    let parameters = network.named_parameters();
+    let tensors: Vec<(&str, &str, &[usize], Vec<u8>)> = parameters
+        .iter()
+        .map(|(name, tensor)| (name.as_str(), "F32", tensor.shape(), f32_bytes(&tensor.to_vec())))
+        .collect();
+    safetensors(&tensors)
+}
+
+#[test_case]
+fn parses_views_of_every_supported_dtype() {
+    let halves: Vec<u8> = [1.5f32, -2.0].iter().flat_map(|&v| f16::from_f32(v).to_le_bytes()).collect();
+    let brains: Vec<u8> = [0.25f32, 3.0].iter().flat_map(|&v| bf16::from_f32(v).to_le_bytes()).collect();
+    let bytes = safetensors(&[
+        ("w", "F32", &[2, 2], f32_bytes(&[1.0, 2.0, 3.0, 4.0])),
+        ("h", "F16", &[2], halves),
+        ("b", "BF16", &[2], brains),
+        ("q", "I8", &[3], vec![0x80, 0xff, 0x7f]),
+        ("steps", "I64", &[], 7i64.to_le_bytes().to_vec()),
+    ]);
+    let file = SafeTensors::parse(&bytes).unwrap();
+    assert_eq!(file.len(), 5);
+    assert_eq!(file.metadata(), &[(String::from("format"), String::from("pt"))]);
+
+    let w = file.get("w").unwrap();
+    assert_eq!((w.dtype(), w.shape()), (SafeDType::F32, &[2, 2][..]));
+    assert_eq!(w.to_tensor().unwrap().to_vec(), vec![1.0, 2.0, 3.0, 4.0]);
+    let h = file.get("h").unwrap().to_tensor().unwrap();
+    assert_eq!((h.dtype(), h.to_vec()), (DType::F16, vec![1.5, -2.0]));
+    let b = file.get("b").unwrap().to_tensor().unwrap();
+    assert_eq!((b.dtype(), b.to_vec()), (DType::BF16, vec![0.25, 3.0]));
+    assert_eq!(file.get("q").unwrap().to_tensor().unwrap().to_vec(), vec![-128.0, -1.0, 127.0]);
+    assert_eq!(
+        file.get("steps").unwrap().to_tensor().err(),
+        Some(SafeTensorsError::UnsupportedDType("I64"))
+    );
+
+    // Sin copia si los datos están alineados; si no, se decodifican igual
+    let mut shifted = vec![0];
+    shifted.extend_from_slice(&bytes);
+    for bytes in [&bytes[..], &shifted[1..]] {
+        let w = SafeTensors::parse(bytes).unwrap().get("w").unwrap().clone();
+        let aligned = (w.data().as_ptr() as usize).is_multiple_of(4);
+        assert_eq!(w.as_f32().map(|values| values.as_ptr() as *const u8), aligned.then(|| w.data().as_ptr()));
+        assert_eq!(w.to_tensor().unwrap().to_vec(), vec![1.0, 2.0, 3.0, 4.0]);
+    }
+}
+
+#[test_case]
+fn malformed_files_are_rejected() {
+    let bytes = safetensors(&[("w", "F32", &[2], f32_bytes(&[1.0, 2.0]))]);
+    assert!(matches!(SafeTensors::parse(&bytes[..4]), Err(SafeTensorsError::Truncated { .. })));
+    assert!(matches!(
+        SafeTensors::parse(&bytes[..bytes.len() - 1]),
+        Err(SafeTensorsError::Truncated { expected: 8, found: 7 })
+    ));
+
+    let wrong_length = safetensors(&[("w", "F32", &[3], f32_bytes(&[1.0, 2.0]))]);
+    assert!(matches!(SafeTensors::parse(&wrong_length), Err(SafeTensorsError::InvalidHeader(_))));
+    let unknown = safetensors(&[("w", "F128", &[1], vec![0; 16])]);
+    assert!(matches!(SafeTensors::parse(&unknown), Err(SafeTensorsError::InvalidHeader(_))));
+
+    let mut broken = bytes.clone();
+    let brace = broken.iter().position(|&byte| byte == b'}').unwrap();
+    broken[brace] = b']';
+    assert!(matches!(SafeTensors::parse(&broken), Err(SafeTensorsError::InvalidHeader(_))));
+
+    // Los escapes de las cadenas se decodifican en los nombres
+    let escaped = safetensors(&[("caf\\u00e9.\\\"w\\\"", "F32", &[1], f32_bytes(&[5.0]))]);
+    let file = SafeTensors::parse(&escaped).unwrap();
+    assert_eq!(file.tensors()[0].name(), "café.\"w\"");
+}
+
+#[test_case]
+fn loads_pytorch_weights_by_name() {
+    manual_seed(3);
+    let mut network = NeuralNetwork::new("mlp");
+    network.add_layer(Layer::new(3, 4, ActivationFunction::Identity).unwrap()).unwrap();
+    network.add_layer(ActivationFunction::ReLU).unwrap();
+    network.add_layer(BatchNorm::new(4, 1e-5, 0.1).unwrap()).unwrap();
+    network.add_layer(Layer::new(4, 2, ActivationFunction::Sigmoid).unwrap()).unwrap();
+    let names: Vec<String> = network.named_parameters().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["0.weight", "0.bias", "2.weight", "2.bias", "3.weight", "3.bias"]);
+
+    // `nn.Linear` guarda `[salida, entrada]`
+    let linear0: Vec<f32> = (0..12).map(|i| i as f32 / 10.0).collect();
+    let linear3: Vec<f32> = (0..8).map(|i| 0.5 - i as f32 / 8.0).collect();
+    let bytes = safetensors(&[
+        ("0.weight", "F32", &[4, 3], f32_bytes(&linear0)),
+        ("0.bias", "F32", &[4], f32_bytes(&[0.1, -0.2, 0.3, -0.4])),
+        ("2.weight", "F32", &[4], f32_bytes(&[1.0, 2.0, 0.5, 1.0])),
+        ("2.bias", "F32", &[4], f32_bytes(&[0.0, 0.1, 0.2, 0.3])),
+        ("2.running_mean", "F32", &[4], f32_bytes(&[0.5, 0.0, -0.5, 1.0])),
+        ("2.running_var", "F32", &[4], f32_bytes(&[1.0, 4.0, 0.25, 1.0])),
+        ("2.num_batches_tracked", "I64", &[], 10i64.to_le_bytes().to_vec()),
+        ("3.weight", "F32", &[2, 4], f32_bytes(&linear3)),
+        ("3.bias", "F32", &[2], f32_bytes(&[0.0, 0.05])),
+    ]);
+    let file = SafeTensors::parse(&bytes).unwrap();
+    let unused = network.load_safetensors(&file, WeightLayout::PyTorch).unwrap();
+    assert_eq!(unused, ["2.num_batches_tracked"]);
+
+    let transposed = file.get("0.weight").unwrap().to_tensor().unwrap().t().unwrap().to_vec();
+    assert_eq!(network.parameters()[0].to_vec(), transposed);
+    assert_eq!(network.parameters()[0].shape(), &[3, 4]);
+    let layer: &dyn core::any::Any = network.layers()[2].as_ref();
+    let norm = layer.downcast_ref::<BatchNorm>().unwrap();
+    assert_eq!(norm.running_var().to_vec(), vec![1.0, 4.0, 0.25, 1.0]);
+
+    // Referencia calculada a mano con las fórmulas de PyTorch
+    let input = [1.0, -1.0, 2.0];
+    let mut hidden = [0.0f32; 4];
+    let (bias, gamma, beta) = ([0.1, -0.2, 0.3, -0.4], [1.0, 2.0, 0.5, 1.0], [0.0, 0.1, 0.2, 0.3]);
+    let (mean, var) = ([0.5, 0.0, -0.5, 1.0], [1.0, 4.0, 0.25, 1.0]);
+    for o in 0..4 {
+        let z: f32 = (0..3).map(|i| input[i] * linear0[o * 3 + i]).sum::<f32>() + bias[o];
+        hidden[o] = (z.max(0.0) - mean[o]) / math::sqrt(var[o] + 1e-5) * gamma[o] + beta[o];
+    }
+    let output = network.forward(Tensor::from_vec(input.to_vec(), &[1, 3]).unwrap()).unwrap();
+    for o in 0..2 {
+        let z: f32 = (0..4).map(|i| hidden[i] * linear3[o * 4 + i]).sum::<f32>() + [0.0, 0.05][o];
+        let expected = 1.0 / (1.0 + math::exp(-z));
+        assert!((output.to_vec()[o] - expected).abs() < 1e-5, "{} != {}", output.to_vec()[o], expected);
+    }
+
+    // Un error no deja la red a medias
+    let before = network.parameters()[0].to_vec();
+    let partial = safetensors(&[("0.weight", "F32", &[4, 3], f32_bytes(&[0.0; 12]))]);
+    let error = network.load_safetensors(&SafeTensors::parse(&partial).unwrap(), WeightLayout::PyTorch);
+    assert_eq!(error.err(), Some(SafeTensorsError::MissingTensor("0.bias".into())));
+    let error = network.load_safetensors(&SafeTensors::parse(&partial).unwrap(), WeightLayout::Native);
+    assert!(matches!(error, Err(SafeTensorsError::ShapeMismatch { .. })));
+    assert_eq!(network.parameters()[0].to_vec(), before);
+}
+
+#[test_case]
+fn loads_pytorch_recurrent_weights() {
+    let values = |len: usize, scale: f32| -> Vec<f32> { (0..len).map(|i| ((i * 5) % 7) as f32 * scale - 0.3).collect() };
+    let tensor = |values: &[f32], shape: &[usize]| Tensor::from_vec(values.to_vec(), shape).unwrap();
+    // `nn.LSTM(3, 2)` y `nn.GRU(2, 2)`: pesos `[puertas * h, entrada]` y dos sesgos
+    let (lstm_ih, lstm_hh, lstm_bih, lstm_bhh) = (values(24, 0.1), values(16, 0.05), values(8, 0.02), values(8, 0.03));
+    let (gru_ih, gru_hh, gru_bih, gru_bhh) = (values(12, 0.04), values(12, 0.06), values(6, 0.01), values(6, 0.07));
+    let bytes = safetensors(&[
+        ("0.weight_ih_l0", "F32", &[8, 3], f32_bytes(&lstm_ih)),
+        ("0.weight_hh_l0", "F32", &[8, 2], f32_bytes(&lstm_hh)),
+        ("0.bias_ih_l0", "F32", &[8], f32_bytes(&lstm_bih)),
+        ("0.bias_hh_l0", "F32", &[8], f32_bytes(&lstm_bhh)),
+        ("1.weight_ih_l0", "F32", &[6, 2], f32_bytes(&gru_ih)),
+        ("1.weight_hh_l0", "F32", &[6, 2], f32_bytes(&gru_hh)),
+        ("1.bias_ih_l0", "F32", &[6], f32_bytes(&gru_bih)),
+        ("1.bias_hh_l0", "F32", &[6], f32_bytes(&gru_bhh)),
+    ]);
+    let mut network = NeuralNetwork::new("rnn");
+    network.add_layer(Lstm::new(3, 2, RecurrentOutput::Sequence).unwrap()).unwrap();
+    network.add_layer(Gru::new(2, 2, RecurrentOutput::LastState).unwrap()).unwrap();
+    let file = SafeTensors::parse(&bytes).unwrap();
+    assert!(network.load_safetensors(&file, WeightLayout::PyTorch).unwrap().is_empty());
+
+    let bias: Vec<f32> = lstm_bih.iter().zip(&lstm_bhh).map(|(a, b)| a + b).collect();
+    let lstm = Lstm::from_parameters(
+        tensor(&lstm_ih, &[8, 3]).t().unwrap(),
+        tensor(&lstm_hh, &[8, 2]).t().unwrap(),
+        tensor(&bias, &[8]),
+        RecurrentOutput::Sequence,
+    )
+    .unwrap();
+    let gru = Gru::from_parameters(
+        tensor(&gru_ih, &[6, 2]).t().unwrap(),
+        tensor(&gru_hh, &[6, 2]).t().unwrap(),
+        tensor(&gru_bih, &[6]),
+        tensor(&gru_bhh, &[6]),
+        RecurrentOutput::LastState,
+    )
+    .unwrap();
+    let input = tensor(&values(12, 0.2), &[2, 2, 3]);
+    let expected = gru.forward(lstm.forward(input.clone()).unwrap()).unwrap();
+    assert_eq!(network.forward(input).unwrap().to_vec(), expected.to_vec());
+
+    // Con la disposición nativa se buscan los nombres propios de la capa
+    let error = network.load_safetensors(&file, WeightLayout::Native);
+    assert_eq!(error.err(), Some(SafeTensorsError::MissingTensor("0.weight_ih".into())));
+}
+
+#[test_case]
+fn native_export_roundtrips_through_safetensors() {
+    manual_seed(4);
+    let mut source = NeuralNetwork::new("transformer");
+    source.add_layer(TransformerBlock::new(4, 2, 8, false).unwrap()).unwrap();
+    source.add_layer(Layer::new(4, 3, ActivationFunction::Softmax).unwrap()).unwrap();
+    let names: Vec<String> = source.named_parameters().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names.len(), 18);
+    assert_eq!(names[2], "0.attention.query.weight");
+    assert_eq!(names[15], "0.ffn_down.bias");
+
+    manual_seed(5);
+    let mut target = NeuralNetwork::new("transformer");
+    target.add_layer(TransformerBlock::new(4, 2, 8, false).unwrap()).unwrap();
+    target.add_layer(Layer::new(4, 3, ActivationFunction::Softmax).unwrap()).unwrap();
+    let bytes = export(&source);
+    let unused = target.load_safetensors(&SafeTensors::parse(&bytes).unwrap(), WeightLayout::Native).unwrap();
+    assert!(unused.is_empty());
+
+    let input = Tensor::from_vec((0..12).map(|i| i as f32 / 6.0 - 1.0).collect(), &[1, 3, 4]).unwrap();
+    assert_eq!(target.forward(input.clone()).unwrap().to_vec(), source.forward(input).unwrap().to_vec());
+}
+
+#[test_case]
+fn static_files_are_loaded_without_copies() {
+    manual_seed(6);
+    let mut source = NeuralNetwork::new("mlp");
+    source.add_layer(Layer::new(4, 3, ActivationFunction::ReLU).unwrap()).unwrap();
+    source.add_layer(Layer::new(3, 2, ActivationFunction::Identity).unwrap()).unwrap();
+    let bytes = export(&source);
+
+    // Copia alineada a 4 bytes que vive lo que el kernel, como un modelo
+    // enlazado con `include_bytes!`
+    let buffer = vec![0u8; bytes.len() + 3].leak();
+    let start = buffer.as_ptr().align_offset(4);
+    buffer[start..start + bytes.len()].copy_from_slice(&bytes);
+    let file = SafeTensors::parse(&buffer[start..start + bytes.len()]).unwrap();
+
+    let view = file.get("0.weight").unwrap();
+    let weight = view.to_static_tensor().unwrap();
+    assert!(weight.is_static());
+    assert!(!view.to_tensor().unwrap().is_static());
+
+    let mut target = NeuralNetwork::new("mlp");
+    target.add_layer(Layer::new(4, 3, ActivationFunction::ReLU).unwrap()).unwrap();
+    target.add_layer(Layer::new(3, 2, ActivationFunction::Identity).unwrap()).unwrap();
+    assert!(target.load_static_safetensors(&file, WeightLayout::Native).unwrap().is_empty());
+    assert!(target.parameters().iter().all(|parameter| parameter.is_static()));
+    let input = Tensor::from_vec(vec![0.5, -1.0, 2.0, 0.25], &[1, 4]).unwrap();
+    assert_eq!(target.forward(input.clone()).unwrap().to_vec(), source.forward(input).unwrap().to_vec());
+
+    // Escribir en un tensor prestado lo copia al heap sin tocar el fichero
+    let mut copy = weight.clone();
+    copy.set(&[0, 0], 42.0).unwrap();
+    assert!(!copy.is_static());
+    assert_eq!(copy.get(&[0, 0]), Some(42.0));
+    assert_eq!(weight.get(&[0, 0]), source.parameters()[0].get(&[0, 0]));
+}
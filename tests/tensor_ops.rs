@@ -0,0 +1,305 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::vec;
+use alloc::vec::Vec;
+use rustai_os::ai::{Conv2dParams, GemmKernel, Pool2dParams, Tensor, TensorError};
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::assert_close;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+// [[1, 5, 3],
+//  [4, 2, 6]]
+fn sample() -> Tensor {
+    Tensor::from_vec(vec![1.0, 5.0, 3.0, 4.0, 2.0, 6.0], &[2, 3]).unwrap()
+}
+
+#[test_case]
+fn sum_and_mean_along_axes() {
+    let t = sample();
+    assert_close(&t.sum(0, false).unwrap(), &[5.0, 7.0, 9.0], 1e-5);
+    assert_close(&t.sum(1, false).unwrap(), &[9.0, 12.0], 1e-5);
+    assert_close(&t.mean(-1, false).unwrap(), &[3.0, 4.0], 1e-5);
+    assert_eq!(t.sum(1, true).unwrap().shape(), &[2, 1]);
+    assert_eq!(t.sum(0, false).unwrap().shape(), &[3]);
+}
+
+#[test_case]
+fn max_min_and_arg_indices() {
+    let t = sample();
+    assert_close(&t.max(1, false).unwrap(), &[5.0, 6.0], 1e-5);
+    assert_close(&t.min(0, false).unwrap(), &[1.0, 2.0, 3.0], 1e-5);
+    assert_close(&t.argmax(1, false).unwrap(), &[1.0, 2.0], 1e-5);
+    assert_close(&t.argmin(0, true).unwrap(), &[0.0, 1.0, 0.0], 1e-5);
+    assert_eq!(t.argmin(0, true).unwrap().shape(), &[1, 3]);
+}
+
+#[test_case]
+fn variance_is_population_variance() {
+    // Fila 0: media 3, desviaciones (-2, 2, 0) -> 8 / 3
+    // Fila 1: media 4, desviaciones (0, -2, 2) -> 8 / 3
+    let t = sample();
+    assert_close(&t.var(1, false).unwrap(), &[8.0 / 3.0, 8.0 / 3.0], 1e-5);
+    // Columnas: (1, 4) -> 2.25, (5, 2) -> 2.25, (3, 6) -> 2.25
+    assert_close(&t.var(0, false).unwrap(), &[2.25, 2.25, 2.25], 1e-5);
+}
+
+#[test_case]
+fn softmax_normalizes_each_row() {
+    let t = Tensor::from_vec(vec![0.0, 0.0, 1.0, 1.0, 3.0, 1.0], &[2, 3]).unwrap();
+    let s = t.softmax(-1).unwrap();
+    // Fila 0: e^0 / (2 + e) y e / (2 + e); fila 1 análoga con e^2
+    let e = core::f32::consts::E;
+    assert_close(&s, &[
+        1.0 / (2.0 + e), 1.0 / (2.0 + e), e / (2.0 + e),
+        1.0 / (2.0 + e * e), e * e / (2.0 + e * e), 1.0 / (2.0 + e * e),
+    ], 1e-5);
+    assert_close(&s.sum(1, false).unwrap(), &[1.0, 1.0], 1e-5);
+}
+
+#[test_case]
+fn log_softmax_matches_log_of_softmax() {
+    let t = Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0], &[2, 2]).unwrap();
+    // Por columnas: ambas diferencias son 2, así que ln(1 / (1 + e^2)) arriba
+    let low = -2.126_928_f32;
+    let high = -0.126_928_f32;
+    assert_close(&t.log_softmax(0).unwrap(), &[low, low, high, high], 1e-5);
+}
+
+#[test_case]
+fn index_select_picks_rows() {
+    let t = sample();
+    let rows = t.index_select(0, &[1, 1, 0]).unwrap();
+    assert_eq!(rows.shape(), &[3, 3]);
+    assert_close(&rows, &[4.0, 2.0, 6.0, 4.0, 2.0, 6.0, 1.0, 5.0, 3.0], 1e-5);
+    assert_eq!(
+        t.index_select(1, &[3]).unwrap_err(),
+        TensorError::IndexOutOfBounds { index: 3, len: 3 }
+    );
+}
+
+#[test_case]
+fn invalid_axis_is_an_error() {
+    let t = sample();
+    assert_eq!(t.sum(2, false).unwrap_err(), TensorError::InvalidAxis { axis: 2, ndim: 2 });
+    assert!(t.softmax(-3).is_err());
+}
+
+#[test_case]
+fn broadcasting_follows_numpy_rules() {
+    let t = sample();
+    // [2, 3] + [3]: la fila se repite en cada fila
+    let row = Tensor::from_vec(vec![10.0, 20.0, 30.0], &[3]).unwrap();
+    let sum = t.add(&row).unwrap();
+    assert_eq!(sum.shape(), &[2, 3]);
+    assert_close(&sum, &[11.0, 25.0, 33.0, 14.0, 22.0, 36.0], 1e-5);
+
+    // [2, 1] * [1, 3]: producto exterior
+    let column = Tensor::from_vec(vec![1.0, 2.0], &[2, 1]).unwrap();
+    let line = Tensor::from_vec(vec![1.0, 10.0, 100.0], &[1, 3]).unwrap();
+    let outer = column.mul(&line).unwrap();
+    assert_eq!(outer.shape(), &[2, 3]);
+    assert_close(&outer, &[1.0, 10.0, 100.0, 2.0, 20.0, 200.0], 1e-5);
+
+    // [2, 1, 3] - [2, 3]: la forma de menor rango se rellena con 1 por la izquierda
+    let batch = Tensor::stack(&[&row, &row.mul_scalar(2.0).unwrap()], 0).unwrap().unsqueeze(1).unwrap();
+    let diff = batch.sub(&t).unwrap();
+    assert_eq!(diff.shape(), &[2, 2, 3]);
+    assert_close(&diff, &[
+        9.0, 15.0, 27.0, 6.0, 18.0, 24.0,
+        19.0, 35.0, 57.0, 16.0, 38.0, 54.0,
+    ], 1e-5);
+}
+
+#[test_case]
+fn comparison_masks_and_pow() {
+    let t = sample();
+    let three = Tensor::full(&[1], 3.0).unwrap();
+    assert_close(&t.equal(&three).unwrap(), &[0.0, 0.0, 1.0, 0.0, 0.0, 0.0], 1e-5);
+    assert_close(&t.not_equal(&three).unwrap(), &[1.0, 1.0, 0.0, 1.0, 1.0, 1.0], 1e-5);
+    assert_close(&t.less(&three).unwrap(), &[1.0, 0.0, 0.0, 0.0, 1.0, 0.0], 1e-5);
+    assert_close(&t.less_equal(&three).unwrap(), &[1.0, 0.0, 1.0, 0.0, 1.0, 0.0], 1e-5);
+    assert_close(&t.greater(&three).unwrap(), &[0.0, 1.0, 0.0, 1.0, 0.0, 1.0], 1e-5);
+    assert_close(&t.greater_equal(&three).unwrap(), &[0.0, 1.0, 1.0, 1.0, 0.0, 1.0], 1e-5);
+
+    // Exponente por columnas: [0, 1, 2]
+    let exponents = Tensor::from_vec(vec![0.0, 1.0, 2.0], &[3]).unwrap();
+    assert_close(&t.pow(&exponents).unwrap(), &[1.0, 5.0, 9.0, 1.0, 2.0, 36.0], 1e-5);
+    let root = Tensor::full(&[1], 0.5).unwrap();
+    assert_close(&Tensor::full(&[2], 16.0).unwrap().pow(&root).unwrap(), &[4.0, 4.0], 1e-5);
+}
+
+#[test_case]
+fn incompatible_shapes_are_rejected() {
+    let t = sample();
+    let two = Tensor::zeros(&[2]).unwrap();
+    assert_eq!(
+        t.add(&two).unwrap_err(),
+        TensorError::ShapeMismatch { lhs: vec![2, 3], rhs: vec![2] }
+    );
+    assert!(matches!(t.less(&Tensor::zeros(&[3, 3]).unwrap()), Err(TensorError::ShapeMismatch { .. })));
+    assert!(matches!(t.pow(&Tensor::zeros(&[4, 1, 2]).unwrap()), Err(TensorError::ShapeMismatch { .. })));
+    // Un tensor no se puede difundir a una forma de menor rango
+    assert!(matches!(t.broadcast_to(&[3]), Err(TensorError::ShapeMismatch { .. })));
+}
+
+#[test_case]
+fn oversized_tensors_and_views_are_errors() {
+    assert_eq!(Tensor::zeros(&[usize::MAX, 2]).unwrap_err(), TensorError::OutOfMemory { bytes: usize::MAX });
+    assert!(matches!(Tensor::full(&[1 << 60], 1.0), Err(TensorError::OutOfMemory { .. })));
+
+    let t = sample();
+    let diagonal = t.as_strided(&[2], &[4], 0).unwrap();
+    assert!(diagonal.shares_storage(&t));
+    assert_close(&diagonal, &[1.0, 2.0], 1e-5);
+    assert_eq!(
+        t.as_strided(&[2, 2], &[1], 0).unwrap_err(),
+        TensorError::LengthMismatch { expected: 2, found: 1 }
+    );
+    assert_eq!(
+        t.as_strided(&[2, 3], &[3, 1], 1).unwrap_err(),
+        TensorError::IndexOutOfBounds { index: 6, len: 6 }
+    );
+    assert!(t.as_strided(&[2], &[usize::MAX], 0).is_err());
+}
+
+#[test_case]
+fn reshape_and_permute_share_storage() {
+    let t = sample();
+    let r = t.reshape(&[3, 2]).unwrap();
+    assert!(r.shares_storage(&t));
+    let p = t.permute(&[1, 0]).unwrap();
+    assert!(p.shares_storage(&t));
+    assert_close(&p, &[1.0, 4.0, 5.0, 2.0, 3.0, 6.0], 1e-5);
+    // La traspuesta no es contigua: aplanarla obliga a copiar
+    let flat = p.reshape(&[6]).unwrap();
+    assert!(!flat.shares_storage(&t));
+    assert_close(&flat, &[1.0, 4.0, 5.0, 2.0, 3.0, 6.0], 1e-5);
+}
+
+#[test_case]
+fn slice_narrow_and_squeeze() {
+    let t = sample();
+    assert_close(&t.slice(1, 0..3, 2).unwrap(), &[1.0, 3.0, 4.0, 6.0], 1e-5);
+    let row = t.narrow(0, 1, 1).unwrap();
+    assert_eq!(row.shape(), &[1, 3]);
+    assert_eq!(row.squeeze(None).unwrap().shape(), &[3]);
+    assert_eq!(row.unsqueeze(0).unwrap().shape(), &[1, 1, 3]);
+    assert!(matches!(t.slice(1, 0..3, 0), Err(TensorError::InvalidArgument(_))));
+}
+
+#[test_case]
+fn concat_stack_and_split() {
+    let t = sample();
+    let c = Tensor::concat(&[&t, &t], 1).unwrap();
+    assert_eq!(c.shape(), &[2, 6]);
+    assert_close(&c, &[1.0, 5.0, 3.0, 1.0, 5.0, 3.0, 4.0, 2.0, 6.0, 4.0, 2.0, 6.0], 1e-5);
+    let s = Tensor::stack(&[&t, &t], 0).unwrap();
+    assert_eq!(s.shape(), &[2, 2, 3]);
+    let parts = t.split(1, 2).unwrap();
+    assert_eq!(parts.len(), 2);
+    assert_close(&parts[1], &[3.0, 6.0], 1e-5);
+    assert!(matches!(t.split(1, 0), Err(TensorError::InvalidArgument(_))));
+}
+
+#[test_case]
+fn matmul_kernels_match_reference() {
+    // Tamaños que no son múltiplos de los bloques ni de los microkernels
+    let (m, k, n) = (50, 130, 37);
+    let a: Vec<f32> = (0..m * k).map(|i| ((i * 7) % 11) as f32 / 11.0 - 0.5).collect();
+    let b: Vec<f32> = (0..k * n).map(|i| ((i * 5) % 13) as f32 / 13.0 - 0.5).collect();
+    let mut expected = vec![0.0; m * n];
+    for i in 0..m {
+        for j in 0..n {
+            expected[i * n + j] = (0..k).map(|p| a[i * k + p] * b[p * n + j]).sum();
+        }
+    }
+
+    let ta = Tensor::from_vec(a, &[m, k]).unwrap();
+    let tb = Tensor::from_vec(b, &[k, n]).unwrap();
+    for kernel in [GemmKernel::Scalar, GemmKernel::Sse2, GemmKernel::Avx2Fma] {
+        let out = ta.matmul_with(&tb, kernel).unwrap();
+        assert_eq!(out.shape(), &[m, n]);
+        for (x, y) in out.to_vec().iter().zip(&expected) {
+            assert!((x - y).abs() < 1e-3, "{}: {} != {}", kernel.name(), x, y);
+        }
+    }
+
+    // Un operando traspuesto se empaqueta directamente desde sus strides
+    let at = ta.t().unwrap().contiguous().unwrap().t().unwrap();
+    for (x, y) in at.matmul(&tb).unwrap().to_vec().iter().zip(&expected) {
+        assert!((x - y).abs() < 1e-3);
+    }
+}
+
+// Plano 4x4 con los valores 0..16 por filas
+fn image() -> Tensor {
+    Tensor::from_vec((0..16).map(|i| i as f32).collect(), &[1, 1, 4, 4]).unwrap()
+}
+
+#[test_case]
+fn conv2d_with_padding_stride_and_groups() {
+    // Núcleo 3x3 de unos con relleno 1: suma de cada vecindario
+    let ones = Tensor::ones(&[1, 1, 3, 3]).unwrap();
+    let params = Conv2dParams { padding: (1, 1), stride: (2, 2), ..Conv2dParams::default() };
+    let out = image().conv2d(&ones, Some(&Tensor::full(&[1], 1.0).unwrap()), params).unwrap();
+    assert_eq!(out.shape(), &[1, 1, 2, 2]);
+    assert_close(&out, &[11.0, 25.0, 52.0, 91.0], 1e-5);
+
+    // Dos grupos: cada canal de salida solo ve su canal de entrada
+    let two = Tensor::concat(&[&image(), &image().mul_scalar(-1.0).unwrap()], 1).unwrap();
+    let weight = Tensor::ones(&[2, 1, 2, 2]).unwrap();
+    let grouped = two.conv2d(&weight, None, Conv2dParams { groups: 2, ..Conv2dParams::default() }).unwrap();
+    let depthwise = two.depthwise_conv2d(&weight, None, Conv2dParams::default()).unwrap();
+    assert_eq!(grouped.shape(), &[1, 2, 3, 3]);
+    assert_close(&grouped, &depthwise.to_vec(), 1e-5);
+    assert_eq!(grouped.get(&[0, 1, 0, 0]), Some(-10.0));
+
+    let bad = Conv2dParams { groups: 3, ..Conv2dParams::default() };
+    assert!(matches!(two.conv2d(&weight, None, bad), Err(TensorError::InvalidArgument(_))));
+}
+
+#[test_case]
+fn pooling_and_flatten() {
+    let t = image();
+    assert_close(&t.max_pool2d(Pool2dParams::new((2, 2))).unwrap(), &[5.0, 7.0, 13.0, 15.0], 1e-5);
+    assert_close(&t.avg_pool2d(Pool2dParams::new((2, 2))).unwrap(), &[2.5, 4.5, 10.5, 12.5], 1e-5);
+    // El relleno no cuenta en la media
+    let padded = Pool2dParams { kernel: (3, 3), stride: (3, 3), padding: (1, 1) };
+    assert_close(&t.avg_pool2d(padded).unwrap(), &[2.5, 4.5, 10.5, 12.5], 1e-5);
+    assert_close(&t.global_avg_pool2d().unwrap(), &[7.5], 1e-5);
+    assert_eq!(t.flatten(1).unwrap().shape(), &[1, 16]);
+    assert_eq!(t.flatten(4).unwrap().shape(), &[16, 1]);
+}
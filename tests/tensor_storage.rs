@@ -0,0 +1,150 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::vec;
+use alloc::vec::Vec;
+use rustai_os::ai::{Tensor, TensorError};
+use rustai_os::math;
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::assert_close;
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+// [[1, 2, 3],
+//  [4, 5, 6]]
+fn sample() -> Tensor {
+    Tensor::from_vec(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[2, 3]).unwrap()
+}
+
+#[test_case]
+fn constructors_lay_out_row_major_storage() {
+    let t = sample();
+    assert_eq!((t.shape(), t.strides(), t.offset()), (&[2, 3][..], &[3, 1][..], 0));
+    assert!(t.is_contiguous());
+    assert_eq!(t.as_slice(), Some(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0][..]));
+    assert_eq!((t.ndim(), t.len()), (2, 6));
+
+    let ones = Tensor::ones(&[2, 2, 2]).unwrap();
+    assert_eq!(ones.strides(), &[4, 2, 1]);
+    assert!(ones.iter().all(|x| x == 1.0));
+    assert!(Tensor::zeros(&[3, 0]).unwrap().is_empty());
+
+    let scalar = Tensor::scalar(2.5);
+    assert_eq!((scalar.ndim(), scalar.len(), scalar.get(&[])), (0, 1, Some(2.5)));
+
+    assert_eq!(
+        Tensor::from_vec(vec![1.0; 5], &[2, 3]).unwrap_err(),
+        TensorError::LengthMismatch { expected: 6, found: 5 }
+    );
+}
+
+#[test_case]
+fn views_share_storage_and_iterate_in_logical_order() {
+    let t = sample();
+    let transposed = t.t().unwrap();
+    assert!(transposed.shares_storage(&t));
+    assert_eq!(transposed.strides(), &[1, 3]);
+    assert!(!transposed.is_contiguous());
+    assert_eq!(transposed.as_slice(), None);
+    assert_eq!(transposed.iter().collect::<Vec<_>>(), vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]);
+    assert_eq!(transposed.get(&[2, 1]), Some(6.0));
+
+    // Columnas 1 y 2: empieza en el segundo elemento y salta el primero de cada fila
+    let columns = t.narrow(1, 1, 2).unwrap();
+    assert_eq!((columns.offset(), columns.strides()), (1, &[3, 1][..]));
+    assert!(!columns.is_contiguous());
+    assert_eq!(columns.to_vec(), vec![2.0, 3.0, 5.0, 6.0]);
+
+    // La difusión repite filas con stride 0
+    let row = Tensor::from_vec(vec![1.0, 2.0, 3.0], &[3]).unwrap();
+    let rows = row.broadcast_to(&[2, 3]).unwrap();
+    assert_eq!(rows.strides(), &[0, 1]);
+    assert_eq!(rows.to_vec(), vec![1.0, 2.0, 3.0, 1.0, 2.0, 3.0]);
+
+    // `contiguous` copia solo las vistas que lo necesitan
+    let copy = transposed.contiguous().unwrap();
+    assert!(!copy.shares_storage(&t));
+    assert_eq!((copy.strides(), copy.as_slice()), (&[2, 1][..], Some(&[1.0, 4.0, 2.0, 5.0, 3.0, 6.0][..])));
+    assert!(t.contiguous().unwrap().shares_storage(&t));
+}
+
+#[test_case]
+fn writes_copy_shared_storage() {
+    let t = sample();
+    let mut row = t.narrow(0, 1, 1).unwrap();
+    assert_eq!(row.set(&[0, 0], 9.0), Some(()));
+    assert!(!row.shares_storage(&t));
+    assert_eq!(row.to_vec(), vec![9.0, 5.0, 6.0]);
+    assert_eq!(t.get(&[1, 0]), Some(4.0));
+
+    // Sin otras vistas escribe en su propio búfer
+    let mut owned = sample();
+    let before = owned.as_slice().unwrap().as_ptr();
+    owned.set(&[0, 2], -1.0).unwrap();
+    assert_eq!(owned.as_slice().unwrap().as_ptr(), before);
+    assert_eq!(owned.get(&[0, 2]), Some(-1.0));
+
+    assert_eq!(owned.set(&[2, 0], 0.0), None);
+    assert_eq!(owned.get(&[0]), None);
+}
+
+#[test_case]
+fn operations_accept_non_contiguous_views() {
+    let t = sample();
+    let transposed = t.t().unwrap();
+    assert_close(&transposed.matmul(&t).unwrap(), &[
+        17.0, 22.0, 27.0,
+        22.0, 29.0, 36.0,
+        27.0, 36.0, 45.0,
+    ], 1e-5);
+    let offsets = Tensor::from_vec(vec![10.0, 20.0, 30.0, 40.0, 50.0, 60.0], &[3, 2]).unwrap();
+    assert_close(&transposed.add(&offsets).unwrap(), &[11.0, 24.0, 32.0, 45.0, 53.0, 66.0], 1e-5);
+
+    // [[-1, 4], [2, -5], [-3, 6]]
+    let signed = Tensor::from_vec(vec![-1.0, 2.0, -3.0, 4.0, -5.0, 6.0], &[2, 3]).unwrap().t().unwrap();
+    let values = signed.to_vec();
+    assert_close(&signed.relu().unwrap(), &[0.0, 4.0, 2.0, 0.0, 0.0, 6.0], 0.0);
+    let sigmoid: Vec<f32> = values.iter().map(|&x| math::sigmoid(x)).collect();
+    assert_close(&signed.sigmoid().unwrap(), &sigmoid, 1e-6);
+    let tanh: Vec<f32> = values.iter().map(|&x| math::tanh(x)).collect();
+    assert_close(&signed.tanh().unwrap(), &tanh, 1e-6);
+
+    let softmax = signed.softmax(-1).unwrap();
+    assert_eq!(softmax.shape(), &[3, 2]);
+    let mut expected = Vec::new();
+    for pair in values.chunks(2) {
+        let (a, b) = (math::exp(pair[0]), math::exp(pair[1]));
+        expected.extend([a / (a + b), b / (a + b)]);
+    }
+    assert_close(&softmax, &expected, 1e-6);
+}
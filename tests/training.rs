@@ -0,0 +1,330 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(rustai_os::test_runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use rustai_os::ai::{
+    accuracy, check_gradients, clip_gradients, manual_seed, ActivationFunction, Adam, BatchNorm,
+    Conv2d, Conv2dParams, Dataset, Dropout, EarlyStopping, Flatten, GradientClip, Layer, LayerNorm,
+    Loss, LrSchedule, MaxPool2d, Metric, NeuralNetwork, Optimizer, Pool2dParams, RmsProp, Sgd,
+    Tensor, Trainer, TrainerConfig,
+};
+use rustai_os::math;
+use rustai_os::{allocator, memory, test_panic_handler};
+
+mod common;
+
+use common::{assert_close, assert_tensor_close, max_abs_diff};
+
+entry_point!(main);
+
+fn main(boot_info: &'static BootInfo) -> ! {
+    use x86_64::VirtAddr;
+
+    rustai_os::init();
+    let phys_mem_offset = VirtAddr::new(boot_info.physical_memory_offset);
+    let mut mapper = unsafe { memory::init(phys_mem_offset) };
+    let mut frame_allocator = unsafe {
+        memory::BootInfoFrameAllocator::init(&boot_info.memory_map)
+    };
+    allocator::init_heap(&mut mapper, &mut frame_allocator)
+        .expect("heap initialization failed");
+
+    test_main();
+    loop {}
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
+
+fn tensor(values: &[f32], shape: &[usize]) -> Tensor {
+    Tensor::from_vec(values.to_vec(), shape).unwrap()
+}
+
+#[test_case]
+fn losses_match_reference_values_and_gradients() {
+    let prediction = tensor(&[0.5, 2.0, -1.0, 0.0], &[2, 2]);
+    let target = tensor(&[1.0, 0.0, -1.0, 3.0], &[2, 2]);
+    let (mse, grad) = Loss::MeanSquaredError.evaluate(&prediction, &target).unwrap();
+    assert!((mse - (0.25 + 4.0 + 0.0 + 9.0) / 4.0).abs() < 1e-6);
+    assert_close(&grad, &[-0.25, 1.0, 0.0, -1.5], 1e-5);
+    let (mae, grad) = Loss::MeanAbsoluteError.evaluate(&prediction, &target).unwrap();
+    assert!((mae - 5.5 / 4.0).abs() < 1e-6);
+    assert_close(&grad, &[-0.25, 0.25, 0.0, -0.25], 1e-5);
+    // Huber con delta = 1: 0.5 · 0.5², 2 - 0.5, 0, 3 - 0.5
+    let (huber, _) = Loss::Huber { delta: 1.0 }.evaluate(&prediction, &target).unwrap();
+    assert!((huber - (0.125 + 1.5 + 2.5) / 4.0).abs() < 1e-6);
+
+    let probabilities = tensor(&[0.7, 0.2, 0.1, 0.25, 0.25, 0.5], &[2, 3]);
+    let one_hot = tensor(&[1.0, 0.0, 0.0, 0.0, 0.0, 1.0], &[2, 3]);
+    let (cce, _) = Loss::CategoricalCrossEntropy.evaluate(&probabilities, &one_hot).unwrap();
+    assert!((cce + (math::ln(0.7) + math::ln(0.5)) / 2.0).abs() < 1e-6);
+    let binary = (tensor(&[0.8, 0.4], &[2]), tensor(&[1.0, 0.0], &[2]));
+    let (bce, _) = Loss::BinaryCrossEntropy.evaluate(&binary.0, &binary.1).unwrap();
+    assert!((bce + (math::ln(0.8) + math::ln(0.6)) / 2.0).abs() < 1e-6);
+    assert!(Loss::MeanSquaredError.evaluate(&prediction, &one_hot).is_err());
+
+    // Gradientes analíticos frente a diferencias centrales, a través de la cinta
+    let logits = tensor(&[0.3, -0.8, 1.2, 0.5, 0.1, -0.4], &[2, 3]);
+    let losses = [
+        (Loss::MeanSquaredError, ActivationFunction::Tanh),
+        (Loss::MeanAbsoluteError, ActivationFunction::Identity),
+        (Loss::Huber { delta: 0.5 }, ActivationFunction::Identity),
+        (Loss::BinaryCrossEntropy, ActivationFunction::Sigmoid),
+        (Loss::CategoricalCrossEntropy, ActivationFunction::Softmax),
+    ];
+    for (loss, activation) in losses {
+        let error = check_gradients(
+            |v| loss.forward(&activation.apply_tape(&v[0])?, &one_hot),
+            core::slice::from_ref(&logits),
+            1e-3,
+        )
+        .unwrap();
+        assert!(error < 5e-3, "{:?}: error relativo {}", loss, error);
+    }
+}
+
+#[test_case]
+fn optimizers_follow_reference_updates() {
+    // Un parámetro p = 1 con gradiente constante 0.5 y lr = 0.1
+    let run = |optimizer: &mut dyn Optimizer, steps: usize| {
+        let mut parameter = Tensor::ones(&[1]).unwrap();
+        for _ in 0..steps {
+            optimizer.step(&mut [&mut parameter], &[Tensor::full(&[1], 0.5).unwrap()]).unwrap();
+        }
+        parameter.to_vec()[0]
+    };
+    let close = |a: f32, e: f32| assert!((a - e).abs() < 1e-5, "{} != {}", a, e);
+    close(run(&mut Sgd::new(0.1), 1), 0.95);
+    // v = 0.5 y después 0.9 · 0.5 + 0.5
+    close(run(&mut Sgd::new(0.1).with_momentum(0.9, false), 2), 0.855);
+    close(run(&mut Sgd::new(0.1).with_momentum(0.9, true), 1), 0.905);
+    close(run(&mut Sgd::new(0.1).with_weight_decay(0.1), 1), 0.94);
+    // El primer paso de Adam mueve cada peso lr en contra del gradiente
+    close(run(&mut Adam::new(0.1), 1), 0.9);
+    close(run(&mut Adam::adamw(0.1, 0.1), 1), 0.89);
+    // s = 0.01 · 0.25, así que g / sqrt(s) = 10
+    close(run(&mut RmsProp::new(0.01), 1), 0.9);
+
+    let mut adam = Adam::new(0.1);
+    let mut parameter = Tensor::ones(&[2]).unwrap();
+    assert!(adam.step(&mut [&mut parameter], &[Tensor::ones(&[3]).unwrap()]).is_err());
+    assert!(adam.step(&mut [&mut parameter], &[]).is_err());
+    adam.set_learning_rate(0.5);
+    assert_eq!(adam.learning_rate(), 0.5);
+}
+
+#[test_case]
+fn network_learns_xor() {
+    manual_seed(3);
+    let mut network = NeuralNetwork::new("xor");
+    network.add_layer(Layer::new(2, 8, ActivationFunction::Tanh).unwrap()).unwrap();
+    network.add_layer(Layer::new(8, 1, ActivationFunction::Sigmoid).unwrap()).unwrap();
+    let input = tensor(&[0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0], &[4, 2]);
+    let target = tensor(&[0.0, 1.0, 1.0, 0.0], &[4, 1]);
+
+    let mut optimizer = Adam::new(0.05);
+    let losses: Vec<f32> = (0..300)
+        .map(|_| network.train_step(input.clone(), &target, Loss::BinaryCrossEntropy, &mut optimizer).unwrap())
+        .collect();
+    assert!(losses[299] < 0.05 && losses[299] < losses[0], "{} -> {}", losses[0], losses[299]);
+    let output = network.forward(input).unwrap();
+    for (prediction, expected) in output.iter().zip(target.iter()) {
+        assert!((prediction - expected).abs() < 0.2, "{} != {}", prediction, expected);
+    }
+
+    // GELU también tiene derivada en la cinta
+    let mut gelu = NeuralNetwork::new("gelu");
+    gelu.add_layer(Layer::new(2, 1, ActivationFunction::Gelu).unwrap()).unwrap();
+    let target = Tensor::ones(&[1, 1]).unwrap();
+    let step = gelu.train_step(tensor(&[1.0, 2.0], &[1, 2]), &target, Loss::MeanSquaredError, &mut Sgd::new(0.1));
+    assert!(step.is_ok());
+}
+
+// Imágenes 4x4 con una línea horizontal (objetivo 0) o vertical (1)
+fn line_images() -> (Tensor, Tensor) {
+    let mut pixels = Vec::new();
+    let mut targets = Vec::new();
+    for vertical in [false, true] {
+        for line in 0..4 {
+            pixels.extend((0..16).map(|i| {
+                let (row, column) = (i / 4, i % 4);
+                if (if vertical { column } else { row }) == line { 1.0 } else { 0.0 }
+            }));
+            targets.push(if vertical { 1.0 } else { 0.0 });
+        }
+    }
+    (tensor(&pixels, &[8, 1, 4, 4]), tensor(&targets, &[8, 1]))
+}
+
+#[test_case]
+fn convolutional_network_learns_line_orientation() {
+    manual_seed(7);
+    let mut network = NeuralNetwork::new("líneas");
+    let same = Conv2dParams { padding: (1, 1), ..Conv2dParams::default() };
+    network.add_layer(Conv2d::new(1, 4, (3, 3), same).unwrap()).unwrap();
+    network.add_layer(ActivationFunction::ReLU).unwrap();
+    network.add_layer(MaxPool2d(Pool2dParams::new((2, 2)))).unwrap();
+    network.add_layer(Flatten).unwrap();
+    network.add_layer(Layer::new(16, 1, ActivationFunction::Sigmoid).unwrap()).unwrap();
+    let (images, targets) = line_images();
+
+    let mut optimizer = Adam::new(0.05);
+    let losses: Vec<f32> = (0..100)
+        .map(|_| network.train_step(images.clone(), &targets, Loss::BinaryCrossEntropy, &mut optimizer).unwrap())
+        .collect();
+    assert!(losses[99] < 0.05 && losses[99] < losses[0], "{} -> {}", losses[0], losses[99]);
+    assert_eq!(accuracy(&network.forward(images).unwrap(), &targets).unwrap(), 1.0);
+}
+
+#[test_case]
+fn normalized_network_learns_xor_in_training_mode() {
+    manual_seed(9);
+    let mut network = NeuralNetwork::new("xor normalizado");
+    network.add_layer(Layer::new(2, 8, ActivationFunction::Identity).unwrap()).unwrap();
+    network.add_layer(BatchNorm::new(8, 1e-5, 0.1).unwrap()).unwrap();
+    network.add_layer(ActivationFunction::ReLU).unwrap();
+    network.add_layer(Dropout::new(0.1, 3).unwrap()).unwrap();
+    network.add_layer(Layer::new(8, 8, ActivationFunction::Identity).unwrap()).unwrap();
+    network.add_layer(LayerNorm::new(&[8], 1e-5).unwrap()).unwrap();
+    network.add_layer(ActivationFunction::Tanh).unwrap();
+    network.add_layer(Layer::new(8, 1, ActivationFunction::Sigmoid).unwrap()).unwrap();
+    let input = tensor(&[0.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 1.0], &[4, 2]);
+    let target = tensor(&[0.0, 1.0, 1.0, 0.0], &[4, 1]);
+
+    // En entrenamiento BatchNorm usa las estadísticas del lote y Dropout
+    // anula activaciones; ambos pasan por la cinta
+    network.set_training(true);
+    let mut optimizer = Adam::new(0.05);
+    let losses: Vec<f32> = (0..200)
+        .map(|_| network.train_step(input.clone(), &target, Loss::BinaryCrossEntropy, &mut optimizer).unwrap())
+        .collect();
+    assert!(losses[199] < 0.2 && losses[199] < losses[0], "{} -> {}", losses[0], losses[199]);
+
+    // En evaluación, con las estadísticas acumuladas durante el entrenamiento
+    network.set_training(false);
+    assert_eq!(accuracy(&network.forward(input).unwrap(), &target).unwrap(), 1.0);
+}
+
+#[test_case]
+fn schedules_clipping_and_accuracy() {
+    let rates = |schedule: LrSchedule| -> Vec<f32> {
+        (0..6).map(|epoch| schedule.learning_rate(1.0, epoch)).collect()
+    };
+    let close = |a: &[f32], e: &[f32]| {
+        for (a, e) in a.iter().zip(e) {
+            assert!((a - e).abs() < 1e-6, "{} != {}", a, e);
+        }
+    };
+    close(&rates(LrSchedule::Step { step_size: 2, gamma: 0.5 }), &[1.0, 1.0, 0.5, 0.5, 0.25, 0.25]);
+    close(&rates(LrSchedule::Cosine { epochs: 4, min_lr: 0.0 }), &[1.0, 0.853_553, 0.5, 0.146_447, 0.0, 0.0]);
+    let cosine = LrSchedule::Cosine { epochs: 2, min_lr: 0.1 };
+    let warmup = LrSchedule::Warmup { epochs: 2, then: Box::new(cosine) };
+    close(&rates(warmup), &[0.5, 1.0, 1.0, 0.55, 0.1, 0.1]);
+
+    // Norma conjunta 5: se escala a 1; por valor se satura cada componente
+    let grads = || vec![tensor(&[3.0], &[1]), tensor(&[0.0, -4.0], &[2])];
+    let mut clipped = grads();
+    assert!((clip_gradients(&mut clipped, GradientClip::Norm(1.0)).unwrap() - 5.0).abs() < 1e-6);
+    assert_close(&clipped[1], &[0.0, -0.8], 1e-5);
+    let mut clipped = grads();
+    clip_gradients(&mut clipped, GradientClip::Value(1.0)).unwrap();
+    assert_close(&clipped[0], &[1.0], 1e-5);
+    assert_close(&clipped[1], &[0.0, -1.0], 1e-5);
+
+    let probabilities = tensor(&[0.7, 0.2, 0.1, 0.3, 0.3, 0.4], &[2, 3]);
+    let one_hot = tensor(&[1.0, 0.0, 0.0, 0.0, 1.0, 0.0], &[2, 3]);
+    assert_eq!(accuracy(&probabilities, &one_hot).unwrap(), 0.5);
+    let binary = accuracy(&tensor(&[0.9, 0.2, 0.6], &[3, 1]), &tensor(&[1.0, 0.0, 0.0], &[3, 1]));
+    assert_eq!(binary.unwrap(), 2.0 / 3.0);
+}
+
+// y = 2 x0 - x1 + 0.5 sobre una rejilla de puntos
+fn linear_dataset(points: usize, offset: f32) -> Dataset {
+    let inputs: Vec<f32> = (0..points * 2).map(|i| ((i * 5) % 9) as f32 / 4.0 - 1.0 + offset).collect();
+    let targets = inputs.chunks(2).map(|x| 2.0 * x[0] - x[1] + 0.5).collect();
+    Dataset::new(tensor(&inputs, &[points, 2]), Tensor::from_vec(targets, &[points, 1]).unwrap()).unwrap()
+}
+
+#[test_case]
+fn trainer_fits_with_early_stopping_and_checkpoints() {
+    manual_seed(5);
+    let mut network = NeuralNetwork::new("regresión");
+    network.add_layer(Layer::new(2, 1, ActivationFunction::Identity).unwrap()).unwrap();
+    let config = TrainerConfig {
+        epochs: 500,
+        batch_size: 8,
+        schedule: LrSchedule::Warmup { epochs: 3, then: Box::new(LrSchedule::Constant) },
+        clip: Some(GradientClip::Norm(5.0)),
+        early_stopping: Some(EarlyStopping { metric: Metric::Loss, patience: 3, min_delta: 1e-6 }),
+        checkpoint_every: 5,
+        shuffle_seed: Some(1),
+        log_every: 10,
+    };
+    let mut trainer = Trainer::new(Loss::MeanSquaredError, Sgd::new(0.05).with_momentum(0.9, true), config);
+    let train = linear_dataset(32, 0.0);
+    let validation = linear_dataset(8, 0.1);
+    let report = trainer.fit(&mut network, &train, Some(&validation)).unwrap();
+
+    assert!(report.stopped_early, "{} épocas", report.history.len());
+    let best = trainer.best().unwrap();
+    assert_eq!(report.best_epoch, Some(best.epoch));
+    assert!(best.metric < 1e-4, "{}", best.metric);
+    assert!(report.history[0].learning_rate < report.history[3].learning_rate);
+    // El modelo se queda con los pesos de la mejor época
+    let parameters = network.parameters();
+    for (parameter, saved) in parameters.iter().zip(&best.parameters) {
+        assert_close(parameter, &saved.to_vec(), 1e-5);
+    }
+    let learned: Vec<f32> = parameters.iter().flat_map(|p| p.to_vec()).collect();
+    for (w, expected) in learned.iter().zip([2.0, -1.0, 0.5]) {
+        assert!((w - expected).abs() < 0.02, "{:?}", learned);
+    }
+    assert_eq!(trainer.checkpoint().unwrap().epoch % 5, 4);
+
+    let mismatched = Dataset::new(Tensor::ones(&[4, 2]).unwrap(), Tensor::ones(&[3, 1]).unwrap());
+    assert!(mismatched.is_err());
+}
+
+#[test_case]
+fn trainer_restores_batch_norm_statistics_of_the_best_epoch() {
+    manual_seed(13);
+    let mut network = NeuralNetwork::new("regresión normalizada");
+    network.add_layer(Layer::new(2, 4, ActivationFunction::Identity).unwrap()).unwrap();
+    network.add_layer(BatchNorm::new(4, 1e-5, 0.1).unwrap()).unwrap();
+    network.add_layer(Dropout::new(0.1, 5).unwrap()).unwrap();
+    network.add_layer(Layer::new(4, 1, ActivationFunction::Identity).unwrap()).unwrap();
+    let initial = network.buffers();
+    let config = TrainerConfig {
+        epochs: 300,
+        batch_size: 8,
+        early_stopping: Some(EarlyStopping { metric: Metric::Loss, patience: 5, min_delta: 1e-4 }),
+        shuffle_seed: Some(2),
+        log_every: 0,
+        ..TrainerConfig::default()
+    };
+    let mut trainer = Trainer::new(Loss::MeanSquaredError, Adam::new(0.05), config);
+    let report = trainer.fit(&mut network, &linear_dataset(32, 0.0), Some(&linear_dataset(8, 0.1))).unwrap();
+
+    assert!(report.stopped_early && !network.is_training());
+    let best = trainer.best().unwrap();
+    assert!(best.metric < 0.05, "{}", best.metric);
+    // Las estadísticas siguen cambiando tras la mejor época; el modelo
+    // vuelve a las de entonces junto con los pesos
+    assert_eq!(best.buffers.len(), 2);
+    assert!(max_abs_diff(&best.buffers[0], &initial[0]) > 0.0);
+    for (buffer, saved) in network.buffers().iter().zip(&best.buffers) {
+        assert_tensor_close(buffer, saved, 0.0);
+    }
+    assert!(network.set_buffers(&best.buffers[..1]).is_err());
+}